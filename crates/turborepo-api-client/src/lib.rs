@@ -12,12 +12,16 @@ use reqwest::{Method, RequestBuilder, StatusCode};
 use serde::Deserialize;
 use turborepo_ci::{is_ci, Vendor};
 use turborepo_vercel_api::{
-    APIError, CachingStatus, CachingStatusResponse, PreflightResponse, SpacesResponse, Team,
-    TeamsResponse, UserResponse, VerificationResponse, VerifiedSsoUser,
+    APIError, ArtifactsQueryRequest, ArtifactsQueryResponse, CachingStatus, CachingStatusResponse,
+    PreflightResponse, SpacesResponse, Team, TeamsResponse, UserResponse, VerificationResponse,
+    VerifiedSsoUser,
 };
 use url::Url;
 
-pub use crate::error::{Error, Result};
+pub use crate::{
+    error::{Error, Result},
+    retry::RetryOpts,
+};
 
 pub mod analytics;
 mod error;
@@ -63,6 +67,17 @@ pub trait Client {
         team_id: Option<&str>,
         team_slug: Option<&str>,
     ) -> Result<Option<Response>>;
+    /// Fetches an artifact starting at `offset` bytes, used to resume an
+    /// interrupted download. Servers that don't support ranges respond with
+    /// the full artifact.
+    async fn fetch_artifact_from(
+        &self,
+        hash: &str,
+        offset: u64,
+        token: &str,
+        team_id: Option<&str>,
+        team_slug: Option<&str>,
+    ) -> Result<Option<Response>>;
     async fn artifact_exists(
         &self,
         hash: &str,
@@ -78,6 +93,13 @@ pub trait Client {
         team_slug: Option<&str>,
         method: Method,
     ) -> Result<Option<Response>>;
+    async fn query_artifacts(
+        &self,
+        hashes: &[&str],
+        token: &str,
+        team_id: Option<&str>,
+        team_slug: Option<&str>,
+    ) -> Result<ArtifactsQueryResponse>;
     fn make_url(&self, endpoint: &str) -> Result<Url>;
 }
 
@@ -87,6 +109,7 @@ pub struct APIClient {
     base_url: String,
     user_agent: String,
     use_preflight: bool,
+    retry_opts: RetryOpts,
 }
 
 #[derive(Clone)]
@@ -112,7 +135,7 @@ impl Client for APIClient {
             .header("User-Agent", self.user_agent.clone())
             .header("Authorization", format!("Bearer {}", token))
             .header("Content-Type", "application/json");
-        let response = retry::make_retryable_request(request_builder)
+        let response = retry::make_retryable_request(request_builder, &self.retry_opts)
             .await?
             .error_for_status()?;
 
@@ -127,7 +150,7 @@ impl Client for APIClient {
             .header("Content-Type", "application/json")
             .header("Authorization", format!("Bearer {}", token));
 
-        let response = retry::make_retryable_request(request_builder)
+        let response = retry::make_retryable_request(request_builder, &self.retry_opts)
             .await?
             .error_for_status()?;
 
@@ -173,7 +196,7 @@ impl Client for APIClient {
 
         let request_builder = Self::add_team_params(request_builder, team_id, team_slug);
 
-        let response = retry::make_retryable_request(request_builder)
+        let response = retry::make_retryable_request(request_builder, &self.retry_opts)
             .await?
             .error_for_status()?;
 
@@ -194,7 +217,7 @@ impl Client for APIClient {
             .header("Content-Type", "application/json")
             .header("Authorization", format!("Bearer {}", token));
 
-        let response = retry::make_retryable_request(request_builder)
+        let response = retry::make_retryable_request(request_builder, &self.retry_opts)
             .await?
             .error_for_status()?;
 
@@ -208,7 +231,7 @@ impl Client for APIClient {
             .query(&[("token", token), ("tokenName", token_name)])
             .header("User-Agent", self.user_agent.clone());
 
-        let response = retry::make_retryable_request(request_builder)
+        let response = retry::make_retryable_request(request_builder, &self.retry_opts)
             .await?
             .error_for_status()?;
 
@@ -268,7 +291,7 @@ impl Client for APIClient {
             request_builder = request_builder.header("x-artifact-tag", tag);
         }
 
-        let response = retry::make_retryable_request(request_builder).await?;
+        let response = retry::make_retryable_request(request_builder, &self.retry_opts).await?;
 
         if response.status() == StatusCode::FORBIDDEN {
             return Err(Self::handle_403(response).await);
@@ -349,6 +372,19 @@ impl Client for APIClient {
             .await
     }
 
+    #[tracing::instrument(skip_all)]
+    async fn fetch_artifact_from(
+        &self,
+        hash: &str,
+        offset: u64,
+        token: &str,
+        team_id: Option<&str>,
+        team_slug: Option<&str>,
+    ) -> Result<Option<Response>> {
+        self.request_artifact(hash, token, team_id, team_slug, Method::GET, Some(offset))
+            .await
+    }
+
     async fn get_artifact(
         &self,
         hash: &str,
@@ -357,7 +393,19 @@ impl Client for APIClient {
         team_slug: Option<&str>,
         method: Method,
     ) -> Result<Option<Response>> {
-        let mut request_url = self.make_url(&format!("/v8/artifacts/{}", hash))?;
+        self.request_artifact(hash, token, team_id, team_slug, method, None)
+            .await
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn query_artifacts(
+        &self,
+        hashes: &[&str],
+        token: &str,
+        team_id: Option<&str>,
+        team_slug: Option<&str>,
+    ) -> Result<ArtifactsQueryResponse> {
+        let mut request_url = self.make_url("/v8/artifacts")?;
         let mut allow_auth = true;
 
         if self.use_preflight {
@@ -365,19 +413,22 @@ impl Client for APIClient {
                 .do_preflight(
                     token,
                     request_url.clone(),
-                    "GET",
-                    "Authorization, User-Agent",
+                    "POST",
+                    "Authorization, Content-Type, User-Agent",
                 )
                 .await?;
 
             allow_auth = preflight_response.allow_authorization_header;
             request_url = preflight_response.location;
-        };
+        }
 
         let mut request_builder = self
             .client
-            .request(method, request_url)
-            .header("User-Agent", self.user_agent.clone());
+            .post(request_url)
+            .header("User-Agent", self.user_agent.clone())
+            .json(&ArtifactsQueryRequest {
+                hashes: hashes.iter().map(|hash| hash.to_string()).collect(),
+            });
 
         if allow_auth {
             request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
@@ -385,13 +436,15 @@ impl Client for APIClient {
 
         request_builder = Self::add_team_params(request_builder, team_id, team_slug);
 
-        let response = retry::make_retryable_request(request_builder).await?;
+        request_builder = Self::add_ci_header(request_builder);
 
-        match response.status() {
-            StatusCode::FORBIDDEN => Err(Self::handle_403(response).await),
-            StatusCode::NOT_FOUND => Ok(None),
-            _ => Ok(Some(response.error_for_status()?)),
+        let response = retry::make_retryable_request(request_builder, &self.retry_opts).await?;
+
+        if response.status() == StatusCode::FORBIDDEN {
+            return Err(Self::handle_403(response).await);
         }
+
+        Ok(response.error_for_status()?.json().await?)
     }
 
     fn make_url(&self, endpoint: &str) -> Result<Url> {
@@ -423,13 +476,69 @@ impl APIClient {
             base_url: base_url.as_ref().to_string(),
             user_agent,
             use_preflight,
+            retry_opts: RetryOpts::default(),
         })
     }
 
+    pub fn with_retry_opts(mut self, retry_opts: RetryOpts) -> Self {
+        self.retry_opts = retry_opts;
+        self
+    }
+
     pub fn base_url(&self) -> &str {
         self.base_url.as_str()
     }
 
+    async fn request_artifact(
+        &self,
+        hash: &str,
+        token: &str,
+        team_id: Option<&str>,
+        team_slug: Option<&str>,
+        method: Method,
+        offset: Option<u64>,
+    ) -> Result<Option<Response>> {
+        let mut request_url = self.make_url(&format!("/v8/artifacts/{}", hash))?;
+        let mut allow_auth = true;
+
+        if self.use_preflight {
+            let request_headers = if offset.is_some() {
+                "Authorization, User-Agent, Range"
+            } else {
+                "Authorization, User-Agent"
+            };
+            let preflight_response = self
+                .do_preflight(token, request_url.clone(), "GET", request_headers)
+                .await?;
+
+            allow_auth = preflight_response.allow_authorization_header;
+            request_url = preflight_response.location;
+        };
+
+        let mut request_builder = self
+            .client
+            .request(method, request_url)
+            .header("User-Agent", self.user_agent.clone());
+
+        if allow_auth {
+            request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
+        }
+
+        if let Some(offset) = offset {
+            request_builder = request_builder.header("Range", format!("bytes={}-", offset));
+        }
+
+        request_builder = Self::add_team_params(request_builder, team_id, team_slug);
+
+        let response = retry::make_retryable_request(request_builder, &self.retry_opts).await?;
+
+        match response.status() {
+            StatusCode::FORBIDDEN => Err(Self::handle_403(response).await),
+            StatusCode::NOT_FOUND => Ok(None),
+            _ => Ok(Some(response.error_for_status()?)),
+        }
+    }
+
     async fn do_preflight(
         &self,
         token: &str,
@@ -445,7 +554,7 @@ impl APIClient {
             .header("Access-Control-Request-Headers", request_headers)
             .header("Authorization", format!("Bearer {}", token));
 
-        let response = retry::make_retryable_request(request_builder).await?;
+        let response = retry::make_retryable_request(request_builder, &self.retry_opts).await?;
 
         let headers = response.headers();
         let location = if let Some(location) = headers.get("Location") {
@@ -605,6 +714,7 @@ fn build_user_agent(version: &str) -> String {
 #[cfg(test)]
 mod test {
     use anyhow::Result;
+    use turborepo_vercel_api::{ArtifactInfo, ArtifactQueryResult};
     use turborepo_vercel_api_mock::start_test_server;
     use url::Url;
 
@@ -668,6 +778,34 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_query_artifacts() -> Result<()> {
+        let port = port_scanner::request_open_port().unwrap();
+        let handle = tokio::spawn(start_test_server(port));
+        let client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
+
+        client
+            .put_artifact("existing", b"contents", 42, None, "token", None, None)
+            .await?;
+
+        let response = client
+            .query_artifacts(&["existing", "missing"], "token", None, None)
+            .await?;
+
+        assert_eq!(
+            response["existing"],
+            Some(ArtifactQueryResult::Found(ArtifactInfo {
+                size: 8,
+                task_duration_ms: 42,
+                tag: None,
+            }))
+        );
+        assert_eq!(response["missing"], None);
+
+        handle.abort();
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_handle_403_includes_text_on_invalid_json() {
         let response = reqwest::Response::from(
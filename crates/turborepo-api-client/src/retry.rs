@@ -1,27 +1,62 @@
+use std::time::Duration;
+
 use reqwest::{RequestBuilder, Response, StatusCode};
 use tokio::time::sleep;
 
 use crate::Error;
 
-const MIN_SLEEP_TIME_SECS: u64 = 2;
-const MAX_SLEEP_TIME_SECS: u64 = 10;
-const RETRY_MAX: u32 = 2;
+const MIN_SLEEP_TIME: Duration = Duration::from_secs(2);
+const MAX_SLEEP_TIME: Duration = Duration::from_secs(10);
+const RETRY_MAX: u32 = 1;
+
+/// How many times a failed request is retried, and how long to wait between
+/// attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryOpts {
+    /// Retries after the initial attempt
+    pub max_retries: u32,
+    pub min_backoff: Duration,
+    pub max_backoff: Duration,
+}
 
-/// Retries a request until `RETRY_MAX` is reached, the `should_retry_request`
-/// function returns false, or the future succeeds. Uses an exponential backoff
-/// with a base of 2 to delay between retries.
+impl Default for RetryOpts {
+    fn default() -> Self {
+        Self {
+            max_retries: RETRY_MAX,
+            min_backoff: MIN_SLEEP_TIME,
+            max_backoff: MAX_SLEEP_TIME,
+        }
+    }
+}
+
+impl RetryOpts {
+    /// Exponential backoff with a base of 2, clamped to the configured bounds
+    pub fn backoff(&self, retry_count: u32) -> Duration {
+        self.min_backoff
+            .saturating_mul(2_u32.saturating_pow(retry_count))
+            .clamp(self.min_backoff, self.max_backoff)
+    }
+}
+
+/// Retries a request until `max_retries` is reached, the request fails with
+/// an error that isn't transient, or the request succeeds. Responses with a
+/// transient status code (429 or most 5xx) are retried as well. If every
+/// attempt gets such a response the last one is returned for the caller to
+/// handle.
 ///
 /// # Arguments
 ///
 /// * `request_builder`: The request builder with everything, i.e. headers and
 ///   body already set. NOTE: This must be cloneable, so no streams are allowed.
+/// * `retry_opts`: The number of retries and backoff between them
 ///
 /// returns: Result<Response, Error>
 pub(crate) async fn make_retryable_request(
     request_builder: RequestBuilder,
+    retry_opts: &RetryOpts,
 ) -> Result<Response, Error> {
-    let mut last_error = None;
-    for retry_count in 0..RETRY_MAX {
+    let mut retry_count = 0;
+    loop {
         // A request builder can fail to clone for two reasons:
         // - the URL given was given as a string and isn't a valid URL this can be
         //   mitigated by constructing requests with pre-parsed URLs via Url::parse
@@ -30,35 +65,55 @@ pub(crate) async fn make_retryable_request(
         let Some(builder) = request_builder.try_clone() else {
             return Ok(request_builder.send().await?);
         };
+        let is_last_attempt = retry_count >= retry_opts.max_retries;
         match builder.send().await {
-            Ok(value) => return Ok(value),
-            Err(err) => {
-                if !should_retry_request(&err) {
-                    return Err(err.into());
-                }
-                last_error = Some(err);
+            Ok(response) if is_last_attempt || !should_retry_status(response.status()) => {
+                return Ok(response)
+            }
+            Ok(_) => {}
+            Err(err) if is_last_attempt && retry_count > 0 => {
+                return Err(Error::TooManyFailures(Box::new(err)))
             }
+            Err(err) if is_last_attempt || !should_retry_request(&err) => return Err(err.into()),
+            Err(_) => {}
         }
 
-        let sleep_period = (2_u64)
-            .pow(retry_count)
-            .clamp(MIN_SLEEP_TIME_SECS, MAX_SLEEP_TIME_SECS);
-        sleep(std::time::Duration::from_secs(sleep_period)).await;
+        sleep(retry_opts.backoff(retry_count)).await;
+        retry_count += 1;
     }
+}
 
-    Err(Error::TooManyFailures(Box::new(last_error.unwrap())))
+fn should_retry_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || (status.is_server_error() && status != StatusCode::NOT_IMPLEMENTED)
 }
 
 fn should_retry_request(error: &reqwest::Error) -> bool {
     if let Some(status) = error.status() {
-        if status == StatusCode::TOO_MANY_REQUESTS {
-            return true;
-        }
-
-        if status.as_u16() >= 500 && status.as_u16() != 501 {
-            return true;
-        }
+        return should_retry_status(status);
     }
 
-    false
+    error.is_timeout() || error.is_connect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::RetryOpts;
+
+    #[test]
+    fn test_backoff_is_exponential_and_clamped() {
+        let retry_opts = RetryOpts {
+            max_retries: 5,
+            min_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+
+        assert_eq!(retry_opts.backoff(0), Duration::from_millis(100));
+        assert_eq!(retry_opts.backoff(1), Duration::from_millis(200));
+        assert_eq!(retry_opts.backoff(2), Duration::from_millis(400));
+        assert_eq!(retry_opts.backoff(3), Duration::from_millis(500));
+        assert_eq!(retry_opts.backoff(40), Duration::from_millis(500));
+    }
 }
@@ -253,6 +253,16 @@ mod tests {
         ) -> turborepo_api_client::Result<Option<Response>> {
             unimplemented!("fetch_artifact")
         }
+        async fn fetch_artifact_from(
+            &self,
+            _hash: &str,
+            _offset: u64,
+            _token: &str,
+            _team_id: Option<&str>,
+            _team_slug: Option<&str>,
+        ) -> turborepo_api_client::Result<Option<Response>> {
+            unimplemented!("fetch_artifact_from")
+        }
         async fn artifact_exists(
             &self,
             _hash: &str,
@@ -272,6 +282,15 @@ mod tests {
         ) -> turborepo_api_client::Result<Option<Response>> {
             unimplemented!("get_artifact")
         }
+        async fn query_artifacts(
+            &self,
+            _hashes: &[&str],
+            _token: &str,
+            _team_id: Option<&str>,
+            _team_slug: Option<&str>,
+        ) -> turborepo_api_client::Result<turborepo_vercel_api::ArtifactsQueryResponse> {
+            unimplemented!("query_artifacts")
+        }
         fn make_url(&self, endpoint: &str) -> turborepo_api_client::Result<Url> {
             let url = format!("{}{}", self.base_url, endpoint);
             Url::parse(&url).map_err(|err| turborepo_api_client::Error::InvalidUrl { url, err })
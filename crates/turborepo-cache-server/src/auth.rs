@@ -0,0 +1,133 @@
+use std::{collections::HashSet, str::FromStr};
+
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum TokenParseError {
+    #[error("token cannot be empty")]
+    EmptyToken,
+    #[error("team list for token cannot be empty")]
+    EmptyTeams,
+}
+
+/// A token accepted by the server, optionally restricted to a set of teams.
+///
+/// Parsed from `<token>` or `<token>:<team>[,<team>...]`, where a team is
+/// matched against either the `teamId` or `slug` query parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    value: String,
+    teams: Option<HashSet<String>>,
+}
+
+impl Token {
+    pub fn new(value: impl Into<String>, teams: Option<HashSet<String>>) -> Self {
+        Self {
+            value: value.into(),
+            teams,
+        }
+    }
+
+    /// Whether this token may access artifacts scoped to `team`. Unscoped
+    /// artifacts can only be accessed by unrestricted tokens.
+    pub fn allows(&self, team: Option<&str>) -> bool {
+        match (&self.teams, team) {
+            (None, _) => true,
+            (Some(teams), Some(team)) => teams.contains(team),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+impl FromStr for Token {
+    type Err = TokenParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (value, teams) = match s.split_once(':') {
+            Some((value, teams)) => {
+                let teams = teams
+                    .split(',')
+                    .map(|team| team.trim())
+                    .filter(|team| !team.is_empty())
+                    .map(|team| team.to_string())
+                    .collect::<HashSet<_>>();
+                if teams.is_empty() {
+                    return Err(TokenParseError::EmptyTeams);
+                }
+                (value, Some(teams))
+            }
+            None => (s, None),
+        };
+        if value.is_empty() {
+            return Err(TokenParseError::EmptyToken);
+        }
+
+        Ok(Token::new(value, teams))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Tokens(Vec<Token>);
+
+impl Tokens {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self(tokens)
+    }
+
+    /// Finds the token matching the value of an `Authorization: Bearer`
+    /// header. Values are compared in constant time so that response
+    /// timings don't leak how much of a token was guessed correctly.
+    pub fn find(&self, authorization: &str) -> Option<&Token> {
+        let value = authorization.strip_prefix("Bearer ")?;
+        self.0
+            .iter()
+            .find(|token| bool::from(token.value.as_bytes().ct_eq(value.as_bytes())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("secret", Ok(Token::new("secret", None)) ; "unrestricted")]
+    #[test_case(
+        "secret:team_a,team_b",
+        Ok(Token::new("secret", Some(["team_a".to_string(), "team_b".to_string()].into()))) ;
+        "restricted"
+    )]
+    #[test_case("", Err(TokenParseError::EmptyToken) ; "empty token")]
+    #[test_case(":team_a", Err(TokenParseError::EmptyToken) ; "empty token with teams")]
+    #[test_case("secret:", Err(TokenParseError::EmptyTeams) ; "empty teams")]
+    fn test_parse_token(input: &str, expected: Result<Token, TokenParseError>) {
+        assert_eq!(input.parse::<Token>(), expected);
+    }
+
+    #[test_case(None, Some("team_a"), true ; "unrestricted with team")]
+    #[test_case(None, None, true ; "unrestricted without team")]
+    #[test_case(Some("team_a"), Some("team_a"), true ; "allowed team")]
+    #[test_case(Some("team_a"), Some("team_b"), false ; "other team")]
+    #[test_case(Some("team_a"), None, false ; "restricted without team")]
+    fn test_allows(restricted_to: Option<&str>, team: Option<&str>, expected: bool) {
+        let token = Token::new(
+            "secret",
+            restricted_to.map(|team| [team.to_string()].into()),
+        );
+        assert_eq!(token.allows(team), expected);
+    }
+
+    #[test_case("Bearer secret", Some("secret") ; "matching token")]
+    #[test_case("Bearer other", Some("other") ; "second token")]
+    #[test_case("Bearer secre", None ; "prefix of token")]
+    #[test_case("Bearer secrets", None ; "token with suffix")]
+    #[test_case("secret", None ; "missing bearer prefix")]
+    fn test_find(authorization: &str, expected: Option<&str>) {
+        let tokens = Tokens::new(vec![Token::new("secret", None), Token::new("other", None)]);
+        assert_eq!(
+            tokens.find(authorization).map(|token| token.value.as_str()),
+            expected
+        );
+    }
+}
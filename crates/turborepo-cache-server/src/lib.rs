@@ -0,0 +1,589 @@
+//! A self-hosted remote cache implementing the `/v8/artifacts` endpoints
+//! used by `turborepo-api-client` and `turborepo-cache`'s `HTTPCache`.
+//!
+//! Artifacts are stored on local disk, scoped by the `teamId` or `slug` query
+//! parameter, and accessed with bearer tokens that can be restricted to a
+//! set of teams.
+#![deny(clippy::all)]
+
+mod auth;
+mod storage;
+
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+
+use axum::{
+    body::StreamBody,
+    extract::{BodyStream, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio_util::io::ReaderStream;
+use tracing::{debug, error};
+use turborepo_vercel_api::{
+    ArtifactInfo, ArtifactQueryError, ArtifactQueryResult, ArtifactsQueryRequest,
+    ArtifactsQueryResponse, CachingStatus, CachingStatusResponse,
+};
+
+pub use crate::{
+    auth::{Token, TokenParseError, Tokens},
+    storage::{ArtifactMetadata, DiskStorage},
+};
+
+const ALLOWED_HEADERS: &str = "Authorization, Content-Type, User-Agent, x-artifact-duration, \
+                               x-artifact-tag, x-artifact-client-ci";
+const MAX_NAME_LENGTH: usize = 128;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("missing or invalid token")]
+    Unauthorized,
+    #[error("token is not authorized for {0}")]
+    Forbidden(String),
+    #[error("invalid artifact hash: {0}")]
+    InvalidHash(String),
+    #[error("invalid team: {0}")]
+    InvalidTeam(String),
+    #[error("invalid x-artifact-duration header: {0}")]
+    InvalidDuration(String),
+    #[error("artifact is larger than the {0} byte limit")]
+    ArtifactTooLarge(u64),
+    #[error("failed to read request body: {0}")]
+    Body(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl Error {
+    fn status_and_code(&self) -> (StatusCode, &'static str) {
+        match self {
+            Error::Unauthorized => (StatusCode::UNAUTHORIZED, "unauthorized"),
+            Error::Forbidden(_) => (StatusCode::FORBIDDEN, "forbidden"),
+            Error::ArtifactTooLarge(_) => (StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large"),
+            Error::InvalidHash(_)
+            | Error::InvalidTeam(_)
+            | Error::InvalidDuration(_)
+            | Error::Body(_) => (StatusCode::BAD_REQUEST, "bad_request"),
+            Error::Io(_) | Error::Json(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        }
+    }
+}
+
+// Errors use the same shape as the Vercel API so the client can surface the
+// message.
+#[derive(Serialize)]
+struct ErrorBody {
+    error: ErrorDetails,
+}
+
+#[derive(Serialize)]
+struct ErrorDetails {
+    code: &'static str,
+    message: String,
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let (status, code) = self.status_and_code();
+        if status.is_server_error() {
+            error!("{self}");
+        }
+        let body = ErrorBody {
+            error: ErrorDetails {
+                code,
+                message: self.to_string(),
+            },
+        };
+
+        (status, Json(body)).into_response()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub cache_dir: PathBuf,
+    /// Maximum total size of stored artifacts in bytes
+    pub max_size: Option<u64>,
+    /// Maximum size of a single uploaded artifact in bytes
+    pub max_artifact_size: Option<u64>,
+    pub tokens: Tokens,
+}
+
+struct AppState {
+    storage: DiskStorage,
+    tokens: Tokens,
+}
+
+#[derive(Debug, Deserialize)]
+struct TeamParams {
+    #[serde(rename = "teamId")]
+    team_id: Option<String>,
+    slug: Option<String>,
+}
+
+pub async fn router(config: Config) -> Result<Router, Error> {
+    let Config {
+        cache_dir,
+        max_size,
+        max_artifact_size,
+        tokens,
+    } = config;
+    let state = Arc::new(AppState {
+        storage: DiskStorage::new(cache_dir, max_size)
+            .await?
+            .with_max_artifact_size(max_artifact_size),
+        tokens,
+    });
+
+    Ok(Router::new()
+        .route("/v8/artifacts/status", get(status).options(preflight))
+        .route("/v8/artifacts/events", post(events).options(preflight))
+        .route("/v8/artifacts", post(query_artifacts).options(preflight))
+        .route(
+            "/v8/artifacts/:hash",
+            get(get_artifact)
+                .head(artifact_exists)
+                .put(put_artifact)
+                .options(preflight),
+        )
+        .with_state(state))
+}
+
+pub async fn serve(addr: SocketAddr, config: Config) -> anyhow::Result<()> {
+    let app = router(config).await?;
+    axum_server::bind(addr)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Checks the request's token against the team it's scoped to, returning the
+/// team.
+fn authorize(
+    state: &AppState,
+    headers: &HeaderMap,
+    params: TeamParams,
+) -> Result<Option<String>, Error> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| state.tokens.find(value))
+        .ok_or(Error::Unauthorized)?;
+
+    let team = params.team_id.or(params.slug);
+    if let Some(team) = &team {
+        if !is_valid_name(team) {
+            return Err(Error::InvalidTeam(team.clone()));
+        }
+    }
+    if !token.allows(team.as_deref()) {
+        return Err(Error::Forbidden(team.map_or_else(
+            || "unscoped artifacts".to_string(),
+            |team| format!("team {team}"),
+        )));
+    }
+
+    Ok(team)
+}
+
+fn validate_hash(hash: &str) -> Result<(), Error> {
+    if is_valid_name(hash) {
+        Ok(())
+    } else {
+        Err(Error::InvalidHash(hash.to_string()))
+    }
+}
+
+fn metadata_headers(metadata: &ArtifactMetadata) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-artifact-duration", HeaderValue::from(metadata.duration));
+    if let Some(tag) = metadata
+        .tag
+        .as_deref()
+        .and_then(|tag| HeaderValue::from_str(tag).ok())
+    {
+        headers.insert("x-artifact-tag", tag);
+    }
+    headers
+}
+
+async fn preflight() -> impl IntoResponse {
+    [
+        (header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"),
+        (
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            "GET, HEAD, PUT, POST, OPTIONS",
+        ),
+        (header::ACCESS_CONTROL_ALLOW_HEADERS, ALLOWED_HEADERS),
+    ]
+}
+
+async fn status(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<TeamParams>,
+) -> Result<Json<CachingStatusResponse>, Error> {
+    authorize(&state, &headers, params)?;
+    Ok(Json(CachingStatusResponse {
+        status: CachingStatus::Enabled,
+    }))
+}
+
+// Analytics events are accepted so clients don't log errors, but not stored.
+async fn events(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<TeamParams>,
+) -> Result<StatusCode, Error> {
+    authorize(&state, &headers, params)?;
+    Ok(StatusCode::OK)
+}
+
+async fn get_artifact(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<TeamParams>,
+) -> Result<Response, Error> {
+    let team = authorize(&state, &headers, params)?;
+    validate_hash(&hash)?;
+
+    let Some((path, metadata)) = state.storage.get(team.as_deref(), &hash).await? else {
+        debug!("miss {hash} (team: {team:?})");
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    let file = match tokio::fs::File::open(&path).await {
+        Ok(file) => file,
+        // Evicted between the lookup and the read
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(StatusCode::NOT_FOUND.into_response())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let size = file.metadata().await?.len();
+    debug!("hit {hash} (team: {team:?})");
+
+    let mut response_headers = metadata_headers(&metadata);
+    response_headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(size));
+
+    Ok((response_headers, StreamBody::new(ReaderStream::new(file))).into_response())
+}
+
+async fn artifact_exists(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<TeamParams>,
+) -> Result<Response, Error> {
+    let team = authorize(&state, &headers, params)?;
+    validate_hash(&hash)?;
+
+    match state.storage.metadata(team.as_deref(), &hash).await? {
+        Some(metadata) => Ok((StatusCode::OK, metadata_headers(&metadata)).into_response()),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+async fn query_artifacts(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<TeamParams>,
+    Json(request): Json<ArtifactsQueryRequest>,
+) -> Result<Json<ArtifactsQueryResponse>, Error> {
+    let team = authorize(&state, &headers, params)?;
+
+    let mut response = ArtifactsQueryResponse::with_capacity(request.hashes.len());
+    for hash in request.hashes {
+        // A single bad hash shouldn't fail the whole query
+        let result = match validate_hash(&hash) {
+            Ok(()) => match state.storage.metadata(team.as_deref(), &hash).await? {
+                Some(metadata) => Some(ArtifactQueryResult::Found(ArtifactInfo {
+                    size: state
+                        .storage
+                        .artifact_size(team.as_deref(), &hash)
+                        .await
+                        .unwrap_or_default(),
+                    task_duration_ms: metadata.duration,
+                    tag: metadata.tag,
+                })),
+                None => None,
+            },
+            Err(e) => Some(ArtifactQueryResult::Error {
+                error: ArtifactQueryError {
+                    message: e.to_string(),
+                },
+            }),
+        };
+        response.insert(hash, result);
+    }
+
+    Ok(Json(response))
+}
+
+async fn put_artifact(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<TeamParams>,
+    body: BodyStream,
+) -> Result<(StatusCode, Json<String>), Error> {
+    let team = authorize(&state, &headers, params)?;
+    validate_hash(&hash)?;
+
+    // Reject uploads that declare their size up front before reading them.
+    // Bodies without a length are checked by the storage as they're written.
+    if let Some(max_artifact_size) = state.storage.max_artifact_size() {
+        let content_length = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        if content_length.is_some_and(|length| length > max_artifact_size) {
+            return Err(Error::ArtifactTooLarge(max_artifact_size));
+        }
+    }
+
+    let duration = match headers.get("x-artifact-duration") {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|duration| duration.parse().ok())
+            .ok_or_else(|| Error::InvalidDuration(format!("{value:?}")))?,
+        None => 0,
+    };
+    let tag = headers
+        .get("x-artifact-tag")
+        .and_then(|tag| tag.to_str().ok())
+        .map(|tag| tag.to_string());
+
+    state
+        .storage
+        .put(
+            team.as_deref(),
+            &hash,
+            &ArtifactMetadata { duration, tag },
+            body,
+        )
+        .await?;
+    debug!("stored {hash} (team: {team:?})");
+
+    Ok((StatusCode::ACCEPTED, Json(hash)))
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+
+    use anyhow::Result;
+    use tempfile::tempdir;
+    use turborepo_api_client::{APIClient, Client, Error as ClientError};
+
+    use super::*;
+
+    const TOKEN: &str = "token";
+    const TEAM_TOKEN: &str = "team-token";
+    const TEAM: &str = "team_a";
+
+    async fn start_server(cache_dir: PathBuf) -> Result<APIClient> {
+        start_server_with_limit(cache_dir, None).await
+    }
+
+    async fn start_server_with_limit(
+        cache_dir: PathBuf,
+        max_artifact_size: Option<u64>,
+    ) -> Result<APIClient> {
+        let app = router(Config {
+            cache_dir,
+            max_size: None,
+            max_artifact_size,
+            tokens: Tokens::new(vec![
+                TOKEN.parse()?,
+                format!("{TEAM_TOKEN}:{TEAM}").parse()?,
+            ]),
+        })
+        .await?;
+        // Bind before spawning so the server is ready to accept requests
+        let listener = std::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        let port = listener.local_addr()?.port();
+        let server = axum_server::from_tcp(listener).serve(app.into_make_service());
+        tokio::spawn(server);
+
+        Ok(APIClient::new(
+            format!("http://localhost:{port}"),
+            200,
+            "2.0.0",
+            true,
+        )?)
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() -> Result<()> {
+        let dir = tempdir()?;
+        let client = start_server(dir.path().to_path_buf()).await?;
+        let hash = "0123456789abcdef";
+
+        assert!(client
+            .artifact_exists(hash, TOKEN, None, None)
+            .await?
+            .is_none());
+
+        client
+            .put_artifact(hash, b"artifact", 42, Some("signature"), TOKEN, None, None)
+            .await?;
+
+        let response = client
+            .artifact_exists(hash, TOKEN, None, None)
+            .await?
+            .unwrap();
+        assert_eq!(response.headers()["x-artifact-duration"], "42");
+
+        let response = client
+            .fetch_artifact(hash, TOKEN, None, None)
+            .await?
+            .unwrap();
+        assert_eq!(response.headers()["x-artifact-tag"], "signature");
+        assert_eq!(response.bytes().await?.as_ref(), b"artifact");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_query_artifacts() -> Result<()> {
+        let dir = tempdir()?;
+        let client = start_server(dir.path().to_path_buf()).await?;
+        let hash = "0123456789abcdef";
+
+        client
+            .put_artifact(hash, b"artifact", 42, Some("signature"), TOKEN, None, None)
+            .await?;
+
+        let response = client
+            .query_artifacts(&[hash, "missing", "not/valid"], TOKEN, None, None)
+            .await?;
+        assert_eq!(
+            response[hash],
+            Some(ArtifactQueryResult::Found(ArtifactInfo {
+                size: 8,
+                task_duration_ms: 42,
+                tag: Some("signature".to_string()),
+            }))
+        );
+        assert_eq!(response["missing"], None);
+        assert!(matches!(
+            response["not/valid"],
+            Some(ArtifactQueryResult::Error { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_team_scoping() -> Result<()> {
+        let dir = tempdir()?;
+        let client = start_server(dir.path().to_path_buf()).await?;
+        let hash = "0123456789abcdef";
+
+        client
+            .put_artifact(hash, b"artifact", 0, None, TEAM_TOKEN, None, Some(TEAM))
+            .await?;
+
+        // Artifacts are only visible to the team they were stored for
+        assert!(client
+            .fetch_artifact(hash, TOKEN, None, Some(TEAM))
+            .await?
+            .is_some());
+        assert!(client
+            .fetch_artifact(hash, TOKEN, None, None)
+            .await?
+            .is_none());
+        assert!(client
+            .fetch_artifact(hash, TOKEN, None, Some("team_b"))
+            .await?
+            .is_none());
+
+        // Restricted tokens can't access other teams
+        let err = client
+            .fetch_artifact(hash, TEAM_TOKEN, None, Some("team_b"))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ClientError::UnknownStatus { ref code, .. } if code == "forbidden"),
+            "unexpected error: {err}"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_token() -> Result<()> {
+        let dir = tempdir()?;
+        let client = start_server(dir.path().to_path_buf()).await?;
+
+        let err = client
+            .put_artifact(
+                "0123456789abcdef",
+                b"artifact",
+                0,
+                None,
+                "wrong",
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ClientError::ReqwestError(ref e) if e.status() == Some(StatusCode::UNAUTHORIZED)
+            ),
+            "unexpected error: {err}"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rejects_oversized_artifact() -> Result<()> {
+        let dir = tempdir()?;
+        let client = start_server_with_limit(dir.path().to_path_buf(), Some(4)).await?;
+
+        client
+            .put_artifact("small", b"1234", 0, None, TOKEN, None, None)
+            .await?;
+        let err = client
+            .put_artifact("large", b"12345", 0, None, TOKEN, None, None)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ClientError::ReqwestError(ref e)
+                    if e.status() == Some(StatusCode::PAYLOAD_TOO_LARGE)
+            ),
+            "unexpected error: {err}"
+        );
+        assert!(client
+            .artifact_exists("large", TOKEN, None, None)
+            .await?
+            .is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+};
+
+use anyhow::Result;
+use clap::Parser;
+use tracing::info;
+use turborepo_cache_server::{serve, Config, Token, Tokens};
+
+/// A self-hosted Turborepo remote cache
+#[derive(Debug, Parser)]
+#[command(name = "turbo-cache-server", version)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, env = "TURBO_CACHE_SERVER_HOST", default_value = "127.0.0.1")]
+    host: IpAddr,
+    /// Port to listen on
+    #[arg(long, env = "TURBO_CACHE_SERVER_PORT", default_value_t = 3000)]
+    port: u16,
+    /// Directory artifacts are stored in
+    #[arg(long, env = "TURBO_CACHE_SERVER_DIR")]
+    cache_dir: PathBuf,
+    /// Maximum total size of stored artifacts in bytes. The least recently
+    /// used artifacts are evicted once this is exceeded.
+    #[arg(long, env = "TURBO_CACHE_SERVER_MAX_SIZE")]
+    max_size: Option<u64>,
+    /// Maximum size of a single uploaded artifact in bytes. Larger uploads
+    /// are rejected with a 413.
+    #[arg(long, env = "TURBO_CACHE_SERVER_MAX_ARTIFACT_SIZE")]
+    max_artifact_size: Option<u64>,
+    /// A token clients may authenticate with, optionally restricted to a
+    /// list of teams: `<token>` or `<token>:<team>[,<team>...]`. Can be
+    /// passed multiple times, or as a space separated list in the
+    /// environment variable.
+    #[arg(
+        long = "token",
+        env = "TURBO_CACHE_SERVER_TOKENS",
+        value_delimiter = ' ',
+        required = true
+    )]
+    tokens: Vec<Token>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let args = Args::parse();
+    let addr = SocketAddr::new(args.host, args.port);
+    info!("serving {} on {addr}", args.cache_dir.display());
+
+    serve(
+        addr,
+        Config {
+            cache_dir: args.cache_dir,
+            max_size: args.max_size,
+            max_artifact_size: args.max_artifact_size,
+            tokens: Tokens::new(args.tokens),
+        },
+    )
+    .await
+}
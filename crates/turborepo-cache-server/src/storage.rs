@@ -0,0 +1,461 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
+use tracing::{debug, warn};
+
+use crate::Error;
+
+const METADATA_EXTENSION: &str = "json";
+const UNSCOPED_DIR: &str = "shared";
+const TEAMS_DIR: &str = "teams";
+
+/// The values the client sends alongside an artifact, returned as headers
+/// when the artifact is fetched.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactMetadata {
+    pub duration: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct ArtifactKey {
+    team: Option<String>,
+    hash: String,
+}
+
+#[derive(Debug)]
+struct Entry {
+    size: u64,
+    last_access: SystemTime,
+}
+
+#[derive(Debug, Default)]
+struct Index {
+    entries: HashMap<ArtifactKey, Entry>,
+    total_size: u64,
+}
+
+/// Artifacts stored on local disk, laid out as `shared/<hash>` for requests
+/// without a team and `teams/<team>/<hash>` otherwise. Each artifact has a
+/// `<hash>.json` sidecar holding its `ArtifactMetadata`.
+///
+/// When `max_size` is set, the least recently read artifacts are evicted
+/// after each write until the cache fits. Access times are only tracked in
+/// memory; on startup they're seeded from file modification times.
+///
+/// The index is behind an async lock: it's held while artifacts are moved
+/// into place or evicted, so other requests keep being served in the
+/// meantime.
+#[derive(Debug)]
+pub struct DiskStorage {
+    root: PathBuf,
+    max_size: Option<u64>,
+    max_artifact_size: Option<u64>,
+    index: Mutex<Index>,
+    next_temp_id: AtomicU64,
+}
+
+impl DiskStorage {
+    pub async fn new(root: impl Into<PathBuf>, max_size: Option<u64>) -> Result<Self, Error> {
+        let root = root.into();
+        fs::create_dir_all(root.join(UNSCOPED_DIR)).await?;
+        fs::create_dir_all(root.join(TEAMS_DIR)).await?;
+
+        let mut index = Index::default();
+        scan_dir(&root.join(UNSCOPED_DIR), None, &mut index).await?;
+        let mut teams = fs::read_dir(root.join(TEAMS_DIR)).await?;
+        while let Some(team_dir) = teams.next_entry().await? {
+            if !team_dir.file_type().await?.is_dir() {
+                continue;
+            }
+            let Some(team) = team_dir.file_name().to_str().map(|team| team.to_string()) else {
+                continue;
+            };
+            scan_dir(&team_dir.path(), Some(team), &mut index).await?;
+        }
+        debug!(
+            "loaded {} artifacts ({} bytes) from {}",
+            index.entries.len(),
+            index.total_size,
+            root.display()
+        );
+
+        let storage = Self {
+            root,
+            max_size,
+            max_artifact_size: None,
+            index: Mutex::new(index),
+            next_temp_id: AtomicU64::new(0),
+        };
+        storage.evict().await;
+
+        Ok(storage)
+    }
+
+    /// Rejects artifacts larger than `max_artifact_size` bytes while they're
+    /// being written.
+    pub fn with_max_artifact_size(mut self, max_artifact_size: Option<u64>) -> Self {
+        self.max_artifact_size = max_artifact_size;
+        self
+    }
+
+    pub fn max_artifact_size(&self) -> Option<u64> {
+        self.max_artifact_size
+    }
+
+    /// Total size in bytes of all stored artifacts.
+    pub async fn size(&self) -> u64 {
+        self.index.lock().await.total_size
+    }
+
+    pub async fn put<S, E>(
+        &self,
+        team: Option<&str>,
+        hash: &str,
+        metadata: &ArtifactMetadata,
+        mut body: S,
+    ) -> Result<(), Error>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let dir = self.team_dir(team);
+        fs::create_dir_all(&dir).await?;
+
+        // Write to temporary files first so a concurrent read never sees a
+        // partially written artifact.
+        let temp_id = self.next_temp_id.fetch_add(1, Ordering::Relaxed);
+        let temp_path = dir.join(format!(".{hash}.{temp_id}.tmp"));
+        let metadata_temp_path = dir.join(format!(".{hash}.{temp_id}.{METADATA_EXTENSION}.tmp"));
+        let result = async {
+            let mut file = fs::File::create(&temp_path).await?;
+            let mut size = 0;
+            while let Some(chunk) = body.next().await {
+                let chunk = chunk.map_err(|e| Error::Body(Box::new(e)))?;
+                size += chunk.len() as u64;
+                if let Some(max_artifact_size) = self.max_artifact_size {
+                    if size > max_artifact_size {
+                        return Err(Error::ArtifactTooLarge(max_artifact_size));
+                    }
+                }
+                file.write_all(&chunk).await?;
+            }
+            file.flush().await?;
+
+            fs::write(&metadata_temp_path, serde_json::to_vec(metadata)?).await?;
+
+            // Eviction deletes files while holding the index lock, so moving
+            // the artifact into place under the same lock keeps eviction from
+            // removing an artifact that was just rewritten.
+            let mut index = self.index.lock().await;
+            fs::rename(&metadata_temp_path, metadata_path(&dir, hash)).await?;
+            fs::rename(&temp_path, dir.join(hash)).await?;
+
+            let key = ArtifactKey {
+                team: team.map(|team| team.to_string()),
+                hash: hash.to_string(),
+            };
+            let entry = Entry {
+                size,
+                last_access: SystemTime::now(),
+            };
+            if let Some(previous) = index.entries.insert(key, entry) {
+                index.total_size -= previous.size;
+            }
+            index.total_size += size;
+            Ok::<_, Error>(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            let _ = fs::remove_file(&temp_path).await;
+            let _ = fs::remove_file(&metadata_temp_path).await;
+            return Err(e);
+        }
+        self.evict().await;
+
+        Ok(())
+    }
+
+    /// Returns the path to the artifact and its metadata, marking it as
+    /// recently used.
+    pub async fn get(
+        &self,
+        team: Option<&str>,
+        hash: &str,
+    ) -> Result<Option<(PathBuf, ArtifactMetadata)>, Error> {
+        let Some(metadata) = self.metadata(team, hash).await? else {
+            return Ok(None);
+        };
+        let key = ArtifactKey {
+            team: team.map(|team| team.to_string()),
+            hash: hash.to_string(),
+        };
+        if let Some(entry) = self.index.lock().await.entries.get_mut(&key) {
+            entry.last_access = SystemTime::now();
+        }
+
+        Ok(Some((self.team_dir(team).join(hash), metadata)))
+    }
+
+    /// Returns the size in bytes of an artifact if it's in the index.
+    pub async fn artifact_size(&self, team: Option<&str>, hash: &str) -> Option<u64> {
+        let key = ArtifactKey {
+            team: team.map(|team| team.to_string()),
+            hash: hash.to_string(),
+        };
+        self.index
+            .lock()
+            .await
+            .entries
+            .get(&key)
+            .map(|entry| entry.size)
+    }
+
+    /// Returns the metadata of an artifact if it exists.
+    pub async fn metadata(
+        &self,
+        team: Option<&str>,
+        hash: &str,
+    ) -> Result<Option<ArtifactMetadata>, Error> {
+        let dir = self.team_dir(team);
+        if !fs::try_exists(dir.join(hash)).await? {
+            return Ok(None);
+        }
+        match fs::read(metadata_path(&dir, hash)).await {
+            Ok(contents) => Ok(Some(serde_json::from_slice(&contents)?)),
+            // The sidecar was removed by hand, serve the artifact without a
+            // duration or tag.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Some(ArtifactMetadata::default())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn team_dir(&self, team: Option<&str>) -> PathBuf {
+        match team {
+            Some(team) => self.root.join(TEAMS_DIR).join(team),
+            None => self.root.join(UNSCOPED_DIR),
+        }
+    }
+
+    // Files are removed while holding the index lock so that a concurrent
+    // `put` can't move a new artifact into place between an entry being
+    // evicted and its files being deleted.
+    async fn evict(&self) {
+        let Some(max_size) = self.max_size else {
+            return;
+        };
+
+        let mut index = self.index.lock().await;
+        if index.total_size <= max_size {
+            return;
+        }
+        let mut by_access = index
+            .entries
+            .iter()
+            .map(|(key, entry)| (entry.last_access, key.clone()))
+            .collect::<Vec<_>>();
+        by_access.sort();
+
+        for (_, key) in by_access {
+            if index.total_size <= max_size {
+                break;
+            }
+            let Some(entry) = index.entries.remove(&key) else {
+                continue;
+            };
+            index.total_size -= entry.size;
+
+            let ArtifactKey { team, hash } = key;
+            debug!("evicting {hash} (team: {team:?})");
+            let dir = self.team_dir(team.as_deref());
+            for path in [dir.join(&hash), metadata_path(&dir, &hash)] {
+                if let Err(e) = fs::remove_file(&path).await {
+                    if e.kind() != io::ErrorKind::NotFound {
+                        warn!("failed to evict {}: {e}", path.display());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn metadata_path(dir: &Path, hash: &str) -> PathBuf {
+    dir.join(format!("{hash}.{METADATA_EXTENSION}"))
+}
+
+async fn scan_dir(dir: &Path, team: Option<String>, index: &mut Index) -> Result<(), Error> {
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let Some(name) = entry.file_name().to_str().map(|name| name.to_string()) else {
+            continue;
+        };
+        let file_type = entry.file_type().await?;
+        if name.starts_with('.') {
+            // Leftover from an interrupted write
+            if file_type.is_file() {
+                let _ = fs::remove_file(entry.path()).await;
+            }
+            continue;
+        }
+        if !file_type.is_file() || Path::new(&name).extension() == Some(METADATA_EXTENSION.as_ref())
+        {
+            continue;
+        }
+
+        let metadata = entry.metadata().await?;
+        let size = metadata.len();
+        index.entries.insert(
+            ArtifactKey {
+                team: team.clone(),
+                hash: name,
+            },
+            Entry {
+                size,
+                last_access: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            },
+        );
+        index.total_size += size;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::Infallible;
+
+    use futures::stream;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn body(contents: &'static [u8]) -> impl Stream<Item = Result<Bytes, Infallible>> + Unpin {
+        stream::iter([Ok(Bytes::from_static(contents))])
+    }
+
+    #[tokio::test]
+    async fn test_put_and_get() -> Result<(), Error> {
+        let dir = tempdir()?;
+        let storage = DiskStorage::new(dir.path(), None).await?;
+        let metadata = ArtifactMetadata {
+            duration: 42,
+            tag: Some("signature".to_string()),
+        };
+
+        storage
+            .put(Some("team_a"), "abc123", &metadata, body(b"contents"))
+            .await?;
+
+        let (path, stored) = storage.get(Some("team_a"), "abc123").await?.unwrap();
+        assert_eq!(std::fs::read(path)?, b"contents");
+        assert_eq!(stored, metadata);
+        assert!(storage.get(Some("team_b"), "abc123").await?.is_none());
+        assert!(storage.get(None, "abc123").await?.is_none());
+        assert_eq!(storage.size().await, 8);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used() -> Result<(), Error> {
+        let dir = tempdir()?;
+        let storage = DiskStorage::new(dir.path(), Some(10)).await?;
+        let metadata = ArtifactMetadata::default();
+
+        storage.put(None, "first", &metadata, body(b"1234")).await?;
+        storage
+            .put(None, "second", &metadata, body(b"1234"))
+            .await?;
+        // Reading `first` makes `second` the least recently used
+        storage.get(None, "first").await?;
+        storage.put(None, "third", &metadata, body(b"1234")).await?;
+
+        assert!(storage.metadata(None, "first").await?.is_some());
+        assert!(storage.metadata(None, "second").await?.is_none());
+        assert!(storage.metadata(None, "third").await?.is_some());
+        assert_eq!(storage.size().await, 8);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rewritten_artifact_survives_eviction() -> Result<(), Error> {
+        let dir = tempdir()?;
+        let storage = DiskStorage::new(dir.path(), Some(12)).await?;
+        let metadata = ArtifactMetadata::default();
+
+        storage.put(None, "first", &metadata, body(b"1234")).await?;
+        storage
+            .put(None, "second", &metadata, body(b"1234"))
+            .await?;
+        // Rewriting `first` makes it the most recently used
+        storage
+            .put(None, "first", &metadata, body(b"12345678"))
+            .await?;
+        storage.put(None, "third", &metadata, body(b"1234")).await?;
+
+        let (path, _) = storage.get(None, "first").await?.unwrap();
+        assert_eq!(std::fs::read(path)?, b"12345678");
+        assert!(storage.metadata(None, "second").await?.is_none());
+        assert_eq!(storage.size().await, 12);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rejects_oversized_artifact() -> Result<(), Error> {
+        let dir = tempdir()?;
+        let storage = DiskStorage::new(dir.path(), None)
+            .await?
+            .with_max_artifact_size(Some(4));
+        let metadata = ArtifactMetadata::default();
+
+        storage.put(None, "small", &metadata, body(b"1234")).await?;
+        let result = storage.put(None, "large", &metadata, body(b"12345")).await;
+        assert!(matches!(result, Err(Error::ArtifactTooLarge(4))));
+
+        // Nothing is left behind from the rejected upload
+        assert!(storage.metadata(None, "large").await?.is_none());
+        assert_eq!(storage.size().await, 4);
+        assert_eq!(
+            std::fs::read_dir(dir.path().join(UNSCOPED_DIR))?.count(),
+            2,
+            "only the small artifact and its metadata should remain"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_loads_existing_artifacts() -> Result<(), Error> {
+        let dir = tempdir()?;
+        {
+            let storage = DiskStorage::new(dir.path(), None).await?;
+            storage
+                .put(
+                    Some("team_a"),
+                    "abc123",
+                    &ArtifactMetadata::default(),
+                    body(b"contents"),
+                )
+                .await?;
+        }
+
+        let storage = DiskStorage::new(dir.path(), None).await?;
+        assert_eq!(storage.size().await, 8);
+        assert!(storage.get(Some("team_a"), "abc123").await?.is_some());
+
+        Ok(())
+    }
+}
@@ -1,13 +1,16 @@
-use std::sync::{atomic::AtomicU8, Arc};
+use std::{
+    collections::HashMap,
+    sync::{atomic::AtomicU8, Arc},
+};
 
 use futures::{stream::FuturesUnordered, StreamExt};
 use tokio::sync::{mpsc, Semaphore};
-use tracing::{warn, Instrument, Level};
+use tracing::{debug, warn, Instrument, Level};
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
 use turborepo_analytics::AnalyticsSender;
 use turborepo_api_client::{APIAuth, APIClient};
 
-use crate::{multiplexer::CacheMultiplexer, CacheError, CacheHitMetadata, CacheOpts};
+use crate::{multiplexer::CacheMultiplexer, CacheConfig, CacheError, CacheHitMetadata, CacheOpts};
 
 const WARNING_CUTOFF: u8 = 4;
 
@@ -15,6 +18,9 @@ const WARNING_CUTOFF: u8 = 4;
 pub struct AsyncCache {
     real_cache: Arc<CacheMultiplexer>,
     writer_sender: mpsc::Sender<WorkerRequest>,
+    prefetch_semaphore: Arc<Semaphore>,
+    // Which of the caches this handle reads from and writes to
+    config: CacheConfig,
 }
 
 enum WorkerRequest {
@@ -23,6 +29,7 @@ enum WorkerRequest {
         key: String,
         duration: u64,
         files: Vec<AnchoredSystemPathBuf>,
+        config: CacheConfig,
     },
     Flush(tokio::sync::oneshot::Sender<()>),
     Shutdown(tokio::sync::oneshot::Sender<()>),
@@ -62,6 +69,7 @@ impl AsyncCache {
                         key,
                         duration,
                         files,
+                        config,
                     } => {
                         let permit = semaphore.clone().acquire_owned().await.unwrap();
                         let real_cache = real_cache.clone();
@@ -69,8 +77,9 @@ impl AsyncCache {
                         let worker_span = tracing::span!(Level::TRACE, "cache worker: cache PUT");
                         workers.push(tokio::spawn(
                             async move {
-                                if let Err(err) =
-                                    real_cache.put(&anchor, &key, &files, duration).await
+                                if let Err(err) = real_cache
+                                    .put(&anchor, &key, &files, duration, config)
+                                    .await
                                 {
                                     let num_warnings =
                                         warnings.load(std::sync::atomic::Ordering::Acquire);
@@ -116,9 +125,20 @@ impl AsyncCache {
         Ok(AsyncCache {
             real_cache,
             writer_sender,
+            prefetch_semaphore: Arc::new(Semaphore::new(max_workers)),
+            config: CacheConfig::default(),
         })
     }
 
+    /// A handle to the same cache that only uses the caches `config` allows.
+    /// Writes still go through the shared worker pool.
+    pub fn with_config(&self, config: CacheConfig) -> Self {
+        Self {
+            config,
+            ..self.clone()
+        }
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn put(
         &self,
@@ -134,6 +154,7 @@ impl AsyncCache {
                 key,
                 duration,
                 files,
+                config: self.config,
             })
             .await
             .is_err()
@@ -146,7 +167,40 @@ impl AsyncCache {
 
     #[tracing::instrument(skip_all)]
     pub async fn exists(&self, key: &str) -> Result<Option<CacheHitMetadata>, CacheError> {
-        self.real_cache.exists(key).await
+        self.real_cache.exists(key, self.config).await
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn exists_many(
+        &self,
+        keys: &[&str],
+    ) -> Result<HashMap<String, Option<CacheHitMetadata>>, CacheError> {
+        self.real_cache.exists_many(keys, self.config).await
+    }
+
+    /// Starts downloading the given remote artifacts in the background so
+    /// they're available locally by the time they're fetched.
+    #[tracing::instrument(skip_all)]
+    pub fn prefetch(&self, keys: Vec<String>) {
+        for key in keys {
+            let real_cache = self.real_cache.clone();
+            let config = self.config;
+            let semaphore = self.prefetch_semaphore.clone();
+            let worker_span = tracing::span!(Level::TRACE, "cache worker: cache prefetch");
+            tokio::spawn(
+                async move {
+                    let Ok(_permit) = semaphore.acquire_owned().await else {
+                        return;
+                    };
+                    // A failed prefetch isn't fatal, the artifact will be
+                    // fetched again when it's needed
+                    if let Err(err) = real_cache.prefetch(&key, config).await {
+                        debug!("failed to prefetch {key}: {err}");
+                    }
+                }
+                .instrument(worker_span),
+            );
+        }
     }
 
     #[tracing::instrument(skip_all)]
@@ -155,7 +209,12 @@ impl AsyncCache {
         anchor: &AbsoluteSystemPath,
         key: &str,
     ) -> Result<Option<(CacheHitMetadata, Vec<AnchoredSystemPathBuf>)>, CacheError> {
-        self.real_cache.fetch(anchor, key).await
+        self.real_cache.fetch(anchor, key, self.config).await
+    }
+
+    /// Why the remote cache was disabled partway through the run, if it was.
+    pub fn remote_cache_disabled_reason(&self) -> Option<String> {
+        self.real_cache.remote_cache_disabled_reason()
     }
 
     // Used for testing to ensure that the workers resolve
@@ -192,12 +251,13 @@ mod tests {
     use futures::future::try_join_all;
     use tempfile::tempdir;
     use turbopath::AbsoluteSystemPathBuf;
-    use turborepo_api_client::{APIAuth, APIClient};
+    use turborepo_api_client::{APIAuth, APIClient, RetryOpts};
     use turborepo_vercel_api_mock::start_test_server;
 
     use crate::{
         test_cases::{get_test_cases, TestCase},
-        AsyncCache, CacheHitMetadata, CacheOpts, CacheSource, RemoteCacheOpts,
+        AsyncCache, CacheActions, CacheConfig, CacheHitMetadata, CacheOpts, CacheSource,
+        RemoteCacheOpts,
     };
 
     #[tokio::test]
@@ -208,7 +268,8 @@ mod tests {
         try_join_all(get_test_cases().into_iter().map(|test_case| async move {
             round_trip_test_with_both_caches(&test_case, port).await?;
             round_trip_test_without_remote_cache(&test_case).await?;
-            round_trip_test_without_fs(&test_case, port).await
+            round_trip_test_without_fs(&test_case, port).await?;
+            round_trip_test_with_remote_read_only_config(&test_case, port).await
         }))
         .await?;
 
@@ -216,6 +277,49 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_remote_cache_circuit_breaker() -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+
+        let opts = CacheOpts {
+            skip_filesystem: true,
+            workers: 10,
+            remote_cache_max_failures: Some(2),
+            ..CacheOpts::default()
+        };
+
+        // Nothing is listening on this port, so every request fails
+        let port = port_scanner::request_open_port().unwrap();
+        let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?
+            .with_retry_opts(RetryOpts {
+                max_retries: 0,
+                ..RetryOpts::default()
+            });
+        let api_auth = Some(APIAuth {
+            team_id: Some("my-team-id".to_string()),
+            token: "my-token".to_string(),
+            team_slug: None,
+        });
+        let async_cache = AsyncCache::new(&opts, &repo_root_path, api_client, api_auth, None)?;
+
+        assert_matches!(async_cache.exists("some-hash").await, Ok(None));
+        assert!(async_cache.remote_cache_disabled_reason().is_none());
+
+        assert_matches!(async_cache.exists("some-hash").await, Ok(None));
+        let reason = async_cache
+            .remote_cache_disabled_reason()
+            .expect("remote cache should be disabled");
+        assert!(
+            reason.starts_with("2 consecutive requests failed"),
+            "unexpected reason: {reason}"
+        );
+
+        async_cache.shutdown().await.unwrap();
+
+        Ok(())
+    }
+
     async fn round_trip_test_without_fs(test_case: &TestCase, port: u16) -> Result<()> {
         let repo_root = tempdir()?;
         let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
@@ -226,12 +330,14 @@ mod tests {
         let opts = CacheOpts {
             override_dir: None,
             remote_cache_read_only: false,
+            remote_cache_max_failures: None,
             skip_remote: false,
             skip_filesystem: true,
             workers: 10,
             remote_cache_opts: Some(RemoteCacheOpts {
                 unused_team_id: Some("my-team".to_string()),
                 signature: false,
+                encryption: false,
             }),
         };
 
@@ -306,12 +412,14 @@ mod tests {
         let opts = CacheOpts {
             override_dir: None,
             remote_cache_read_only: false,
+            remote_cache_max_failures: None,
             skip_remote: true,
             skip_filesystem: false,
             workers: 10,
             remote_cache_opts: Some(RemoteCacheOpts {
                 unused_team_id: Some("my-team".to_string()),
                 signature: false,
+                encryption: false,
             }),
         };
 
@@ -396,12 +504,14 @@ mod tests {
         let opts = CacheOpts {
             override_dir: None,
             remote_cache_read_only: false,
+            remote_cache_max_failures: None,
             skip_remote: false,
             skip_filesystem: false,
             workers: 10,
             remote_cache_opts: Some(RemoteCacheOpts {
                 unused_team_id: Some("my-team".to_string()),
                 signature: false,
+                encryption: false,
             }),
         };
 
@@ -471,6 +581,23 @@ mod tests {
             })
         );
 
+        // Confirm that a batched query also finds it in the remote cache
+        let statuses = async_cache.exists_many(&[&hash]).await?;
+        assert_eq!(statuses[&hash], response);
+
+        // Prefetching downloads the artifact into the fs cache, but it's still
+        // reported as a remote hit when fetched
+        async_cache
+            .real_cache
+            .prefetch(&hash, async_cache.config)
+            .await?;
+        assert!(fs_cache_path.exists());
+        let (cache_hit, _) = async_cache
+            .fetch(&repo_root_path, &hash)
+            .await?
+            .expect("prefetched artifact should be fetched");
+        assert_eq!(cache_hit.source, CacheSource::Remote);
+
         async_cache.shutdown().await.unwrap();
         assert!(
             async_cache.shutdown().await.is_err(),
@@ -479,4 +606,98 @@ mod tests {
 
         Ok(())
     }
+
+    async fn round_trip_test_with_remote_read_only_config(
+        test_case: &TestCase,
+        port: u16,
+    ) -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+        test_case.initialize(&repo_root_path)?;
+
+        let hash = format!("{}-remote-read-only-config", test_case.hash);
+
+        let opts = CacheOpts {
+            workers: 10,
+            remote_cache_opts: Some(RemoteCacheOpts {
+                unused_team_id: Some("my-team".to_string()),
+                signature: false,
+                encryption: false,
+            }),
+            ..CacheOpts::default()
+        };
+
+        let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
+        let api_auth = Some(APIAuth {
+            team_id: Some("my-team-id".to_string()),
+            token: "my-token".to_string(),
+            team_slug: None,
+        });
+        let async_cache = AsyncCache::new(&opts, &repo_root_path, api_client, api_auth, None)?;
+        let workspace_cache = async_cache.with_config(CacheConfig {
+            local: CacheActions::default(),
+            remote: CacheActions {
+                read: true,
+                write: false,
+            },
+        });
+
+        workspace_cache
+            .put(
+                repo_root_path.clone(),
+                hash.clone(),
+                test_case
+                    .files
+                    .iter()
+                    .map(|f| f.path().to_owned())
+                    .collect(),
+                test_case.duration,
+            )
+            .await
+            .unwrap();
+        workspace_cache.wait().await.unwrap();
+
+        let fs_cache_path = repo_root_path.join_components(&[
+            "node_modules",
+            ".cache",
+            "turbo",
+            &format!("{}.tar.zst", hash),
+        ]);
+
+        // The artifact is only saved locally
+        assert!(fs_cache_path.exists());
+        fs_cache_path.remove_file()?;
+        assert_matches!(async_cache.exists(&hash).await, Ok(None));
+
+        // A handle that can't read the fs cache doesn't see local artifacts
+        async_cache
+            .put(
+                repo_root_path.clone(),
+                hash.clone(),
+                test_case
+                    .files
+                    .iter()
+                    .map(|f| f.path().to_owned())
+                    .collect(),
+                test_case.duration,
+            )
+            .await
+            .unwrap();
+        async_cache.wait().await.unwrap();
+        let remote_only_cache = async_cache.with_config(CacheConfig {
+            local: CacheActions::DISABLED,
+            remote: CacheActions::default(),
+        });
+        assert_matches!(
+            remote_only_cache.exists(&hash).await,
+            Ok(Some(CacheHitMetadata {
+                source: CacheSource::Remote,
+                ..
+            }))
+        );
+
+        async_cache.shutdown().await.unwrap();
+
+        Ok(())
+    }
 }
@@ -0,0 +1,354 @@
+use std::env;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use thiserror::Error;
+
+const ENCRYPTION_KEY_ENV_VAR: &str = "TURBO_REMOTE_CACHE_ENCRYPTION_KEY";
+// Key ID used when a key is given without one
+const DEFAULT_KEY_ID: &str = "default";
+
+// Encrypted artifacts are laid out as:
+// | magic | version | key id length | key id | nonce | ciphertext + tag |
+const MAGIC: &[u8] = b"TURBOENC";
+const VERSION: u8 = 1;
+
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error(
+        "encryption key not found. You must specify a key in the \
+         TURBO_REMOTE_CACHE_ENCRYPTION_KEY environment variable"
+    )]
+    NoEncryptionKey,
+    #[error("invalid encryption key `{0}`: keys must be 32 bytes encoded as base64")]
+    InvalidKey(String),
+    #[error("invalid encryption key id `{0}`: ids must be 1-255 bytes without `:` or `,`")]
+    InvalidKeyId(String),
+    #[error("encryption key id `{0}` is used more than once")]
+    DuplicateKeyId(String),
+    #[error("failed to encrypt artifact")]
+    EncryptionFailed,
+    #[error("artifact is not encrypted, but remote cache encryption is enabled")]
+    NotEncrypted,
+    #[error("artifact was encrypted with unsupported version {0}")]
+    UnsupportedVersion(u8),
+    #[error("encrypted artifact is malformed")]
+    Malformed,
+    #[error(
+        "artifact was encrypted with key `{0}`, which is not in \
+         TURBO_REMOTE_CACHE_ENCRYPTION_KEY. Keep retired keys in the list until their artifacts \
+         have expired"
+    )]
+    UnknownKeyId(String),
+    #[error(
+        "failed to decrypt artifact with key `{0}`. Either the key is wrong or the artifact has \
+         been tampered with"
+    )]
+    DecryptionFailed(String),
+}
+
+#[derive(Debug)]
+struct EncryptionKey {
+    id: String,
+    key: LessSafeKey,
+}
+
+/// Encrypts artifacts with AES-256-GCM before they're uploaded to the remote
+/// cache and decrypts them when they're downloaded.
+///
+/// Keys are read from `TURBO_REMOTE_CACHE_ENCRYPTION_KEY` as a comma
+/// separated list of `<key id>:<base64 key>` entries. The first key encrypts
+/// new artifacts, the rest are only used to decrypt artifacts written before
+/// a key rotation. The key id is stored alongside each artifact so the right
+/// key can be picked when it's downloaded.
+///
+/// Each artifact is bound to its hash and to the team it was uploaded for,
+/// which is the team id or, when only a slug is configured, the team slug.
+#[derive(Debug)]
+pub struct ArtifactEncryptor {
+    team: Vec<u8>,
+    // Never empty, the first key encrypts new artifacts
+    keys: Vec<EncryptionKey>,
+}
+
+impl ArtifactEncryptor {
+    /// Parses the keys up front so that a malformed key fails the run before
+    /// any artifact is written. `keys_override` is used instead of the
+    /// environment variable in tests to avoid env var race conditions.
+    pub fn new(team: Vec<u8>, keys_override: Option<String>) -> Result<Self, EncryptionError> {
+        let keys = match keys_override {
+            Some(keys) => keys,
+            None => {
+                env::var(ENCRYPTION_KEY_ENV_VAR).map_err(|_| EncryptionError::NoEncryptionKey)?
+            }
+        };
+
+        Ok(Self {
+            team,
+            keys: Self::parse_keys(&keys)?,
+        })
+    }
+
+    fn parse_keys(keys: &str) -> Result<Vec<EncryptionKey>, EncryptionError> {
+        let mut parsed: Vec<EncryptionKey> = Vec::new();
+        for entry in keys
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (id, encoded_key) = entry.split_once(':').unwrap_or((DEFAULT_KEY_ID, entry));
+            if id.is_empty() || id.len() > u8::MAX as usize {
+                return Err(EncryptionError::InvalidKeyId(id.to_string()));
+            }
+            if parsed.iter().any(|key| key.id == id) {
+                return Err(EncryptionError::DuplicateKeyId(id.to_string()));
+            }
+
+            let key = BASE64_STANDARD
+                .decode(encoded_key)
+                .ok()
+                .and_then(|bytes| UnboundKey::new(&AES_256_GCM, &bytes).ok())
+                .ok_or_else(|| EncryptionError::InvalidKey(id.to_string()))?;
+            parsed.push(EncryptionKey {
+                id: id.to_string(),
+                key: LessSafeKey::new(key),
+            });
+        }
+
+        if parsed.is_empty() {
+            return Err(EncryptionError::NoEncryptionKey);
+        }
+
+        Ok(parsed)
+    }
+
+    // Binds the ciphertext to the artifact it was written for, so an artifact
+    // can't be swapped in for another hash or team. Each field is length
+    // prefixed so that no two (hash, team) pairs produce the same bytes.
+    fn construct_metadata(&self, hash: &[u8]) -> Vec<u8> {
+        let mut metadata = Vec::with_capacity(8 + hash.len() + self.team.len());
+        for field in [hash, &self.team] {
+            metadata.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            metadata.extend_from_slice(field);
+        }
+
+        metadata
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub fn encrypt(&self, hash: &[u8], artifact_body: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        // CORRECTNESS: `new` returns an error if there are no keys
+        let EncryptionKey { id, key } = &self.keys[0];
+
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+        let mut in_out = artifact_body.to_vec();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(self.construct_metadata(hash)),
+            &mut in_out,
+        )
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+        let mut encrypted =
+            Vec::with_capacity(MAGIC.len() + 2 + id.len() + NONCE_LEN + in_out.len());
+        encrypted.extend_from_slice(MAGIC);
+        encrypted.push(VERSION);
+        // CORRECTNESS: key ids are validated to fit in a byte when parsed
+        encrypted.push(id.len() as u8);
+        encrypted.extend_from_slice(id.as_bytes());
+        encrypted.extend_from_slice(&nonce);
+        encrypted.extend_from_slice(&in_out);
+
+        Ok(encrypted)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub fn decrypt(&self, hash: &[u8], encrypted: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let rest = encrypted
+            .strip_prefix(MAGIC)
+            .ok_or(EncryptionError::NotEncrypted)?;
+        let (&version, rest) = rest.split_first().ok_or(EncryptionError::Malformed)?;
+        if version != VERSION {
+            return Err(EncryptionError::UnsupportedVersion(version));
+        }
+        let (&id_len, rest) = rest.split_first().ok_or(EncryptionError::Malformed)?;
+        if rest.len() < id_len as usize + NONCE_LEN {
+            return Err(EncryptionError::Malformed);
+        }
+        let (id, rest) = rest.split_at(id_len as usize);
+        let id = std::str::from_utf8(id).map_err(|_| EncryptionError::Malformed)?;
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let EncryptionKey { key, .. } = self
+            .keys
+            .iter()
+            .find(|key| key.id == id)
+            .ok_or_else(|| EncryptionError::UnknownKeyId(id.to_string()))?;
+
+        let nonce =
+            Nonce::try_assume_unique_for_key(nonce).map_err(|_| EncryptionError::Malformed)?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext_len = key
+            .open_in_place(nonce, Aad::from(self.construct_metadata(hash)), &mut in_out)
+            .map_err(|_| EncryptionError::DecryptionFailed(id.to_string()))?
+            .len();
+        in_out.truncate(plaintext_len);
+
+        Ok(in_out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    // 32 byte keys, base64 encoded
+    const KEY_A: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+    const KEY_B: &str = "ICEiIyQlJicoKSorLC0uLzAxMjM0NTY3ODk6Ozw9Pj8=";
+
+    fn with_keys(keys: &str) -> ArtifactEncryptor {
+        ArtifactEncryptor::new(b"team_id".to_vec(), Some(keys.to_string())).unwrap()
+    }
+
+    fn parse_keys(keys: &str) -> Result<ArtifactEncryptor, EncryptionError> {
+        ArtifactEncryptor::new(b"team_id".to_vec(), Some(keys.to_string()))
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let encryptor = with_keys(&format!("2024-01:{KEY_A}"));
+        let encrypted = encryptor.encrypt(b"hash", b"artifact body")?;
+
+        assert!(!encrypted
+            .windows(b"artifact body".len())
+            .any(|window| window == b"artifact body"));
+        assert_eq!(encryptor.decrypt(b"hash", &encrypted)?, b"artifact body");
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_without_id() -> Result<()> {
+        let encryptor = with_keys(KEY_A);
+        let encrypted = encryptor.encrypt(b"hash", b"artifact body")?;
+
+        assert_eq!(encryptor.decrypt(b"hash", &encrypted)?, b"artifact body");
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_rotation() -> Result<()> {
+        let old = with_keys(&format!("old:{KEY_A}"));
+        let encrypted_with_old = old.encrypt(b"hash", b"artifact body")?;
+
+        let rotated = with_keys(&format!("new:{KEY_B}, old:{KEY_A}"));
+        assert_eq!(
+            rotated.decrypt(b"hash", &encrypted_with_old)?,
+            b"artifact body"
+        );
+
+        // New artifacts use the first key, which the old configuration can't read
+        let encrypted_with_new = rotated.encrypt(b"hash", b"artifact body")?;
+        assert_matches!(
+            old.decrypt(b"hash", &encrypted_with_new),
+            Err(EncryptionError::UnknownKeyId(id)) if id == "new"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_decryption_failures() -> Result<()> {
+        let encryptor = with_keys(&format!("a:{KEY_A}"));
+        let encrypted = encryptor.encrypt(b"hash", b"artifact body")?;
+
+        // Same key id, different key
+        assert_matches!(
+            with_keys(&format!("a:{KEY_B}")).decrypt(b"hash", &encrypted),
+            Err(EncryptionError::DecryptionFailed(_))
+        );
+        // Artifact moved to another hash
+        assert_matches!(
+            encryptor.decrypt(b"other-hash", &encrypted),
+            Err(EncryptionError::DecryptionFailed(_))
+        );
+        // Artifact body modified
+        let mut tampered = encrypted.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_matches!(
+            encryptor.decrypt(b"hash", &tampered),
+            Err(EncryptionError::DecryptionFailed(_))
+        );
+        assert_matches!(
+            encryptor.decrypt(b"hash", &encrypted[..MAGIC.len() + 4]),
+            Err(EncryptionError::Malformed)
+        );
+        assert_matches!(
+            encryptor.decrypt(b"hash", b"artifact body"),
+            Err(EncryptionError::NotEncrypted)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        assert_matches!(parse_keys(""), Err(EncryptionError::NoEncryptionKey));
+        assert_matches!(
+            parse_keys("a:not base64"),
+            Err(EncryptionError::InvalidKey(id)) if id == "a"
+        );
+        // Valid base64, but only 16 bytes
+        assert_matches!(
+            parse_keys("a:AAECAwQFBgcICQoLDA0ODw=="),
+            Err(EncryptionError::InvalidKey(_))
+        );
+        assert_matches!(
+            parse_keys(&format!("a:{KEY_A},a:{KEY_B}")),
+            Err(EncryptionError::DuplicateKeyId(id)) if id == "a"
+        );
+        assert_matches!(
+            parse_keys(&format!(":{KEY_A}")),
+            Err(EncryptionError::InvalidKeyId(_))
+        );
+    }
+
+    #[test]
+    fn test_bound_to_team() -> Result<()> {
+        let encryptor = with_keys(&format!("a:{KEY_A}"));
+        let encrypted = encryptor.encrypt(b"hash", b"artifact body")?;
+
+        let other_team = ArtifactEncryptor::new(b"other".to_vec(), Some(format!("a:{KEY_A}")))?;
+        assert_matches!(
+            other_team.decrypt(b"hash", &encrypted),
+            Err(EncryptionError::DecryptionFailed(_))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_is_unambiguous() -> Result<()> {
+        // Without delimiting the fields, both of these would be `hashteam_id`
+        let encryptor = with_keys(&format!("a:{KEY_A}"));
+        let shifted = ArtifactEncryptor::new(b"m_id".to_vec(), Some(format!("a:{KEY_A}")))?;
+        assert_ne!(
+            encryptor.construct_metadata(b"hash"),
+            shifted.construct_metadata(b"hashtea")
+        );
+
+        let encrypted = encryptor.encrypt(b"hash", b"artifact body")?;
+        assert_matches!(
+            shifted.decrypt(b"hashtea", &encrypted),
+            Err(EncryptionError::DecryptionFailed(_))
+        );
+        Ok(())
+    }
+}
@@ -30,7 +30,7 @@ impl CacheMetadata {
 }
 
 impl FSCache {
-    fn resolve_cache_dir(
+    pub fn resolve_cache_dir(
         repo_root: &AbsoluteSystemPath,
         override_dir: Option<&Utf8Path>,
     ) -> AbsoluteSystemPathBuf {
@@ -159,6 +159,35 @@ impl FSCache {
             cache_item.add_file(anchor, file)?;
         }
 
+        self.write_metadata(hash, duration)
+    }
+
+    /// Stores an already compressed archive, such as one downloaded from the
+    /// remote cache, without restoring it.
+    #[tracing::instrument(skip_all)]
+    pub(crate) fn put_archive(
+        &self,
+        hash: &str,
+        archive: &[u8],
+        duration: u64,
+    ) -> Result<(), CacheError> {
+        let cache_path = self
+            .cache_directory
+            .join_component(&format!("{}.tar.zst", hash));
+        // Write to a temporary file first so a concurrent fetch never reads a
+        // partial archive
+        let temp_path = self
+            .cache_directory
+            .join_component(&format!("{}.tar.zst.tmp", hash));
+
+        temp_path.create_with_contents(archive)?;
+        self.write_metadata(hash, duration)?;
+        temp_path.rename(&cache_path)?;
+
+        Ok(())
+    }
+
+    fn write_metadata(&self, hash: &str, duration: u64) -> Result<(), CacheError> {
         let metadata_path = self
             .cache_directory
             .join_component(&format!("{}-meta.json", hash));
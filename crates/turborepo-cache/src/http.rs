@@ -1,21 +1,28 @@
-use std::{backtrace::Backtrace, io::Write};
+use std::{backtrace::Backtrace, collections::HashMap, io::Write};
 
+use bytes::Bytes;
+use reqwest::{header::CONTENT_RANGE, StatusCode};
 use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
 use turborepo_analytics::AnalyticsSender;
 use turborepo_api_client::{
     analytics, analytics::AnalyticsEvent, APIAuth, APIClient, Client, Response,
 };
+use turborepo_vercel_api::ArtifactQueryResult;
 
 use crate::{
     cache_archive::{CacheReader, CacheWriter},
+    encryption::ArtifactEncryptor,
     signature_authentication::ArtifactSignatureAuthenticator,
     CacheError, CacheHitMetadata, CacheOpts, CacheSource,
 };
 
+const MAX_DOWNLOAD_RESUMES: u32 = 3;
+
 pub struct HTTPCache {
     client: APIClient,
     signer_verifier: Option<ArtifactSignatureAuthenticator>,
+    encryptor: Option<ArtifactEncryptor>,
     repo_root: AbsoluteSystemPathBuf,
     api_auth: APIAuth,
     analytics_recorder: Option<AnalyticsSender>,
@@ -29,7 +36,7 @@ impl HTTPCache {
         repo_root: AbsoluteSystemPathBuf,
         api_auth: APIAuth,
         analytics_recorder: Option<AnalyticsSender>,
-    ) -> HTTPCache {
+    ) -> Result<HTTPCache, CacheError> {
         let signer_verifier = if opts
             .remote_cache_opts
             .as_ref()
@@ -48,13 +55,30 @@ impl HTTPCache {
             None
         };
 
-        HTTPCache {
+        let encryptor = opts
+            .remote_cache_opts
+            .as_ref()
+            .map_or(false, |remote_cache_opts| remote_cache_opts.encryption)
+            .then(|| {
+                // Artifacts need to be bound to a team even when only the
+                // slug is known
+                let team = api_auth
+                    .team_id
+                    .as_deref()
+                    .or(api_auth.team_slug.as_deref())
+                    .unwrap_or_default();
+                ArtifactEncryptor::new(team.as_bytes().to_vec(), None)
+            })
+            .transpose()?;
+
+        Ok(HTTPCache {
             client,
             signer_verifier,
+            encryptor,
             repo_root,
             api_auth,
             analytics_recorder,
-        }
+        })
     }
 
     #[tracing::instrument(skip_all)]
@@ -68,6 +92,12 @@ impl HTTPCache {
         let mut artifact_body = Vec::new();
         self.write(&mut artifact_body, anchor, files).await?;
 
+        // Encrypt before signing so the signature can be checked without the
+        // encryption key
+        if let Some(encryptor) = &self.encryptor {
+            artifact_body = encryptor.encrypt(hash.as_bytes(), &artifact_body)?;
+        }
+
         let tag = self
             .signer_verifier
             .as_ref()
@@ -156,11 +186,63 @@ impl HTTPCache {
         }
     }
 
+    /// Checks the existence of many artifacts with a single request.
+    /// Every hash in `hashes` is present in the returned map.
+    #[tracing::instrument(skip_all)]
+    pub async fn exists_many(
+        &self,
+        hashes: &[&str],
+    ) -> Result<HashMap<String, Option<CacheHitMetadata>>, CacheError> {
+        let mut response = self
+            .client
+            .query_artifacts(
+                hashes,
+                &self.api_auth.token,
+                self.api_auth.team_id.as_deref(),
+                self.api_auth.team_slug.as_deref(),
+            )
+            .await?;
+
+        Ok(hashes
+            .iter()
+            .map(|hash| {
+                let status = match response.remove(*hash).flatten() {
+                    Some(ArtifactQueryResult::Found(info)) => Some(CacheHitMetadata {
+                        source: CacheSource::Remote,
+                        time_saved: info.task_duration_ms,
+                    }),
+                    Some(ArtifactQueryResult::Error { error }) => {
+                        debug!("failed to query artifact {hash}: {}", error.message);
+                        None
+                    }
+                    None => None,
+                };
+                (hash.to_string(), status)
+            })
+            .collect())
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn fetch(
         &self,
         hash: &str,
     ) -> Result<Option<(CacheHitMetadata, Vec<AnchoredSystemPathBuf>)>, CacheError> {
+        let Some((cache_hit_metadata, body)) = self.fetch_archive(hash).await? else {
+            return Ok(None);
+        };
+
+        let files = Self::restore_tar(&self.repo_root, &body)?;
+
+        Ok(Some((cache_hit_metadata, files)))
+    }
+
+    /// Downloads and verifies an artifact without restoring it, returning
+    /// the compressed archive.
+    #[tracing::instrument(skip_all)]
+    pub async fn fetch_archive(
+        &self,
+        hash: &str,
+    ) -> Result<Option<(CacheHitMetadata, Bytes)>, CacheError> {
         let Some(response) = self
             .client
             .fetch_artifact(
@@ -188,12 +270,7 @@ impl HTTPCache {
                 .map_err(|_| CacheError::InvalidTag(Backtrace::capture()))?
                 .to_string();
 
-            let body = response.bytes().await.map_err(|e| {
-                CacheError::ApiClientError(
-                    Box::new(turborepo_api_client::Error::ReqwestError(e)),
-                    Backtrace::capture(),
-                )
-            })?;
+            let body = self.read_body(hash, response).await?;
             let is_valid = signer_verifier.validate(hash.as_bytes(), &body, &expected_tag)?;
 
             if !is_valid {
@@ -202,15 +279,13 @@ impl HTTPCache {
 
             body
         } else {
-            response.bytes().await.map_err(|e| {
-                CacheError::ApiClientError(
-                    Box::new(turborepo_api_client::Error::ReqwestError(e)),
-                    Backtrace::capture(),
-                )
-            })?
+            self.read_body(hash, response).await?
         };
 
-        let files = Self::restore_tar(&self.repo_root, &body)?;
+        let body = match &self.encryptor {
+            Some(encryptor) => encryptor.decrypt(hash.as_bytes(), &body)?.into(),
+            None => body,
+        };
 
         self.log_fetch(analytics::CacheEvent::Hit, hash, duration);
         Ok(Some((
@@ -218,10 +293,74 @@ impl HTTPCache {
                 source: CacheSource::Remote,
                 time_saved: duration,
             },
-            files,
+            body,
         )))
     }
 
+    /// Reads an artifact download to completion. If the connection drops
+    /// partway through, the download is resumed from where it stopped using
+    /// a range request, or restarted if the server doesn't support ranges.
+    #[tracing::instrument(skip_all)]
+    async fn read_body(&self, hash: &str, mut response: Response) -> Result<Bytes, CacheError> {
+        let mut body = Vec::new();
+        let mut resumes = 0;
+        loop {
+            let err = match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    body.extend_from_slice(&chunk);
+                    continue;
+                }
+                Ok(None) => return Ok(body.into()),
+                Err(err) => err,
+            };
+
+            if body.is_empty() || resumes >= MAX_DOWNLOAD_RESUMES {
+                return Err(CacheError::ApiClientError(
+                    Box::new(turborepo_api_client::Error::ReqwestError(err)),
+                    Backtrace::capture(),
+                ));
+            }
+            resumes += 1;
+            let offset = body.len();
+            debug!("download of {hash} interrupted after {offset} bytes, resuming: {err}");
+
+            let Some(resumed) = self
+                .client
+                .fetch_artifact_from(
+                    hash,
+                    offset as u64,
+                    &self.api_auth.token,
+                    self.api_auth.team_id.as_deref(),
+                    self.api_auth.team_slug.as_deref(),
+                )
+                .await?
+            else {
+                return Err(CacheError::ApiClientError(
+                    Box::new(turborepo_api_client::Error::ReqwestError(err)),
+                    Backtrace::capture(),
+                ));
+            };
+
+            if resumed.status() != StatusCode::PARTIAL_CONTENT {
+                // The server doesn't support ranges and sent the whole artifact again
+                body.clear();
+            } else if !resumed
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|range| range.to_str().ok())
+                .map_or(false, |range| {
+                    range.starts_with(&format!("bytes {offset}-"))
+                })
+            {
+                return Err(CacheError::ApiClientError(
+                    Box::new(turborepo_api_client::Error::ReqwestError(err)),
+                    Backtrace::capture(),
+                ));
+            }
+            response = resumed;
+        }
+    }
+
     #[tracing::instrument(skip_all)]
     pub(crate) fn restore_tar(
         root: &AbsoluteSystemPath,
@@ -243,9 +382,10 @@ mod test {
     use turborepo_vercel_api_mock::start_test_server;
 
     use crate::{
+        encryption::ArtifactEncryptor,
         http::{APIAuth, HTTPCache},
         test_cases::{get_test_cases, validate_analytics, TestCase},
-        CacheOpts, CacheSource,
+        CacheError, CacheOpts, CacheSource,
     };
 
     #[tokio::test]
@@ -291,7 +431,7 @@ mod test {
             repo_root_path.to_owned(),
             api_auth,
             Some(analytics_recorder),
-        );
+        )?;
 
         // Should be a cache miss at first
         let miss = cache.fetch(hash).await?;
@@ -307,6 +447,11 @@ mod test {
         assert_eq!(cache_response.time_saved, duration);
         assert_eq!(cache_response.source, CacheSource::Remote);
 
+        let missing_hash = format!("{hash}-missing");
+        let statuses = cache.exists_many(&[hash, &missing_hash]).await?;
+        assert_eq!(statuses[hash], Some(cache_response));
+        assert_eq!(statuses[&missing_hash], None);
+
         let (cache_response, received_files) = cache.fetch(hash).await?.unwrap();
 
         assert_eq!(cache_response.time_saved, duration);
@@ -325,4 +470,64 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_encrypted_round_trip() -> Result<()> {
+        let port = port_scanner::request_open_port().unwrap();
+        let handle = tokio::spawn(start_test_server(port));
+        let test_cases = get_test_cases();
+        let test_case = &test_cases[0];
+
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+        test_case.initialize(&repo_root_path)?;
+
+        let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
+        let api_auth = APIAuth {
+            team_id: Some("my-team".to_string()),
+            token: "my-token".to_string(),
+            team_slug: None,
+        };
+        let cache_with_keys = |keys: &str| {
+            let mut cache = HTTPCache::new(
+                api_client.clone(),
+                &CacheOpts::default(),
+                repo_root_path.to_owned(),
+                api_auth.clone(),
+                None,
+            )
+            .unwrap();
+            cache.encryptor =
+                Some(ArtifactEncryptor::new(b"my-team".to_vec(), Some(keys.to_string())).unwrap());
+            cache
+        };
+
+        let cache = cache_with_keys("a:AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=");
+        let anchored_files: Vec<_> = test_case
+            .files
+            .iter()
+            .map(|f| f.path().to_owned())
+            .collect();
+        cache
+            .put(
+                &repo_root_path,
+                test_case.hash,
+                &anchored_files,
+                test_case.duration,
+            )
+            .await?;
+
+        let (_, received_files) = cache.fetch(test_case.hash).await?.unwrap();
+        assert_eq!(received_files, anchored_files);
+
+        // A cache with the wrong key reports an error instead of a miss
+        let wrong_key = cache_with_keys("a:ICEiIyQlJicoKSorLC0uLzAxMjM0NTY3ODk6Ozw9Pj8=");
+        assert!(matches!(
+            wrong_key.fetch(test_case.hash).await,
+            Err(CacheError::EncryptionError(..))
+        ));
+
+        handle.abort();
+        Ok(())
+    }
 }
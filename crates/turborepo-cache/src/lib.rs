@@ -7,6 +7,9 @@
 mod async_cache;
 /// The core cache creation and restoration logic.
 pub mod cache_archive;
+/// Remote cache encryption lets users provide their own keys to encrypt
+/// artifacts before they're uploaded.
+pub mod encryption;
 /// File system cache
 pub mod fs;
 /// Remote cache
@@ -27,7 +30,7 @@ use camino::Utf8PathBuf;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::signature_authentication::SignatureError;
+use crate::{encryption::EncryptionError, signature_authentication::SignatureError};
 
 #[derive(Debug, Error)]
 pub enum CacheError {
@@ -46,6 +49,8 @@ pub enum CacheError {
     ApiClientError(Box<turborepo_api_client::Error>, #[backtrace] Backtrace),
     #[error("signing artifact failed: {0}")]
     SignatureError(#[from] SignatureError, #[backtrace] Backtrace),
+    #[error("artifact encryption failed: {0}")]
+    EncryptionError(#[from] EncryptionError, #[backtrace] Backtrace),
     #[error("invalid duration")]
     InvalidDuration(#[backtrace] Backtrace),
     #[error("Invalid file path: {0}")]
@@ -102,23 +107,73 @@ pub struct CacheHitMetadata {
 pub struct CacheOpts {
     pub override_dir: Option<Utf8PathBuf>,
     pub remote_cache_read_only: bool,
+    /// Disable the remote cache for the rest of the run after this many
+    /// consecutive failed requests
+    pub remote_cache_max_failures: Option<u32>,
     pub skip_remote: bool,
     pub skip_filesystem: bool,
     pub workers: u32,
     pub remote_cache_opts: Option<RemoteCacheOpts>,
 }
 
+/// Whether a cache can be read from and written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CacheActions {
+    pub read: bool,
+    pub write: bool,
+}
+
+impl CacheActions {
+    pub const DISABLED: Self = Self {
+        read: false,
+        write: false,
+    };
+}
+
+impl Default for CacheActions {
+    fn default() -> Self {
+        Self {
+            read: true,
+            write: true,
+        }
+    }
+}
+
+/// The caches an individual task may use. This narrows what `CacheOpts`
+/// allows for the whole run, it never enables a cache that's been skipped.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CacheConfig {
+    pub local: CacheActions,
+    pub remote: CacheActions,
+}
+
+impl CacheConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn can_read(&self) -> bool {
+        self.local.read || self.remote.read
+    }
+
+    pub fn can_write(&self) -> bool {
+        self.local.write || self.remote.write
+    }
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct RemoteCacheOpts {
     unused_team_id: Option<String>,
     signature: bool,
+    encryption: bool,
 }
 
 impl RemoteCacheOpts {
-    pub fn new(unused_team_id: Option<String>, signature: bool) -> Self {
+    pub fn new(unused_team_id: Option<String>, signature: bool, encryption: bool) -> Self {
         Self {
             unused_team_id,
             signature,
+            encryption,
         }
     }
 }
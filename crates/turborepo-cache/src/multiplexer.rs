@@ -1,11 +1,19 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Mutex,
+    },
+};
 
 use tracing::{debug, warn};
 use turbopath::{AbsoluteSystemPath, AnchoredSystemPathBuf};
 use turborepo_analytics::AnalyticsSender;
 use turborepo_api_client::{APIAuth, APIClient};
 
-use crate::{fs::FSCache, http::HTTPCache, CacheError, CacheHitMetadata, CacheOpts};
+use crate::{
+    fs::FSCache, http::HTTPCache, CacheConfig, CacheError, CacheHitMetadata, CacheOpts, CacheSource,
+};
 
 pub struct CacheMultiplexer {
     // We use an `AtomicBool` instead of removing the cache because that would require
@@ -17,6 +25,14 @@ pub struct CacheMultiplexer {
     // being read-only
     should_print_skipping_remote_put: AtomicBool,
     remote_cache_read_only: bool,
+    // Artifacts downloaded from the remote cache into the fs cache ahead of
+    // time. We still report these as remote hits when they're fetched.
+    prefetched: Mutex<HashSet<String>>,
+    // Consecutive failed requests to the remote cache. Once this reaches
+    // `max_http_failures` the remote cache is disabled for the rest of the run.
+    http_failures: AtomicU32,
+    max_http_failures: Option<u32>,
+    http_disabled_reason: Mutex<Option<String>>,
     fs: Option<FSCache>,
     http: Option<HTTPCache>,
 }
@@ -61,12 +77,17 @@ impl CacheMultiplexer {
                     api_auth,
                     analytics_recorder.clone(),
                 )
-            });
+            })
+            .transpose()?;
 
         Ok(CacheMultiplexer {
             should_print_skipping_remote_put: AtomicBool::new(true),
             should_use_http_cache: AtomicBool::new(http_cache.is_some()),
             remote_cache_read_only: opts.remote_cache_read_only,
+            prefetched: Mutex::new(HashSet::new()),
+            http_failures: AtomicU32::new(0),
+            max_http_failures: opts.remote_cache_max_failures,
+            http_disabled_reason: Mutex::new(None),
             fs: fs_cache,
             http: http_cache,
         })
@@ -82,6 +103,64 @@ impl CacheMultiplexer {
         }
     }
 
+    fn readable_fs_cache(&self, config: CacheConfig) -> Option<&FSCache> {
+        self.fs.as_ref().filter(|_| config.local.read)
+    }
+
+    fn writable_fs_cache(&self, config: CacheConfig) -> Option<&FSCache> {
+        self.fs.as_ref().filter(|_| config.local.write)
+    }
+
+    fn readable_http_cache(&self, config: CacheConfig) -> Option<&HTTPCache> {
+        self.get_http_cache().filter(|_| config.remote.read)
+    }
+
+    /// Why the remote cache was disabled partway through the run, if it was.
+    pub fn remote_cache_disabled_reason(&self) -> Option<String> {
+        self.http_disabled_reason
+            .lock()
+            .expect("http disabled reason lock poisoned")
+            .clone()
+    }
+
+    fn disable_http_cache(&self, reason: String) {
+        // Only the first reason is kept, later requests were already in flight
+        if self.should_use_http_cache.swap(false, Ordering::Relaxed) {
+            warn!("disabling remote cache for the rest of the run: {reason}");
+            *self
+                .http_disabled_reason
+                .lock()
+                .expect("http disabled reason lock poisoned") = Some(reason);
+        }
+    }
+
+    // Keeps track of consecutive remote cache failures so we stop sending
+    // requests to a cache that's down instead of waiting on it for every task.
+    fn record_http_result<T>(&self, result: &Result<T, CacheError>) {
+        match result {
+            Ok(_) => self.http_failures.store(0, Ordering::Relaxed),
+            Err(CacheError::ApiClientError(err, ..))
+                if matches!(**err, turborepo_api_client::Error::CacheDisabled { .. }) =>
+            {
+                self.disable_http_cache(err.to_string())
+            }
+            // Only count errors from talking to the remote cache, not e.g. invalid
+            // artifacts
+            Err(err @ CacheError::ApiClientError(..)) => {
+                let failures = self.http_failures.fetch_add(1, Ordering::Relaxed) + 1;
+                if self
+                    .max_http_failures
+                    .map_or(false, |max_failures| failures >= max_failures)
+                {
+                    self.disable_http_cache(format!(
+                        "{failures} consecutive requests failed, last error: {err}"
+                    ));
+                }
+            }
+            Err(_) => {}
+        }
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn put(
         &self,
@@ -89,13 +168,16 @@ impl CacheMultiplexer {
         key: &str,
         files: &[AnchoredSystemPathBuf],
         duration: u64,
+        config: CacheConfig,
     ) -> Result<(), CacheError> {
-        self.fs
-            .as_ref()
+        self.writable_fs_cache(config)
             .map(|fs| fs.put(anchor, key, files, duration))
             .transpose()?;
 
         let http_result = match self.get_http_cache() {
+            // Only this task's workspace is kept out of the remote cache, so unlike
+            // a read-only remote cache there's nothing to warn about
+            Some(_) if !config.remote.write => None,
             Some(http) => {
                 if self.remote_cache_read_only {
                     if self
@@ -112,6 +194,7 @@ impl CacheMultiplexer {
                     None
                 } else {
                     let http_result = http.put(anchor, key, files, duration).await;
+                    self.record_http_result(&http_result);
 
                     Some(http_result)
                 }
@@ -120,14 +203,11 @@ impl CacheMultiplexer {
         };
 
         match http_result {
+            // The remote cache has been disabled, which isn't an error for this task
             Some(Err(CacheError::ApiClientError(
                 box turborepo_api_client::Error::CacheDisabled { .. },
                 ..,
-            ))) => {
-                warn!("failed to put to http cache: cache disabled");
-                self.should_use_http_cache.store(false, Ordering::Relaxed);
-                Ok(())
-            }
+            ))) => Ok(()),
             Some(Err(e)) => Err(e),
             None | Some(Ok(())) => Ok(()),
         }
@@ -138,26 +218,41 @@ impl CacheMultiplexer {
         &self,
         anchor: &AbsoluteSystemPath,
         key: &str,
+        config: CacheConfig,
     ) -> Result<Option<(CacheHitMetadata, Vec<AnchoredSystemPathBuf>)>, CacheError> {
-        if let Some(fs) = &self.fs {
-            if let response @ Ok(Some(_)) = fs.fetch(anchor, key) {
-                return response;
+        if let Some(fs) = self.readable_fs_cache(config) {
+            if let Ok(Some((mut cache_hit_metadata, files))) = fs.fetch(anchor, key) {
+                if self
+                    .prefetched
+                    .lock()
+                    .expect("prefetched lock poisoned")
+                    .remove(key)
+                {
+                    cache_hit_metadata.source = CacheSource::Remote;
+                }
+                return Ok(Some((cache_hit_metadata, files)));
             }
         }
 
-        if let Some(http) = self.get_http_cache() {
-            if let Ok(Some((CacheHitMetadata { source, time_saved }, files))) =
-                http.fetch(key).await
-            {
-                // Store this into fs cache. We can ignore errors here because we know
-                // we have previously successfully stored in HTTP cache, and so the overall
-                // result is a success at fetching. Storing in lower-priority caches is an
-                // optimization.
-                if let Some(fs) = &self.fs {
-                    let _ = fs.put(anchor, key, &files, time_saved);
-                }
+        if let Some(http) = self.readable_http_cache(config) {
+            let http_result = http.fetch(key).await;
+            self.record_http_result(&http_result);
+            match http_result {
+                Ok(Some((CacheHitMetadata { source, time_saved }, files))) => {
+                    // Store this into fs cache. We can ignore errors here because we know
+                    // we have previously successfully stored in HTTP cache, and so the overall
+                    // result is a success at fetching. Storing in lower-priority caches is an
+                    // optimization.
+                    if let Some(fs) = self.writable_fs_cache(config) {
+                        let _ = fs.put(anchor, key, &files, time_saved);
+                    }
 
-                return Ok(Some((CacheHitMetadata { source, time_saved }, files)));
+                    return Ok(Some((CacheHitMetadata { source, time_saved }, files)));
+                }
+                // An artifact we can't decrypt usually means a missing or rotated key.
+                // Report it instead of quietly treating every remote hit as a miss.
+                Err(err @ CacheError::EncryptionError(..)) => return Err(err),
+                Ok(None) | Err(_) => {}
             }
         }
 
@@ -165,8 +260,12 @@ impl CacheMultiplexer {
     }
 
     #[tracing::instrument(skip_all)]
-    pub async fn exists(&self, key: &str) -> Result<Option<CacheHitMetadata>, CacheError> {
-        if let Some(fs) = &self.fs {
+    pub async fn exists(
+        &self,
+        key: &str,
+        config: CacheConfig,
+    ) -> Result<Option<CacheHitMetadata>, CacheError> {
+        if let Some(fs) = self.readable_fs_cache(config) {
             match fs.exists(key) {
                 cache_hit @ Ok(Some(_)) => {
                     return cache_hit;
@@ -176,8 +275,10 @@ impl CacheMultiplexer {
             }
         }
 
-        if let Some(http) = self.get_http_cache() {
-            match http.exists(key).await {
+        if let Some(http) = self.readable_http_cache(config) {
+            let http_result = http.exists(key).await;
+            self.record_http_result(&http_result);
+            match http_result {
                 cache_hit @ Ok(Some(_)) => {
                     return cache_hit;
                 }
@@ -188,4 +289,100 @@ impl CacheMultiplexer {
 
         Ok(None)
     }
+
+    /// Checks the existence of many artifacts at once. The remote cache is
+    /// queried with a single request for every key that isn't in the fs
+    /// cache.
+    #[tracing::instrument(skip_all)]
+    pub async fn exists_many(
+        &self,
+        keys: &[&str],
+        config: CacheConfig,
+    ) -> Result<HashMap<String, Option<CacheHitMetadata>>, CacheError> {
+        let mut statuses = HashMap::with_capacity(keys.len());
+        let mut remaining = Vec::new();
+
+        for key in keys {
+            let fs_hit = match self.readable_fs_cache(config).map(|fs| fs.exists(key)) {
+                Some(Ok(cache_hit)) => cache_hit,
+                Some(Err(err)) => {
+                    debug!("failed to check fs cache: {:?}", err);
+                    None
+                }
+                None => None,
+            };
+            match fs_hit {
+                Some(cache_hit) => {
+                    statuses.insert(key.to_string(), Some(cache_hit));
+                }
+                None => remaining.push(*key),
+            }
+        }
+
+        if let Some(http) = self
+            .readable_http_cache(config)
+            .filter(|_| !remaining.is_empty())
+        {
+            match http.exists_many(&remaining).await {
+                Ok(remote_statuses) => {
+                    self.http_failures.store(0, Ordering::Relaxed);
+                    statuses.extend(remote_statuses);
+                }
+                Err(err) => {
+                    // The remote cache might not support batched queries, so fall back to
+                    // checking each artifact individually
+                    debug!("failed to query http cache: {:?}", err);
+                    for key in &remaining {
+                        if self.get_http_cache().is_none() {
+                            break;
+                        }
+                        let http_result = http.exists(key).await;
+                        self.record_http_result(&http_result);
+                        match http_result {
+                            Ok(cache_hit) => {
+                                statuses.insert(key.to_string(), cache_hit);
+                            }
+                            Err(err) => debug!("failed to check http cache: {:?}", err),
+                        }
+                    }
+                }
+            }
+        }
+
+        for key in remaining {
+            statuses.entry(key.to_string()).or_insert(None);
+        }
+
+        Ok(statuses)
+    }
+
+    /// Downloads an artifact from the remote cache into the fs cache so a
+    /// later `fetch` doesn't have to wait on the network. Does nothing if the
+    /// artifact is already local or there's no fs cache to store it in.
+    #[tracing::instrument(skip_all)]
+    pub async fn prefetch(&self, key: &str, config: CacheConfig) -> Result<(), CacheError> {
+        // The artifact is later read from the fs cache, so it has to be readable too
+        let (Some(fs), Some(http)) = (
+            self.writable_fs_cache(config).filter(|_| config.local.read),
+            self.readable_http_cache(config),
+        ) else {
+            return Ok(());
+        };
+
+        if let Ok(Some(_)) = fs.exists(key) {
+            return Ok(());
+        }
+
+        let http_result = http.fetch_archive(key).await;
+        self.record_http_result(&http_result);
+        if let Some((CacheHitMetadata { time_saved, .. }, archive)) = http_result? {
+            fs.put_archive(key, &archive, time_saved)?;
+            self.prefetched
+                .lock()
+                .expect("prefetched lock poisoned")
+                .insert(key.to_string());
+        }
+
+        Ok(())
+    }
 }
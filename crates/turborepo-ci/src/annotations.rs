@@ -0,0 +1,192 @@
+//! The native formats that CI vendors use for grouping logs and reporting
+//! failures.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::vendor_behavior::{Annotation, TaskFailure};
+
+// GitHub Actions workflow commands
+// https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions
+
+pub(crate) fn github_error(failure: &TaskFailure) -> Annotation {
+    let mut message = failure.error.to_string();
+    if !failure.stderr_tail.is_empty() {
+        message.push('\n');
+        message.push_str(failure.stderr_tail);
+    }
+    Annotation::Log(format!(
+        "::error file={},title={}::{}\n",
+        github_escape_property(failure.log_file),
+        github_escape_property(&format!("{} failed", failure.task_id)),
+        github_escape_data(&message),
+    ))
+}
+
+fn github_escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn github_escape_property(value: &str) -> String {
+    github_escape_data(value)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+// GitLab CI collapsible sections
+// https://docs.gitlab.com/ee/ci/jobs/#custom-collapsible-sections
+
+pub(crate) fn gitlab_section_start(group_name: &str) -> String {
+    gitlab_section_header(group_name, "[collapsed=true]", group_name)
+}
+
+pub(crate) fn gitlab_section_end(group_name: &str) -> String {
+    format!(
+        "\x1b[0Ksection_end:{}:{}\r\x1b[0K\n",
+        unix_seconds(),
+        gitlab_section_name(group_name)
+    )
+}
+
+pub(crate) fn gitlab_error(failure: &TaskFailure) -> Annotation {
+    let name = format!("{} failed", failure.task_id);
+    let mut section = gitlab_section_header(
+        &name,
+        "",
+        &format!("\x1b[31m{}: {}\x1b[0m", name, failure.error),
+    );
+    if !failure.stderr_tail.is_empty() {
+        section.push_str(failure.stderr_tail);
+        section.push('\n');
+    }
+    section.push_str(&gitlab_section_end(&name));
+    Annotation::Log(section)
+}
+
+fn gitlab_section_header(group_name: &str, options: &str, header: &str) -> String {
+    format!(
+        "\x1b[0Ksection_start:{}:{}{}\r\x1b[0K{}\n",
+        unix_seconds(),
+        gitlab_section_name(group_name),
+        options,
+        header
+    )
+}
+
+// Section names may only contain letters, numbers, `_`, `.` and `-`
+fn gitlab_section_name(group_name: &str) -> String {
+    group_name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '.' | '-' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn unix_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs())
+}
+
+// Buildkite annotations
+// https://buildkite.com/docs/agent/v3/cli-annotate
+
+pub(crate) fn buildkite_error(failure: &TaskFailure) -> Annotation {
+    let mut input = format!("**`{}` failed**: {}\n", failure.task_id, failure.error);
+    if !failure.stderr_tail.is_empty() {
+        input.push_str(&format!("\n```term\n{}\n```\n", failure.stderr_tail));
+    }
+    Annotation::Command {
+        program: "buildkite-agent",
+        args: vec![
+            "annotate".to_string(),
+            "--style".to_string(),
+            "error".to_string(),
+            "--context".to_string(),
+            format!("turbo-{}", failure.task_id),
+        ],
+        input,
+    }
+}
+
+// Azure Pipelines logging commands
+// https://learn.microsoft.com/en-us/azure/devops/pipelines/scripts/logging-commands
+
+pub(crate) fn azure_error(failure: &TaskFailure) -> Annotation {
+    let mut message = format!("{} failed: {}", failure.task_id, failure.error);
+    if !failure.stderr_tail.is_empty() {
+        message.push('\n');
+        message.push_str(failure.stderr_tail);
+    }
+    Annotation::Log(format!(
+        "##vso[task.logissue type=error;sourcepath={}]{}\n",
+        azure_escape(failure.log_file)
+            .replace(';', "%3B")
+            .replace(']', "%5D"),
+        azure_escape(&message),
+    ))
+}
+
+pub(crate) fn azure_upload_summary(path: &str) -> String {
+    format!("##vso[task.uploadsummary]{}\n", azure_escape(path))
+}
+
+fn azure_escape(value: &str) -> String {
+    value
+        .replace('%', "%AZP25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn failure() -> TaskFailure<'static> {
+        TaskFailure {
+            task_id: "web#build",
+            log_file: "apps/web/.turbo/turbo-build.log",
+            error: "command exited (1)",
+            stderr_tail: "error: 100% broken\nat index.ts:1",
+        }
+    }
+
+    #[test]
+    fn test_github_error() {
+        assert_eq!(
+            github_error(&failure()),
+            Annotation::Log(
+                "::error file=apps/web/.turbo/turbo-build.log,title=web#build failed::command \
+                 exited (1)%0Aerror: 100%25 broken%0Aat index.ts:1\n"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_gitlab_section_name() {
+        assert_eq!(gitlab_section_name("@acme/web:build"), "_acme_web_build");
+        let Annotation::Log(section) = gitlab_error(&failure()) else {
+            panic!("gitlab errors are printed to the log");
+        };
+        assert!(section.contains(":web_build_failed\r"));
+        assert!(section.contains("error: 100% broken\nat index.ts:1\n"));
+    }
+
+    #[test]
+    fn test_azure_error() {
+        assert_eq!(
+            azure_error(&failure()),
+            Annotation::Log(
+                "##vso[task.logissue \
+                 type=error;sourcepath=apps/web/.turbo/turbo-build.log]web#build failed: command \
+                 exited (1)%0Aerror: 100%AZP25 broken%0Aat index.ts:1\n"
+                    .to_string()
+            )
+        );
+    }
+}
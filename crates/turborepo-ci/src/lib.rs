@@ -1,12 +1,16 @@
 #![deny(clippy::all)]
 
+mod annotations;
 mod vendor_behavior;
 mod vendors;
 
 use std::{env, sync::OnceLock};
 
 use crate::vendors::get_vendors;
-pub use crate::{vendor_behavior::VendorBehavior, vendors::Vendor};
+pub use crate::{
+    vendor_behavior::{Annotation, JobSummary, TaskFailure, VendorBehavior},
+    vendors::Vendor,
+};
 
 static IS_CI: OnceLock<bool> = OnceLock::new();
 static VENDOR: OnceLock<Option<&'static Vendor>> = OnceLock::new();
@@ -1,7 +1,45 @@
 type GroupPrefixFn = fn(group_name: &str) -> String;
+type ErrorAnnotationFn = fn(failure: &TaskFailure) -> Annotation;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct VendorBehavior {
     pub group_prefix: GroupPrefixFn,
     pub group_suffix: GroupPrefixFn,
+    /// How a failed task is surfaced in the vendor's UI
+    pub error_annotation: Option<ErrorAnnotationFn>,
+    /// Where a markdown summary of the run goes
+    pub job_summary: Option<JobSummary>,
+}
+
+/// A task that failed during the run
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaskFailure<'a> {
+    pub task_id: &'a str,
+    /// The task's log file, relative to the repository root
+    pub log_file: &'a str,
+    pub error: &'a str,
+    /// The last lines the task wrote to stderr
+    pub stderr_tail: &'a str,
+}
+
+/// A vendor specific report of a failed task
+#[derive(Clone, Debug, PartialEq)]
+pub enum Annotation {
+    /// Printed to the job log, where the vendor picks it up
+    Log(String),
+    /// Sent on stdin to a command that the vendor's agent provides
+    Command {
+        program: &'static str,
+        args: Vec<String>,
+        input: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobSummary {
+    /// Append the summary to the file named by this environment variable
+    AppendToFile { env_var: &'static str },
+    /// Write the summary to a file and print the returned logging command with
+    /// its path
+    Upload(fn(path: &str) -> String),
 }
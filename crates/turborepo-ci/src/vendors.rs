@@ -1,6 +1,9 @@
 use std::{collections::HashMap, fmt::Debug, sync::OnceLock};
 
-use crate::vendor_behavior::VendorBehavior;
+use crate::{
+    annotations,
+    vendor_behavior::{JobSummary, VendorBehavior},
+};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct VendorEnvs {
@@ -80,6 +83,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     behavior: Some(VendorBehavior {
                         group_prefix: |group_name| format!("##[group]{group_name}\r\n"),
                         group_suffix: |_| String::from("##[endgroup]\r\n"),
+                        error_annotation: Some(annotations::azure_error),
+                        job_summary: Some(JobSummary::Upload(annotations::azure_upload_summary)),
                     }),
                 },
                 Vendor {
@@ -145,7 +150,12 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
-                    behavior: None,
+                    behavior: Some(VendorBehavior {
+                        group_prefix: |group_name| format!("--- {group_name}\n"),
+                        group_suffix: |_| String::new(),
+                        error_annotation: Some(annotations::buildkite_error),
+                        job_summary: None,
+                    }),
                 },
                 Vendor {
                     name: "CircleCI",
@@ -269,6 +279,10 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     behavior: Some(VendorBehavior {
                         group_prefix: |group_name| format!("::group::{group_name}\n"),
                         group_suffix: |_| String::from("::endgroup::\n"),
+                        error_annotation: Some(annotations::github_error),
+                        job_summary: Some(JobSummary::AppendToFile {
+                            env_var: "GITHUB_STEP_SUMMARY",
+                        }),
                     }),
                 },
                 Vendor {
@@ -282,7 +296,12 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
-                    behavior: None,
+                    behavior: Some(VendorBehavior {
+                        group_prefix: annotations::gitlab_section_start,
+                        group_suffix: annotations::gitlab_section_end,
+                        error_annotation: Some(annotations::gitlab_error),
+                        job_summary: None,
+                    }),
                 },
                 Vendor {
                     name: "GoCD",
@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use turbopath::AbsoluteSystemPath;
+
+use crate::{EnvironmentVariableMap, Error};
+
+/// Placeholder in a `dotEnv` entry that is replaced with the current mode,
+/// e.g. `.env.{mode}` becomes `.env.production` when `NODE_ENV=production`.
+pub const DOT_ENV_MODE_PLACEHOLDER: &str = "{mode}";
+
+/// The environment variable that selects the mode used to expand
+/// `DOT_ENV_MODE_PLACEHOLDER`.
+pub const DOT_ENV_MODE_VAR: &str = "NODE_ENV";
+
+/// The `.env` files a workspace loads when a task sets `dotEnvCascade`, in
+/// the order they're applied: each file overrides the ones before it.
+pub const DOT_ENV_CASCADE: [&str; 4] = [".env", ".env.local", ".env.{mode}", ".env.{mode}.local"];
+
+/// The cascade with the mode expanded, highest priority first like the files
+/// given to `DotEnvMap::load`. The mode specific files are skipped if no mode
+/// is set.
+pub fn dot_env_cascade(mode: Option<&str>) -> Vec<String> {
+    let mut files = expand_dot_env_mode(DOT_ENV_CASCADE, mode);
+    files.reverse();
+    files
+}
+
+/// Expands the mode placeholder in a list of `.env` file names.
+/// Entries that reference the mode are dropped if no mode is set.
+pub fn expand_dot_env_mode<'a>(
+    files: impl IntoIterator<Item = &'a str>,
+    mode: Option<&str>,
+) -> Vec<String> {
+    files
+        .into_iter()
+        .filter_map(
+            |file| match (file.contains(DOT_ENV_MODE_PLACEHOLDER), mode) {
+                (false, _) => Some(file.to_string()),
+                (true, Some(mode)) if !mode.is_empty() => {
+                    Some(file.replace(DOT_ENV_MODE_PLACEHOLDER, mode))
+                }
+                (true, _) => None,
+            },
+        )
+        .collect()
+}
+
+/// Environment variables loaded from an ordered cascade of `.env` files.
+///
+/// Files are given highest priority first: a variable defined in an earlier
+/// file is never overridden by a later one.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DotEnvMap {
+    // The files that existed and were loaded, in priority order.
+    pub files: Vec<String>,
+    // Maps each variable name to the file it was read from.
+    pub sources: BTreeMap<String, String>,
+    #[serde(skip)]
+    pub vars: EnvironmentVariableMap,
+}
+
+impl DotEnvMap {
+    /// Loads each `(path, label)` pair in order, skipping files that don't
+    /// exist. `label` is what gets reported as the source of a variable.
+    pub fn load<P: AsRef<AbsoluteSystemPath>>(
+        files: impl IntoIterator<Item = (P, String)>,
+    ) -> Result<Self, Error> {
+        let mut map = DotEnvMap::default();
+        for (path, label) in files {
+            let contents = match path.as_ref().read_to_string() {
+                Ok(contents) => contents,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    return Err(Error::DotEnv {
+                        path: label,
+                        reason: e.to_string(),
+                    })
+                }
+            };
+            let dot_env = dotenv::from_read(contents.as_bytes()).map_err(|e| Error::DotEnv {
+                path: label.clone(),
+                reason: e.to_string(),
+            })?;
+            for (key, value) in dot_env.iter() {
+                if map.vars.contains_key(key) {
+                    continue;
+                }
+                map.vars.insert(key.to_string(), value);
+                map.sources.insert(key.to_string(), label.clone());
+            }
+            map.files.push(label);
+        }
+
+        Ok(map)
+    }
+
+    /// Layers `self` on top of `base`: variables from `base` are only added if
+    /// `self` doesn't already define them.
+    pub fn with_base(mut self, base: &DotEnvMap) -> Self {
+        for (key, value) in base.vars.iter() {
+            if self.vars.contains_key(key) {
+                continue;
+            }
+            self.vars.insert(key.clone(), value.clone());
+            if let Some(source) = base.sources.get(key) {
+                self.sources.insert(key.clone(), source.clone());
+            }
+        }
+        self.files.extend(base.files.iter().cloned());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+    use test_case::test_case;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+
+    #[test_case(&[".env", ".env.{mode}"], Some("production"), &[".env", ".env.production"] ; "with mode")]
+    #[test_case(&[".env", ".env.{mode}"], None, &[".env"] ; "without mode")]
+    #[test_case(&[".env.{mode}"], Some(""), &[] ; "empty mode")]
+    fn test_expand_dot_env_mode(files: &[&str], mode: Option<&str>, expected: &[&str]) {
+        assert_eq!(
+            expand_dot_env_mode(files.iter().copied(), mode),
+            expected.iter().map(|s| s.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test_case(Some("production"), &[".env.production.local", ".env.production", ".env.local", ".env"] ; "with mode")]
+    #[test_case(None, &[".env.local", ".env"] ; "without mode")]
+    fn test_dot_env_cascade(mode: Option<&str>, expected: &[&str]) {
+        assert_eq!(dot_env_cascade(mode), expected);
+    }
+
+    #[test]
+    fn test_dot_env_cascade_overrides() -> Result<(), Error> {
+        let tmp = tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        // In cascade order, each file overrides the ones before it
+        for (file, contents) in [
+            (".env", "A=env\nB=env\nC=env\nD=env\n"),
+            (".env.local", "B=local\nC=local\nD=local\n"),
+            (".env.production", "C=production\nD=production\n"),
+            (".env.production.local", "D=production.local\n"),
+        ] {
+            root.join_component(file)
+                .create_with_contents(contents)
+                .unwrap();
+        }
+
+        let cascade = DotEnvMap::load(
+            dot_env_cascade(Some("production"))
+                .into_iter()
+                .map(|file| (root.join_component(&file), file)),
+        )?;
+        assert_eq!(cascade.vars.get("A").unwrap(), "env");
+        assert_eq!(cascade.vars.get("B").unwrap(), "local");
+        assert_eq!(cascade.vars.get("C").unwrap(), "production");
+        assert_eq!(cascade.vars.get("D").unwrap(), "production.local");
+        assert_eq!(cascade.sources.get("B").unwrap(), ".env.local");
+        assert_eq!(cascade.sources.get("D").unwrap(), ".env.production.local");
+
+        // Without a mode only the shared files apply
+        let cascade = DotEnvMap::load(
+            dot_env_cascade(None)
+                .into_iter()
+                .map(|file| (root.join_component(&file), file)),
+        )?;
+        assert_eq!(cascade.files, vec![".env.local", ".env"]);
+        assert_eq!(cascade.vars.get("D").unwrap(), "local");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cascade_priority() -> Result<(), Error> {
+        let tmp = tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let local = root.join_component(".env.local");
+        let env = root.join_component(".env");
+        let missing = root.join_component(".env.production");
+        local.create_with_contents("API_URL=local\n").unwrap();
+        env.create_with_contents("API_URL=shared\nPORT=3000\n")
+            .unwrap();
+
+        let root_env = root.join_component("root.env");
+        root_env
+            .create_with_contents("PORT=8080\nROOT_ONLY=1\n")
+            .unwrap();
+
+        let workspace = DotEnvMap::load([
+            (&missing, ".env.production".to_string()),
+            (&local, ".env.local".to_string()),
+            (&env, ".env".to_string()),
+        ])?;
+        let base = DotEnvMap::load([(&root_env, "root.env".to_string())])?;
+        let cascade = workspace.with_base(&base);
+
+        assert_eq!(cascade.files, vec![".env.local", ".env", "root.env"]);
+        assert_eq!(cascade.vars.get("API_URL").unwrap(), "local");
+        assert_eq!(cascade.vars.get("PORT").unwrap(), "3000");
+        assert_eq!(cascade.vars.get("ROOT_ONLY").unwrap(), "1");
+        assert_eq!(cascade.sources.get("API_URL").unwrap(), ".env.local");
+        assert_eq!(cascade.sources.get("ROOT_ONLY").unwrap(), "root.env");
+
+        Ok(())
+    }
+}
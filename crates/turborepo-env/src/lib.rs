@@ -12,10 +12,17 @@ use serde::Serialize;
 use sha2::{Digest, Sha256};
 use thiserror::Error;
 
+mod dot_env;
+
+pub use dot_env::{
+    dot_env_cascade, expand_dot_env_mode, DotEnvMap, DOT_ENV_CASCADE, DOT_ENV_MODE_PLACEHOLDER,
+    DOT_ENV_MODE_VAR,
+};
+
 const DEFAULT_ENV_VARS: [&str; 1] = ["VERCEL_ANALYTICS_ID"];
 
 /// Environment mode after we've resolved the `Infer` variant
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ResolvedEnvMode {
     Loose,
     Strict,
@@ -34,6 +41,8 @@ impl std::fmt::Display for ResolvedEnvMode {
 pub enum Error {
     #[error("Failed to parse regex: {0}")]
     Regex(#[from] regex::Error),
+    #[error("Failed to load {path}: {reason}")]
+    DotEnv { path: String, reason: String },
 }
 
 // TODO: Consider using immutable data structures here
@@ -90,6 +99,7 @@ impl EnvironmentVariableMap {
 pub struct BySource {
     pub explicit: EnvironmentVariableMap,
     pub matching: EnvironmentVariableMap,
+    pub dot_env: DotEnvMap,
 }
 
 // DetailedMap contains the composite and the detailed maps of environment
@@ -311,6 +321,7 @@ pub fn get_global_hashable_env_vars(
         by_source: BySource {
             explicit: explicit_env_var_map,
             matching: matching_env_var_map,
+            dot_env: DotEnvMap::default(),
         },
     })
 }
@@ -5,6 +5,7 @@ use std::{
 
 use biome_deserialize::{Deserializable, DeserializableValue, DeserializationDiagnostic};
 use miette::SourceSpan;
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
 use serde::Serialize;
 
 pub const TURBO_SITE: &str = "https://turbo.build";
@@ -38,6 +39,22 @@ impl<T: Deserializable> Deserializable for Spanned<T> {
     }
 }
 
+// Spans are only tracked for diagnostics, so a `Spanned` value has the same
+// schema as the value itself
+impl<T: JsonSchema> JsonSchema for Spanned<T> {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        T::schema_name()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        T::json_schema(gen)
+    }
+}
+
 impl<T> Spanned<T> {
     pub fn new(t: T) -> Self {
         Self {
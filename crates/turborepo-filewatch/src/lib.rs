@@ -39,7 +39,11 @@ pub mod cookies;
 #[cfg(target_os = "macos")]
 mod fsevent;
 pub mod globwatcher;
+pub mod package_graph_watcher;
 pub mod package_watcher;
+mod stats;
+
+pub use stats::WatchStats;
 
 #[cfg(not(target_os = "macos"))]
 type Backend = RecommendedWatcher;
@@ -86,6 +90,7 @@ pub struct FileSystemWatcher {
     // to be notified of a close.
     _exit_ch: tokio::sync::oneshot::Sender<()>,
     cookie_dir: AbsoluteSystemPathBuf,
+    stats: Arc<WatchStats>,
 }
 
 impl FileSystemWatcher {
@@ -119,6 +124,7 @@ impl FileSystemWatcher {
         // Ensure we are ready to receive new events, not events for existing state
         debug!("waiting for initial filesystem cookie");
         wait_for_cookie(cookie_dir, &mut recv_file_events).await?;
+        let stats = WatchStats::spawn(watch_root.clone(), sender.subscribe());
         tokio::task::spawn(watch_events(
             watcher,
             watch_root,
@@ -131,6 +137,7 @@ impl FileSystemWatcher {
             sender,
             _exit_ch: exit_ch,
             cookie_dir: cookie_dir.to_owned(),
+            stats,
         })
     }
 
@@ -141,6 +148,11 @@ impl FileSystemWatcher {
     pub fn cookie_dir(&self) -> &AbsoluteSystemPath {
         &self.cookie_dir
     }
+
+    /// Returns the counters for the activity this watcher has seen.
+    pub fn stats(&self) -> Arc<WatchStats> {
+        self.stats.clone()
+    }
 }
 
 fn setup_cookie_dir(cookie_dir: &AbsoluteSystemPath) -> Result<(), WatchError> {
@@ -0,0 +1,467 @@
+//! This module hosts the `PackageGraphWatcher` type, which keeps a package
+//! graph up to date as files change and reports which packages those changes
+//! affect.
+
+use std::{
+    collections::BTreeSet,
+    future::IntoFuture,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use notify::Event;
+use thiserror::Error;
+use tokio::sync::{
+    broadcast::{
+        self,
+        error::{RecvError, TryRecvError},
+    },
+    oneshot, watch,
+};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+use turborepo_repository::{
+    discovery::PackageDiscovery,
+    package_graph::{PackageGraph, WorkspaceName},
+    package_json::PackageJson,
+    package_manager::PackageManager,
+    workspace_provider::WorkspaceProviderKind,
+};
+
+use crate::NotifyError;
+
+/// Files under these directories never affect a package's inputs
+const IGNORED_DIRS: &[&str] = &[".git", "node_modules", ".turbo"];
+
+/// How long to wait for the package watcher to process the same burst of
+/// events before rebuilding the graph from its state
+const REBUILD_DELAY: Duration = Duration::from_millis(50);
+
+const CHANGE_EVENT_BUFFER: usize = 128;
+
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    #[error("timed out waiting for the package graph")]
+    Timeout,
+    #[error("package graph watcher exited")]
+    Closed,
+    #[error("failed to build package graph: {0}")]
+    Build(String),
+}
+
+/// A change reported by the `PackageGraphWatcher`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageChangeEvent {
+    /// Files changed in these packages.
+    Packages(BTreeSet<WorkspaceName>),
+    /// The package graph was rebuilt, so the set of packages or their
+    /// dependencies may have changed.
+    Rediscover,
+}
+
+type GraphState = Option<Result<Arc<PackageGraph>, Error>>;
+
+/// Reads the kinds of workspace providers configured for a repository, which
+/// live in its root turbo.json
+pub type LoadWorkspaceProviders =
+    Box<dyn Fn(&AbsoluteSystemPath) -> Result<Vec<WorkspaceProviderKind>, String> + Send + Sync>;
+
+/// Watches the filesystem and rebuilds the package graph whenever a
+/// package.json, lockfile or workspace configuration changes.
+pub struct PackageGraphWatcher {
+    // _exit_tx exists to trigger a close on the receiver when an instance
+    // of this struct is dropped. The task that is receiving events will exit,
+    // dropping the sender for the change broadcast, causing all receivers
+    // to be notified of a close.
+    _exit_tx: oneshot::Sender<()>,
+    _handle: tokio::task::JoinHandle<()>,
+
+    graph_rx: watch::Receiver<GraphState>,
+    changes_tx: broadcast::Sender<PackageChangeEvent>,
+}
+
+impl PackageGraphWatcher {
+    /// Creates a new package graph watcher. The graph is built from the
+    /// packages reported by `discovery`, which is expected to be cheap to
+    /// query, for example a `PackageWatcher`'s discovery, along with the
+    /// workspaces found by the providers `workspace_providers` loads.
+    pub fn new<D: PackageDiscovery + Clone + Send + Sync + 'static>(
+        repo_root: AbsoluteSystemPathBuf,
+        recv: broadcast::Receiver<Result<Event, NotifyError>>,
+        discovery: D,
+        workspace_providers: LoadWorkspaceProviders,
+    ) -> Self {
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let (graph_tx, graph_rx) = watch::channel(None);
+        let (changes_tx, _) = broadcast::channel(CHANGE_EVENT_BUFFER);
+        let subscriber = Subscriber {
+            exit_rx,
+            recv,
+            repo_root,
+            discovery,
+            workspace_providers,
+            graph_tx,
+            changes_tx: changes_tx.clone(),
+            last_graph: None,
+        };
+        let handle = tokio::spawn(subscriber.watch());
+        Self {
+            _exit_tx: exit_tx,
+            _handle: handle,
+            graph_rx,
+            changes_tx,
+        }
+    }
+
+    /// Returns the current package graph, waiting up to `timeout` if it is
+    /// being rebuilt.
+    pub async fn get_package_graph(&self, timeout: Duration) -> Result<Arc<PackageGraph>, Error> {
+        let mut rx = self.graph_rx.clone();
+        let state = tokio::time::timeout(timeout, rx.wait_for(Option::is_some))
+            .await
+            .map_err(|_| Error::Timeout)?
+            .map_err(|_| Error::Closed)?;
+
+        state.clone().expect("guaranteed some above")
+    }
+
+    /// Subscribes to the packages affected by file changes.
+    pub fn subscribe(&self) -> broadcast::Receiver<PackageChangeEvent> {
+        self.changes_tx.subscribe()
+    }
+}
+
+#[derive(Default)]
+struct Batch {
+    paths: Vec<PathBuf>,
+    rediscover: bool,
+}
+
+/// The underlying task that listens to file system events and rebuilds the
+/// package graph.
+struct Subscriber<D> {
+    exit_rx: oneshot::Receiver<()>,
+    recv: broadcast::Receiver<Result<Event, NotifyError>>,
+    repo_root: AbsoluteSystemPathBuf,
+    discovery: D,
+    workspace_providers: LoadWorkspaceProviders,
+    graph_tx: watch::Sender<GraphState>,
+    changes_tx: broadcast::Sender<PackageChangeEvent>,
+    // the most recent graph that built successfully, used to map changed
+    // files onto packages while a rebuild is pending or after one fails
+    last_graph: Option<Arc<PackageGraph>>,
+}
+
+impl<D: PackageDiscovery + Clone + Send + Sync + 'static> Subscriber<D> {
+    async fn watch(mut self) {
+        self.rebuild().await;
+
+        loop {
+            let mut batch = Batch::default();
+            tokio::select! {
+                biased;
+                _ = &mut self.exit_rx => {
+                    tracing::info!("exiting package graph watcher");
+                    return
+                },
+                file_event = self.recv.recv().into_future() => match file_event {
+                    Ok(Ok(event)) => batch.paths.extend(event.paths),
+                    // if we get an error, we need to rebuild the graph
+                    Ok(Err(_)) => batch.rediscover = true,
+                    Err(RecvError::Closed) => return,
+                    Err(RecvError::Lagged(count)) => {
+                        tracing::warn!("lagged behind {count} processing file watching events");
+                        batch.rediscover = true;
+                    },
+                }
+            }
+            if !self.drain(&mut batch) {
+                return;
+            }
+
+            let manager = self
+                .last_graph
+                .as_ref()
+                .map(|graph| *graph.package_manager());
+            let rediscover = batch.rediscover
+                || batch
+                    .paths
+                    .iter()
+                    .any(|path| is_graph_input(&self.repo_root, manager.as_ref(), path));
+
+            if rediscover {
+                self.graph_tx.send_replace(None);
+                tokio::select! {
+                    biased;
+                    _ = &mut self.exit_rx => return,
+                    _ = tokio::time::sleep(REBUILD_DELAY) => {}
+                }
+                if !self.drain(&mut batch) {
+                    return;
+                }
+            }
+
+            self.notify_changed_packages(&batch.paths);
+
+            if rediscover {
+                self.rebuild().await;
+                // there might not be any subscribers, that's fine
+                self.changes_tx.send(PackageChangeEvent::Rediscover).ok();
+            }
+        }
+    }
+
+    /// Adds any events that are already queued to the batch, returning false
+    /// if the event stream has closed.
+    fn drain(&mut self, batch: &mut Batch) -> bool {
+        loop {
+            match self.recv.try_recv() {
+                Ok(Ok(event)) => batch.paths.extend(event.paths),
+                Ok(Err(_)) | Err(TryRecvError::Lagged(_)) => batch.rediscover = true,
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Closed) => return false,
+            }
+        }
+    }
+
+    fn notify_changed_packages(&self, paths: &[PathBuf]) {
+        let Some(graph) = &self.last_graph else {
+            return;
+        };
+        let packages = paths
+            .iter()
+            .filter_map(|path| package_for_path(&self.repo_root, graph, path))
+            .collect::<BTreeSet<_>>();
+        if !packages.is_empty() {
+            self.changes_tx
+                .send(PackageChangeEvent::Packages(packages))
+                .ok();
+        }
+    }
+
+    async fn rebuild(&mut self) {
+        tracing::debug!("rebuilding package graph");
+        let state = match self.build().await {
+            Ok(graph) => {
+                let graph = Arc::new(graph);
+                self.last_graph = Some(graph.clone());
+                Ok(graph)
+            }
+            Err(e) => {
+                tracing::warn!("{}", e);
+                Err(e)
+            }
+        };
+        self.graph_tx.send_replace(Some(state));
+    }
+
+    async fn build(&self) -> Result<PackageGraph, Error> {
+        let root_package_json = PackageJson::load(&self.repo_root.join_component("package.json"))
+            .map_err(|e| Error::Build(e.to_string()))?;
+        let workspace_providers =
+            (self.workspace_providers)(&self.repo_root).map_err(Error::Build)?;
+        PackageGraph::builder(&self.repo_root, root_package_json)
+            .with_workspace_providers(
+                workspace_providers
+                    .iter()
+                    .map(|kind| kind.provider())
+                    .collect(),
+            )
+            .with_package_discovery(self.discovery.clone())
+            .build()
+            .await
+            .map_err(|e| Error::Build(e.to_string()))
+    }
+}
+
+/// Returns the path of `path` relative to the repo root, or `None` if it is
+/// outside of the repository or inside a directory that is never an input.
+fn relative_input_path<'a>(repo_root: &AbsoluteSystemPath, path: &'a Path) -> Option<&'a Path> {
+    let relative = path.strip_prefix(repo_root.as_std_path()).ok()?;
+    let ignored = relative
+        .components()
+        .any(|component| IGNORED_DIRS.iter().any(|dir| component.as_os_str() == *dir));
+    (!ignored && !relative.as_os_str().is_empty()).then_some(relative)
+}
+
+/// Whether a change to `path` can change the package graph. Without a package
+/// manager, any file at the repository root is assumed to be relevant.
+fn is_graph_input(
+    repo_root: &AbsoluteSystemPath,
+    manager: Option<&PackageManager>,
+    path: &Path,
+) -> bool {
+    let Some(relative) = relative_input_path(repo_root, path) else {
+        return false;
+    };
+    if relative
+        .file_name()
+        .is_some_and(|name| name == "package.json")
+    {
+        return true;
+    }
+    // the root turbo.json configures the workspace providers
+    if relative == Path::new("turbo.json") {
+        return true;
+    }
+
+    match manager {
+        Some(manager) => {
+            relative == Path::new(manager.lockfile_name())
+                || manager
+                    .workspace_configuration_path()
+                    .is_some_and(|config| relative == Path::new(config))
+        }
+        None => relative.parent() == Some(Path::new("")),
+    }
+}
+
+/// Maps a changed file onto the package that contains it. Files that aren't in
+/// any workspace belong to the root package.
+fn package_for_path(
+    repo_root: &AbsoluteSystemPath,
+    graph: &PackageGraph,
+    path: &Path,
+) -> Option<WorkspaceName> {
+    let relative = relative_input_path(repo_root, path)?;
+    let package = graph
+        .workspaces()
+        .filter(|(name, _)| **name != WorkspaceName::Root)
+        .filter(|(_, info)| relative.starts_with(info.package_path().as_path()))
+        // nested workspaces are matched by the most specific path
+        .max_by_key(|(_, info)| info.package_path().components().count())
+        .map_or(WorkspaceName::Root, |(name, _)| name.clone());
+
+    Some(package)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeSet, time::Duration};
+
+    use itertools::Itertools;
+    use tokio::sync::broadcast;
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_repository::{
+        discovery::{self, DiscoveryResponse, PackageDiscovery, WorkspaceData},
+        package_graph::WorkspaceName,
+        package_manager::PackageManager,
+    };
+
+    use super::{PackageChangeEvent, PackageGraphWatcher};
+
+    #[derive(Clone)]
+    struct MockDiscovery {
+        workspaces: Vec<WorkspaceData>,
+    }
+
+    impl PackageDiscovery for MockDiscovery {
+        async fn discover_packages(&mut self) -> Result<DiscoveryResponse, discovery::Error> {
+            Ok(DiscoveryResponse {
+                package_manager: PackageManager::Npm,
+                workspaces: self.workspaces.clone(),
+            })
+        }
+    }
+
+    fn event(path: &AbsoluteSystemPathBuf) -> notify::Event {
+        notify::Event {
+            kind: notify::EventKind::Modify(notify::event::ModifyKind::Any),
+            paths: vec![path.as_std_path().to_owned()],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_package_graph_watcher() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+
+        root.join_component("package.json")
+            .create_with_contents(r#"{"name": "root", "workspaces": ["packages/*"]}"#)
+            .unwrap();
+        let mut workspaces = Vec::new();
+        for name in ["a", "b"] {
+            let package_json = root.join_components(&["packages", name, "package.json"]);
+            package_json.ensure_dir().unwrap();
+            package_json
+                .create_with_contents(format!(r#"{{"name": "{name}"}}"#))
+                .unwrap();
+            workspaces.push(WorkspaceData {
+                package_json,
+                turbo_json: None,
+            });
+        }
+
+        let (tx, rx) = broadcast::channel(10);
+        let watcher = PackageGraphWatcher::new(
+            root.clone(),
+            rx,
+            MockDiscovery { workspaces },
+            Box::new(|_| Ok(vec![])),
+        );
+
+        let graph = watcher
+            .get_package_graph(Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            graph
+                .workspaces()
+                .map(|(name, _)| name.to_string())
+                .sorted()
+                .collect::<Vec<_>>(),
+            vec!["//", "a", "b"]
+        );
+
+        let mut changes = watcher.subscribe();
+        tx.send(Ok(event(
+            &root.join_components(&["packages", "b", "src", "index.js"]),
+        )))
+        .unwrap();
+        tx.send(Ok(event(&root.join_component("README.md"))))
+            .unwrap();
+        tx.send(Ok(event(&root.join_components(&[
+            "packages",
+            "a",
+            "node_modules",
+            "dep",
+            "index.js",
+        ]))))
+        .unwrap();
+
+        let change = tokio::time::timeout(Duration::from_secs(1), changes.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            change,
+            PackageChangeEvent::Packages(BTreeSet::from([
+                WorkspaceName::Root,
+                WorkspaceName::Other("b".to_string())
+            ]))
+        );
+
+        tx.send(Ok(event(&root.join_components(&[
+            "packages",
+            "a",
+            "package.json",
+        ]))))
+        .unwrap();
+
+        let change = tokio::time::timeout(Duration::from_secs(1), changes.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            change,
+            PackageChangeEvent::Packages(BTreeSet::from([WorkspaceName::Other("a".to_string())]))
+        );
+        let change = tokio::time::timeout(Duration::from_secs(1), changes.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(change, PackageChangeEvent::Rediscover);
+    }
+}
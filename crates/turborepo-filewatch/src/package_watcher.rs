@@ -18,7 +18,7 @@ use tokio::{
 };
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 use turborepo_repository::{
-    discovery::{PackageDiscovery, WorkspaceData},
+    discovery::{self, DiscoveryResponse, PackageDiscovery, WorkspaceData},
     package_manager::{self, Error, PackageManager, WorkspaceGlobs},
 };
 
@@ -69,6 +69,38 @@ impl PackageWatcher {
     pub async fn get_package_manager(&self) -> PackageManager {
         *self.manager_rx.borrow()
     }
+
+    /// Returns a `PackageDiscovery` that answers from this watcher's current
+    /// state. It does not keep the watcher alive.
+    pub fn discovery(&self) -> WatchingPackageDiscovery {
+        WatchingPackageDiscovery {
+            package_data: self.package_data.clone(),
+            manager_rx: self.manager_rx.clone(),
+        }
+    }
+}
+
+/// Discovers packages from the state maintained by a `PackageWatcher`.
+#[derive(Clone)]
+pub struct WatchingPackageDiscovery {
+    package_data: Arc<Mutex<HashMap<AbsoluteSystemPathBuf, WorkspaceData>>>,
+    manager_rx: watch::Receiver<PackageManager>,
+}
+
+impl PackageDiscovery for WatchingPackageDiscovery {
+    async fn discover_packages(&mut self) -> Result<DiscoveryResponse, discovery::Error> {
+        let workspaces = self
+            .package_data
+            .lock()
+            .expect("not poisoned")
+            .values()
+            .cloned()
+            .collect();
+        Ok(DiscoveryResponse {
+            workspaces,
+            package_manager: *self.manager_rx.borrow(),
+        })
+    }
 }
 
 /// The underlying task that listens to file system events and updates the
@@ -0,0 +1,181 @@
+//! Accounting for the filesystem activity seen by a `FileSystemWatcher`, so
+//! that a daemon watching several repositories can report which ones are
+//! expensive to watch.
+
+use std::{
+    future::IntoFuture,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use notify::{
+    event::{CreateKind, RemoveKind},
+    Event, EventKind,
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+use turbopath::AbsoluteSystemPathBuf;
+use walkdir::WalkDir;
+
+use crate::NotifyError;
+
+/// Counters for a single watched root. All counts are approximate: they are
+/// updated from the event stream, which can lag behind the filesystem.
+#[derive(Debug)]
+pub struct WatchStats {
+    started: Instant,
+    events: AtomicU64,
+    errors: AtomicU64,
+    // directories found by the initial walk plus those created since
+    directories_added: AtomicU64,
+    directories_removed: AtomicU64,
+}
+
+impl WatchStats {
+    /// Starts tracking the events on `recv`, and counts the directories under
+    /// `root` in the background.
+    pub(crate) fn spawn(
+        root: AbsoluteSystemPathBuf,
+        recv: broadcast::Receiver<Result<Event, NotifyError>>,
+    ) -> Arc<Self> {
+        let stats = Arc::new(Self {
+            started: Instant::now(),
+            events: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            directories_added: AtomicU64::new(0),
+            directories_removed: AtomicU64::new(0),
+        });
+
+        let walk_stats = stats.clone();
+        tokio::task::spawn_blocking(move || {
+            let directories = WalkDir::new(root.as_std_path())
+                .follow_links(false)
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_dir())
+                .count();
+            walk_stats
+                .directories_added
+                .fetch_add(directories as u64, Ordering::Relaxed);
+        });
+        tokio::spawn(Self::record_events(Arc::downgrade(&stats), recv));
+
+        stats
+    }
+
+    async fn record_events(
+        stats: std::sync::Weak<Self>,
+        mut recv: broadcast::Receiver<Result<Event, NotifyError>>,
+    ) {
+        loop {
+            let event = recv.recv().into_future().await;
+            // stop once nobody is interested in the numbers anymore
+            let Some(stats) = stats.upgrade() else {
+                return;
+            };
+            match event {
+                Ok(Ok(event)) => stats.record(&event),
+                Ok(Err(_)) => {
+                    stats.errors.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(RecvError::Lagged(count)) => {
+                    stats.events.fetch_add(count, Ordering::Relaxed);
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    fn record(&self, event: &Event) {
+        self.events.fetch_add(1, Ordering::Relaxed);
+        let directories = event.paths.len() as u64;
+        match event.kind {
+            EventKind::Create(CreateKind::Folder) => {
+                self.directories_added
+                    .fetch_add(directories, Ordering::Relaxed);
+            }
+            EventKind::Remove(RemoveKind::Folder) => {
+                self.directories_removed
+                    .fetch_add(directories, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+    }
+
+    /// How long the root has been watched
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// The number of filesystem events seen
+    pub fn events(&self) -> u64 {
+        self.events.load(Ordering::Relaxed)
+    }
+
+    /// The number of errors reported by the watcher backend
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// The average number of events per minute since watching started
+    pub fn events_per_minute(&self) -> f64 {
+        let minutes = self.uptime().as_secs_f64() / 60.0;
+        if minutes > 0.0 {
+            self.events() as f64 / minutes
+        } else {
+            0.0
+        }
+    }
+
+    /// The number of directories being watched
+    pub fn watched_directories(&self) -> u64 {
+        self.directories_added
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.directories_removed.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use notify::{
+        event::{CreateKind, ModifyKind},
+        Event, EventKind,
+    };
+    use tokio::sync::broadcast;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::WatchStats;
+
+    #[tokio::test]
+    async fn test_watch_stats() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        root.join_components(&["a", "b"]).create_dir_all().unwrap();
+
+        let (tx, rx) = broadcast::channel(10);
+        let stats = WatchStats::spawn(root.clone(), rx);
+
+        tx.send(Ok(Event {
+            kind: EventKind::Create(CreateKind::Folder),
+            paths: vec![root.join_component("c").as_std_path().to_owned()],
+            ..Default::default()
+        }))
+        .unwrap();
+        tx.send(Ok(Event {
+            kind: EventKind::Modify(ModifyKind::Any),
+            paths: vec![root.join_component("file").as_std_path().to_owned()],
+            ..Default::default()
+        }))
+        .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(stats.events(), 2);
+        assert_eq!(stats.errors(), 0);
+        // the root, a, a/b and the newly created c
+        assert_eq!(stats.watched_directories(), 4);
+    }
+}
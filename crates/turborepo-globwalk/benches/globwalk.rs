@@ -0,0 +1,168 @@
+//! Compares walking a package's `inputs` with a single compiled `MultiGlob`
+//! against walking each glob separately with `wax`, on synthetic repositories.
+
+use std::{fs, path::Path, str::FromStr, time::Duration};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use globwalk::{globwalk, ValidatedGlob, WalkType};
+use itertools::Itertools;
+use turbopath::AbsoluteSystemPathBuf;
+use wax::{
+    walk::{Entry, FileIterator},
+    Glob,
+};
+
+const INPUTS: &[&str] = &[
+    "src/**/*.ts",
+    "src/**/*.tsx",
+    "src/**/*.css",
+    "src/**/*.json",
+    "lib/**/*.ts",
+    "lib/**/*.js",
+    "test/**/*.ts",
+    "scripts/*.js",
+    "public/**",
+    "*.config.js",
+    "tsconfig.json",
+    "package.json",
+    "README.md",
+    ".env",
+    ".env.*",
+    "src/components/**/*.ts",
+    "src/components/**/*.tsx",
+    "src/utils/**",
+    "src/hooks/**",
+    "src/styles/**/*.css",
+    "assets/**/*.svg",
+    "assets/**/*.png",
+    "locales/**/*.json",
+    "types/**/*.d.ts",
+];
+
+const EXCLUDES: &[&str] = &["**/node_modules/**", "dist/**", "**/*.snap"];
+
+// Without excluding node_modules, only the include globs keep the walk out of
+// it and the other directories no input can be in
+const EXCLUDES_WITHOUT_NODE_MODULES: &[&str] = &["dist/**", "**/*.snap"];
+
+/// Creates a package with `width` directories at each of `depth` levels under
+/// each of its top-level directories, plus a large `node_modules`.
+fn setup_package(root: &Path, width: usize, depth: usize) {
+    fn fill(dir: &Path, width: usize, depth: usize) {
+        fs::create_dir_all(dir).unwrap();
+        for ext in ["ts", "tsx", "css", "json", "js", "snap", "md"] {
+            fs::write(dir.join(format!("file.{ext}")), "").unwrap();
+        }
+        if depth > 0 {
+            for i in 0..width {
+                fill(&dir.join(format!("dir{i}")), width, depth - 1);
+            }
+        }
+    }
+
+    for dir in [
+        "src/components",
+        "src/utils",
+        "src/hooks",
+        "src/styles",
+        "lib",
+        "test",
+        "assets",
+        "locales",
+        "dist",
+        "node_modules/dep",
+    ] {
+        fill(&root.join(dir), width, depth);
+    }
+    for file in [
+        "package.json",
+        "tsconfig.json",
+        "README.md",
+        "next.config.js",
+    ] {
+        fs::write(root.join(file), "").unwrap();
+    }
+}
+
+/// The previous implementation: one `wax` walk per include glob, each
+/// filtering with every exclusion.
+fn walk_per_glob(base_path: &Path, include: &[String], exclude: &[String]) -> usize {
+    let exclude = exclude
+        .iter()
+        .map(|glob| Glob::new(glob).unwrap())
+        .collect::<Vec<_>>();
+    include
+        .iter()
+        .map(|glob| Glob::new(glob).unwrap())
+        .flat_map(|glob| {
+            glob.walk(base_path)
+                .not(exclude.clone())
+                .unwrap()
+                .filter_map(Result::ok)
+                .filter(|entry| !entry.file_type().is_dir())
+                .map(|entry| entry.into_path())
+                .collect::<Vec<_>>()
+        })
+        .collect::<std::collections::HashSet<_>>()
+        .len()
+}
+
+fn bench_inputs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("globwalk-inputs");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(10));
+
+    for ((width, depth), (excludes_name, excludes)) in
+        [(2, 2), (3, 3), (4, 4)].into_iter().cartesian_product([
+            ("excludes", EXCLUDES),
+            ("no-node-modules-exclude", EXCLUDES_WITHOUT_NODE_MODULES),
+        ])
+    {
+        let tmp = tempdir::TempDir::new("globwalk-bench").unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+        setup_package(root.as_std_path(), width, depth);
+
+        let include = INPUTS
+            .iter()
+            .map(|glob| ValidatedGlob::from_str(glob).unwrap())
+            .collect::<Vec<_>>();
+        let exclude = excludes
+            .iter()
+            .map(|glob| ValidatedGlob::from_str(glob).unwrap())
+            .collect::<Vec<_>>();
+        let expected = globwalk(&root, &include, &exclude, WalkType::Files)
+            .unwrap()
+            .len();
+
+        let absolute = |globs: &[&str]| {
+            globs
+                .iter()
+                .map(|glob| format!("{}/{glob}", root.as_str()))
+                .collect::<Vec<_>>()
+        };
+        let per_glob_include = absolute(INPUTS);
+        let per_glob_exclude = absolute(excludes);
+        assert_eq!(
+            walk_per_glob(root.as_std_path(), &per_glob_include, &per_glob_exclude),
+            expected
+        );
+
+        let input = format!("{width}x{depth}/{excludes_name}");
+        group.bench_with_input(BenchmarkId::new("multi_glob", &input), &root, |b, root| {
+            b.iter(|| globwalk(root, &include, &exclude, WalkType::Files).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("per_glob", &input), &root, |b, root| {
+            b.iter(|| walk_per_glob(root.as_std_path(), &per_glob_include, &per_glob_exclude))
+        });
+    }
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default();
+    targets = bench_inputs
+);
+criterion_main!(benches);
@@ -1,6 +1,8 @@
 #![feature(assert_matches)]
 #![deny(clippy::all)]
 
+mod multi_glob;
+
 use std::{
     borrow::Cow,
     collections::HashSet,
@@ -15,7 +17,9 @@ use path_clean::PathClean;
 use path_slash::PathExt;
 use regex::Regex;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, PathError};
-use wax::{walk::FileIterator, BuildError, Glob};
+use wax::{BuildError, Glob};
+
+pub use crate::multi_glob::MultiGlob;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum WalkType {
@@ -25,7 +29,6 @@ pub enum WalkType {
 }
 
 pub use walkdir::Error as WalkDirError;
-use wax::walk::Entry;
 
 #[derive(Debug, thiserror::Error)]
 pub enum WalkError {
@@ -308,59 +311,18 @@ pub fn globwalk_internal(
     let (base_path_new, include_paths, exclude_paths) =
         preprocess_paths_and_globs(base_path, include, exclude)?;
 
-    let ex_patterns = exclude_paths
+    let include = include_paths
         .into_iter()
         .map(glob_with_contextual_error)
         .collect::<Result<_, _>>()?;
-
-    include_paths
+    let exclude = exclude_paths
         .into_iter()
         .map(glob_with_contextual_error)
-        .map_ok(|glob| walk_glob(walk_type, &base_path_new, &ex_patterns, glob))
-        // flat map to bring the results in the vec to the same level as the potential outer err
-        // this is the same as a flat_map_ok
-        .flat_map(|s| s.unwrap_or_else(|e| vec![Err(e)]))
-        .collect()
-}
-
-#[tracing::instrument(skip(ex_patterns), fields(glob=glob.to_string().as_str()))]
-fn walk_glob(
-    walk_type: WalkType,
-    base_path_new: &PathBuf,
-    ex_patterns: &Vec<Glob>,
-    glob: Glob,
-) -> Vec<Result<AbsoluteSystemPathBuf, WalkError>> {
-    glob.walk(&base_path_new)
-        .not(ex_patterns.clone())
-        .unwrap_or_else(|e| {
-            // Per docs, only fails if exclusion list is too large, since we're using
-            // pre-compiled globs
-            panic!(
-                "Failed to compile exclusion globs: {:?}: {}",
-                ex_patterns, e,
-            )
-        })
-        .filter_map(|entry| visit_file(walk_type, entry))
-        .collect::<Vec<_>>()
-}
+        .collect::<Result<_, _>>()?;
 
-#[tracing::instrument]
-fn visit_file(
-    walk_type: WalkType,
-    entry: Result<wax::walk::GlobEntry, wax::walk::WalkError>,
-) -> Option<Result<AbsoluteSystemPathBuf, WalkError>> {
-    match entry {
-        Ok(entry) if walk_type == WalkType::Files && entry.file_type().is_dir() => None,
-        Ok(entry) => Some(AbsoluteSystemPathBuf::try_from(entry.path()).map_err(|e| e.into())),
-        Err(e) => {
-            let io_err = std::io::Error::from(e);
-            match io_err.kind() {
-                // Ignore DNE and permission errors
-                std::io::ErrorKind::NotFound | std::io::ErrorKind::PermissionDenied => None,
-                _ => Some(Err(io_err.into())),
-            }
-        }
-    }
+    MultiGlob::new(include, exclude)?
+        .walk(&base_path_new, walk_type)
+        .collect()
 }
 
 #[cfg(test)]
@@ -0,0 +1,553 @@
+//! A matcher that compiles many include and exclude globs into a single
+//! program and walks the file system once for all of them.
+//!
+//! Walking each include glob separately means re-reading any directory that
+//! is shared between globs, and re-running every exclusion against each of
+//! those walks. For packages with dozens of `inputs` that adds up quickly.
+//! Instead, the invariant prefixes of the include globs are collected into a
+//! trie so that only the shallowest prefixes are walked, and every entry is
+//! matched against one compiled program for the includes and one for the
+//! excludes. Directories are only read if a component-by-component match of
+//! some include glob says something inside of them can match.
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    path::{Component, Path, PathBuf},
+};
+
+use itertools::Itertools;
+use turbopath::AbsoluteSystemPathBuf;
+use walkdir::{DirEntry, WalkDir};
+use wax::{Any, CandidatePath, Glob, Program};
+
+use crate::{WalkError, WalkType};
+
+#[derive(Clone, Debug)]
+pub struct MultiGlob {
+    // the invariant prefix of every include glob
+    prefixes: Vec<PathBuf>,
+    // when walking, rooted globs are matched against absolute paths and
+    // relative globs against paths relative to the base of the walk. Mixing
+    // the two isn't supported.
+    rooted: bool,
+    include: Option<Any<'static>>,
+    // one per include glob, to tell which directories can contain a match
+    component_matchers: Vec<ComponentMatcher>,
+    // excludes that match entire subtrees, e.g. `dist/**`, let us skip reading
+    // the matching directories altogether
+    exhaustive_exclude: Option<Any<'static>>,
+    exclude: Option<Any<'static>>,
+}
+
+impl MultiGlob {
+    pub fn new(
+        include: Vec<Glob<'static>>,
+        exclude: Vec<Glob<'static>>,
+    ) -> Result<Self, WalkError> {
+        let rooted = include.iter().any(is_rooted);
+        let prefixes = include
+            .iter()
+            .map(|glob| glob.clone().partition().0)
+            .collect();
+        let component_matchers = include.iter().map(ComponentMatcher::new).collect();
+        let (exhaustive_exclude, exclude): (Vec<_>, Vec<_>) =
+            exclude.into_iter().partition(|glob| glob.is_exhaustive());
+
+        Ok(Self {
+            prefixes,
+            rooted,
+            include: compile(include)?,
+            component_matchers,
+            exhaustive_exclude: compile(exhaustive_exclude)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    /// Returns `true` if the path matches an include glob. A `MultiGlob`
+    /// without any include globs doesn't match anything.
+    pub fn is_included(&self, path: impl AsRef<Path>) -> bool {
+        let candidate = CandidatePath::from(path.as_ref());
+        self.include
+            .as_ref()
+            .map_or(false, |include| include.is_match(candidate))
+    }
+
+    /// Returns `true` if the path matches an exclude glob.
+    pub fn is_excluded(&self, path: impl AsRef<Path>) -> bool {
+        let candidate = CandidatePath::from(path.as_ref());
+        [&self.exhaustive_exclude, &self.exclude]
+            .into_iter()
+            .flatten()
+            .any(|exclude| exclude.is_match(candidate.clone()))
+    }
+
+    pub fn is_match(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        self.is_included(path) && !self.is_excluded(path)
+    }
+
+    /// Walks every path under `base_path` that matches the globs. As with
+    /// `wax::Glob::walk`, rooted globs ignore `base_path`, relative globs are
+    /// matched against paths relative to it, and symbolic links are yielded
+    /// as files rather than followed.
+    pub fn walk<'a>(
+        &'a self,
+        base_path: &Path,
+        walk_type: WalkType,
+    ) -> impl Iterator<Item = Result<AbsoluteSystemPathBuf, WalkError>> + 'a {
+        let base_path = base_path.to_path_buf();
+        self.roots(&base_path).into_iter().flat_map(move |root| {
+            let base_path = base_path.clone();
+            self.walk_root(base_path.clone(), root)
+                .filter_map(move |entry| self.visit(&base_path, walk_type, entry))
+        })
+    }
+
+    /// Walks every entry under `root`, without reading excluded trees or
+    /// directories that can't contain a match
+    fn walk_root<'a>(
+        &'a self,
+        base_path: PathBuf,
+        root: PathBuf,
+    ) -> impl Iterator<Item = Result<DirEntry, walkdir::Error>> + 'a {
+        let mut entries = WalkDir::new(root)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry({
+                let base_path = base_path.clone();
+                move |entry| !self.is_excluded_tree(self.candidate(&base_path, entry.path()))
+            });
+        std::iter::from_fn(move || {
+            let entry = entries.next()?;
+            if let Ok(entry) = &entry {
+                if entry.file_type().is_dir()
+                    && !self.can_match_below(self.candidate(&base_path, entry.path()))
+                {
+                    // the directory itself is still yielded, it can be a match
+                    entries.skip_current_dir();
+                }
+            }
+            Some(entry)
+        })
+    }
+
+    /// Returns `true` if an include glob can match a path inside of `dir`
+    fn can_match_below(&self, dir: &Path) -> bool {
+        self.component_matchers
+            .iter()
+            .any(|matcher| matcher.can_match_below(dir))
+    }
+
+    /// The directories to start walking from. Any prefix that lives inside
+    /// another one is covered by the walk of its ancestor, so only the
+    /// shallowest prefixes of the trie are returned.
+    fn roots(&self, base_path: &Path) -> Vec<PathBuf> {
+        let mut trie = PrefixTrie::default();
+        // walkdir always follows a symlink at the root of a walk, so a prefix
+        // that is itself a symlink has to be walked on its own
+        let mut symlinks = Vec::new();
+        for prefix in &self.prefixes {
+            let root = anchor(base_path, prefix);
+            if root.is_symlink() {
+                symlinks.push(root);
+            } else {
+                trie.insert(&root);
+            }
+        }
+
+        let mut roots = Vec::new();
+        trie.collect_roots(&mut PathBuf::new(), &mut roots);
+        roots.extend(symlinks.into_iter().unique());
+        roots
+    }
+
+    fn candidate<'p>(&self, base_path: &Path, path: &'p Path) -> &'p Path {
+        if self.rooted {
+            path
+        } else {
+            path.strip_prefix(base_path).unwrap_or(path)
+        }
+    }
+
+    fn is_excluded_tree(&self, path: &Path) -> bool {
+        self.exhaustive_exclude
+            .as_ref()
+            .map_or(false, |exclude| exclude.is_match(path))
+    }
+
+    fn visit(
+        &self,
+        base_path: &Path,
+        walk_type: WalkType,
+        entry: Result<DirEntry, walkdir::Error>,
+    ) -> Option<Result<AbsoluteSystemPathBuf, WalkError>> {
+        match entry {
+            Ok(entry) if walk_type == WalkType::Files && entry.file_type().is_dir() => None,
+            Ok(entry) => {
+                let candidate = self.candidate(base_path, entry.path());
+                let is_match = self.is_included(candidate)
+                    && !self
+                        .exclude
+                        .as_ref()
+                        .map_or(false, |exclude| exclude.is_match(candidate));
+                is_match
+                    .then(|| AbsoluteSystemPathBuf::try_from(entry.path()).map_err(|e| e.into()))
+            }
+            Err(e) => match e.io_error().map(|io_err| io_err.kind()) {
+                // Ignore DNE and permission errors
+                Some(std::io::ErrorKind::NotFound | std::io::ErrorKind::PermissionDenied) => None,
+                _ => Some(Err(e.into())),
+            },
+        }
+    }
+}
+
+/// Returns `true` if the glob is matched against absolute paths. `wax` only
+/// treats a leading separator as a root, so Windows globs that start with a
+/// drive, like `C\:/repo/**`, are rooted too.
+fn is_rooted(glob: &Glob<'static>) -> bool {
+    glob.has_root() || (cfg!(windows) && has_drive_prefix(&glob.to_string()))
+}
+
+fn has_drive_prefix(glob: &str) -> bool {
+    let mut chars = glob.chars();
+    if !chars.next().map_or(false, |c| c.is_ascii_alphabetic()) {
+        return false;
+    }
+    let rest = chars.as_str();
+    let rest = rest.strip_prefix('\\').unwrap_or(rest);
+    rest.starts_with(":/")
+}
+
+fn compile(globs: Vec<Glob<'static>>) -> Result<Option<Any<'static>>, WalkError> {
+    if globs.is_empty() {
+        return Ok(None);
+    }
+    let patterns = globs.iter().join(", ");
+    wax::any(globs)
+        .map(Some)
+        .map_err(|e| WalkError::BadPattern(patterns, Box::new(e)))
+}
+
+/// Joins an invariant prefix onto the base path. Like `wax`, we don't let the
+/// walk root cross a symlinked directory below the base path, since the link
+/// wouldn't be followed if it was encountered during the walk.
+fn anchor(base_path: &Path, prefix: &Path) -> PathBuf {
+    if prefix.as_os_str().is_empty() {
+        return base_path.to_path_buf();
+    }
+    let root = base_path.join(prefix);
+    let mut last_symlink = None;
+    for ancestor in root.ancestors().skip(1) {
+        if ancestor == base_path {
+            break;
+        }
+        if ancestor.is_symlink() {
+            last_symlink = Some(ancestor);
+        }
+    }
+    last_symlink
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .unwrap_or(root)
+}
+
+/// Matches an include glob against a directory one path component at a time,
+/// to tell whether anything inside of the directory can match the glob.
+#[derive(Clone, Debug)]
+struct ComponentMatcher {
+    // the glob's components up to its first `**`
+    components: Vec<Glob<'static>>,
+    // whether the glob matches at any depth below its components
+    unbounded: bool,
+}
+
+impl ComponentMatcher {
+    fn new(glob: &Glob<'static>) -> Self {
+        let text = glob.to_string();
+        // Alternatives, repetitions and flags can span separators, so splitting
+        // a glob that uses them into components would change what it matches
+        if text.contains(['{', '<', '(']) {
+            return Self::unbounded();
+        }
+
+        let mut components = Vec::new();
+        // rooted globs start with a separator, rooted paths with a root
+        // component, and neither is matched
+        let text = text.strip_prefix('/').unwrap_or(&text);
+        for component in text.split('/') {
+            if component == "**" {
+                return Self {
+                    components,
+                    unbounded: true,
+                };
+            }
+            if component.is_empty() || component.contains("**") {
+                return Self::unbounded();
+            }
+            match Glob::new(component) {
+                Ok(glob) => components.push(glob.into_owned()),
+                Err(_) => return Self::unbounded(),
+            }
+        }
+        Self {
+            components,
+            unbounded: false,
+        }
+    }
+
+    fn unbounded() -> Self {
+        Self {
+            components: Vec::new(),
+            unbounded: true,
+        }
+    }
+
+    fn can_match_below(&self, dir: &Path) -> bool {
+        let mut depth = 0;
+        for component in dir.components() {
+            if matches!(component, Component::RootDir) {
+                continue;
+            }
+            let Some(glob) = self.components.get(depth) else {
+                return self.unbounded;
+            };
+            if !glob.is_match(CandidatePath::from(Path::new(component.as_os_str()))) {
+                return false;
+            }
+            depth += 1;
+        }
+        // a directory as deep as the glob can only be a match itself
+        self.unbounded || depth < self.components.len()
+    }
+}
+
+/// A trie of path components where a node marked as a root covers its entire
+/// subtree.
+#[derive(Debug, Default)]
+struct PrefixTrie {
+    is_root: bool,
+    children: BTreeMap<OsString, PrefixTrie>,
+}
+
+impl PrefixTrie {
+    fn insert(&mut self, path: &Path) {
+        let mut node = self;
+        for component in path.components() {
+            if node.is_root {
+                return;
+            }
+            node = node
+                .children
+                .entry(component.as_os_str().to_owned())
+                .or_default();
+        }
+        node.is_root = true;
+        node.children.clear();
+    }
+
+    fn collect_roots(&self, path: &mut PathBuf, roots: &mut Vec<PathBuf>) {
+        if self.is_root {
+            roots.push(path.clone());
+            return;
+        }
+        for (component, child) in &self.children {
+            path.push(component);
+            child.collect_roots(path, roots);
+            path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashSet,
+        path::{Path, PathBuf},
+    };
+
+    use test_case::test_case;
+    use turbopath::AbsoluteSystemPathBuf;
+    use wax::Glob;
+
+    use super::{has_drive_prefix, MultiGlob, PrefixTrie};
+
+    #[cfg(unix)]
+    const ROOT: &str = "/";
+    #[cfg(windows)]
+    const ROOT: &str = "C:\\";
+    #[cfg(unix)]
+    const GLOB_ROOT: &str = "/";
+    #[cfg(windows)]
+    const GLOB_ROOT: &str = "C\\:/";
+
+    fn multi_glob(include: &[&str], exclude: &[&str]) -> MultiGlob {
+        let globs = |globs: &[&str]| {
+            globs
+                .iter()
+                .map(|glob| Glob::new(glob).unwrap().into_owned())
+                .collect()
+        };
+        MultiGlob::new(globs(include), globs(exclude)).unwrap()
+    }
+
+    #[test_case(&["a/b", "a/b/c", "a/d"], &["a/b", "a/d"] ; "nested prefix")]
+    #[test_case(&["a/b/c", "a/b"], &["a/b"] ; "shallower prefix inserted last")]
+    #[test_case(&["a", "ab"], &["a", "ab"] ; "shared text is not a shared component")]
+    #[test_case(&["", "a/b"], &[""] ; "empty prefix covers everything")]
+    fn test_prefix_trie(prefixes: &[&str], expected: &[&str]) {
+        let mut trie = PrefixTrie::default();
+        for prefix in prefixes {
+            trie.insert(Path::new(prefix));
+        }
+        let mut roots = Vec::new();
+        trie.collect_roots(&mut PathBuf::new(), &mut roots);
+        assert_eq!(
+            roots,
+            expected.iter().map(PathBuf::from).collect::<Vec<_>>()
+        );
+    }
+
+    #[test_case("src/index.ts", true ; "included")]
+    #[test_case("src/index.test.ts", false ; "excluded file")]
+    #[test_case("src/fixtures/data.ts", false ; "excluded tree")]
+    #[test_case("README.md", true ; "second include")]
+    #[test_case("dist/index.js", false ; "not included")]
+    fn test_is_match(path: &str, expected: bool) {
+        let globs = multi_glob(
+            &["src/**/*.ts", "README.md"],
+            &["**/*.test.ts", "src/fixtures/**"],
+        );
+        assert_eq!(globs.is_match(path), expected);
+    }
+
+    #[test_case("src/*.ts", "", true ; "base of the walk")]
+    #[test_case("src/*.ts", "src", true ; "parent of a match")]
+    #[test_case("src/*.ts", "src/nested", false ; "deeper than the glob")]
+    #[test_case("src/*/b.ts", "src/nested", true ; "wildcard component")]
+    #[test_case("src/*/b.ts", "lib", false ; "different component")]
+    #[test_case("src/**/b.ts", "src/a/b/c", true ; "any depth")]
+    #[test_case("src/**/b.ts", "lib/a", false ; "any depth below another directory")]
+    #[test_case("{src,lib}/*.ts", "other/deep", true ; "alternatives aren't split")]
+    fn test_can_match_below(glob: &str, dir: &str, expected: bool) {
+        let globs = multi_glob(&[glob], &[]);
+        assert_eq!(globs.can_match_below(Path::new(dir)), expected);
+    }
+
+    #[test_case("repo/src", true ; "parent of a match")]
+    #[test_case("repo/src/nested", false ; "deeper than the glob")]
+    #[test_case("other", false ; "different directory")]
+    fn test_can_match_below_rooted(dir: &str, expected: bool) {
+        let globs = multi_glob(&[&format!("{GLOB_ROOT}repo/src/*.ts")], &[]);
+        assert!(globs.rooted);
+        assert_eq!(globs.can_match_below(&Path::new(ROOT).join(dir)), expected);
+    }
+
+    #[test_case("C\\:/repo/**", true ; "escaped")]
+    #[test_case("c:/repo", true ; "unescaped")]
+    #[test_case("C\\:", false ; "no separator")]
+    #[test_case("/repo/**", false ; "unix root")]
+    #[test_case("src/**", false ; "relative")]
+    fn test_has_drive_prefix(glob: &str, expected: bool) {
+        assert_eq!(has_drive_prefix(glob), expected);
+    }
+
+    #[test]
+    fn test_walk_rooted() {
+        // globwalk joins the base path onto every glob, so they're all rooted
+        let dir = tempdir::TempDir::new("multi-glob").unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(dir.path()).unwrap();
+        for file in ["src/a.ts", "src/nested/b.ts"] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::File::create(path).unwrap();
+        }
+
+        let files = crate::globwalk_internal(
+            &root,
+            &["src/*.ts".to_string()],
+            &[],
+            crate::WalkType::Files,
+        )
+        .unwrap();
+        assert_eq!(
+            files,
+            HashSet::from([root.join_components(&["src", "a.ts"])])
+        );
+    }
+
+    #[test]
+    fn test_walk_does_not_descend_below_globs() {
+        let dir = tempdir::TempDir::new("multi-glob").unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        for file in ["src/a.ts", "src/nested/b.ts", "src/nested/deeper/c.ts"] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::File::create(path).unwrap();
+        }
+        let globs = multi_glob(&["src/*.ts", "src/*/b.ts"], &[]);
+
+        // src/nested/deeper is too deep for either glob, so it isn't read
+        let mut visited = globs
+            .walk_root(root.clone(), root.join("src"))
+            .map(|entry| {
+                entry
+                    .unwrap()
+                    .path()
+                    .strip_prefix(&root)
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect::<Vec<_>>();
+        visited.sort();
+        assert_eq!(
+            visited,
+            [
+                "src",
+                "src/a.ts",
+                "src/nested",
+                "src/nested/b.ts",
+                "src/nested/deeper"
+            ]
+            .iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_no_includes_matches_nothing() {
+        let globs = multi_glob(&[], &[]);
+        assert!(!globs.is_match("anything"));
+    }
+
+    #[test]
+    fn test_walk_prunes_excluded_trees() {
+        let dir = tempdir::TempDir::new("multi-glob").unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        for file in ["src/a.ts", "src/b.ts", "src/gen/c.ts", "lib/d.ts"] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::File::create(path).unwrap();
+        }
+        let globs = multi_glob(&["src/**", "src/a.ts"], &["src/gen/**"]);
+        assert_eq!(globs.roots(&root), vec![root.join("src")]);
+
+        let mut files = globs
+            .walk(&root, crate::WalkType::Files)
+            .map(|path| {
+                path.unwrap()
+                    .as_std_path()
+                    .strip_prefix(&root)
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![PathBuf::from("src/a.ts"), PathBuf::from("src/b.ts")]
+        );
+    }
+}
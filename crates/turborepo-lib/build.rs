@@ -3,8 +3,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .build_server(true)
         .file_descriptor_set_path("src/daemon/file_descriptor_set.bin")
         .compile(
-            &["./src/daemon/proto/turbod.proto"],
-            &["./src/daemon/proto"],
+            &[
+                "./src/daemon/proto/turbod.proto",
+                "./src/distributed/proto/turbow.proto",
+            ],
+            &["./src/daemon/proto", "./src/distributed/proto"],
         );
     let capnpc_result = capnpc::CompilerCommand::new()
         .file("./src/hash/proto.capnp")
@@ -5,7 +5,7 @@ use thiserror::Error;
 use turborepo_repository::package_graph;
 
 use crate::{
-    commands::{bin, generate, prune},
+    commands::{bin, generate, migrate, prune, worker},
     daemon::DaemonError,
     rewrite_json::RewriteError,
     run,
@@ -37,6 +37,8 @@ pub enum Error {
     #[error(transparent)]
     Generate(#[from] generate::Error),
     #[error(transparent)]
+    Migrate(#[from] migrate::Error),
+    #[error(transparent)]
     Prune(#[from] prune::Error),
     #[error(transparent)]
     PackageJson(#[from] turborepo_repository::package_json::Error),
@@ -47,4 +49,6 @@ pub enum Error {
     Run(#[from] run::Error),
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
+    #[error(transparent)]
+    Worker(#[from] worker::Error),
 }
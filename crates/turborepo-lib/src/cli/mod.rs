@@ -2,11 +2,12 @@ use std::{backtrace, backtrace::Backtrace, env, fmt, fmt::Display, io, mem, proc
 
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::{
-    builder::NonEmptyStringValueParser, ArgAction, ArgGroup, CommandFactory, Parser, Subcommand,
-    ValueEnum,
+    builder::{NonEmptyStringValueParser, PossibleValuesParser, TypedValueParser},
+    ArgAction, ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum,
 };
 use clap_complete::{generate, Shell};
 pub use error::Error;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error};
 use turbopath::AbsoluteSystemPathBuf;
@@ -24,12 +25,13 @@ use turborepo_ui::UI;
 
 use crate::{
     commands::{
-        bin, daemon, generate, info, link, login, logout, prune, run, telemetry, unlink,
-        CommandBase,
+        bin, daemon, doctor, generate, info, link, login, logout, migrate, prune, run, telemetry,
+        unlink, worker, CommandBase,
     },
     get_version,
     shim::TurboState,
     tracing::TurboSubscriber,
+    turbo_json::turbo_json_schema,
 };
 
 mod error;
@@ -39,11 +41,11 @@ mod error;
 pub const INVOCATION_DIR_ENV_VAR: &str = "TURBO_INVOCATION_DIR";
 
 // Default value for the --cache-workers argument
-const DEFAULT_NUM_WORKERS: u32 = 10;
+pub(crate) const DEFAULT_NUM_WORKERS: u32 = 10;
 const SUPPORTED_GRAPH_FILE_EXTENSIONS: [&str; 8] =
     ["svg", "png", "jpg", "pdf", "json", "html", "mermaid", "dot"];
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema, ValueEnum)]
 pub enum OutputLogsMode {
     #[serde(rename = "full")]
     Full,
@@ -101,6 +103,42 @@ impl Display for LogOrder {
     }
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    #[serde(rename = "raw")]
+    Raw,
+    #[serde(rename = "structured")]
+    Structured,
+}
+
+impl Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LogFormat::Raw => "raw",
+            LogFormat::Structured => "structured",
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, ValueEnum)]
+pub enum RunOutputFormat {
+    #[default]
+    #[serde(rename = "text")]
+    Text,
+    #[serde(rename = "ndjson")]
+    Ndjson,
+}
+
+impl Display for RunOutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RunOutputFormat::Text => "text",
+            RunOutputFormat::Ndjson => "ndjson",
+        })
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, ValueEnum)]
 pub enum DryRunMode {
     Text,
@@ -116,7 +154,28 @@ impl Display for DryRunMode {
     }
 }
 
-#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, ValueEnum)]
+/// What `--summarize` writes to `.turbo/runs`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SummarizeMode {
+    #[default]
+    Disabled,
+    Json,
+    Html,
+}
+
+fn summarize_mode_parser() -> impl TypedValueParser<Value = SummarizeMode> {
+    PossibleValuesParser::new(["true", "false", "json", "html"]).map(|s| match s.as_str() {
+        "json" | "true" => SummarizeMode::Json,
+        "html" => SummarizeMode::Html,
+        _ => SummarizeMode::Disabled,
+    })
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
 pub enum EnvMode {
     #[default]
     Infer,
@@ -256,6 +315,8 @@ pub enum DaemonCommand {
     Clean,
     /// Shows the daemon logs
     Logs,
+    /// Streams the packages affected by file changes as JSON lines
+    Watch,
 }
 
 #[derive(Subcommand, Copy, Clone, Debug, Serialize, PartialEq)]
@@ -316,6 +377,15 @@ impl Args {
                     run_args.single_package = is_single_package;
                 }
 
+                // Workers run tasks, so they need it too
+                if let Some(Command::Worker {
+                    ref mut single_package,
+                    ..
+                }) = args.command
+                {
+                    *single_package = is_single_package;
+                }
+
                 args
             }
             // Don't use error logger when displaying help text
@@ -432,6 +502,15 @@ pub enum Command {
         #[serde(flatten)]
         command: Option<DaemonCommand>,
     },
+    /// Check your repository for common problems
+    Doctor {
+        /// Override the filesystem cache directory to check
+        #[clap(long)]
+        cache_dir: Option<Utf8PathBuf>,
+        /// Output the results of each check as JSON
+        #[clap(long)]
+        json: bool,
+    },
     /// Generate a new app / package
     #[clap(aliases = ["g", "gen"])]
     Generate {
@@ -461,6 +540,9 @@ pub enum Command {
         #[serde(flatten)]
         command: Option<TelemetryCommand>,
     },
+    /// Print the JSON schema for turbo.json
+    #[clap(hide = true)]
+    Schema {},
     #[clap(hide = true)]
     Info {
         workspace: Option<String>,
@@ -486,6 +568,16 @@ pub enum Command {
     },
     /// Logout to your Vercel account
     Logout {},
+    /// Apply migrations for deprecated turbo.json options
+    Migrate {
+        /// The version of turbo the repository was last migrated for. Only
+        /// migrations introduced after it are applied (default: all)
+        #[clap(long)]
+        from: Option<String>,
+        /// Print the migrations that would be applied without writing them
+        #[clap(long)]
+        dry_run: bool,
+    },
     /// Prepare a subset of your monorepo.
     Prune {
         #[clap(hide = true, long)]
@@ -520,6 +612,25 @@ pub enum Command {
         #[clap(long, value_enum, default_value_t = LinkTarget::RemoteCache)]
         target: LinkTarget,
     },
+    /// Execute tasks dispatched by `turbo run --workers`
+    Worker {
+        /// The address to listen for coordinators on
+        #[clap(long, default_value_t = String::from("127.0.0.1:4000"))]
+        listen: String,
+        /// How many tasks to run at once (default number of CPUs)
+        #[clap(long)]
+        concurrency: Option<u32>,
+        /// Override the filesystem cache directory.
+        #[clap(long)]
+        cache_dir: Option<Utf8PathBuf>,
+        /// The token coordinators must present to run tasks on this worker
+        #[clap(long, env = "TURBO_WORKER_TOKEN", hide_env_values = true)]
+        #[serde(skip)]
+        worker_token: Option<String>,
+        /// Run tasks in single-package mode
+        #[clap(long)]
+        single_package: bool,
+    },
 }
 
 #[derive(Parser, Clone, Debug, Default, Serialize, PartialEq)]
@@ -743,9 +854,15 @@ pub struct RunArgs {
     #[clap(long, env = "TURBO_REMOTE_CACHE_READ_ONLY", value_name = "BOOL", action = ArgAction::Set, default_value = "false", default_missing_value = "true", num_args = 0..=1)]
     #[serde(skip)]
     pub remote_cache_read_only: bool,
-    /// Generate a summary of the turbo run
-    #[clap(long, env = "TURBO_RUN_SUMMARY", default_missing_value = "true")]
-    pub summarize: Option<Option<bool>>,
+    /// Write the outputs of cache hits to disk only once a task that depends
+    /// on them executes, or when the run finishes. Tasks whose logs are
+    /// replayed are still restored right away.
+    #[clap(long, env = "TURBO_LAZY_RESTORE", value_name = "BOOL", action = ArgAction::Set, default_value = "false", default_missing_value = "true", num_args = 0..=1)]
+    pub lazy_restore: bool,
+    /// Generate a summary of the turbo run, use "html" for an interactive
+    /// report
+    #[clap(long, env = "TURBO_RUN_SUMMARY", default_missing_value = "true", value_parser = summarize_mode_parser())]
+    pub summarize: Option<Option<SummarizeMode>>,
 
     /// Use "none" to remove prefixes from task logs. Use "task" to get task id
     /// prefixing. Use "auto" to let turbo decide how to prefix the logs
@@ -756,6 +873,27 @@ pub struct RunArgs {
     /// auto)
     #[clap(long, value_enum, default_value_t = LogPrefix::Auto)]
     pub log_prefix: LogPrefix,
+    /// Set the format of the task log files written to .turbo. Use "structured"
+    /// to record the stream and time of every line as JSON, which lets
+    /// --output-logs=errors-only show just the stderr of failed tasks.
+    /// (default raw)
+    #[clap(long, env = "TURBO_LOG_FORMAT", value_enum, default_value_t = LogFormat::Raw)]
+    pub log_format: LogFormat,
+    /// Set how turbo reports the run on stdout. Use "ndjson" to print one
+    /// JSON event per line as tasks are queued, started, hit or miss the
+    /// cache, log and finish, instead of the usual human readable output.
+    /// Task logs are still written to their log files. (default text)
+    #[clap(long, value_enum, default_value_t = RunOutputFormat::Text)]
+    pub output: RunOutputFormat,
+    /// Addresses of `turbo worker` processes to run tasks that miss the
+    /// cache on, separated by commas
+    #[clap(long, value_delimiter = ',')]
+    pub workers: Vec<String>,
+    /// The token to present to workers, which must match their
+    /// --worker-token
+    #[clap(long, env = "TURBO_WORKER_TOKEN", hide_env_values = true)]
+    #[serde(skip)]
+    pub worker_token: Option<String>,
 
     // NOTE: The following two are hidden because clap displays them in the help text incorrectly:
     // > Usage: turbo [OPTIONS] [TASKS]... [-- <FORWARDED_ARGS>...] [COMMAND]
@@ -807,6 +945,7 @@ impl RunArgs {
         track_usage!(telemetry, self.parallel, |val| val);
         track_usage!(telemetry, self.remote_only, |val| val);
         track_usage!(telemetry, self.remote_cache_read_only, |val| val);
+        track_usage!(telemetry, self.lazy_restore, |val| val);
 
         // default to None
         track_usage!(telemetry, &self.cache_dir, Option::is_some);
@@ -817,6 +956,12 @@ impl RunArgs {
         track_usage!(telemetry, &self.anon_profile, Option::is_some);
         track_usage!(telemetry, &self.summarize, Option::is_some);
         track_usage!(telemetry, &self.experimental_space_id, Option::is_some);
+        track_usage!(telemetry, &self.worker_token, Option::is_some);
+
+        // default to empty
+        if !self.workers.is_empty() {
+            telemetry.track_arg_usage("workers", true);
+        }
 
         // track values
         if let Some(dry_run) = &self.dry_run {
@@ -857,6 +1002,14 @@ impl RunArgs {
             telemetry.track_arg_value("log-prefix", self.log_prefix, EventType::NonSensitive);
         }
 
+        if self.log_format != LogFormat::default() {
+            telemetry.track_arg_value("log-format", self.log_format, EventType::NonSensitive);
+        }
+
+        if self.output != RunOutputFormat::default() {
+            telemetry.track_arg_value("output", self.output, EventType::NonSensitive);
+        }
+
         // track sizes
         if !self.filter.is_empty() {
             telemetry.track_arg_value("filter:length", self.filter.len(), EventType::NonSensitive);
@@ -1044,6 +1197,15 @@ pub async fn run(
 
             Ok(0)
         }
+        Command::Doctor { cache_dir, json } => {
+            CommandEventBuilder::new("doctor")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let json = *json;
+            let cache_dir = cache_dir.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            doctor::run(&base, cache_dir.as_deref(), json).await
+        }
         Command::Generate {
             tag,
             generator_name,
@@ -1115,6 +1277,23 @@ pub async fn run(
 
             Ok(0)
         }
+        Command::Migrate { from, dry_run } => {
+            CommandEventBuilder::new("migrate")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let dry_run = *dry_run;
+            let from = from.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            migrate::run(&base, from.as_deref(), dry_run).await
+        }
+        Command::Schema {} => {
+            CommandEventBuilder::new("schema")
+                .with_parent(&root_telemetry)
+                .track_call();
+            println!("{}", serde_json::to_string_pretty(&turbo_json_schema())?);
+
+            Ok(0)
+        }
         Command::Login { sso_team } => {
             let event = CommandEventBuilder::new("login").with_parent(&root_telemetry);
             event.track_call();
@@ -1196,6 +1375,33 @@ pub async fn run(
             prune::prune(&base, &scope, docker, &output_dir, event_child).await?;
             Ok(0)
         }
+        Command::Worker {
+            listen,
+            concurrency,
+            cache_dir,
+            worker_token,
+            single_package,
+        } => {
+            CommandEventBuilder::new("worker")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let listen = listen.clone();
+            let concurrency = *concurrency;
+            let cache_dir = cache_dir.clone();
+            let worker_token = worker_token.clone();
+            let single_package = *single_package;
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            worker::worker(
+                &base,
+                &listen,
+                concurrency,
+                cache_dir,
+                worker_token.as_deref(),
+                single_package,
+            )
+            .await?;
+            Ok(0)
+        }
         Command::Completion { shell } => {
             CommandEventBuilder::new("completion")
                 .with_parent(&root_telemetry)
@@ -1280,7 +1486,8 @@ mod test {
     use anyhow::Result;
 
     use crate::cli::{
-        Args, Command, DryRunMode, EnvMode, LogOrder, LogPrefix, OutputLogsMode, RunArgs, Verbosity,
+        Args, Command, DryRunMode, EnvMode, LogFormat, LogOrder, LogPrefix, OutputLogsMode,
+        RunArgs, RunOutputFormat, SummarizeMode, Verbosity,
     };
 
     #[test_case::test_case(
@@ -1527,6 +1734,28 @@ mod test {
             ..Args::default()
         }
 	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--summarize"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                summarize: Some(Some(SummarizeMode::Json)),
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        }
+	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--summarize=html"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                summarize: Some(Some(SummarizeMode::Html)),
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        }
+	)]
     #[test_case::test_case(
 		&["turbo", "run", "build", "--force"],
         Args {
@@ -1755,6 +1984,28 @@ mod test {
             ..Args::default()
         }
 	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--log-format", "structured"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                log_format: LogFormat::Structured,
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        }
+	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--output", "ndjson"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                output: RunOutputFormat::Ndjson,
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        }
+	)]
     #[test_case::test_case(
 		&["turbo", "run", "build"],
         Args {
@@ -1801,6 +2052,18 @@ mod test {
 		} ;
         "remote_only default to false"
 	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--workers", "127.0.0.1:4000,10.0.0.2:4000"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                workers: vec!["127.0.0.1:4000".to_string(), "10.0.0.2:4000".to_string()],
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+		} ;
+        "workers are comma separated"
+	)]
     #[test_case::test_case(
 		&["turbo", "run", "build", "--remote-only"],
         Args {
@@ -1837,6 +2100,18 @@ mod test {
 		} ;
         "remote_only=false works"
 	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--lazy-restore"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                lazy_restore: true,
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+		} ;
+        "lazy_restore with no value, means true"
+	)]
     #[test_case::test_case(
 		&["turbo", "run", "build", "--scope", "foo", "--scope", "bar"],
         Args {
@@ -1949,6 +2224,62 @@ mod test {
         .test();
     }
 
+    #[test]
+    fn test_parse_doctor() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "doctor"]).unwrap(),
+            Args {
+                command: Some(Command::Doctor {
+                    cache_dir: None,
+                    json: false,
+                }),
+                ..Args::default()
+            }
+        );
+
+        CommandTestCase {
+            command: "doctor",
+            command_args: vec![vec!["--json"], vec!["--cache-dir", "cache"]],
+            global_args: vec![],
+            expected_output: Args {
+                command: Some(Command::Doctor {
+                    cache_dir: Some(Utf8PathBuf::from("cache")),
+                    json: true,
+                }),
+                ..Args::default()
+            },
+        }
+        .test();
+    }
+
+    #[test]
+    fn test_parse_migrate() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "migrate"]).unwrap(),
+            Args {
+                command: Some(Command::Migrate {
+                    from: None,
+                    dry_run: false,
+                }),
+                ..Args::default()
+            }
+        );
+
+        CommandTestCase {
+            command: "migrate",
+            command_args: vec![vec!["--dry-run"], vec!["--from", "1.5.0"]],
+            global_args: vec![],
+            expected_output: Args {
+                command: Some(Command::Migrate {
+                    from: Some("1.5.0".to_string()),
+                    dry_run: true,
+                }),
+                ..Args::default()
+            },
+        }
+        .test();
+    }
+
     #[test]
     fn test_parse_login() {
         assert_eq!(
@@ -2036,6 +2367,47 @@ mod test {
         .test();
     }
 
+    #[test]
+    fn test_parse_worker() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "worker"]).unwrap(),
+            Args {
+                command: Some(Command::Worker {
+                    listen: "127.0.0.1:4000".to_string(),
+                    concurrency: None,
+                    cache_dir: None,
+                    worker_token: None,
+                    single_package: false,
+                }),
+                ..Args::default()
+            }
+        );
+
+        assert_eq!(
+            Args::try_parse_from([
+                "turbo",
+                "worker",
+                "--listen",
+                "0.0.0.0:5000",
+                "--concurrency",
+                "4",
+                "--worker-token",
+                "secret"
+            ])
+            .unwrap(),
+            Args {
+                command: Some(Command::Worker {
+                    listen: "0.0.0.0:5000".to_string(),
+                    concurrency: Some(4),
+                    cache_dir: None,
+                    worker_token: Some("secret".to_string()),
+                    single_package: false,
+                }),
+                ..Args::default()
+            }
+        );
+    }
+
     #[test]
     fn test_parse_prune() {
         let default_prune = Command::Prune {
@@ -15,7 +15,8 @@ use super::CommandBase;
 use crate::{
     cli::DaemonCommand,
     daemon::{
-        endpoint::SocketOpenError, CloseReason, DaemonConnector, DaemonConnectorError, DaemonError,
+        endpoint::SocketOpenError, proto, CloseReason, DaemonConnector, DaemonConnectorError,
+        DaemonError,
     },
     tracing::TurboSubscriber,
 };
@@ -28,7 +29,7 @@ pub async fn daemon_client(command: &DaemonCommand, base: &CommandBase) -> Resul
     let (can_start_server, can_kill_server) = match command {
         DaemonCommand::Status { .. } | DaemonCommand::Logs => (false, false),
         DaemonCommand::Stop => (false, true),
-        DaemonCommand::Restart | DaemonCommand::Start => (true, true),
+        DaemonCommand::Restart | DaemonCommand::Start | DaemonCommand::Watch => (true, true),
         DaemonCommand::Clean => (false, true),
     };
 
@@ -40,6 +41,7 @@ pub async fn daemon_client(command: &DaemonCommand, base: &CommandBase) -> Resul
         can_kill_server,
         pid_file: pid_file.clone(),
         sock_file: sock_file.clone(),
+        repo_root: base.repo_root.clone(),
     };
 
     match command {
@@ -105,6 +107,8 @@ pub async fn daemon_client(command: &DaemonCommand, base: &CommandBase) -> Resul
                 log_file: log_file.into(),
                 pid_file: client.pid_file().to_owned(),
                 sock_file: client.sock_file().to_owned(),
+                memory_bytes: status.memory_bytes,
+                repos: status.repos.into_iter().map(RepoStatus::from).collect(),
             };
 
             if *json {
@@ -126,6 +130,26 @@ pub async fn daemon_client(command: &DaemonCommand, base: &CommandBase) -> Resul
                     "socket file: {}",
                     color!(base.ui, GREY, "{}", status.sock_file)
                 );
+                println!(
+                    "memory: {}",
+                    color!(base.ui, GREY, "{}MiB", status.memory_bytes / (1024 * 1024))
+                );
+                println!("repositories: {}", status.repos.len());
+                for repo in &status.repos {
+                    println!(
+                        "  {} {}",
+                        repo.repo_root,
+                        color!(
+                            base.ui,
+                            GREY,
+                            "({} directories, {:.1} events/min, ~{}MiB, idle {})",
+                            repo.watched_directories,
+                            repo.events_per_minute,
+                            repo.memory_bytes / (1024 * 1024),
+                            humantime::format_duration(Duration::from_secs(repo.idle_ms / 1000))
+                        )
+                    );
+                }
             }
         }
         DaemonCommand::Logs => {
@@ -139,6 +163,23 @@ pub async fn daemon_client(command: &DaemonCommand, base: &CommandBase) -> Resul
                 .status()
                 .expect("failed to execute tail");
         }
+        DaemonCommand::Watch => {
+            let mut client = connector.connect().await?;
+            let mut changes = client.watch_changes().await?;
+            while let Some(change) = changes.message().await? {
+                let event = match change.event {
+                    Some(proto::change_event::Event::PackagesChanged(changed)) => {
+                        json!({ "event": "packagesChanged", "packages": changed.packages })
+                    }
+                    Some(proto::change_event::Event::RediscoverPackages(_)) => {
+                        json!({ "event": "rediscoverPackages" })
+                    }
+                    // an event from a newer daemon that we don't know about
+                    None => continue,
+                };
+                println!("{event}");
+            }
+        }
         DaemonCommand::Clean => {
             // try to connect and shutdown the daemon
             let client = connector.connect().await;
@@ -222,9 +263,9 @@ pub async fn daemon_server(
             AbsoluteSystemPathBuf::new(directories.data_dir().to_str().expect("UTF-8 path"))
                 .expect("absolute");
 
+        // one daemon serves all of a user's repositories, so it has one log
         let log_folder = folder.join_component("logs");
-        let log_file =
-            log_folder.join_component(format!("{}-turbo.log", base.repo_hash()).as_str());
+        let log_file = log_folder.join_component("turbod.log");
 
         (log_folder, log_file)
     };
@@ -249,15 +290,9 @@ pub async fn daemon_server(
         }
         CloseReason::Interrupt
     });
-    let server = crate::daemon::TurboGrpcService::new(
-        base.repo_root.clone(),
-        daemon_root,
-        log_file,
-        timeout,
-        exit_signal,
-    );
+    let server = crate::daemon::TurboGrpcService::new(daemon_root, log_file, timeout, exit_signal);
 
-    let reason = server.serve().await?;
+    let reason = server.serve().await;
 
     match reason {
         CloseReason::SocketOpenError(SocketOpenError::LockError(AlreadyOwned)) => {
@@ -285,4 +320,37 @@ pub struct DaemonStatus {
     pub log_file: Utf8PathBuf,
     pub pid_file: turbopath::AbsoluteSystemPathBuf,
     pub sock_file: turbopath::AbsoluteSystemPathBuf,
+    // the whole process, each repository's share is in its status
+    pub memory_bytes: u64,
+    pub repos: Vec<RepoStatus>,
+}
+
+#[derive(serde::Serialize)]
+pub struct RepoStatus {
+    pub repo_root: String,
+    pub uptime_ms: u64,
+    pub idle_ms: u64,
+    pub watching: bool,
+    pub watched_directories: u64,
+    pub events: u64,
+    pub events_per_minute: f64,
+    pub errors: u64,
+    // approximate, measured while the repository started being watched
+    pub memory_bytes: u64,
+}
+
+impl From<proto::RepoStatus> for RepoStatus {
+    fn from(status: proto::RepoStatus) -> Self {
+        Self {
+            repo_root: status.repo_root,
+            uptime_ms: status.uptime_msec,
+            idle_ms: status.idle_msec,
+            watching: status.watching,
+            watched_directories: status.watched_directories,
+            events: status.events,
+            events_per_minute: status.events_per_minute,
+            errors: status.errors,
+            memory_bytes: status.memory_bytes,
+        }
+    }
 }
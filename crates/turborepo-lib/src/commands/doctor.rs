@@ -0,0 +1,471 @@
+//! A command for diagnosing common problems with a repository, like a daemon
+//! that won't start, a cache directory turbo can't write to, or turbo.json
+//! tasks that will never run. Each check reports independently so one
+//! failing check doesn't hide the others.
+use std::{collections::HashSet, fs, time::Duration};
+
+use camino::Utf8Path;
+use serde::Serialize;
+use turbopath::{AbsoluteSystemPath, AnchoredSystemPath};
+use turborepo_api_client::Client;
+use turborepo_cache::fs::FSCache;
+use turborepo_repository::{
+    package_graph::{PackageGraph, WorkspaceName},
+    package_json::PackageJson,
+};
+use turborepo_ui::{color, BOLD_GREEN, BOLD_RED, GREY, YELLOW};
+use turborepo_vercel_api::CachingStatus;
+
+use crate::{
+    cli,
+    commands::CommandBase,
+    daemon::{DaemonConnector, DaemonConnectorError},
+    turbo_json::{package_graph_builder, TurboJson},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Ok,
+    Warning,
+    Error,
+    Skipped,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Check {
+    name: &'static str,
+    status: Status,
+    message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    details: Vec<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            message: message.into(),
+            details: Vec::new(),
+        }
+    }
+
+    fn with_details(mut self, details: Vec<String>) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DoctorReport {
+    checks: Vec<Check>,
+}
+
+pub async fn run(
+    base: &CommandBase,
+    cache_dir: Option<&Utf8Path>,
+    json: bool,
+) -> Result<i32, cli::Error> {
+    let mut checks = vec![
+        check_daemon(base).await,
+        check_cache_dir(&base.repo_root, cache_dir),
+        check_remote_cache(base).await?,
+    ];
+    checks.extend(check_repository(&base.repo_root).await?);
+
+    let report = DoctorReport { checks };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        report.print(base);
+    }
+
+    let has_errors = report
+        .checks
+        .iter()
+        .any(|check| check.status == Status::Error);
+    Ok(if has_errors { 1 } else { 0 })
+}
+
+impl DoctorReport {
+    fn print(&self, base: &CommandBase) {
+        for check in &self.checks {
+            let symbol = match check.status {
+                Status::Ok => color!(base.ui, BOLD_GREEN, "✓"),
+                Status::Warning => color!(base.ui, YELLOW, "!"),
+                Status::Error => color!(base.ui, BOLD_RED, "x"),
+                Status::Skipped => color!(base.ui, GREY, "-"),
+            };
+            println!("{} {}: {}", symbol, check.name, check.message);
+            for detail in &check.details {
+                println!("    {}", color!(base.ui, GREY, "{}", detail));
+            }
+        }
+    }
+}
+
+async fn check_daemon(base: &CommandBase) -> Check {
+    const NAME: &str = "daemon";
+
+    let connector = DaemonConnector {
+        can_start_server: false,
+        can_kill_server: false,
+        pid_file: base.daemon_file_root().join_component("turbod.pid"),
+        sock_file: base.daemon_file_root().join_component("turbod.sock"),
+        repo_root: base.repo_root.clone(),
+    };
+    let mut client = match connector.connect().await {
+        Ok(client) => client,
+        Err(DaemonConnectorError::NotRunning) => {
+            return Check::new(
+                NAME,
+                Status::Warning,
+                "not running, run `turbo daemon start` to start it",
+            )
+        }
+        Err(e) => return Check::new(NAME, Status::Error, format!("unable to connect: {e}")),
+    };
+
+    match client.status().await {
+        Ok(status) => Check::new(
+            NAME,
+            Status::Ok,
+            format!(
+                "running for {}",
+                humantime::format_duration(Duration::from_secs(status.uptime_msec / 1000))
+            ),
+        )
+        .with_details(vec![format!("log file: {}", status.log_file)]),
+        Err(e) => Check::new(NAME, Status::Error, format!("unable to get status: {e}")),
+    }
+}
+
+fn check_cache_dir(repo_root: &AbsoluteSystemPath, cache_dir: Option<&Utf8Path>) -> Check {
+    const NAME: &str = "cache directory";
+
+    let cache_dir = FSCache::resolve_cache_dir(repo_root, cache_dir);
+    if !cache_dir.exists() {
+        return Check::new(
+            NAME,
+            Status::Ok,
+            format!("{cache_dir} will be created on the first run"),
+        );
+    }
+
+    // Check that the directory is writable by writing to it rather than
+    // interpreting its permissions, which differ between platforms
+    let probe = cache_dir.join_component(&format!(".turbo-doctor-{}", std::process::id()));
+    if let Err(e) = probe.create_with_contents("") {
+        return Check::new(
+            NAME,
+            Status::Error,
+            format!("{cache_dir} is not writable: {e}"),
+        );
+    }
+    let _ = probe.remove_file();
+
+    let size = dir_size(cache_dir.as_std_path());
+    Check::new(
+        NAME,
+        Status::Ok,
+        format!(
+            "{cache_dir} is writable and uses {}MiB",
+            size / (1024 * 1024)
+        ),
+    )
+}
+
+fn dir_size(path: &std::path::Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map_or(0, |metadata| metadata.len()),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+async fn check_remote_cache(base: &CommandBase) -> Result<Check, cli::Error> {
+    const NAME: &str = "remote cache";
+
+    let Some(api_auth) = base.api_auth()? else {
+        return Ok(Check::new(NAME, Status::Skipped, "not logged in"));
+    };
+    if api_auth.team_id.is_none() && api_auth.team_slug.is_none() {
+        return Ok(Check::new(
+            NAME,
+            Status::Warning,
+            "logged in but not linked, run `turbo link` to enable remote caching",
+        ));
+    }
+
+    let api_client = base.api_client()?;
+    let check = match api_client
+        .get_caching_status(
+            &api_auth.token,
+            api_auth.team_id.as_deref(),
+            api_auth.team_slug.as_deref(),
+        )
+        .await
+    {
+        Ok(response) => match response.status {
+            CachingStatus::Enabled => Check::new(
+                NAME,
+                Status::Ok,
+                format!("enabled at {}", api_client.base_url()),
+            ),
+            CachingStatus::Disabled => Check::new(
+                NAME,
+                Status::Warning,
+                "remote caching is disabled for this team",
+            ),
+            CachingStatus::OverLimit => {
+                Check::new(NAME, Status::Warning, "remote caching usage limit reached")
+            }
+            CachingStatus::Paused => Check::new(
+                NAME,
+                Status::Warning,
+                "remote caching is paused due to spending limits",
+            ),
+        },
+        Err(turborepo_api_client::Error::ReqwestError(e))
+            if e.status().is_some_and(|status| {
+                status == reqwest::StatusCode::UNAUTHORIZED
+                    || status == reqwest::StatusCode::FORBIDDEN
+            }) =>
+        {
+            Check::new(
+                NAME,
+                Status::Error,
+                "the token was rejected, run `turbo login` to log in again",
+            )
+        }
+        Err(e) => Check::new(
+            NAME,
+            Status::Error,
+            format!("unable to reach {}: {e}", api_client.base_url()),
+        ),
+    };
+
+    Ok(check)
+}
+
+async fn check_repository(repo_root: &AbsoluteSystemPath) -> Result<Vec<Check>, cli::Error> {
+    let root_package_json = PackageJson::load(&repo_root.join_component("package.json"))?;
+
+    let package_graph = match package_graph_builder(repo_root, root_package_json.clone()) {
+        Ok(builder) => builder.build().await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    let package_graph = match package_graph {
+        Ok(package_graph) => package_graph,
+        Err(e) => return Ok(vec![Check::new("package graph", Status::Error, e)]),
+    };
+
+    let mut checks = vec![
+        check_lockfile(&package_graph, repo_root, &root_package_json),
+        match package_graph.validate() {
+            Ok(()) => Check::new(
+                "package graph",
+                Status::Ok,
+                format!("{} packages without cycles", package_graph.len() - 1),
+            ),
+            Err(e) => Check::new("package graph", Status::Error, e.to_string()),
+        },
+    ];
+
+    match TurboJson::load(
+        repo_root,
+        AnchoredSystemPath::empty(),
+        &root_package_json,
+        false,
+    ) {
+        Ok(turbo_json) => {
+            checks.push(check_unused_tasks(&turbo_json, &package_graph));
+            checks.push(check_globs(&turbo_json));
+        }
+        Err(e) => checks.push(Check::new("turbo.json", Status::Error, e.to_string())),
+    }
+
+    Ok(checks)
+}
+
+fn check_lockfile(
+    package_graph: &PackageGraph,
+    repo_root: &AbsoluteSystemPath,
+    root_package_json: &PackageJson,
+) -> Check {
+    const NAME: &str = "lockfile";
+
+    let package_manager = package_graph.package_manager();
+    let lockfile_path = package_manager.lockfile_path(repo_root);
+    if !lockfile_path.exists() {
+        return Check::new(
+            NAME,
+            Status::Warning,
+            format!(
+                "no {} found, changes to external dependencies won't be detected",
+                package_manager.lockfile_name()
+            ),
+        );
+    }
+
+    match package_manager.read_lockfile(repo_root, root_package_json) {
+        Ok(_) => Check::new(
+            NAME,
+            Status::Ok,
+            format!(
+                "parsed {} as a {package_manager} lockfile",
+                package_manager.lockfile_name()
+            ),
+        ),
+        Err(e) => Check::new(
+            NAME,
+            Status::Error,
+            format!(
+                "unable to parse {} as a {package_manager} lockfile, all packages will be \
+                 considered changed: {e}",
+                package_manager.lockfile_name()
+            ),
+        ),
+    }
+}
+
+/// Finds tasks in the root turbo.json that no package can run because no
+/// package.json has a matching script and the task doesn't set a `command`
+fn check_unused_tasks(turbo_json: &TurboJson, package_graph: &PackageGraph) -> Check {
+    const NAME: &str = "tasks";
+
+    let scripts = package_graph
+        .workspaces()
+        .flat_map(|(workspace, info)| {
+            info.package_json
+                .scripts
+                .keys()
+                .map(move |script| (workspace, script.as_str()))
+        })
+        .collect::<HashSet<_>>();
+
+    let unused = unused_tasks(turbo_json, |package, task| match package {
+        Some(package) => scripts.contains(&(&WorkspaceName::from(package), task)),
+        None => scripts.iter().any(|(workspace, script)| {
+            !matches!(workspace, WorkspaceName::Root) && *script == task
+        }),
+    });
+
+    if unused.is_empty() {
+        Check::new(
+            NAME,
+            Status::Ok,
+            format!(
+                "all {} tasks have a script to run",
+                turbo_json.pipeline.len()
+            ),
+        )
+    } else {
+        Check::new(
+            NAME,
+            Status::Warning,
+            format!("{} tasks have no script in any package", unused.len()),
+        )
+        .with_details(unused)
+    }
+}
+
+fn unused_tasks(
+    turbo_json: &TurboJson,
+    has_script: impl Fn(Option<&str>, &str) -> bool,
+) -> Vec<String> {
+    turbo_json
+        .pipeline
+        .iter()
+        .filter(|(task_name, definition)| {
+            !definition.value.has_command() && !has_script(task_name.package(), task_name.task())
+        })
+        .map(|(task_name, _)| task_name.to_string())
+        .collect()
+}
+
+/// Finds `inputs` and `outputs` globs in the root turbo.json that don't
+/// compile, since those are otherwise only reported when the task runs
+fn check_globs(turbo_json: &TurboJson) -> Check {
+    const NAME: &str = "globs";
+
+    let invalid = invalid_globs(turbo_json);
+    if invalid.is_empty() {
+        Check::new(NAME, Status::Ok, "all inputs and outputs globs are valid")
+    } else {
+        Check::new(
+            NAME,
+            Status::Error,
+            format!("{} invalid inputs or outputs globs", invalid.len()),
+        )
+        .with_details(invalid)
+    }
+}
+
+fn invalid_globs(turbo_json: &TurboJson) -> Vec<String> {
+    turbo_json
+        .pipeline
+        .iter()
+        .flat_map(|(task_name, definition)| {
+            definition.value.globs().filter_map(move |(key, glob)| {
+                let pattern = globwalk::fix_glob_pattern(glob.strip_prefix('!').unwrap_or(glob));
+                wax::Glob::new(&pattern)
+                    .err()
+                    .map(|e| format!("{task_name} {key} \"{glob}\": {e}"))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::turbo_json::RawTurboJson;
+
+    fn turbo_json(value: serde_json::Value) -> TurboJson {
+        TurboJson::try_from(RawTurboJson::parse_from_serde(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_unused_tasks() {
+        let turbo_json = turbo_json(json!({
+            "pipeline": {
+                "build": {},
+                "lint": {},
+                "web#deploy": {},
+                "//#format": {},
+                "typecheck": { "command": "tsc --noEmit" }
+            }
+        }));
+        let unused = unused_tasks(&turbo_json, |package, task| {
+            matches!((package, task), (None, "build") | (Some("//"), "format"))
+        });
+        assert_eq!(unused, vec!["lint", "web#deploy"]);
+    }
+
+    #[test]
+    fn test_invalid_globs() {
+        let turbo_json = turbo_json(json!({
+            "pipeline": {
+                "build": {
+                    "inputs": ["src/**/*.ts", "!src/**/*.test.ts"],
+                    "outputs": ["dist/**", "dist/{a"]
+                }
+            }
+        }));
+        let invalid = invalid_globs(&turbo_json);
+        assert_eq!(invalid.len(), 1);
+        assert!(invalid[0].starts_with("build outputs \"dist/{a\""));
+    }
+}
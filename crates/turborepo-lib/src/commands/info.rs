@@ -8,20 +8,51 @@ use turbopath::AnchoredSystemPath;
 use turborepo_repository::{
     package_graph::{PackageGraph, WorkspaceName, WorkspaceNode},
     package_json::PackageJson,
-    package_manager::PackageManager,
+    package_manager::{PackageManager, PackageManagerVersion, PackageManagerVersionMismatch},
 };
-use turborepo_ui::GREY;
+use turborepo_ui::{GREY, YELLOW};
 
-use crate::{cli, commands::CommandBase, config::ConfigurationOptions};
+use crate::{
+    cli, commands::CommandBase, config::ConfigurationOptions, turbo_json::package_graph_builder,
+};
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct RepositoryDetails<'a> {
     config: &'a ConfigurationOptions,
     package_manager: &'a PackageManager,
+    package_manager_version: PackageManagerVersionDetails,
     workspaces: Vec<(&'a WorkspaceName, RepositoryWorkspaceDetails<'a>)>,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageManagerVersionDetails {
+    // The `packageManager` field of the root package.json
+    pinned: Option<String>,
+    installed: Option<String>,
+    mismatch: Option<PackageManagerVersionMismatch>,
+}
+
+impl From<PackageManagerVersion> for PackageManagerVersionDetails {
+    fn from(version: PackageManagerVersion) -> Self {
+        Self {
+            mismatch: version.mismatch(),
+            pinned: version.pinned.map(|pinned| {
+                let mut pinned_version = format!("{}@{}", pinned.name, pinned.version);
+                if let Some(hash) = pinned.hash {
+                    pinned_version.push('+');
+                    pinned_version.push_str(&hash);
+                }
+                pinned_version
+            }),
+            installed: version
+                .installed
+                .map(|installed| format!("{}@{}", version.name, installed)),
+        }
+    }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct RepositoryWorkspaceDetails<'a> {
@@ -41,7 +72,7 @@ pub async fn run(
 ) -> Result<(), cli::Error> {
     let root_package_json = PackageJson::load(&base.repo_root.join_component("package.json"))?;
 
-    let package_graph = PackageGraph::builder(&base.repo_root, root_package_json)
+    let package_graph = package_graph_builder(&base.repo_root, root_package_json.clone())?
         .build()
         .await?;
 
@@ -55,7 +86,12 @@ pub async fn run(
             workspace_details.print();
         }
     } else {
-        let repo_details = RepositoryDetails::new(&package_graph, config);
+        let package_manager_version = PackageManagerVersion::detect(
+            package_graph.package_manager(),
+            &base.repo_root,
+            &root_package_json,
+        )?;
+        let repo_details = RepositoryDetails::new(&package_graph, package_manager_version, config);
         if json {
             println!("{}", serde_json::to_string_pretty(&repo_details)?);
         } else {
@@ -67,7 +103,11 @@ pub async fn run(
 }
 
 impl<'a> RepositoryDetails<'a> {
-    fn new(package_graph: &'a PackageGraph, config: &'a ConfigurationOptions) -> Self {
+    fn new(
+        package_graph: &'a PackageGraph,
+        package_manager_version: PackageManagerVersion,
+        config: &'a ConfigurationOptions,
+    ) -> Self {
         let mut workspaces: Vec<_> = package_graph
             .workspaces()
             .map(|(workspace_name, workspace_info)| {
@@ -83,6 +123,7 @@ impl<'a> RepositoryDetails<'a> {
         Self {
             config,
             package_manager: package_graph.package_manager(),
+            package_manager_version: package_manager_version.into(),
             workspaces,
         }
     }
@@ -98,6 +139,17 @@ impl<'a> RepositoryDetails<'a> {
             (false, _, _) => println!("You are not logged in"),
         }
 
+        if let Some(PackageManagerVersionMismatch { pinned, installed }) =
+            &self.package_manager_version.mismatch
+        {
+            println!(
+                "{}",
+                YELLOW.apply_to(format!(
+                    "package.json pins {pinned} in \"packageManager\" but {installed} is installed"
+                ))
+            );
+        }
+
         // We subtract 1 for the root workspace
         println!(
             "{} packages found in workspace\n",
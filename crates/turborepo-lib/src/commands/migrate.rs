@@ -0,0 +1,107 @@
+//! Applies the turbo.json migrations to every turbo.json in the repository.
+//! See `turbo_json::migrations` for the migrations themselves.
+use std::collections::BTreeSet;
+
+use semver::Version;
+use thiserror::Error;
+use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+use turborepo_repository::package_json::PackageJson;
+use turborepo_ui::{color, BOLD, GREY};
+
+use crate::{
+    cli,
+    commands::CommandBase,
+    rewrite_json::RewriteError,
+    turbo_json::{migrations::migrations_since, package_graph_builder, CONFIG_FILE},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid version passed to --from: {0}")]
+    InvalidFrom(#[source] semver::Error),
+    #[error("failed to read or write {path}: {error}")]
+    Io {
+        path: AbsoluteSystemPathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+    #[error("failed to migrate {path}: {error}")]
+    Rewrite {
+        path: AnchoredSystemPathBuf,
+        #[source]
+        error: RewriteError,
+    },
+}
+
+pub async fn run(base: &CommandBase, from: Option<&str>, dry_run: bool) -> Result<i32, cli::Error> {
+    let from = from
+        .map(|from| Version::parse(from.trim_start_matches('v')))
+        .transpose()
+        .map_err(Error::InvalidFrom)?;
+
+    let repo_root = &base.repo_root;
+    let root_package_json = PackageJson::load(&repo_root.join_component("package.json"))?;
+    let package_graph = package_graph_builder(repo_root, root_package_json)?
+        .build()
+        .await?;
+
+    // Every workspace can have its own turbo.json
+    let turbo_json_paths = package_graph
+        .workspaces()
+        .map(|(_, info)| info.package_path().join_component(CONFIG_FILE))
+        .collect::<BTreeSet<_>>();
+
+    let mut changed_files = 0;
+    for path in turbo_json_paths {
+        let absolute_path = repo_root.resolve(&path);
+        if !absolute_path.exists() {
+            continue;
+        }
+        let original = absolute_path.read_to_string().map_err(|error| Error::Io {
+            path: absolute_path.clone(),
+            error,
+        })?;
+
+        let mut text = original.clone();
+        let mut applied = Vec::new();
+        for migration in migrations_since(from.as_ref()) {
+            let migrated = migration.apply(&text).map_err(|error| Error::Rewrite {
+                path: path.clone(),
+                error,
+            })?;
+            if let Some(migrated) = migrated {
+                text = migrated;
+                applied.push(migration);
+            }
+        }
+        if applied.is_empty() {
+            continue;
+        }
+
+        changed_files += 1;
+        println!("{}", color!(base.ui, BOLD, "{}", path));
+        for migration in applied {
+            println!(
+                "  {} {}",
+                migration.name,
+                color!(base.ui, GREY, "({})", migration.description)
+            );
+        }
+        if !dry_run {
+            absolute_path
+                .create_with_contents(&text)
+                .map_err(|error| Error::Io {
+                    path: absolute_path.clone(),
+                    error,
+                })?;
+        }
+    }
+
+    match (changed_files, dry_run) {
+        (0, _) => println!("No migrations needed"),
+        (n, true) => println!("Would migrate {n} file(s), run without --dry-run to apply"),
+        (n, false) => println!("Migrated {n} file(s)"),
+    }
+
+    Ok(0)
+}
@@ -3,25 +3,28 @@ use std::cell::OnceCell;
 use dirs_next::config_dir;
 use sha2::{Digest, Sha256};
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
-use turborepo_api_client::{APIAuth, APIClient};
+use turborepo_api_client::{APIAuth, APIClient, RetryOpts};
 use turborepo_ui::UI;
 
 use crate::{
     config::{ConfigurationOptions, Error as ConfigError, TurborepoConfigBuilder},
-    Args,
+    get_version, Args,
 };
 
 pub(crate) mod bin;
 pub(crate) mod daemon;
+pub(crate) mod doctor;
 pub(crate) mod generate;
 pub(crate) mod info;
 pub(crate) mod link;
 pub(crate) mod login;
 pub(crate) mod logout;
+pub(crate) mod migrate;
 pub(crate) mod prune;
 pub(crate) mod run;
 pub(crate) mod telemetry;
 pub(crate) mod unlink;
+pub(crate) mod worker;
 
 #[derive(Debug)]
 pub struct CommandBase {
@@ -121,7 +124,13 @@ impl CommandBase {
         let api_url = config.api_url();
         let timeout = config.timeout();
 
+        let retry_opts = RetryOpts {
+            max_retries: config.retries(),
+            ..RetryOpts::default()
+        };
+
         APIClient::new(api_url, timeout, self.version, args.preflight)
+            .map(|api_client| api_client.with_retry_opts(retry_opts))
             .map_err(ConfigError::ApiClient)
     }
 
@@ -134,12 +143,9 @@ impl CommandBase {
         &self.repo_root
     }
 
+    /// The directory holding the pid file and socket of the user's daemon
     pub fn daemon_file_root(&self) -> AbsoluteSystemPathBuf {
-        DaemonRootHasher(&self.repo_root).daemon_file_root()
-    }
-
-    fn repo_hash(&self) -> String {
-        DaemonRootHasher(&self.repo_root).repo_hash()
+        user_daemon_root()
     }
 
     pub fn version(&self) -> &'static str {
@@ -147,6 +153,31 @@ impl CommandBase {
     }
 }
 
+/// The directory holding the pid file and socket of the current user's daemon.
+/// A single daemon serves every repository the user works in with the same
+/// version of turbo.
+pub fn user_daemon_root() -> AbsoluteSystemPathBuf {
+    let home = dirs_next::home_dir().unwrap_or_default();
+    AbsoluteSystemPathBuf::new(std::env::temp_dir().to_str().expect("UTF-8 path"))
+        .expect("temp dir is valid")
+        .join_component("turbod")
+        .join_component(&format!(
+            "user-{}",
+            user_hash(&home.to_string_lossy(), get_version())
+        ))
+}
+
+// The temp dir can be shared between users, so the daemon is keyed by home
+// directory. Different versions of turbo get their own daemon rather than
+// repeatedly replacing each other's.
+fn user_hash(home: &str, version: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(home.as_bytes());
+    hasher.update([0]);
+    hasher.update(version.as_bytes());
+    hex::encode(&hasher.finalize()[..8])
+}
+
 pub struct DaemonRootHasher<'a>(&'a AbsoluteSystemPath);
 
 impl<'a> DaemonRootHasher<'a> {
@@ -154,6 +185,9 @@ impl<'a> DaemonRootHasher<'a> {
         Self(repo_root)
     }
 
+    /// The directory for state that is specific to this repository. The
+    /// daemon itself is shared between repositories and lives in
+    /// `user_daemon_root`.
     pub fn daemon_file_root(&self) -> AbsoluteSystemPathBuf {
         AbsoluteSystemPathBuf::new(std::env::temp_dir().to_str().expect("UTF-8 path"))
             .expect("temp dir is valid")
@@ -161,12 +195,16 @@ impl<'a> DaemonRootHasher<'a> {
             .join_component(self.repo_hash().as_str())
     }
 
-    pub fn sock_path(&self) -> AbsoluteSystemPathBuf {
-        self.daemon_file_root().join_component("turbod.sock")
+    /// The socket of the user's daemon, which is the same for every
+    /// repository
+    pub fn sock_path() -> AbsoluteSystemPathBuf {
+        user_daemon_root().join_component("turbod.sock")
     }
 
-    pub fn lock_path(&self) -> AbsoluteSystemPathBuf {
-        self.daemon_file_root().join_component("turbod.pid")
+    /// The pid file of the user's daemon, which is the same for every
+    /// repository
+    pub fn lock_path() -> AbsoluteSystemPathBuf {
+        user_daemon_root().join_component("turbod.pid")
     }
 
     pub fn lsp_path(&self) -> AbsoluteSystemPathBuf {
@@ -184,21 +222,14 @@ impl<'a> DaemonRootHasher<'a> {
 mod test {
     use test_case::test_case;
     use turbopath::AbsoluteSystemPathBuf;
-    use turborepo_ui::UI;
-
-    use crate::get_version;
 
     #[cfg(not(target_os = "windows"))]
     #[test_case("/tmp/turborepo", "6e0cfa616f75a61c"; "basic example")]
     fn test_repo_hash(path: &str, expected_hash: &str) {
-        use super::CommandBase;
-        use crate::Args;
+        use super::DaemonRootHasher;
 
-        let args = Args::default();
         let repo_root = AbsoluteSystemPathBuf::new(path).unwrap();
-        let command_base = CommandBase::new(args, repo_root, get_version(), UI::new(true));
-
-        let hash = command_base.repo_hash();
+        let hash = DaemonRootHasher::new(&repo_root).repo_hash();
 
         assert_eq!(hash, expected_hash);
         assert_eq!(hash.len(), 16);
@@ -207,16 +238,23 @@ mod test {
     #[cfg(target_os = "windows")]
     #[test_case("C:\\\\tmp\\turborepo", "0103736e6883e35f"; "basic example")]
     fn test_repo_hash_win(path: &str, expected_hash: &str) {
-        use super::CommandBase;
-        use crate::Args;
+        use super::DaemonRootHasher;
 
-        let args = Args::default();
         let repo_root = AbsoluteSystemPathBuf::new(path).unwrap();
-        let command_base = CommandBase::new(args, repo_root, get_version(), UI::new(true));
-
-        let hash = command_base.repo_hash();
+        let hash = DaemonRootHasher::new(&repo_root).repo_hash();
 
         assert_eq!(hash, expected_hash);
         assert_eq!(hash.len(), 16);
     }
+
+    #[test]
+    fn test_user_hash() {
+        use super::user_hash;
+
+        let hash = user_hash("/home/turbo", "1.13.0");
+        assert_eq!(hash.len(), 16);
+        assert_eq!(hash, user_hash("/home/turbo", "1.13.0"));
+        assert_ne!(hash, user_hash("/home/other", "1.13.0"));
+        assert_ne!(hash, user_hash("/home/turbo", "1.13.1"));
+    }
 }
@@ -15,7 +15,7 @@ use turborepo_telemetry::events::command::CommandEventBuilder;
 use turborepo_ui::BOLD;
 
 use super::CommandBase;
-use crate::turbo_json::RawTurboJson;
+use crate::turbo_json::{package_graph_builder, RawTurboJson};
 
 pub const DEFAULT_OUTPUT_DIR: &str = "out";
 
@@ -32,6 +32,8 @@ pub enum Error {
     #[error(transparent)]
     TurboJsonParser(#[from] crate::turbo_json::parser::Error),
     #[error(transparent)]
+    Config(#[from] crate::config::Error),
+    #[error(transparent)]
     PackageJson(#[from] turborepo_repository::package_json::Error),
     #[error(transparent)]
     PackageGraph(#[from] package_graph::Error),
@@ -265,7 +267,7 @@ impl<'a> Prune<'a> {
         let root_package_json_path = base.repo_root.join_component("package.json");
         let root_package_json = PackageJson::load(&root_package_json_path)?;
 
-        let package_graph = PackageGraph::builder(&base.repo_root, root_package_json)
+        let package_graph = package_graph_builder(&base.repo_root, root_package_json)?
             .build()
             .await?;
 
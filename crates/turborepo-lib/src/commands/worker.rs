@@ -0,0 +1,87 @@
+use camino::Utf8PathBuf;
+use futures::FutureExt;
+use tokio::{net::TcpListener, signal::ctrl_c};
+use tracing::error;
+use turborepo_cache::{AsyncCache, CacheOpts};
+use turborepo_ui::{cprintln, GREY};
+
+use crate::{
+    cli::DEFAULT_NUM_WORKERS,
+    commands::CommandBase,
+    distributed::{TaskResolver, WorkerService},
+    process::ProcessManager,
+    run::configure_remote_cache,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to listen on {address}: {source}")]
+    Listen {
+        address: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Config(#[from] crate::config::Error),
+    #[error(transparent)]
+    Cache(#[from] turborepo_cache::CacheError),
+    #[error(transparent)]
+    Distributed(#[from] crate::distributed::Error),
+}
+
+/// Runs tasks dispatched by `turbo run --workers` until interrupted
+pub async fn worker(
+    base: &CommandBase,
+    listen: &str,
+    concurrency: Option<u32>,
+    cache_dir: Option<Utf8PathBuf>,
+    token: Option<&str>,
+    single_package: bool,
+) -> Result<(), Error> {
+    // Checked before anything else, a worker without a token can't serve
+    // anyone
+    let token = token
+        .filter(|token| !token.is_empty())
+        .ok_or(crate::distributed::Error::MissingToken)?;
+    let resolver = TaskResolver::new(base.repo_root.clone(), single_package).await?;
+    let api_auth = base.api_auth()?;
+    let api_client = base.api_client()?;
+    let mut cache_opts = CacheOpts {
+        override_dir: cache_dir,
+        workers: DEFAULT_NUM_WORKERS,
+        ..CacheOpts::default()
+    };
+    configure_remote_cache(&mut cache_opts, base.config()?, &api_auth);
+    let cache = AsyncCache::new(&cache_opts, &base.repo_root, api_client, api_auth, None)?;
+
+    let listener = TcpListener::bind(listen)
+        .await
+        .map_err(|source| Error::Listen {
+            address: listen.to_string(),
+            source,
+        })?;
+    let concurrency = concurrency.unwrap_or_else(|| num_cpus::get() as u32).max(1);
+    cprintln!(
+        base.ui,
+        GREY,
+        "Worker listening on {} with concurrency {concurrency}",
+        listener
+            .local_addr()
+            .map_or_else(|_| listen.to_string(), |addr| addr.to_string())
+    );
+
+    let shutdown = ctrl_c().map(|result| {
+        if let Err(e) = result {
+            error!("Error with signal handling: {}", e);
+        }
+    });
+    let service = WorkerService::new(
+        resolver,
+        cache,
+        ProcessManager::infer(),
+        concurrency as usize,
+    );
+    service.serve(token, listener, shutdown).await?;
+
+    Ok(())
+}
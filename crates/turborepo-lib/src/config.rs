@@ -2,6 +2,7 @@ use std::{collections::HashMap, ffi::OsString, io};
 
 use convert_case::{Case, Casing};
 use miette::{Diagnostic, SourceSpan};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use struct_iterable::Iterable;
 use thiserror::Error;
@@ -88,9 +89,32 @@ pub enum Error {
         #[source_code]
         text: String,
     },
-    #[error("You can only extend from the root workspace")]
-    ExtendFromNonRoot {
-        #[label("non-root workspace found here")]
+    #[error(
+        "Could not find a workspace or an installed package named \"{workspace}\" to extend from \
+         in {path}"
+    )]
+    ExtendsUnknownWorkspace {
+        workspace: String,
+        path: String,
+        #[label("workspace or package not found")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        text: String,
+    },
+    #[error("Cannot extend from \"{workspace}\" in {path} because it has no turbo.json")]
+    ExtendsWithoutTurboJson {
+        workspace: String,
+        path: String,
+        #[label("extended from here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        text: String,
+    },
+    #[error("Circular \"extends\" found in {path}: {cycle}")]
+    ExtendsCycle {
+        cycle: String,
+        path: String,
+        #[label("this extends back to the one that extends from it")]
         span: Option<SourceSpan>,
         #[source_code]
         text: String,
@@ -103,8 +127,26 @@ pub enum Error {
         #[source_code]
         text: String,
     },
+    #[error("`{field}` cannot leave the workspace's directory")]
+    ParentDirInConfig {
+        field: &'static str,
+        #[label("\"..\" found here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        text: String,
+    },
+    #[error("Unknown workspace provider \"{provider}\", expected one of: cargo, deno, go")]
+    UnknownWorkspaceProvider {
+        provider: String,
+        #[label("unknown workspace provider found here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        text: String,
+    },
     #[error("No \"extends\" key found in {path}")]
     NoExtends { path: String },
+    #[error("\"workspaceProviders\" can only be set in the root turbo.json, found in {path}")]
+    WorkspaceProvidersOutsideRoot { path: String },
     #[error("Failed to create APIClient: {0}")]
     ApiClient(#[source] turborepo_api_client::Error),
     #[error("{0} is not UTF8.")]
@@ -115,6 +157,10 @@ pub enum Error {
     InvalidRemoteCacheEnabled,
     #[error("TURBO_REMOTE_CACHE_TIMEOUT: error parsing timeout.")]
     InvalidRemoteCacheTimeout(#[source] std::num::ParseIntError),
+    #[error("TURBO_REMOTE_CACHE_RETRIES: error parsing retries.")]
+    InvalidRemoteCacheRetries(#[source] std::num::ParseIntError),
+    #[error("TURBO_REMOTE_CACHE_MAX_FAILURES: error parsing max failures.")]
+    InvalidRemoteCacheMaxFailures(#[source] std::num::ParseIntError),
     #[error("TURBO_PREFLIGHT should be either 1 or 0.")]
     InvalidPreflight,
     #[error(transparent)]
@@ -134,8 +180,10 @@ macro_rules! create_builder {
 const DEFAULT_API_URL: &str = "https://vercel.com/api";
 const DEFAULT_LOGIN_URL: &str = "https://vercel.com";
 const DEFAULT_TIMEOUT: u64 = 30;
+const DEFAULT_RETRIES: u32 = 1;
+const DEFAULT_MAX_FAILURES: u32 = 5;
 
-#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, Iterable)]
+#[derive(Serialize, Deserialize, JsonSchema, Default, Debug, PartialEq, Eq, Clone, Iterable)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigurationOptions {
     #[serde(alias = "apiurl")]
@@ -156,8 +204,11 @@ pub struct ConfigurationOptions {
     pub(crate) team_id: Option<String>,
     pub(crate) token: Option<String>,
     pub(crate) signature: Option<bool>,
+    pub(crate) encryption: Option<bool>,
     pub(crate) preflight: Option<bool>,
     pub(crate) timeout: Option<u64>,
+    pub(crate) retries: Option<u32>,
+    pub(crate) max_failures: Option<u32>,
     pub(crate) enabled: Option<bool>,
 }
 
@@ -200,6 +251,12 @@ impl ConfigurationOptions {
         self.signature.unwrap_or_default()
     }
 
+    /// Whether artifacts are encrypted before they're uploaded to the remote
+    /// cache
+    pub fn encryption(&self) -> bool {
+        self.encryption.unwrap_or_default()
+    }
+
     pub fn enabled(&self) -> bool {
         self.enabled.unwrap_or(true)
     }
@@ -211,6 +268,17 @@ impl ConfigurationOptions {
     pub fn timeout(&self) -> u64 {
         self.timeout.unwrap_or(DEFAULT_TIMEOUT)
     }
+
+    /// Number of times a failed remote cache request is retried
+    pub fn retries(&self) -> u32 {
+        self.retries.unwrap_or(DEFAULT_RETRIES)
+    }
+
+    /// Consecutive remote cache failures after which the remote cache is
+    /// disabled for the rest of the run. `None` if it should never be disabled.
+    pub fn max_failures(&self) -> Option<u32> {
+        Some(self.max_failures.unwrap_or(DEFAULT_MAX_FAILURES)).filter(|max| *max > 0)
+    }
 }
 
 // Maps Some("") to None to emulate how Go handles empty strings
@@ -278,6 +346,11 @@ fn get_env_var_config(
     turbo_mapping.insert(OsString::from("turbo_teamid"), "team_id");
     turbo_mapping.insert(OsString::from("turbo_token"), "token");
     turbo_mapping.insert(OsString::from("turbo_remote_cache_timeout"), "timeout");
+    turbo_mapping.insert(OsString::from("turbo_remote_cache_retries"), "retries");
+    turbo_mapping.insert(
+        OsString::from("turbo_remote_cache_max_failures"),
+        "max_failures",
+    );
 
     // We do not enable new config sources:
     // turbo_mapping.insert(String::from("turbo_signature"), "signature"); // new
@@ -347,6 +420,28 @@ fn get_env_var_config(
         None
     };
 
+    // Process retries
+    let retries = if let Some(retries) = output_map.get("retries") {
+        Some(
+            retries
+                .parse::<u32>()
+                .map_err(Error::InvalidRemoteCacheRetries)?,
+        )
+    } else {
+        None
+    };
+
+    // Process max failures
+    let max_failures = if let Some(max_failures) = output_map.get("max_failures") {
+        Some(
+            max_failures
+                .parse::<u32>()
+                .map_err(Error::InvalidRemoteCacheMaxFailures)?,
+        )
+    } else {
+        None
+    };
+
     let output = ConfigurationOptions {
         api_url: output_map.get("api_url").cloned(),
         login_url: output_map.get("login_url").cloned(),
@@ -356,11 +451,14 @@ fn get_env_var_config(
 
         // Processed booleans
         signature,
+        encryption: None,
         preflight,
         enabled,
 
         // Processed numbers
         timeout,
+        retries,
+        max_failures,
     };
 
     Ok(output)
@@ -401,9 +499,12 @@ fn get_override_env_var_config(
         token: output_map.get("token").cloned(),
 
         signature: None,
+        encryption: None,
         preflight: None,
         enabled: None,
         timeout: None,
+        retries: None,
+        max_failures: None,
     };
 
     Ok(output)
@@ -490,9 +591,12 @@ impl TurborepoConfigBuilder {
     create_builder!(with_team_id, team_id, Option<String>);
     create_builder!(with_token, token, Option<String>);
     create_builder!(with_signature, signature, Option<bool>);
+    create_builder!(with_encryption, encryption, Option<bool>);
     create_builder!(with_enabled, enabled, Option<bool>);
     create_builder!(with_preflight, preflight, Option<bool>);
     create_builder!(with_timeout, timeout, Option<u64>);
+    create_builder!(with_retries, retries, Option<u32>);
+    create_builder!(with_max_failures, max_failures, Option<u32>);
 
     pub fn build(&self) -> Result<ConfigurationOptions, Error> {
         // Priority, from least significant to most significant:
@@ -565,6 +669,9 @@ impl TurborepoConfigBuilder {
                     if let Some(signature) = current_source_config.signature {
                         acc.signature = Some(signature);
                     }
+                    if let Some(encryption) = current_source_config.encryption {
+                        acc.encryption = Some(encryption);
+                    }
                     if let Some(enabled) = current_source_config.enabled {
                         acc.enabled = Some(enabled);
                     }
@@ -574,6 +681,12 @@ impl TurborepoConfigBuilder {
                     if let Some(timeout) = current_source_config.timeout {
                         acc.timeout = Some(timeout);
                     }
+                    if let Some(retries) = current_source_config.retries {
+                        acc.retries = Some(retries);
+                    }
+                    if let Some(max_failures) = current_source_config.max_failures {
+                        acc.max_failures = Some(max_failures);
+                    }
 
                     acc
                 })
@@ -592,7 +705,7 @@ mod test {
     use crate::config::{
         get_env_var_config, get_override_env_var_config, ConfigurationOptions, RawTurboJson,
         ResolvedConfigurationOptions, TurborepoConfigBuilder, DEFAULT_API_URL, DEFAULT_LOGIN_URL,
-        DEFAULT_TIMEOUT,
+        DEFAULT_MAX_FAILURES, DEFAULT_RETRIES, DEFAULT_TIMEOUT,
     };
 
     #[test]
@@ -604,9 +717,12 @@ mod test {
         assert_eq!(defaults.team_id(), None);
         assert_eq!(defaults.token(), None);
         assert!(!defaults.signature());
+        assert!(!defaults.encryption());
         assert!(defaults.enabled());
         assert!(!defaults.preflight());
         assert_eq!(defaults.timeout(), DEFAULT_TIMEOUT);
+        assert_eq!(defaults.retries(), DEFAULT_RETRIES);
+        assert_eq!(defaults.max_failures(), Some(DEFAULT_MAX_FAILURES));
     }
 
     #[test]
@@ -619,6 +735,8 @@ mod test {
         let turbo_teamid = "team_nLlpyC6REAqxydlFKbrMDlud";
         let turbo_token = "abcdef1234567890abcdef";
         let turbo_remote_cache_timeout = 200;
+        let turbo_remote_cache_retries = 3;
+        let turbo_remote_cache_max_failures = 0;
 
         env.insert("turbo_api".into(), turbo_api.into());
         env.insert("turbo_login".into(), turbo_login.into());
@@ -629,6 +747,14 @@ mod test {
             "turbo_remote_cache_timeout".into(),
             turbo_remote_cache_timeout.to_string().into(),
         );
+        env.insert(
+            "turbo_remote_cache_retries".into(),
+            turbo_remote_cache_retries.to_string().into(),
+        );
+        env.insert(
+            "turbo_remote_cache_max_failures".into(),
+            turbo_remote_cache_max_failures.to_string().into(),
+        );
 
         let config = get_env_var_config(&env).unwrap();
         assert_eq!(turbo_api, config.api_url.unwrap());
@@ -637,6 +763,9 @@ mod test {
         assert_eq!(turbo_teamid, config.team_id.unwrap());
         assert_eq!(turbo_token, config.token.unwrap());
         assert_eq!(turbo_remote_cache_timeout, config.timeout.unwrap());
+        assert_eq!(turbo_remote_cache_retries, config.retries());
+        // Setting max failures to 0 never disables the remote cache
+        assert_eq!(config.max_failures(), None);
     }
 
     #[test]
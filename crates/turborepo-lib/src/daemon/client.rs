@@ -1,7 +1,7 @@
 use std::io;
 
 use thiserror::Error;
-use tonic::{Code, Status};
+use tonic::{metadata::MetadataValue, Code, Status};
 use tracing::info;
 use turbopath::AbsoluteSystemPathBuf;
 
@@ -9,19 +9,26 @@ use super::{
     connector::{DaemonConnector, DaemonConnectorError},
     endpoint::SocketOpenError,
     proto::DiscoverPackagesResponse,
+    REPO_ROOT_METADATA,
 };
 use crate::{daemon::proto, globwatcher::HashGlobSetupError};
 
 #[derive(Debug, Clone)]
 pub struct DaemonClient<T> {
     client: proto::turbod_client::TurbodClient<tonic::transport::Channel>,
+    // the daemon serves many repositories, so every request says which one it is for
+    repo_root: AbsoluteSystemPathBuf,
     connect_settings: T,
 }
 
 impl DaemonClient<()> {
-    pub fn new(client: proto::turbod_client::TurbodClient<tonic::transport::Channel>) -> Self {
+    pub fn new(
+        client: proto::turbod_client::TurbodClient<tonic::transport::Channel>,
+        repo_root: AbsoluteSystemPathBuf,
+    ) -> Self {
         Self {
             client,
+            repo_root,
             connect_settings: (),
         }
     }
@@ -34,12 +41,23 @@ impl DaemonClient<()> {
     ) -> DaemonClient<DaemonConnector> {
         DaemonClient {
             client: self.client,
+            repo_root: self.repo_root,
             connect_settings,
         }
     }
 }
 
 impl<T> DaemonClient<T> {
+    /// Wraps a message for an RPC that is scoped to this client's repository.
+    fn request<M>(&self, message: M) -> tonic::Request<M> {
+        let mut request = tonic::Request::new(message);
+        request.metadata_mut().insert_bin(
+            REPO_ROOT_METADATA,
+            MetadataValue::from_bytes(self.repo_root.as_str().as_bytes()),
+        );
+        request
+    }
+
     /// Interrogate the server for its version.
     #[tracing::instrument(skip(self))]
     pub(super) async fn handshake(&mut self) -> Result<(), DaemonError> {
@@ -79,7 +97,9 @@ impl<T> DaemonClient<T> {
             .collect();
         Ok(self
             .client
-            .get_changed_outputs(proto::GetChangedOutputsRequest { hash, output_globs })
+            .get_changed_outputs(
+                self.request(proto::GetChangedOutputsRequest { hash, output_globs }),
+            )
             .await?
             .into_inner()
             .changed_output_globs)
@@ -101,12 +121,12 @@ impl<T> DaemonClient<T> {
             .map(|raw_glob| format_repo_relative_glob(raw_glob))
             .collect();
         self.client
-            .notify_outputs_written(proto::NotifyOutputsWrittenRequest {
+            .notify_outputs_written(self.request(proto::NotifyOutputsWrittenRequest {
                 hash,
                 output_globs,
                 output_exclusion_globs,
                 time_saved,
-            })
+            }))
             .await?;
 
         Ok(())
@@ -125,12 +145,23 @@ impl<T> DaemonClient<T> {
     pub async fn discover_packages(&mut self) -> Result<DiscoverPackagesResponse, DaemonError> {
         let response = self
             .client
-            .discover_packages(proto::DiscoverPackagesRequest {})
+            .discover_packages(self.request(proto::DiscoverPackagesRequest {}))
             .await?
             .into_inner();
 
         Ok(response)
     }
+
+    /// Stream the packages affected by file changes.
+    pub async fn watch_changes(
+        &mut self,
+    ) -> Result<tonic::Streaming<proto::ChangeEvent>, DaemonError> {
+        Ok(self
+            .client
+            .watch_changes(self.request(proto::WatchChangesRequest {}))
+            .await?
+            .into_inner())
+    }
 }
 
 impl DaemonClient<DaemonConnector> {
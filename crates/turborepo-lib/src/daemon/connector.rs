@@ -66,6 +66,8 @@ pub struct DaemonConnector {
     pub can_kill_server: bool,
     pub pid_file: turbopath::AbsoluteSystemPathBuf,
     pub sock_file: turbopath::AbsoluteSystemPathBuf,
+    /// The repository that requests made through the connection are for.
+    pub repo_root: turbopath::AbsoluteSystemPathBuf,
 }
 
 impl DaemonConnector {
@@ -104,7 +106,7 @@ impl DaemonConnector {
                 rest => rest?,
             };
 
-            let mut client = DaemonClient::new(conn);
+            let mut client = DaemonClient::new(conn, self.repo_root.clone());
 
             match client.handshake().await {
                 Ok(_) => {
@@ -435,6 +437,7 @@ mod test {
             sock_file: sock_path(&tmp_path),
             can_kill_server: false,
             can_start_server: false,
+            repo_root: AbsoluteSystemPathBuf::try_from(tmp_path.as_path()).unwrap(),
         };
 
         assert_matches!(
@@ -456,6 +459,7 @@ mod test {
             sock_file: sock,
             can_kill_server: false,
             can_start_server: false,
+            repo_root: AbsoluteSystemPathBuf::try_from(tmp_path.as_path()).unwrap(),
         };
 
         assert_matches!(
@@ -477,6 +481,7 @@ mod test {
             sock_file: sock,
             can_kill_server: false,
             can_start_server: false,
+            repo_root: AbsoluteSystemPathBuf::try_from(tmp_path.as_path()).unwrap(),
         };
 
         assert_matches!(
@@ -500,6 +505,7 @@ mod test {
             sock_file: sock,
             can_kill_server: false,
             can_start_server: false,
+            repo_root: AbsoluteSystemPathBuf::try_from(tmp_path.as_path()).unwrap(),
         };
 
         assert_matches!(
@@ -536,6 +542,7 @@ mod test {
             sock_file: sock,
             can_kill_server: true,
             can_start_server: false,
+            repo_root: AbsoluteSystemPathBuf::try_from(tmp_path.as_path()).unwrap(),
         };
 
         let kill_pid = Pid::from(usize::MAX);
@@ -572,6 +579,7 @@ mod test {
             sock_file: sock,
             can_kill_server: true,
             can_start_server: false,
+            repo_root: AbsoluteSystemPathBuf::try_from(tmp_path.as_path()).unwrap(),
         };
 
         assert_matches!(
@@ -664,15 +672,17 @@ mod test {
             }))
             .serve_with_incoming(stream);
 
-        let (pid_file, sock_file) = if cfg!(windows) {
+        let (pid_file, sock_file, repo_root) = if cfg!(windows) {
             (
                 AbsoluteSystemPathBuf::new("C:\\pid").unwrap(),
                 AbsoluteSystemPathBuf::new("C:\\sock").unwrap(),
+                AbsoluteSystemPathBuf::new("C:\\repo").unwrap(),
             )
         } else {
             (
                 AbsoluteSystemPathBuf::new("/pid").unwrap(),
                 AbsoluteSystemPathBuf::new("/sock").unwrap(),
+                AbsoluteSystemPathBuf::new("/repo").unwrap(),
             )
         };
 
@@ -682,6 +692,7 @@ mod test {
             sock_file,
             can_kill_server: false,
             can_start_server: false,
+            repo_root: repo_root.clone(),
         };
 
         let mut client = Endpoint::try_from("http://[::]:50051")
@@ -714,7 +725,7 @@ mod test {
             .unwrap_err()
             .into();
         assert_matches!(hello_resp, DaemonError::VersionMismatch(_));
-        let client = DaemonClient::new(client);
+        let client = DaemonClient::new(client, repo_root);
 
         let shutdown_fut = conn.kill_live_server(client, Pid::from(1000));
 
@@ -3,12 +3,17 @@ mod bump_timeout_layer;
 mod client;
 mod connector;
 pub(crate) mod endpoint;
+mod rpc_timeout_layer;
 mod server;
 
 pub use client::{DaemonClient, DaemonError};
 pub use connector::{DaemonConnector, DaemonConnectorError};
 pub use server::{CloseReason, TurboGrpcService};
 
+/// The gRPC metadata key that clients use to say which repository a request
+/// is for. It is a binary key so that any path can be sent.
+const REPO_ROOT_METADATA: &str = "turbo-repo-root-bin";
+
 pub(crate) mod proto {
 
     tonic::include_proto!("turbodprotocol");
@@ -26,7 +31,7 @@ pub(crate) mod proto {
     /// - Bump the minor version if adding new features, such that clients can
     ///   mandate at least some set of features on the target server.
     /// - Bump the patch version if making backwards compatible bug fixes.
-    pub const VERSION: &str = "1.11.0";
+    pub const VERSION: &str = "1.13.0";
 
     impl From<PackageManager> for turborepo_repository::package_manager::PackageManager {
         fn from(pm: PackageManager) -> Self {
@@ -53,4 +58,22 @@ pub(crate) mod proto {
             }
         }
     }
+
+    impl From<turborepo_filewatch::package_graph_watcher::PackageChangeEvent> for ChangeEvent {
+        fn from(event: turborepo_filewatch::package_graph_watcher::PackageChangeEvent) -> Self {
+            use turborepo_filewatch::package_graph_watcher::PackageChangeEvent;
+
+            let event = match event {
+                PackageChangeEvent::Packages(packages) => {
+                    change_event::Event::PackagesChanged(PackagesChanged {
+                        packages: packages.iter().map(|name| name.to_string()).collect(),
+                    })
+                }
+                PackageChangeEvent::Rediscover => {
+                    change_event::Event::RediscoverPackages(RediscoverPackages {})
+                }
+            };
+            Self { event: Some(event) }
+        }
+    }
 }
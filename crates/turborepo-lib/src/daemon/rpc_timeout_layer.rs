@@ -0,0 +1,119 @@
+//! rpc_timeout_layer
+//!
+//! This is middleware for tonic that limits how long each RPC can take. Most
+//! RPCs are answered from the daemon's warm state and share a short default,
+//! RPCs that do more work per request can be given a longer timeout.
+
+use std::{future::Future, pin::Pin, time::Duration};
+
+use tonic::{body::BoxBody, codegen::http, transport::NamedService, Status};
+use tower::{Layer, Service};
+
+/// A layer that fails RPCs with `DEADLINE_EXCEEDED` once they run out of
+/// time.
+pub struct RpcTimeoutLayer {
+    default: Duration,
+    // RPC paths, such as `/turbodprotocol.Turbod/GetTaskGraph`, with their own
+    // timeouts
+    overrides: &'static [(&'static str, Duration)],
+}
+
+impl RpcTimeoutLayer {
+    pub fn new(default: Duration, overrides: &'static [(&'static str, Duration)]) -> Self {
+        Self { default, overrides }
+    }
+}
+
+impl<S> Layer<S> for RpcTimeoutLayer {
+    type Service = RpcTimeoutService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcTimeoutService {
+            inner,
+            default: self.default,
+            overrides: self.overrides,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RpcTimeoutService<S> {
+    inner: S,
+    default: Duration,
+    overrides: &'static [(&'static str, Duration)],
+}
+
+impl<S> RpcTimeoutService<S> {
+    fn timeout(&self, path: &str) -> Duration {
+        self.overrides
+            .iter()
+            .find_map(|(rpc, timeout)| (*rpc == path).then_some(*timeout))
+            .unwrap_or(self.default)
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for RpcTimeoutService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let timeout = self.timeout(req.uri().path());
+        let response = self.inner.call(req);
+        // Only the time until the response starts counts, streaming responses
+        // can stay open for as long as the client wants
+        Box::pin(async move {
+            match tokio::time::timeout(timeout, response).await {
+                Ok(response) => response,
+                Err(_) => Ok(Status::deadline_exceeded("request timed out").to_http()),
+            }
+        })
+    }
+}
+
+impl<T: NamedService> NamedService for RpcTimeoutService<T> {
+    const NAME: &'static str = T::NAME;
+}
+
+#[cfg(test)]
+mod test {
+    use std::{convert::Infallible, time::Duration};
+
+    use tonic::{body::empty_body, codegen::http, Code, Status};
+    use tower::{service_fn, Layer, Service};
+
+    use super::RpcTimeoutLayer;
+
+    const OVERRIDES: &[(&str, Duration)] = &[("/test.Test/Slow", Duration::from_millis(500))];
+
+    #[tokio::test]
+    async fn test_rpc_timeouts() {
+        let mut service = RpcTimeoutLayer::new(Duration::from_millis(100), OVERRIDES).layer(
+            service_fn(|_: http::Request<()>| async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                Ok::<_, Infallible>(http::Response::new(empty_body()))
+            }),
+        );
+        let mut call = |path: &str| service.call(http::Request::get(path).body(()).unwrap());
+
+        let timed_out = call("/test.Test/Fast").await.unwrap();
+        assert_eq!(
+            Status::from_header_map(timed_out.headers()).map(|status| status.code()),
+            Some(Code::DeadlineExceeded)
+        );
+
+        let finished = call("/test.Test/Slow").await.unwrap();
+        assert_eq!(Status::from_header_map(finished.headers()), None);
+    }
+}
@@ -16,38 +16,58 @@ use std::{
     collections::{HashMap, HashSet},
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
+        Arc, Mutex, OnceLock,
     },
     time::{Duration, Instant},
 };
 
 use futures::Future;
+use itertools::Itertools;
 use semver::Version;
+use sysinfo::{ProcessExt, ProcessRefreshKind, SystemExt};
 use thiserror::Error;
 use tokio::{
     select,
-    sync::{mpsc, oneshot, watch},
+    sync::{broadcast, mpsc, oneshot, watch},
+    task::JoinHandle,
 };
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
 use tonic::transport::{NamedService, Server};
 use tower::ServiceBuilder;
 use tracing::{error, info, trace, warn};
-use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath};
 use turborepo_filewatch::{
     cookies::CookieWriter,
     globwatcher::{Error as GlobWatcherError, GlobError, GlobSet, GlobWatcher},
+    package_graph_watcher::{Error as PackageGraphError, PackageChangeEvent, PackageGraphWatcher},
     package_watcher::PackageWatcher,
     FileSystemWatcher, WatchError,
 };
-use turborepo_repository::discovery::{
-    DiscoveryResponse, LocalPackageDiscoveryBuilder, PackageDiscovery, PackageDiscoveryBuilder,
+use turborepo_repository::{
+    discovery::{
+        DiscoveryResponse, LocalPackageDiscoveryBuilder, PackageDiscovery, PackageDiscoveryBuilder,
+    },
+    package_graph::{PackageGraph, WorkspaceName, WorkspaceNode},
 };
+use turborepo_scm::SCM;
 
 use super::{
     bump_timeout::BumpTimeout,
     endpoint::SocketOpenError,
     proto::{self},
+    REPO_ROOT_METADATA,
+};
+use crate::{
+    daemon::{
+        bump_timeout_layer::BumpTimeoutLayer, endpoint::listen_socket,
+        rpc_timeout_layer::RpcTimeoutLayer,
+    },
+    engine::{EngineBuilder, TaskNode},
+    opts::{LegacyFilter, ScopeOpts},
+    run::{has_root_task, scope, task_id::TaskName},
+    turbo_json::TurboJson,
 };
-use crate::daemon::{bump_timeout_layer::BumpTimeoutLayer, endpoint::listen_socket};
 
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -61,9 +81,12 @@ pub enum CloseReason {
 }
 
 pub struct FileWatching {
-    _watcher: FileSystemWatcher,
+    watcher: FileSystemWatcher,
     pub glob_watcher: GlobWatcher,
     pub package_watcher: PackageWatcher,
+    pub package_graph_watcher: PackageGraphWatcher,
+    // How much the daemon's resident memory grew while this started
+    memory_bytes: u64,
 }
 
 #[derive(Debug, Error)]
@@ -76,6 +99,18 @@ enum RpcError {
     GlobWatching(#[from] GlobWatcherError),
     #[error("filewatching unavailable")]
     NoFileWatching,
+    #[error(transparent)]
+    PackageGraph(#[from] PackageGraphError),
+    #[error("invalid filter: {0}")]
+    InvalidFilter(#[from] scope::ResolutionError),
+    #[error("invalid turbo.json: {0}")]
+    TurboJson(String),
+    #[error("invalid task graph: {0}")]
+    TaskGraph(String),
+    #[error("no repository root was sent with the request")]
+    NoRepoRoot,
+    #[error("invalid repository root: {0}")]
+    InvalidRepoRoot(String),
 }
 
 impl From<RpcError> for tonic::Status {
@@ -87,6 +122,17 @@ impl From<RpcError> for tonic::Status {
             RpcError::InvalidGlob(e) => tonic::Status::invalid_argument(e.to_string()),
             RpcError::GlobWatching(e) => tonic::Status::unavailable(e.to_string()),
             RpcError::NoFileWatching => tonic::Status::unavailable("filewatching unavailable"),
+            RpcError::PackageGraph(PackageGraphError::Timeout) => {
+                tonic::Status::deadline_exceeded("failed to load the package graph in time")
+            }
+            RpcError::PackageGraph(e) => tonic::Status::unavailable(e.to_string()),
+            RpcError::InvalidFilter(e) => tonic::Status::invalid_argument(e.to_string()),
+            RpcError::TurboJson(e) => tonic::Status::internal(e),
+            RpcError::TaskGraph(e) => tonic::Status::invalid_argument(e),
+            RpcError::NoRepoRoot => {
+                tonic::Status::invalid_argument("no repository root was sent with the request")
+            }
+            RpcError::InvalidRepoRoot(e) => tonic::Status::invalid_argument(e),
         }
     }
 }
@@ -96,6 +142,8 @@ async fn start_filewatching<PD: PackageDiscovery + Send + 'static>(
     watcher_tx: watch::Sender<Option<Arc<FileWatching>>>,
     backup_discovery: PD,
 ) -> Result<(), WatchError> {
+    let _startup = filewatching_startup().lock().await;
+    let memory_before = process_memory();
     let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).await?;
     let cookie_writer = CookieWriter::new(watcher.cookie_dir(), Duration::from_millis(100));
     let glob_watcher = GlobWatcher::new(&repo_root, cookie_writer, watcher.subscribe());
@@ -103,23 +151,53 @@ async fn start_filewatching<PD: PackageDiscovery + Send + 'static>(
         PackageWatcher::new(repo_root.clone(), watcher.subscribe(), backup_discovery)
             .await
             .map_err(|e| WatchError::Setup(format!("{:?}", e)))?;
+    let package_graph_watcher = PackageGraphWatcher::new(
+        repo_root,
+        watcher.subscribe(),
+        package_watcher.discovery(),
+        Box::new(|repo_root| {
+            TurboJson::load_workspace_providers(repo_root).map_err(|e| e.to_string())
+        }),
+    );
     // We can ignore failures here, it means the server is shutting down and
     // receivers have gone out of scope.
     let _ = watcher_tx.send(Some(Arc::new(FileWatching {
-        _watcher: watcher,
+        watcher,
         glob_watcher,
         package_watcher,
+        package_graph_watcher,
+        memory_bytes: process_memory().saturating_sub(memory_before),
     })));
     Ok(())
 }
 
-/// Timeout for every RPC the server handles
+/// Repositories start watching one at a time, so the memory the daemon gains
+/// while one starts can be attributed to it
+fn filewatching_startup() -> &'static tokio::sync::Mutex<()> {
+    static STARTUP: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+    STARTUP.get_or_init(|| tokio::sync::Mutex::new(()))
+}
+
+/// Timeout for every RPC the server handles, unless it has its own below
 const REQUEST_TIMEOUT: Duration = Duration::from_millis(100);
 
+/// Building a task graph loads turbo.json files on every request
+const TASK_GRAPH_TIMEOUT: Duration = Duration::from_secs(5);
+
+const RPC_TIMEOUTS: &[(&str, Duration)] =
+    &[("/turbodprotocol.Turbod/GetTaskGraph", TASK_GRAPH_TIMEOUT)];
+
+/// How many change events a `WatchChanges` client can fall behind by
+const CHANGE_EVENT_BUFFER: usize = 64;
+
+/// How many repositories can be waiting to be evicted at once
+const EVICTION_BUFFER: usize = 16;
+
+/// Bounds on how often the daemon checks for idle repositories
+const MIN_EVICTION_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
 pub struct TurboGrpcService<S, PDB> {
-    watcher_tx: watch::Sender<Option<Arc<FileWatching>>>,
-    watcher_rx: watch::Receiver<Option<Arc<FileWatching>>>,
-    repo_root: AbsoluteSystemPathBuf,
     daemon_root: AbsoluteSystemPathBuf,
     log_file: AbsoluteSystemPathBuf,
     timeout: Duration,
@@ -128,76 +206,70 @@ pub struct TurboGrpcService<S, PDB> {
     package_discovery_backup: PDB,
 }
 
-impl<S> TurboGrpcService<S, LocalPackageDiscoveryBuilder>
+impl<S> TurboGrpcService<S, fn(AbsoluteSystemPathBuf) -> LocalPackageDiscoveryBuilder>
 where
     S: Future<Output = CloseReason>,
 {
     /// Create a gRPC server providing the Turbod interface. external_shutdown
     /// can be used to deliver a signal to shutdown the server. This is expected
     /// to be wired to signal handling. By default, the server will set up a
-    /// file system watcher for the purposes of managing package discovery
-    /// state, and use a `LocalPackageDiscovery` instance to refresh the
-    /// state if the filewatcher encounters errors.
+    /// file system watcher for each repository it is asked about for the
+    /// purposes of managing package discovery state, and use a
+    /// `LocalPackageDiscovery` instance to refresh the state if the
+    /// filewatcher encounters errors.
     pub fn new(
-        repo_root: AbsoluteSystemPathBuf,
         daemon_root: AbsoluteSystemPathBuf,
         log_file: AbsoluteSystemPathBuf,
         timeout: Duration,
         external_shutdown: S,
     ) -> Self {
-        let (watcher_tx, watcher_rx) = watch::channel(None);
-
-        let package_discovery_backup =
-            LocalPackageDiscoveryBuilder::new(repo_root.clone(), None, None);
-
         // Run the actual service. It takes ownership of the struct given to it,
         // so we use a private struct with just the pieces of state needed to handle
         // RPCs.
         TurboGrpcService {
-            watcher_tx,
-            watcher_rx,
-            repo_root,
             daemon_root,
             log_file,
             timeout,
             external_shutdown,
-            package_discovery_backup,
+            package_discovery_backup: |repo_root| {
+                LocalPackageDiscoveryBuilder::new(repo_root, None, None)
+            },
         }
     }
 }
 
-impl<S, PDB> TurboGrpcService<S, PDB>
+impl<S, PDB, B> TurboGrpcService<S, PDB>
 where
     S: Future<Output = CloseReason>,
-    PDB: PackageDiscoveryBuilder,
-    PDB::Output: PackageDiscovery + Send + 'static,
+    PDB: Fn(AbsoluteSystemPathBuf) -> B + Send + Sync + 'static,
+    B: PackageDiscoveryBuilder + Send + 'static,
+    B::Output: PackageDiscovery + Send + 'static,
 {
-    /// If errors are encountered when loading the package discovery, this
-    /// builder will be used as a backup to refresh the state.
-    pub fn with_package_discovery_backup<PDB2: PackageDiscoveryBuilder>(
+    /// If errors are encountered when loading the package discovery for a
+    /// repository, a builder made by this function will be used as a backup to
+    /// refresh the state.
+    pub fn with_package_discovery_backup<PDB2, B2>(
         self,
         package_discovery_backup: PDB2,
-    ) -> TurboGrpcService<S, PDB2> {
+    ) -> TurboGrpcService<S, PDB2>
+    where
+        PDB2: Fn(AbsoluteSystemPathBuf) -> B2,
+        B2: PackageDiscoveryBuilder,
+    {
         TurboGrpcService {
             daemon_root: self.daemon_root,
             external_shutdown: self.external_shutdown,
             log_file: self.log_file,
-            repo_root: self.repo_root,
             timeout: self.timeout,
-            watcher_rx: self.watcher_rx,
-            watcher_tx: self.watcher_tx,
             package_discovery_backup,
         }
     }
 
-    pub async fn serve(self) -> Result<CloseReason, PDB::Error> {
+    pub async fn serve(self) -> CloseReason {
         let Self {
-            watcher_tx,
-            watcher_rx,
             daemon_root,
             external_shutdown,
             log_file,
-            repo_root,
             timeout,
             package_discovery_backup,
         } = self;
@@ -205,52 +277,43 @@ where
         let running = Arc::new(AtomicBool::new(true));
         let (_pid_lock, stream) = match listen_socket(&daemon_root, running.clone()).await {
             Ok((pid_lock, stream)) => (pid_lock, stream),
-            Err(e) => return Ok(CloseReason::SocketOpenError(e)),
+            Err(e) => return CloseReason::SocketOpenError(e),
         };
         trace!("acquired connection stream for socket");
 
-        let watcher_repo_root = repo_root.to_owned();
         // A channel to trigger the shutdown of the gRPC server. This is handed out
-        // to components internal to the server process such as root watching, as
-        // well as available to the gRPC server itself to handle the shutdown RPC.
+        // to the gRPC server itself to handle the shutdown RPC.
         let (trigger_shutdown, mut shutdown_signal) = mpsc::channel::<()>(1);
 
-        let backup_discovery = package_discovery_backup.build()?;
-
-        // watch receivers as a group own the filewatcher, which will exit when
-        // all references are dropped.
-        let fw_shutdown = trigger_shutdown.clone();
-        let fw_handle = tokio::task::spawn(async move {
-            if let Err(e) =
-                start_filewatching(watcher_repo_root, watcher_tx, backup_discovery).await
-            {
-                error!("filewatching failed to start: {}", e);
-                let _ = fw_shutdown.send(()).await;
-            }
-            info!("filewatching started");
-        });
-        // exit_root_watch delivers a signal to the root watch loop to exit.
-        // In the event that the server shuts down via some other mechanism, this
-        // cleans up root watching task.
-        let (exit_root_watch, root_watch_exit_signal) = oneshot::channel();
-        let watch_root_handle = tokio::task::spawn(watch_root(
-            watcher_rx.clone(),
-            repo_root.to_owned(),
-            trigger_shutdown.clone(),
-            root_watch_exit_signal,
-        ));
+        // Repositories are watched from the first request for them until they
+        // are deleted, their watcher fails, or they go unused for the idle
+        // timeout. Root watching reports the first two over this channel.
+        let (evict_tx, evict_rx) = mpsc::channel(EVICTION_BUFFER);
+        let repos = Arc::new(RepoRoots::new(move |repo_root: AbsoluteSystemPathBuf| {
+            let backup_discovery = package_discovery_backup(repo_root.clone());
+            Repo::start(repo_root, backup_discovery, evict_tx.clone())
+        }));
+        let eviction_handle = tokio::task::spawn(evict_repos(repos.clone(), evict_rx, timeout));
 
         let bump_timeout = Arc::new(BumpTimeout::new(timeout));
         let timeout_fut = bump_timeout.wait();
 
+        // Graceful shutdown waits for open streams to finish, so streaming RPCs
+        // end their streams when this is cancelled.
+        let streams_shutdown = CancellationToken::new();
+
         // when one of these futures complete, let the server gracefully shutdown
         let (grpc_shutdown_tx, shutdown_reason) = oneshot::channel();
-        let shutdown_fut = async move {
-            select! {
-                _ = shutdown_signal.recv() => grpc_shutdown_tx.send(CloseReason::Shutdown).ok(),
-                _ = timeout_fut => grpc_shutdown_tx.send(CloseReason::Timeout).ok(),
-                reason = external_shutdown => grpc_shutdown_tx.send(reason).ok(),
-            };
+        let shutdown_fut = {
+            let streams_shutdown = streams_shutdown.clone();
+            async move {
+                select! {
+                    _ = shutdown_signal.recv() => grpc_shutdown_tx.send(CloseReason::Shutdown).ok(),
+                    _ = timeout_fut => grpc_shutdown_tx.send(CloseReason::Timeout).ok(),
+                    reason = external_shutdown => grpc_shutdown_tx.send(reason).ok(),
+                };
+                streams_shutdown.cancel();
+            }
         };
 
         // Run the actual service. It takes ownership of the struct given to it,
@@ -258,53 +321,51 @@ where
         // RPCs.
         let service = TurboGrpcServiceInner {
             shutdown: trigger_shutdown,
-            watcher_rx,
-            times_saved: Arc::new(Mutex::new(HashMap::new())),
+            repos: repos.clone(),
             start_time: Instant::now(),
             log_file: log_file.to_owned(),
+            bump_timeout: bump_timeout.clone(),
+            streams_shutdown,
         };
         let server_fut = {
             let service = ServiceBuilder::new()
                 .layer(BumpTimeoutLayer::new(bump_timeout.clone()))
+                // set a max timeout for RPCs
+                .layer(RpcTimeoutLayer::new(REQUEST_TIMEOUT, RPC_TIMEOUTS))
                 .service(crate::daemon::proto::turbod_server::TurbodServer::new(
                     service,
                 ));
 
             Server::builder()
-                // set a max timeout for RPCs
-                .timeout(REQUEST_TIMEOUT)
                 .add_service(service)
                 .serve_with_incoming_shutdown(stream, shutdown_fut)
         };
         // Wait for the server to exit.
-        // This can be triggered by timeout, root watcher, or an RPC
+        // This can be triggered by timeout, or an RPC
         let _ = server_fut.await;
         info!("gRPC server exited");
         // Ensure our timer will exit
         running.store(false, Ordering::SeqCst);
         // We expect to have a signal from the grpc server on what triggered the exit
         let close_reason = shutdown_reason.await.unwrap_or(CloseReason::ServerClosed);
-        // Now that the server has exited, the TurboGrpcService instance should be
-        // dropped. The root watcher still has a reference to a receiver, keeping
-        // the filewatcher alive. Trigger the root watcher to exit. We don't care
-        // if we fail to send, root watching may have exited already
-        let _ = exit_root_watch.send(());
-        let _ = watch_root_handle.await;
-        trace!("root watching exited");
-        // Clean up the filewatching handle in the event that we never even got
-        // started with filewatching. Again, we don't care about the error here.
-        let _ = fw_handle.await;
-        trace!("filewatching handle joined");
-        Ok(close_reason)
+        // Now that the server has exited, the TurboGrpcService instance has been
+        // dropped. Stop evicting repositories, and then stop watching all of them
+        // at once. We don't care about the error here, the task was aborted.
+        eviction_handle.abort();
+        let _ = eviction_handle.await;
+        repos.clear();
+        trace!("repository watching stopped");
+        close_reason
     }
 }
 
 struct TurboGrpcServiceInner {
     shutdown: mpsc::Sender<()>,
-    watcher_rx: watch::Receiver<Option<Arc<FileWatching>>>,
-    times_saved: Arc<Mutex<HashMap<String, u64>>>,
+    repos: Arc<RepoRoots>,
     start_time: Instant,
     log_file: AbsoluteSystemPathBuf,
+    bump_timeout: Arc<BumpTimeout>,
+    streams_shutdown: CancellationToken,
 }
 
 impl TurboGrpcServiceInner {
@@ -313,6 +374,115 @@ impl TurboGrpcServiceInner {
         let _ = self.shutdown.send(()).await;
     }
 
+    /// Finds the repository that a request is for, and starts serving it if
+    /// this is the first request for it.
+    fn repo<M>(&self, request: &tonic::Request<M>) -> Result<Arc<Repo>, RpcError> {
+        let repo_root = request
+            .metadata()
+            .get_bin(REPO_ROOT_METADATA)
+            .ok_or(RpcError::NoRepoRoot)?
+            .to_bytes()
+            .map_err(|e| RpcError::InvalidRepoRoot(e.to_string()))?;
+        let repo_root = String::from_utf8(repo_root.to_vec())
+            .map_err(|e| RpcError::InvalidRepoRoot(e.to_string()))?;
+        let repo_root = AbsoluteSystemPathBuf::new(repo_root)
+            .map_err(|e| RpcError::InvalidRepoRoot(e.to_string()))?;
+        Ok(self.repos.get_or_start(repo_root))
+    }
+}
+
+/// A repository that the daemon is serving. Dropping it stops watching the
+/// repository.
+struct Repo {
+    root: AbsoluteSystemPathBuf,
+    watcher_rx: watch::Receiver<Option<Arc<FileWatching>>>,
+    times_saved: Mutex<HashMap<String, u64>>,
+    started: Instant,
+    last_used: Mutex<Instant>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Repo {
+    /// Starts watching `root` in the background. If filewatching fails to
+    /// start, or the root is deleted, the root is sent to `evict`.
+    fn start<B>(
+        root: AbsoluteSystemPathBuf,
+        backup_discovery: B,
+        evict: mpsc::Sender<AbsoluteSystemPathBuf>,
+    ) -> Self
+    where
+        B: PackageDiscoveryBuilder + Send + 'static,
+        B::Output: PackageDiscovery + Send + 'static,
+    {
+        // watch receivers as a group own the filewatcher, which will exit when
+        // all references are dropped.
+        let (watcher_tx, watcher_rx) = watch::channel(None);
+
+        let fw_root = root.clone();
+        let fw_evict = evict.clone();
+        let fw_handle = tokio::task::spawn(async move {
+            let backup_discovery = backup_discovery
+                .build()
+                .map_err(|e| WatchError::Setup(e.to_string()));
+            let result = match backup_discovery {
+                Ok(backup_discovery) => {
+                    start_filewatching(fw_root.clone(), watcher_tx, backup_discovery).await
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => info!("filewatching started for {}", fw_root),
+                Err(e) => {
+                    error!("filewatching failed to start for {}: {}", fw_root, e);
+                    let _ = fw_evict.send(fw_root).await;
+                }
+            }
+        });
+        let watch_root_handle =
+            tokio::task::spawn(watch_root(watcher_rx.clone(), root.clone(), evict));
+
+        let now = Instant::now();
+        Self {
+            root,
+            watcher_rx,
+            times_saved: Mutex::new(HashMap::new()),
+            started: now,
+            last_used: Mutex::new(now),
+            tasks: vec![fw_handle, watch_root_handle],
+        }
+    }
+
+    fn touch(&self) {
+        *self.last_used.lock().expect("last used lock poisoned") = Instant::now();
+    }
+
+    fn idle_time(&self) -> Duration {
+        self.last_used
+            .lock()
+            .expect("last used lock poisoned")
+            .elapsed()
+    }
+
+    fn status(&self) -> proto::RepoStatus {
+        let (stats, memory_bytes) = self
+            .watcher_rx
+            .borrow()
+            .as_ref()
+            .map(|fw| (Some(fw.watcher.stats()), fw.memory_bytes))
+            .unwrap_or_default();
+        proto::RepoStatus {
+            repo_root: self.root.to_string(),
+            uptime_msec: self.started.elapsed().as_millis() as u64,
+            idle_msec: self.idle_time().as_millis() as u64,
+            watching: stats.is_some(),
+            watched_directories: stats.as_ref().map_or(0, |s| s.watched_directories()),
+            events: stats.as_ref().map_or(0, |s| s.events()),
+            events_per_minute: stats.as_ref().map_or(0.0, |s| s.events_per_minute()),
+            errors: stats.as_ref().map_or(0, |s| s.errors()),
+            memory_bytes,
+        }
+    }
+
     async fn wait_for_filewatching(&self) -> Result<Arc<FileWatching>, RpcError> {
         let rx = self.watcher_rx.clone();
         wait_for_filewatching(rx, Duration::from_millis(100)).await
@@ -361,6 +531,227 @@ impl TurboGrpcServiceInner {
             package_manager: fw.package_watcher.get_package_manager().await,
         })
     }
+
+    async fn package_graph(&self) -> Result<Arc<PackageGraph>, RpcError> {
+        let fw = self.wait_for_filewatching().await?;
+        Ok(fw
+            .package_graph_watcher
+            .get_package_graph(REQUEST_TIMEOUT)
+            .await?)
+    }
+
+    /// Resolves filters the same way `turbo run` does, returning the matched
+    /// packages and whether that is every package in the repo
+    fn resolve_packages(
+        &self,
+        package_graph: &PackageGraph,
+        filter_patterns: Vec<String>,
+        ignore_patterns: Vec<String>,
+        global_deps: Vec<String>,
+    ) -> Result<(HashSet<WorkspaceName>, bool), RpcError> {
+        let opts = ScopeOpts {
+            pkg_inference_root: None,
+            legacy_filter: LegacyFilter::default(),
+            global_deps,
+            filter_patterns,
+            ignore_patterns,
+        };
+        let scm = SCM::new(&self.root);
+        Ok(scope::resolve_packages(
+            &opts,
+            &self.root,
+            package_graph,
+            &scm,
+        )?)
+    }
+
+    async fn task_graph(
+        &self,
+        tasks: Vec<String>,
+        filter_patterns: Vec<String>,
+        only: bool,
+    ) -> Result<Vec<proto::Task>, RpcError> {
+        let package_graph = self.package_graph().await?;
+        let root_turbo_json = TurboJson::load(
+            &self.root,
+            AnchoredSystemPath::empty(),
+            package_graph.root_package_json(),
+            false,
+        )
+        .map_err(|e| RpcError::TurboJson(e.to_string()))?;
+
+        let (mut packages, is_all_packages) =
+            self.resolve_packages(&package_graph, filter_patterns, Vec::new(), Vec::new())?;
+        if is_all_packages && has_root_task(&root_turbo_json, &tasks) {
+            packages.insert(WorkspaceName::Root);
+        }
+
+        let engine = EngineBuilder::new(&self.root, &package_graph, false)
+            .with_root_tasks(root_turbo_json.pipeline.keys().cloned())
+            .with_turbo_jsons(Some(
+                Some((WorkspaceName::Root, root_turbo_json.clone()))
+                    .into_iter()
+                    .collect(),
+            ))
+            .with_tasks_only(only)
+            .with_workspaces(packages.into_iter().collect())
+            .with_tasks(
+                tasks
+                    .iter()
+                    .map(|task| TaskName::from(task.as_str()).into_owned()),
+            )
+            .build()
+            .map_err(|e| RpcError::TaskGraph(e.to_string()))?;
+
+        let task_ids = |nodes: Option<HashSet<&TaskNode>>| {
+            nodes
+                .into_iter()
+                .flatten()
+                .filter_map(|node| match node {
+                    TaskNode::Task(task_id) => Some(task_id.to_string()),
+                    TaskNode::Root => None,
+                })
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+        Ok(engine
+            .tasks()
+            .filter_map(|node| match node {
+                TaskNode::Task(task_id) => Some(proto::Task {
+                    id: task_id.to_string(),
+                    dependencies: task_ids(engine.dependencies(task_id)),
+                }),
+                TaskNode::Root => None,
+            })
+            .sorted_by(|a, b| a.id.cmp(&b.id))
+            .collect())
+    }
+}
+
+impl Drop for Repo {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// The repositories that the daemon is serving, keyed by their root
+struct RepoRoots {
+    repos: Mutex<HashMap<AbsoluteSystemPathBuf, Arc<Repo>>>,
+    start_repo: Box<dyn Fn(AbsoluteSystemPathBuf) -> Repo + Send + Sync>,
+}
+
+impl RepoRoots {
+    fn new(start_repo: impl Fn(AbsoluteSystemPathBuf) -> Repo + Send + Sync + 'static) -> Self {
+        Self {
+            repos: Mutex::new(HashMap::new()),
+            start_repo: Box::new(start_repo),
+        }
+    }
+
+    /// Returns the repository at `root`, starting to serve it if needed
+    fn get_or_start(&self, root: AbsoluteSystemPathBuf) -> Arc<Repo> {
+        let mut repos = self.repos.lock().expect("repos lock poisoned");
+        let repo = repos.entry(root).or_insert_with_key(|root| {
+            info!("serving {}", root);
+            Arc::new((self.start_repo)(root.clone()))
+        });
+        repo.touch();
+        repo.clone()
+    }
+
+    fn evict(&self, root: &AbsoluteSystemPath) {
+        let mut repos = self.repos.lock().expect("repos lock poisoned");
+        if repos.remove(root).is_some() {
+            warn!("stopped serving {}", root);
+        }
+    }
+
+    /// Stops serving the repositories that have gone unused for
+    /// `idle_timeout`. Repositories with requests or streams in flight hold
+    /// an extra reference, and are kept.
+    fn evict_idle(&self, idle_timeout: Duration) {
+        let mut repos = self.repos.lock().expect("repos lock poisoned");
+        repos.retain(|root, repo| {
+            let idle = repo.idle_time() >= idle_timeout && Arc::strong_count(repo) == 1;
+            if idle {
+                info!("stopped serving idle repository {}", root);
+            }
+            !idle
+        });
+    }
+
+    fn statuses(&self) -> Vec<proto::RepoStatus> {
+        let repos = self.repos.lock().expect("repos lock poisoned");
+        repos
+            .values()
+            .map(|repo| repo.status())
+            .sorted_by(|a, b| a.repo_root.cmp(&b.repo_root))
+            .collect()
+    }
+
+    fn clear(&self) {
+        self.repos.lock().expect("repos lock poisoned").clear();
+    }
+}
+
+/// Removes repositories that root watching gives up on, and periodically
+/// removes the ones that have gone idle
+async fn evict_repos(
+    repos: Arc<RepoRoots>,
+    mut evict: mpsc::Receiver<AbsoluteSystemPathBuf>,
+    idle_timeout: Duration,
+) {
+    let mut interval =
+        tokio::time::interval(idle_timeout.clamp(MIN_EVICTION_INTERVAL, MAX_EVICTION_INTERVAL));
+    loop {
+        select! {
+            root = evict.recv() => match root {
+                Some(root) => repos.evict(&root),
+                None => return,
+            },
+            _ = interval.tick() => repos.evict_idle(idle_timeout),
+        }
+    }
+}
+
+/// The resident memory of the daemon process, or 0 if it can't be read
+fn process_memory() -> u64 {
+    let Ok(pid) = sysinfo::get_current_pid() else {
+        return 0;
+    };
+    let mut system = sysinfo::System::new();
+    system.refresh_process_specifics(pid, ProcessRefreshKind::new());
+    system.process(pid).map_or(0, |process| process.memory())
+}
+
+/// Forwards package changes to a `WatchChanges` client until it disconnects
+/// or the server shuts down
+async fn forward_changes(
+    mut changes: broadcast::Receiver<PackageChangeEvent>,
+    tx: mpsc::Sender<Result<proto::ChangeEvent, tonic::Status>>,
+    shutdown: CancellationToken,
+    bump_timeout: Arc<BumpTimeout>,
+) {
+    loop {
+        let event = select! {
+            _ = shutdown.cancelled() => return,
+            _ = tx.closed() => return,
+            event = changes.recv() => event,
+        };
+        let event = match event {
+            Ok(event) => event,
+            // we dropped some changes, so the client has to start over
+            Err(broadcast::error::RecvError::Lagged(_)) => PackageChangeEvent::Rediscover,
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        // a client that is watching for changes keeps the daemon alive
+        bump_timeout.reset();
+        if tx.send(Ok(event.into())).await.is_err() {
+            return;
+        }
+    }
 }
 
 async fn wait_for_filewatching(
@@ -375,51 +766,56 @@ async fn wait_for_filewatching(
     return Ok(fw.as_ref().expect("guaranteed some above").clone());
 }
 
+/// Watches for `root` being deleted, or its filewatcher failing, and sends it
+/// to `evict` when that happens. This runs until the repository is dropped.
 async fn watch_root(
     filewatching_access: watch::Receiver<Option<Arc<FileWatching>>>,
     root: AbsoluteSystemPathBuf,
-    trigger_shutdown: mpsc::Sender<()>,
-    mut exit_signal: oneshot::Receiver<()>,
-) -> Result<(), WatchError> {
+    evict: mpsc::Sender<AbsoluteSystemPathBuf>,
+) {
     let mut recv_events = {
         let Ok(fw) = wait_for_filewatching(filewatching_access, Duration::from_secs(5)).await
         else {
-            return Ok(());
+            return;
         };
 
-        fw._watcher.subscribe()
+        fw.watcher.subscribe()
     };
 
     loop {
         // Ignore the outer layer of Result, if the sender has closed, filewatching has
         // gone away and we can return.
-        select! {
-            _ = &mut exit_signal => return Ok(()),
-            event = recv_events.recv() => {
-                let Ok(event) = event else {
-                    return Ok(());
-                };
-                let should_trigger_shutdown = match event {
-                    // filewatching can throw some weird events, so check that the root is actually gone
-                    // before triggering a shutdown
-                    Ok(event) if event.paths.iter().any(|p| p == (&root as &AbsoluteSystemPath)) => !root.exists(),
-                    Ok(_) => false,
-                    Err(_) => true
-                };
-                if should_trigger_shutdown {
-                    warn!("Root watcher triggering shutdown");
-                    // We don't care if a shutdown has already been triggered,
-                    // so we can ignore the error.
-                    let _ = trigger_shutdown.send(()).await;
-                    return Ok(());
-                }
+        let Ok(event) = recv_events.recv().await else {
+            return;
+        };
+        let should_evict = match event {
+            // filewatching can throw some weird events, so check that the root is actually gone
+            // before evicting it
+            Ok(event)
+                if event
+                    .paths
+                    .iter()
+                    .any(|p| p == (&root as &AbsoluteSystemPath)) =>
+            {
+                !root.exists()
             }
+            Ok(_) => false,
+            Err(_) => true,
+        };
+        if should_evict {
+            warn!("Root watcher evicting {}", root);
+            // We don't care if the daemon is already shutting down, so we can
+            // ignore the error.
+            let _ = evict.send(root).await;
+            return;
         }
     }
 }
 
 #[tonic::async_trait]
 impl proto::turbod_server::Turbod for TurboGrpcServiceInner {
+    type WatchChangesStream = ReceiverStream<Result<proto::ChangeEvent, tonic::Status>>;
+
     async fn hello(
         &self,
         request: tonic::Request<proto::HelloRequest>,
@@ -469,6 +865,8 @@ impl proto::turbod_server::Turbod for TurboGrpcServiceInner {
             daemon_status: Some(proto::DaemonStatus {
                 uptime_msec: self.start_time.elapsed().as_millis() as u64,
                 log_file: self.log_file.to_string(),
+                memory_bytes: process_memory(),
+                repos: self.repos.statuses(),
             }),
         }))
     }
@@ -477,9 +875,10 @@ impl proto::turbod_server::Turbod for TurboGrpcServiceInner {
         &self,
         request: tonic::Request<proto::NotifyOutputsWrittenRequest>,
     ) -> Result<tonic::Response<proto::NotifyOutputsWrittenResponse>, tonic::Status> {
+        let repo = self.repo(&request)?;
         let inner = request.into_inner();
 
-        self.watch_globs(
+        repo.watch_globs(
             inner.hash,
             inner.output_globs,
             inner.output_exclusion_globs,
@@ -493,8 +892,9 @@ impl proto::turbod_server::Turbod for TurboGrpcServiceInner {
         &self,
         request: tonic::Request<proto::GetChangedOutputsRequest>,
     ) -> Result<tonic::Response<proto::GetChangedOutputsResponse>, tonic::Status> {
+        let repo = self.repo(&request)?;
         let inner = request.into_inner();
-        let (changed, time_saved) = self
+        let (changed, time_saved) = repo
             .get_changed_outputs(inner.hash, HashSet::from_iter(inner.output_globs))
             .await?;
         Ok(tonic::Response::new(proto::GetChangedOutputsResponse {
@@ -505,9 +905,9 @@ impl proto::turbod_server::Turbod for TurboGrpcServiceInner {
 
     async fn discover_packages(
         &self,
-        _request: tonic::Request<proto::DiscoverPackagesRequest>,
+        request: tonic::Request<proto::DiscoverPackagesRequest>,
     ) -> Result<tonic::Response<proto::DiscoverPackagesResponse>, tonic::Status> {
-        let resp = self.discover_packages().await?;
+        let resp = self.repo(&request)?.discover_packages().await?;
         Ok(tonic::Response::new(proto::DiscoverPackagesResponse {
             package_files: resp
                 .workspaces
@@ -520,6 +920,92 @@ impl proto::turbod_server::Turbod for TurboGrpcServiceInner {
             package_manager: proto::PackageManager::from(resp.package_manager).into(),
         }))
     }
+
+    async fn get_package_graph(
+        &self,
+        request: tonic::Request<proto::GetPackageGraphRequest>,
+    ) -> Result<tonic::Response<proto::GetPackageGraphResponse>, tonic::Status> {
+        let package_graph = self.repo(&request)?.package_graph().await?;
+        let packages = package_graph
+            .workspaces()
+            .map(|(name, info)| proto::Package {
+                name: name.to_string(),
+                path: info.package_path().to_unix().to_string(),
+                dependencies: package_graph
+                    .immediate_dependencies(&WorkspaceNode::Workspace(name.clone()))
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|node| match node {
+                        WorkspaceNode::Workspace(dependency) => Some(dependency.to_string()),
+                        // an edge to the root node means there are no internal dependencies
+                        WorkspaceNode::Root => None,
+                    })
+                    .sorted()
+                    .collect(),
+            })
+            .sorted_by(|a, b| a.name.cmp(&b.name))
+            .collect();
+
+        Ok(tonic::Response::new(proto::GetPackageGraphResponse {
+            package_manager: proto::PackageManager::from(*package_graph.package_manager()).into(),
+            packages,
+        }))
+    }
+
+    async fn resolve_filter(
+        &self,
+        request: tonic::Request<proto::ResolveFilterRequest>,
+    ) -> Result<tonic::Response<proto::ResolveFilterResponse>, tonic::Status> {
+        let repo = self.repo(&request)?;
+        let inner = request.into_inner();
+        let package_graph = repo.package_graph().await?;
+        let (packages, _) = repo.resolve_packages(
+            &package_graph,
+            inner.filter_patterns,
+            inner.ignore_patterns,
+            inner.global_deps,
+        )?;
+        Ok(tonic::Response::new(proto::ResolveFilterResponse {
+            packages: packages
+                .iter()
+                .map(|name| name.to_string())
+                .sorted()
+                .collect(),
+        }))
+    }
+
+    async fn get_task_graph(
+        &self,
+        request: tonic::Request<proto::GetTaskGraphRequest>,
+    ) -> Result<tonic::Response<proto::GetTaskGraphResponse>, tonic::Status> {
+        let repo = self.repo(&request)?;
+        let inner = request.into_inner();
+        let tasks = repo
+            .task_graph(inner.tasks, inner.filter_patterns, inner.only)
+            .await?;
+        Ok(tonic::Response::new(proto::GetTaskGraphResponse { tasks }))
+    }
+
+    async fn watch_changes(
+        &self,
+        request: tonic::Request<proto::WatchChangesRequest>,
+    ) -> Result<tonic::Response<Self::WatchChangesStream>, tonic::Status> {
+        let repo = self.repo(&request)?;
+        let fw = repo.wait_for_filewatching().await?;
+        let (tx, rx) = mpsc::channel(CHANGE_EVENT_BUFFER);
+        let changes = forward_changes(
+            fw.package_graph_watcher.subscribe(),
+            tx,
+            self.streams_shutdown.clone(),
+            self.bump_timeout.clone(),
+        );
+        tokio::spawn(async move {
+            changes.await;
+            // the repository isn't idle while a client is watching it
+            drop(repo);
+        });
+        Ok(tonic::Response::new(ReceiverStream::new(rx)))
+    }
 }
 
 /// Determine whether a server can serve a client's request based on its
@@ -552,22 +1038,31 @@ impl NamedService for TurboGrpcServiceInner {
 #[cfg(test)]
 mod test {
     use std::{
-        assert_matches::{self, assert_matches},
+        assert_matches,
+        collections::BTreeSet,
+        sync::Arc,
         time::{Duration, Instant},
     };
 
     use futures::FutureExt;
     use semver::Version;
     use test_case::test_case;
-    use tokio::sync::oneshot;
+    use tokio::sync::{broadcast, mpsc, oneshot};
+    use tokio_util::sync::CancellationToken;
     use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_filewatch::package_graph_watcher::PackageChangeEvent;
     use turborepo_repository::{
         discovery::{DiscoveryResponse, PackageDiscovery},
+        package_graph::WorkspaceName,
         package_manager::PackageManager,
     };
 
-    use super::compare_versions;
-    use crate::daemon::{proto::VersionRange, CloseReason, TurboGrpcService};
+    use super::{compare_versions, forward_changes, Repo, RepoRoots};
+    use crate::daemon::{
+        bump_timeout::BumpTimeout,
+        proto::{change_event, PackagesChanged, RediscoverPackages, VersionRange},
+        CloseReason, TurboGrpcService,
+    };
 
     #[test_case("1.2.3", "1.2.3", VersionRange::Exact, true ; "exact match")]
     #[test_case("1.2.3", "1.2.3", VersionRange::Patch, true ; "patch match")]
@@ -622,7 +1117,6 @@ mod test {
             .to_realpath()
             .unwrap();
 
-        let repo_root = path.join_component("repo");
         let daemon_root = path.join_component("daemon");
         let log_file = daemon_root.join_component("log");
         tracing::info!("start");
@@ -633,19 +1127,12 @@ mod test {
         let exit_signal = rx.map(|_result| CloseReason::Interrupt);
 
         let service = TurboGrpcService::new(
-            repo_root.clone(),
             daemon_root,
             log_file,
             Duration::from_secs(60 * 60),
             exit_signal,
         )
-        .with_package_discovery_backup(MockDiscovery);
-
-        // the package watcher reads data from the package.json file
-        // so we need to create it
-        repo_root.create_dir_all().unwrap();
-        let package_json = repo_root.join_component("package.json");
-        std::fs::write(package_json, r#"{"workspaces": ["packages/*"]}"#).unwrap();
+        .with_package_discovery_backup(|_| MockDiscovery);
 
         let handle = tokio::task::spawn(service.serve());
 
@@ -657,7 +1144,7 @@ mod test {
         );
         // signal server exit
         tx.send(CloseReason::Interrupt).unwrap();
-        handle.await.unwrap().unwrap();
+        handle.await.unwrap();
 
         // The serve future should be dropped here, closing the server.
         tracing::info!("yay we are done");
@@ -678,7 +1165,6 @@ mod test {
             .to_realpath()
             .unwrap();
 
-        let repo_root = path.join_component("repo");
         let daemon_root = path.join_component("daemon");
         let log_file = daemon_root.join_component("log");
 
@@ -689,19 +1175,12 @@ mod test {
         let exit_signal = rx.map(|_result| CloseReason::Interrupt);
 
         let server = TurboGrpcService::new(
-            repo_root.clone(),
             daemon_root,
             log_file,
             Duration::from_millis(10),
             exit_signal,
         )
-        .with_package_discovery_backup(MockDiscovery);
-
-        // the package watcher reads data from the package.json file
-        // so we need to create it
-        repo_root.create_dir_all().unwrap();
-        let package_json = repo_root.join_component("package.json");
-        std::fs::write(package_json, r#"{"workspaces": ["packages/*"]}"#).unwrap();
+        .with_package_discovery_backup(|_| MockDiscovery);
 
         let close_reason = server.serve().await;
 
@@ -711,12 +1190,61 @@ mod test {
         );
         assert_matches::assert_matches!(
             close_reason,
-            Ok(CloseReason::Timeout),
+            CloseReason::Timeout,
             "must close due to timeout"
         );
         assert!(!pid_path.exists(), "pid file must be deleted");
     }
 
+    #[tokio::test]
+    async fn test_forward_changes() {
+        let (changes_tx, changes_rx) = broadcast::channel(4);
+        let (tx, mut rx) = mpsc::channel(4);
+        let shutdown = CancellationToken::new();
+        let bump_timeout = Arc::new(BumpTimeout::new(Duration::from_secs(60)));
+        let handle = tokio::spawn(forward_changes(
+            changes_rx,
+            tx,
+            shutdown.clone(),
+            bump_timeout,
+        ));
+
+        changes_tx
+            .send(PackageChangeEvent::Packages(BTreeSet::from([
+                WorkspaceName::Root,
+                WorkspaceName::Other("web".to_string()),
+            ])))
+            .unwrap();
+        changes_tx.send(PackageChangeEvent::Rediscover).unwrap();
+
+        assert_eq!(
+            rx.recv().await.unwrap().unwrap().event,
+            Some(change_event::Event::PackagesChanged(PackagesChanged {
+                packages: vec!["//".to_string(), "web".to_string()]
+            }))
+        );
+        assert_eq!(
+            rx.recv().await.unwrap().unwrap().event,
+            Some(change_event::Event::RediscoverPackages(
+                RediscoverPackages {}
+            ))
+        );
+
+        // shutting down the server ends the stream
+        shutdown.cancel();
+        handle.await.unwrap();
+        assert!(rx.recv().await.is_none());
+    }
+
+    // a repository with the package.json that the package watcher reads
+    fn test_repo(path: &AbsoluteSystemPathBuf, name: &str) -> AbsoluteSystemPathBuf {
+        let repo_root = path.join_component(name);
+        repo_root.create_dir_all().unwrap();
+        let package_json = repo_root.join_component("package.json");
+        std::fs::write(package_json, r#"{"workspaces": ["packages/*"]}"#).unwrap();
+        repo_root
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     #[tracing_test::traced_test]
     async fn test_delete_root() {
@@ -725,35 +1253,61 @@ mod test {
             .unwrap()
             .to_realpath()
             .unwrap();
+        let repo_root = test_repo(&path, "repo");
 
-        let repo_root = path.join_component("repo");
-        let daemon_root = path.join_component("daemon");
-        daemon_root.create_dir_all().unwrap();
-        let log_file = daemon_root.join_component("log");
-
-        let (_tx, rx) = oneshot::channel::<CloseReason>();
-        let exit_signal = rx.map(|_result| CloseReason::Interrupt);
-
-        let server = TurboGrpcService::new(
-            repo_root.clone(),
-            daemon_root,
-            log_file,
-            Duration::from_secs(60 * 60),
-            exit_signal,
-        )
-        .with_package_discovery_backup(MockDiscovery);
-
-        let handle = tokio::task::spawn(server.serve());
+        let (evict_tx, mut evict_rx) = mpsc::channel(1);
+        let _repo = Repo::start(repo_root.clone(), MockDiscovery, evict_tx);
 
         // give filewatching some time to bootstrap
         tokio::time::sleep(Duration::from_secs(1)).await;
+
         // Remove the root
         repo_root.remove_dir_all().unwrap();
 
-        let close_reason = tokio::time::timeout(Duration::from_secs(1), handle)
+        let evicted = tokio::time::timeout(Duration::from_secs(1), evict_rx.recv())
             .await
-            .expect("no timeout")
-            .expect("server exited");
-        assert_matches!(close_reason, Ok(CloseReason::Shutdown));
+            .expect("no timeout");
+        assert_eq!(evicted, Some(repo_root));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_evict_idle() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = AbsoluteSystemPathBuf::try_from(tempdir.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+        let repo_a = test_repo(&path, "a");
+        let repo_b = test_repo(&path, "b");
+
+        let (evict_tx, _evict_rx) = mpsc::channel(1);
+        let repos = RepoRoots::new(move |repo_root| {
+            Repo::start(repo_root, MockDiscovery, evict_tx.clone())
+        });
+
+        let in_use = repos.get_or_start(repo_a.clone());
+        let _ = repos.get_or_start(repo_b.clone());
+        // asking again gives back the same repository
+        assert!(Arc::ptr_eq(&in_use, &repos.get_or_start(repo_a.clone())));
+        let served = |repos: &RepoRoots| {
+            repos
+                .statuses()
+                .into_iter()
+                .map(|status| status.repo_root)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(served(&repos), vec![repo_a.to_string(), repo_b.to_string()]);
+
+        repos.evict_idle(Duration::from_secs(60));
+        assert_eq!(served(&repos), vec![repo_a.to_string(), repo_b.to_string()]);
+
+        // only the repository that nobody is using is evicted
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        repos.evict_idle(Duration::from_millis(10));
+        assert_eq!(served(&repos), vec![repo_a.to_string()]);
+
+        drop(in_use);
+        repos.evict(&repo_a);
+        assert!(served(&repos).is_empty());
     }
 }
@@ -0,0 +1,106 @@
+//! Shared token authentication between coordinators and workers. The token
+//! is sent as an `authorization: Bearer <token>` header on every request.
+use subtle::ConstantTimeEq;
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    service::Interceptor,
+    Request, Status,
+};
+
+use super::Error;
+
+const AUTHORIZATION: &str = "authorization";
+
+fn header_value(token: &str) -> Result<MetadataValue<Ascii>, Error> {
+    if token.is_empty() {
+        return Err(Error::MissingToken);
+    }
+    format!("Bearer {token}")
+        .parse()
+        .map_err(|_| Error::InvalidToken)
+}
+
+/// Attaches the token to every request a coordinator sends
+#[derive(Debug, Clone)]
+pub(crate) struct BearerToken(MetadataValue<Ascii>);
+
+impl BearerToken {
+    pub fn new(token: &str) -> Result<Self, Error> {
+        Ok(Self(header_value(token)?))
+    }
+}
+
+impl Interceptor for BearerToken {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        request.metadata_mut().insert(AUTHORIZATION, self.0.clone());
+        Ok(request)
+    }
+}
+
+/// Rejects requests to a worker that don't carry its token
+#[derive(Debug, Clone)]
+pub(crate) struct RequireToken(MetadataValue<Ascii>);
+
+impl RequireToken {
+    pub fn new(token: &str) -> Result<Self, Error> {
+        Ok(Self(header_value(token)?))
+    }
+}
+
+impl Interceptor for RequireToken {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        // Compared in constant time so response timings don't leak the token
+        let authorized = request
+            .metadata()
+            .get(AUTHORIZATION)
+            .map_or(false, |value| {
+                bool::from(value.as_bytes().ct_eq(self.0.as_bytes()))
+            });
+        if !authorized {
+            return Err(Status::unauthenticated("missing or invalid worker token"));
+        }
+
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(Some("Bearer secret"), true ; "matching token")]
+    #[test_case(Some("Bearer secre"), false ; "prefix of token")]
+    #[test_case(Some("Bearer other"), false ; "other token")]
+    #[test_case(Some("secret"), false ; "missing bearer prefix")]
+    #[test_case(None, false ; "missing header")]
+    fn test_require_token(header: Option<&str>, authorized: bool) {
+        let mut request = Request::new(());
+        if let Some(header) = header {
+            request
+                .metadata_mut()
+                .insert(AUTHORIZATION, header.parse().unwrap());
+        }
+        let mut interceptor = RequireToken::new("secret").unwrap();
+        assert_eq!(interceptor.call(request).is_ok(), authorized);
+    }
+
+    #[test]
+    fn test_bearer_token_is_accepted() {
+        let request = BearerToken::new("secret")
+            .unwrap()
+            .call(Request::new(()))
+            .unwrap();
+        assert!(RequireToken::new("secret").unwrap().call(request).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_tokens() {
+        assert!(matches!(BearerToken::new(""), Err(Error::MissingToken)));
+        assert!(matches!(
+            BearerToken::new("new\nline"),
+            Err(Error::InvalidToken)
+        ));
+    }
+}
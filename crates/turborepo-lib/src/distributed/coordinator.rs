@@ -0,0 +1,292 @@
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, SemaphorePermit};
+use tonic::{service::interceptor::InterceptedService, transport::Channel, Code};
+use tracing::{debug, warn};
+
+use super::{
+    auth::BearerToken,
+    proto::{
+        run_task_event::Event, worker_client::WorkerClient, HelloRequest, HelloResponse,
+        RunTaskRequest, TaskFinished,
+    },
+    Error,
+};
+use crate::get_version;
+
+struct Worker {
+    address: String,
+    client: WorkerClient<InterceptedService<Channel, BearerToken>>,
+    session: Mutex<String>,
+    slots: Arc<Semaphore>,
+    // Cleared once the worker fails, it isn't sent any more tasks
+    healthy: AtomicBool,
+}
+
+impl Worker {
+    async fn connect(address: &str, token: BearerToken) -> Result<Self, Error> {
+        let channel = Channel::from_shared(format!("http://{address}"))
+            .map_err(|_| Error::InvalidAddress(address.to_string()))?
+            .connect()
+            .await
+            .map_err(|source| Error::Connect {
+                address: address.to_string(),
+                source,
+            })?;
+        let worker = Self {
+            address: address.to_string(),
+            client: WorkerClient::with_interceptor(channel, token),
+            session: Mutex::default(),
+            slots: Arc::new(Semaphore::new(0)),
+            healthy: AtomicBool::new(true),
+        };
+        let concurrency = worker.hello().await?;
+        debug!("connected to worker {address} with concurrency {concurrency}");
+        worker.slots.add_permits(concurrency.max(1) as usize);
+
+        Ok(worker)
+    }
+
+    /// Starts a new session, returning how many tasks the worker runs at once
+    async fn hello(&self) -> Result<u32, Error> {
+        let HelloResponse {
+            concurrency,
+            session,
+        } = self
+            .client
+            .clone()
+            .hello(HelloRequest {
+                version: get_version().to_string(),
+            })
+            .await
+            .map_err(|status| Error::Hello {
+                address: self.address.clone(),
+                status,
+            })?
+            .into_inner();
+        *self.session.lock().expect("session lock poisoned") = session;
+
+        Ok(concurrency)
+    }
+
+    async fn run_task(
+        &self,
+        mut request: RunTaskRequest,
+        output: &mut impl Write,
+    ) -> Result<TaskFinished, Error> {
+        request.session = self.session.lock().expect("session lock poisoned").clone();
+        let rpc_error = |status| Error::RunTask {
+            address: self.address.clone(),
+            status,
+        };
+        let mut events = self
+            .client
+            .clone()
+            .run_task(request)
+            .await
+            .map_err(rpc_error)?
+            .into_inner();
+
+        while let Some(event) = events.message().await.map_err(rpc_error)? {
+            match event.event {
+                Some(Event::Output(bytes)) => output.write_all(&bytes)?,
+                Some(Event::Finished(finished)) => return Ok(finished),
+                None => (),
+            }
+        }
+
+        Err(Error::Disconnected(self.address.clone()))
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Acquire)
+    }
+}
+
+// A slot on a worker along with the pool's permit for it. The worker's slot
+// is released first so a task woken up by the pool always finds a free slot.
+struct Slot<'a> {
+    _worker: OwnedSemaphorePermit,
+    _pool: SemaphorePermit<'a>,
+}
+
+/// The workers a coordinator dispatches tasks to. Each worker reports how
+/// many tasks it can run at once and a task waits until one of them has a
+/// free slot.
+///
+/// A task is retried on another worker if the one it ran on fails. Workers
+/// whose connection fails aren't sent any more tasks, once none are left
+/// `run_task` returns an error and the task should be run locally.
+pub struct WorkerPool {
+    workers: Vec<Worker>,
+    // One permit for every slot across all workers, so waiting tasks don't
+    // need to poll each worker. Closed once every worker has failed.
+    slots: Semaphore,
+    concurrency: usize,
+}
+
+impl WorkerPool {
+    /// Connects to every worker, authenticating with the workers' shared
+    /// `token`
+    pub async fn connect(addresses: &[String], token: &str) -> Result<Self, Error> {
+        let token = BearerToken::new(token)?;
+        let mut workers = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            workers.push(Worker::connect(address, token.clone()).await?);
+        }
+
+        let concurrency = workers
+            .iter()
+            .map(|worker| worker.slots.available_permits())
+            .sum();
+
+        Ok(Self {
+            workers,
+            slots: Semaphore::new(concurrency),
+            concurrency,
+        })
+    }
+
+    pub fn worker_count(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// How many tasks can run across all workers at once
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Runs a task on the first worker with a free slot, writing its output
+    /// as it arrives. The task's outputs are in the cache once this returns
+    /// successfully.
+    ///
+    /// If the worker fails before the task finishes, or can't run the task
+    /// at all, the task is retried on a worker it hasn't been tried on yet.
+    /// Output from the failed attempts has already been written by then.
+    pub async fn run_task(
+        &self,
+        request: RunTaskRequest,
+        mut output: impl Write,
+    ) -> Result<TaskFinished, Error> {
+        let mut tried = Vec::new();
+        let mut last_error = None;
+        while let Some((index, _slot)) = self.acquire_worker(&tried).await {
+            tried.push(index);
+            let worker = &self.workers[index];
+            debug!("running {} on worker {}", request.task_id, worker.address);
+
+            let error = match self.run_on(worker, request.clone(), &mut output).await {
+                Ok(TaskFinished {
+                    error: Some(error), ..
+                }) => Error::Task {
+                    address: worker.address.clone(),
+                    error,
+                },
+                Ok(finished) => return Ok(finished),
+                // The task itself is invalid or its output can't be written,
+                // another worker won't do any better
+                Err(e) if !is_worker_failure(&e) => return Err(e),
+                Err(e) => {
+                    self.remove(worker);
+                    e
+                }
+            };
+            warn!("{error}, retrying {} on another worker", request.task_id);
+            last_error = Some(error);
+        }
+
+        Err(last_error.unwrap_or(Error::NoWorkers))
+    }
+
+    async fn run_on(
+        &self,
+        worker: &Worker,
+        request: RunTaskRequest,
+        output: &mut impl Write,
+    ) -> Result<TaskFinished, Error> {
+        match worker.run_task(request.clone(), output).await {
+            // The worker ended the session, most likely because the
+            // connection was reestablished or it went unused for too long
+            Err(Error::RunTask { status, .. }) if status.code() == Code::FailedPrecondition => {
+                debug!("session with worker {} expired", worker.address);
+                worker.hello().await?;
+                worker.run_task(request, output).await
+            }
+            result => result,
+        }
+    }
+
+    /// Waits for a slot on a healthy worker that isn't in `tried`. Returns
+    /// `None` if there aren't any such workers.
+    async fn acquire_worker(&self, tried: &[usize]) -> Option<(usize, Slot<'_>)> {
+        loop {
+            let pool = self.slots.acquire().await.ok()?;
+            let candidates = || {
+                self.workers
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, worker)| worker.is_healthy() && !tried.contains(index))
+            };
+            if let Some((index, worker)) = candidates().find_map(|(index, worker)| {
+                Some((index, worker.slots.clone().try_acquire_owned().ok()?))
+            }) {
+                return Some((
+                    index,
+                    Slot {
+                        _worker: worker,
+                        _pool: pool,
+                    },
+                ));
+            }
+
+            // The free slot belongs to a worker that failed, retire it so
+            // tasks stop waking up for it
+            if let Some(worker) = self
+                .workers
+                .iter()
+                .filter(|worker| !worker.is_healthy())
+                .find_map(|worker| worker.slots.clone().try_acquire_owned().ok())
+            {
+                worker.forget();
+                pool.forget();
+                continue;
+            }
+
+            // Only workers that already failed this task have a free slot,
+            // wait for one of the others
+            let (index, worker) = candidates().next()?;
+            let worker = worker.slots.clone().acquire_owned().await.ok()?;
+            return Some((
+                index,
+                Slot {
+                    _worker: worker,
+                    _pool: pool,
+                },
+            ));
+        }
+    }
+
+    fn remove(&self, worker: &Worker) {
+        worker.healthy.store(false, Ordering::Release);
+        if !self.workers.iter().any(Worker::is_healthy) {
+            // Wakes up every waiting task so it can run locally
+            self.slots.close();
+        }
+    }
+}
+
+// Whether an error means the worker can't be used anymore, rather than
+// something being wrong with the task
+fn is_worker_failure(error: &Error) -> bool {
+    match error {
+        Error::RunTask { status, .. } => status.code() != Code::InvalidArgument,
+        Error::Output(_) => false,
+        _ => true,
+    }
+}
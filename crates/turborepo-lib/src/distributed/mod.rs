@@ -0,0 +1,401 @@
+//! Distributed task execution
+//!
+//! `turbo run --workers` turns the run into a coordinator: it still walks the
+//! task graph, hashes tasks and checks the cache, but cache misses are sent to
+//! `turbo worker` processes over gRPC instead of being spawned locally.
+//!
+//! Workers run in their own checkout of the repository at the same commit.
+//! Task outputs aren't sent back over the connection, the cache is used as
+//! the transport instead: before running a task a worker restores the outputs
+//! of its dependencies from the cache, and once it succeeds the worker puts
+//! the task's outputs in the cache for the coordinator and other workers to
+//! restore. Workers sharing a checkout and filesystem cache with the
+//! coordinator, as they do when all of them run on one machine, don't need a
+//! remote cache at all.
+
+mod auth;
+mod coordinator;
+mod task;
+mod worker;
+
+pub use coordinator::WorkerPool;
+pub use task::TaskResolver;
+use thiserror::Error;
+pub use worker::WorkerService;
+
+pub(crate) mod proto {
+    tonic::include_proto!("turboworkerprotocol");
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to connect to worker {address}: {source}")]
+    Connect {
+        address: String,
+        #[source]
+        source: tonic::transport::Error,
+    },
+    #[error("worker {address} refused the connection: {}", status.message())]
+    Hello {
+        address: String,
+        status: tonic::Status,
+    },
+    #[error("worker {address} failed to run task: {}", status.message())]
+    RunTask {
+        address: String,
+        status: tonic::Status,
+    },
+    #[error("worker {0} disconnected before the task finished")]
+    Disconnected(String),
+    #[error("worker {address} couldn't run the task: {error}")]
+    Task { address: String, error: String },
+    #[error("no workers are available")]
+    NoWorkers,
+    #[error("failed to write task output: {0}")]
+    Output(#[from] std::io::Error),
+    #[error("worker server failed: {0}")]
+    Serve(#[from] tonic::transport::Error),
+    #[error("workers require a shared token, set one with --worker-token or TURBO_WORKER_TOKEN")]
+    MissingToken,
+    #[error("worker token must only contain visible ASCII characters")]
+    InvalidToken,
+    #[error("invalid worker address {0}")]
+    InvalidAddress(String),
+    #[error(transparent)]
+    PackageJson(#[from] turborepo_repository::package_json::Error),
+    #[error(transparent)]
+    PackageGraph(#[from] turborepo_repository::package_graph::builder::Error),
+    #[error(transparent)]
+    Config(#[from] crate::config::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, time::Duration};
+
+    use tempdir::TempDir;
+    use tokio::{net::TcpListener, sync::oneshot, task::JoinHandle};
+    use tonic::{service::interceptor::InterceptedService, transport::Channel, Code};
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_api_client::APIClient;
+    use turborepo_cache::{AsyncCache, CacheOpts};
+
+    use super::{
+        auth::BearerToken,
+        proto::{worker_client::WorkerClient, HelloRequest, RunTaskRequest},
+        Error, TaskResolver, WorkerPool, WorkerService,
+    };
+    use crate::{get_version, process::ProcessManager};
+
+    const TOKEN: &str = "secret";
+
+    fn cache(repo_root: &AbsoluteSystemPathBuf) -> AsyncCache {
+        let opts = CacheOpts {
+            skip_remote: true,
+            workers: 10,
+            ..CacheOpts::default()
+        };
+        let api_client = APIClient::new("http://localhost:1", 200, "2.0.0", true).unwrap();
+        AsyncCache::new(&opts, repo_root, api_client, None, None).unwrap()
+    }
+
+    fn setup_repo(repo_root: &AbsoluteSystemPathBuf) {
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(
+                r#"{"name": "root", "packageManager": "npm@10.2.0", "workspaces": ["packages/*"]}"#,
+            )
+            .unwrap();
+        repo_root
+            .join_component("turbo.json")
+            .create_with_contents(
+                r#"{
+                    "pipeline": {
+                        "build": {
+                            "command": "node build.js",
+                            "outputs": ["dist/**"]
+                        },
+                        "fail": { "command": "node -e \"process.exit(3)\"" },
+                        "dev": { "command": "node build.js", "persistent": true }
+                    }
+                }"#,
+            )
+            .unwrap();
+        let package = repo_root.join_components(&["packages", "a"]);
+        package.create_dir_all().unwrap();
+        package
+            .join_component("package.json")
+            .create_with_contents(r#"{"name": "a"}"#)
+            .unwrap();
+        package
+            .join_component("build.js")
+            .create_with_contents(
+                "require('fs').mkdirSync('dist'); require('fs').writeFileSync('dist/out.txt', \
+                 process.env.TURBO_HASH + (process.env.SECRET ?? '')); console.log('building')",
+            )
+            .unwrap();
+    }
+
+    fn request(task_id: &str, hash: &str) -> RunTaskRequest {
+        RunTaskRequest {
+            task_id: task_id.to_string(),
+            hash: hash.to_string(),
+            ..RunTaskRequest::default()
+        }
+    }
+
+    struct TestWorker {
+        address: String,
+        stop: oneshot::Sender<()>,
+        server: JoinHandle<Result<(), Error>>,
+    }
+
+    impl TestWorker {
+        async fn start(repo_root: &AbsoluteSystemPathBuf, session_ttl: Duration) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap().to_string();
+            let (stop, stopped) = oneshot::channel::<()>();
+            let service = WorkerService::new(
+                TaskResolver::new(repo_root.clone(), false).await.unwrap(),
+                cache(repo_root),
+                ProcessManager::new(false),
+                2,
+            )
+            .with_session_ttl(session_ttl);
+            let server = tokio::spawn(service.serve(TOKEN, listener, async {
+                stopped.await.ok();
+            }));
+
+            Self {
+                address,
+                stop,
+                server,
+            }
+        }
+
+        async fn client(&self) -> WorkerClient<InterceptedService<Channel, BearerToken>> {
+            let channel = Channel::from_shared(format!("http://{}", self.address))
+                .unwrap()
+                .connect()
+                .await
+                .unwrap();
+            WorkerClient::with_interceptor(channel, BearerToken::new(TOKEN).unwrap())
+        }
+
+        async fn stop(self) {
+            self.stop.send(()).unwrap();
+            self.server.await.unwrap().unwrap();
+        }
+    }
+
+    fn repo() -> (TempDir, AbsoluteSystemPathBuf) {
+        let dir = TempDir::new("distributed").unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(dir.path()).unwrap();
+        setup_repo(&repo_root);
+        (dir, repo_root)
+    }
+
+    // Unknown tasks are rejected after the session is checked, so this
+    // checks the session without running anything
+    async fn session_code(
+        client: &mut WorkerClient<InterceptedService<Channel, BearerToken>>,
+        session: &str,
+    ) -> Code {
+        client
+            .run_task(RunTaskRequest {
+                session: session.to_string(),
+                ..request("a#unknown", "unknown")
+            })
+            .await
+            .unwrap_err()
+            .code()
+    }
+
+    #[tokio::test]
+    async fn test_run_task_on_worker() {
+        let (_dir, repo_root) = repo();
+        let worker = TestWorker::start(&repo_root, Duration::from_secs(60)).await;
+        let address = worker.address.clone();
+
+        // Coordinators without the token are turned away
+        assert!(matches!(
+            WorkerPool::connect(&[address.clone()], "wrong").await,
+            Err(Error::Hello { status, .. }) if status.code() == Code::Unauthenticated
+        ));
+        assert!(matches!(
+            WorkerPool::connect(&[address.clone()], "").await,
+            Err(Error::MissingToken)
+        ));
+
+        // Tasks can't be run without a session from Hello
+        let mut client = worker.client().await;
+        let status = client
+            .run_task(request("a#build", "built"))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        let workers = WorkerPool::connect(&[address], TOKEN).await.unwrap();
+        assert_eq!(workers.worker_count(), 1);
+        assert_eq!(workers.concurrency(), 2);
+
+        let mut output = Vec::new();
+        let finished = workers
+            .run_task(
+                RunTaskRequest {
+                    env: HashMap::from([("SECRET".to_string(), "-declared".to_string())]),
+                    ..request("a#build", "built")
+                },
+                &mut output,
+            )
+            .await
+            .unwrap();
+        assert_eq!(finished.exit_code, Some(0));
+        assert_eq!(finished.error, None);
+        assert!(String::from_utf8(output).unwrap().contains("building"));
+        assert_eq!(
+            repo_root
+                .join_components(&["packages", "a", "dist", "out.txt"])
+                .read_to_string()
+                .unwrap(),
+            "built-declared"
+        );
+        assert!(repo_root
+            .join_components(&["packages", "a", ".turbo", "turbo-build.log"])
+            .exists());
+        // The worker has to have cached the outputs before reporting success
+        assert!(cache(&repo_root).exists("built").await.unwrap().is_some());
+
+        let finished = workers
+            .run_task(request("a#fail", "failed"), std::io::sink())
+            .await
+            .unwrap();
+        assert_eq!(finished.exit_code, Some(3));
+        assert!(cache(&repo_root).exists("failed").await.unwrap().is_none());
+
+        // Only tasks defined in the worker's checkout can be run, and hashes
+        // can't be used to write outside of the cache
+        for rejected in [
+            request("a#unknown", "unknown"),
+            request("b#build", "unknown"),
+            request("a#dev", "persistent"),
+            request("a#build", "../../escape"),
+            RunTaskRequest {
+                dependency_hashes: vec!["../escape".to_string()],
+                ..request("a#build", "dependency")
+            },
+        ] {
+            assert!(matches!(
+                workers.run_task(rejected, std::io::sink()).await,
+                Err(Error::RunTask { status, .. }) if status.code() == Code::InvalidArgument
+            ));
+        }
+
+        drop(workers);
+        worker.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_session_ends_with_connection() {
+        let (_dir, repo_root) = repo();
+        let worker = TestWorker::start(&repo_root, Duration::from_secs(60)).await;
+
+        let mut client = worker.client().await;
+        let session = client
+            .hello(HelloRequest {
+                version: get_version().to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .session;
+        assert_eq!(
+            session_code(&mut client, &session).await,
+            Code::InvalidArgument
+        );
+        drop(client);
+
+        // The worker notices the connection closing in the background
+        let mut client = worker.client().await;
+        let mut code = session_code(&mut client, &session).await;
+        for _ in 0..50 {
+            if code == Code::FailedPrecondition {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            code = session_code(&mut client, &session).await;
+        }
+        assert_eq!(code, Code::FailedPrecondition);
+
+        drop(client);
+        worker.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_session_expires() {
+        let (_dir, repo_root) = repo();
+        let worker = TestWorker::start(&repo_root, Duration::from_millis(200)).await;
+
+        let mut client = worker.client().await;
+        let session = client
+            .hello(HelloRequest {
+                version: get_version().to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .session;
+        let workers = WorkerPool::connect(&[worker.address.clone()], TOKEN)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        assert_eq!(
+            session_code(&mut client, &session).await,
+            Code::FailedPrecondition
+        );
+        // The pool starts a new session when its old one has expired
+        let finished = workers
+            .run_task(request("a#build", "expired"), std::io::sink())
+            .await
+            .unwrap();
+        assert_eq!(finished.exit_code, Some(0));
+
+        drop((client, workers));
+        worker.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_retries_on_another_worker() {
+        let (_dir, repo_root) = repo();
+        let first = TestWorker::start(&repo_root, Duration::from_secs(60)).await;
+        let second = TestWorker::start(&repo_root, Duration::from_secs(60)).await;
+        let workers = WorkerPool::connect(&[first.address.clone(), second.address.clone()], TOKEN)
+            .await
+            .unwrap();
+        assert_eq!(workers.concurrency(), 4);
+
+        // Tasks go to the first worker with a free slot, so this one is
+        // retried on the second worker
+        first.stop().await;
+        let finished = workers
+            .run_task(request("a#build", "retried"), std::io::sink())
+            .await
+            .unwrap();
+        assert_eq!(finished.exit_code, Some(0));
+        assert!(cache(&repo_root).exists("retried").await.unwrap().is_some());
+
+        // Once every worker is gone tasks have to run locally
+        second.stop().await;
+        assert!(workers
+            .run_task(request("a#build", "local"), std::io::sink())
+            .await
+            .is_err());
+        assert!(matches!(
+            workers
+                .run_task(request("a#build", "local"), std::io::sink())
+                .await,
+            Err(Error::NoWorkers)
+        ));
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath};
+use turborepo_repository::{
+    package_graph::{PackageGraph, WorkspaceName},
+    package_json::PackageJson,
+};
+
+use super::Error;
+use crate::{
+    engine::{BuilderError, EngineBuilder},
+    run::task_id::{TaskId, TaskName},
+    task_graph::{program_and_args, ArgsError, TaskDefinition, TaskOutputs},
+    turbo_json::{package_graph_builder, TurboJson},
+};
+
+#[derive(Debug, Error)]
+pub enum ResolveError {
+    #[error("invalid task id {0}")]
+    InvalidTaskId(String),
+    #[error("invalid hash {0}")]
+    InvalidHash(String),
+    #[error("package {0} isn't in the worker's checkout")]
+    UnknownPackage(String),
+    #[error("failed to resolve {task_id}: {source}")]
+    Engine {
+        task_id: String,
+        #[source]
+        source: Box<BuilderError>,
+    },
+    #[error("{0} isn't defined in the worker's checkout")]
+    UnknownTask(String),
+    #[error("{0} has no command to run")]
+    NoCommand(String),
+    #[error("{0} can't be run on a worker")]
+    NotDistributable(String),
+    #[error("{task_id} has an invalid cwd: {source}")]
+    Cwd {
+        task_id: String,
+        #[source]
+        source: turbopath::PathError,
+    },
+    #[error("{0} has a cwd outside of the repository")]
+    CwdOutsideRepo(String),
+    #[error("{task_id} can't be run: {source}")]
+    Args {
+        task_id: String,
+        #[source]
+        source: ArgsError,
+    },
+}
+
+/// Everything a worker needs to run a task and cache its outputs
+pub struct ResolvedTask {
+    pub directory: AbsoluteSystemPathBuf,
+    pub program: &'static str,
+    pub args: Vec<String>,
+    pub outputs: TaskOutputs,
+    pub log_file: AbsoluteSystemPathBuf,
+}
+
+/// Resolves the tasks a coordinator asks for from the worker's own checkout,
+/// so a coordinator can only run tasks that are defined in the repository
+/// and their outputs can't be written outside of it.
+pub struct TaskResolver {
+    repo_root: AbsoluteSystemPathBuf,
+    package_graph: PackageGraph,
+    root_turbo_json: TurboJson,
+    is_single_package: bool,
+}
+
+impl TaskResolver {
+    pub async fn new(
+        repo_root: AbsoluteSystemPathBuf,
+        is_single_package: bool,
+    ) -> Result<Self, Error> {
+        let root_package_json = PackageJson::load(&repo_root.join_component("package.json"))?;
+        let root_turbo_json = TurboJson::load(
+            &repo_root,
+            AnchoredSystemPath::empty(),
+            &root_package_json,
+            is_single_package,
+        )?;
+        let package_graph = package_graph_builder(&repo_root, root_package_json)?
+            .with_single_package_mode(is_single_package)
+            .build()
+            .await?;
+
+        Ok(Self {
+            repo_root,
+            package_graph,
+            root_turbo_json,
+            is_single_package,
+        })
+    }
+
+    pub fn repo_root(&self) -> &AbsoluteSystemPath {
+        &self.repo_root
+    }
+
+    pub fn resolve(&self, task_id: &str) -> Result<ResolvedTask, ResolveError> {
+        let task_id = TaskId::try_from(task_id)
+            .map_err(|_| ResolveError::InvalidTaskId(task_id.to_string()))?
+            .into_owned();
+        let workspace = WorkspaceName::from(task_id.package());
+        let workspace_info = self
+            .package_graph
+            .workspace_info(&workspace)
+            .ok_or_else(|| ResolveError::UnknownPackage(task_id.package().to_string()))?;
+
+        let engine =
+            EngineBuilder::new(&self.repo_root, &self.package_graph, self.is_single_package)
+                .with_root_tasks(self.root_turbo_json.pipeline.keys().cloned())
+                .with_turbo_jsons(Some(
+                    [(WorkspaceName::Root, self.root_turbo_json.clone())]
+                        .into_iter()
+                        .collect::<HashMap<_, _>>(),
+                ))
+                .with_tasks_only(true)
+                .with_workspaces(vec![workspace])
+                .with_tasks(Some(TaskName::from(task_id.task()).into_owned()))
+                .build()
+                .map_err(|source| ResolveError::Engine {
+                    task_id: task_id.to_string(),
+                    source: Box::new(source),
+                })?;
+        let definition = engine
+            .task_definition(&task_id)
+            .ok_or_else(|| ResolveError::UnknownTask(task_id.to_string()))?;
+        // Coordinators never send these, their outputs couldn't be returned
+        if definition.persistent || !definition.cache {
+            return Err(ResolveError::NotDistributable(task_id.to_string()));
+        }
+        if definition
+            .resolved_command(&task_id, &workspace_info.package_json)
+            .map_or(true, str::is_empty)
+        {
+            return Err(ResolveError::NoCommand(task_id.to_string()));
+        }
+
+        let package_path = workspace_info.package_path();
+        let mut directory = self.repo_root.resolve(package_path);
+        if let Some(cwd) = &definition.cwd {
+            directory = directory
+                .join_unix_path(cwd)
+                .map_err(|source| ResolveError::Cwd {
+                    task_id: task_id.to_string(),
+                    source,
+                })?;
+            if !self.repo_root.contains(&directory) {
+                return Err(ResolveError::CwdOutsideRepo(task_id.to_string()));
+            }
+        }
+        let (program, args) = program_and_args(
+            definition.command.as_deref(),
+            definition.script(&task_id),
+            &definition.args,
+            self.package_graph.package_manager(),
+        )
+        .map_err(|source| ResolveError::Args {
+            task_id: task_id.to_string(),
+            source,
+        })?;
+
+        Ok(ResolvedTask {
+            directory,
+            program,
+            args,
+            outputs: definition.repo_relative_hashable_outputs(&task_id, package_path),
+            log_file: self
+                .repo_root
+                .resolve(package_path)
+                .resolve(&TaskDefinition::workspace_relative_log_file(task_id.task())),
+        })
+    }
+}
+
+/// Hashes are used as cache keys, which end up in file names
+pub fn validate_hash(hash: &str) -> Result<(), ResolveError> {
+    if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(ResolveError::InvalidHash(hash.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::validate_hash;
+
+    #[test_case("0123abcd", true ; "hex hash")]
+    #[test_case("", false ; "empty")]
+    #[test_case("../../etc/passwd", false ; "path traversal")]
+    #[test_case("abc/def", false ; "separator")]
+    fn test_validate_hash(hash: &str, valid: bool) {
+        assert_eq!(validate_hash(hash).is_ok(), valid);
+    }
+}
@@ -0,0 +1,436 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures::{Future, StreamExt};
+use globwalk::WalkType;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, Semaphore},
+};
+use tokio_stream::wrappers::{TcpListenerStream, UnboundedReceiverStream};
+use tonic::{
+    transport::{server::Connected, Server},
+    Request, Response, Status,
+};
+use tracing::{debug, info};
+use turbopath::AnchoredSystemPathBuf;
+use turborepo_cache::AsyncCache;
+use which::which;
+
+use super::{
+    auth::RequireToken,
+    proto::{
+        run_task_event::Event,
+        worker_server::{Worker, WorkerServer},
+        HelloRequest, HelloResponse, RunTaskEvent, RunTaskRequest, TaskFinished,
+    },
+    task::{validate_hash, ResolvedTask, TaskResolver},
+    Error,
+};
+use crate::{
+    get_version,
+    process::{ChildExit, Command, ProcessManager, ShutdownPolicy},
+    task_hash::DEFAULT_PASS_THROUGH_ENV,
+};
+
+type EventSender = mpsc::UnboundedSender<Result<RunTaskEvent, Status>>;
+
+/// How long a session lasts after it was last used. Sessions normally end
+/// when the coordinator's connection closes, this cleans up after
+/// connections that are never closed.
+const SESSION_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// Identifies the connection a request arrived on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ConnectionId(u64);
+
+struct Session {
+    connection: Option<ConnectionId>,
+    last_used: Instant,
+}
+
+/// The sessions handed out by Hello. RunTask requests must use one of them.
+struct Sessions {
+    sessions: Mutex<HashMap<String, Session>>,
+    ttl: Duration,
+    next_connection: AtomicU64,
+}
+
+impl Sessions {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            sessions: Mutex::default(),
+            ttl,
+            next_connection: AtomicU64::new(0),
+        }
+    }
+
+    fn start(&self, connection: Option<ConnectionId>) -> String {
+        let session = format!("{:032x}", rand::random::<u128>());
+        let mut sessions = self.sessions.lock().expect("sessions lock poisoned");
+        self.expire(&mut sessions);
+        sessions.insert(
+            session.clone(),
+            Session {
+                connection,
+                last_used: Instant::now(),
+            },
+        );
+        session
+    }
+
+    /// Whether `session` is still valid, extending it if it is
+    fn touch(&self, session: &str) -> bool {
+        let mut sessions = self.sessions.lock().expect("sessions lock poisoned");
+        self.expire(&mut sessions);
+        match sessions.get_mut(session) {
+            Some(session) => {
+                session.last_used = Instant::now();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expire(&self, sessions: &mut HashMap<String, Session>) {
+        sessions.retain(|_, session| session.last_used.elapsed() < self.ttl);
+    }
+
+    fn track(self: &Arc<Self>, stream: TcpStream) -> Connection {
+        Connection {
+            stream,
+            id: ConnectionId(self.next_connection.fetch_add(1, Ordering::Relaxed)),
+            sessions: self.clone(),
+        }
+    }
+
+    fn end_connection(&self, connection: ConnectionId) {
+        let mut sessions = self.sessions.lock().expect("sessions lock poisoned");
+        sessions.retain(|_, session| session.connection != Some(connection));
+    }
+}
+
+/// A coordinator's connection, its sessions end when it's closed
+struct Connection {
+    stream: TcpStream,
+    id: ConnectionId,
+    sessions: Arc<Sessions>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.sessions.end_connection(self.id);
+    }
+}
+
+impl Connected for Connection {
+    type ConnectInfo = ConnectionId;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.id
+    }
+}
+
+impl AsyncRead for Connection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Connection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+/// Runs tasks sent by a coordinator in the worker's own checkout of the
+/// repository
+pub struct WorkerService {
+    resolver: Arc<TaskResolver>,
+    cache: AsyncCache,
+    manager: ProcessManager,
+    concurrency: usize,
+    slots: Arc<Semaphore>,
+    sessions: Arc<Sessions>,
+}
+
+impl WorkerService {
+    pub fn new(
+        resolver: TaskResolver,
+        cache: AsyncCache,
+        manager: ProcessManager,
+        concurrency: usize,
+    ) -> Self {
+        Self {
+            resolver: Arc::new(resolver),
+            cache,
+            manager,
+            concurrency,
+            slots: Arc::new(Semaphore::new(concurrency)),
+            sessions: Arc::new(Sessions::new(SESSION_TTL)),
+        }
+    }
+
+    /// How long a session lasts after it was last used, 30 minutes by
+    /// default
+    pub fn with_session_ttl(mut self, ttl: Duration) -> Self {
+        self.sessions = Arc::new(Sessions::new(ttl));
+        self
+    }
+
+    /// Serves coordinators that present `token` until `shutdown` resolves,
+    /// then stops any tasks that are still running
+    pub async fn serve(
+        self,
+        token: &str,
+        listener: TcpListener,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<(), Error> {
+        let token = RequireToken::new(token)?;
+        let manager = self.manager.clone();
+        let sessions = self.sessions.clone();
+        let incoming = TcpListenerStream::new(listener)
+            .map(move |stream| stream.map(|stream| sessions.track(stream)));
+        let result = Server::builder()
+            .add_service(WorkerServer::with_interceptor(self, token))
+            .serve_with_incoming_shutdown(incoming, shutdown)
+            .await;
+        manager.stop().await;
+
+        Ok(result?)
+    }
+}
+
+#[tonic::async_trait]
+impl Worker for WorkerService {
+    type RunTaskStream = UnboundedReceiverStream<Result<RunTaskEvent, Status>>;
+
+    async fn hello(
+        &self,
+        request: Request<HelloRequest>,
+    ) -> Result<Response<HelloResponse>, Status> {
+        let connection = request.extensions().get::<ConnectionId>().copied();
+        let version = request.into_inner().version;
+        if version != get_version() {
+            return Err(Status::failed_precondition(format!(
+                "worker is running turbo {}, but the coordinator is running turbo {version}",
+                get_version()
+            )));
+        }
+
+        let session = self.sessions.start(connection);
+
+        Ok(Response::new(HelloResponse {
+            concurrency: self.concurrency as u32,
+            session,
+        }))
+    }
+
+    async fn run_task(
+        &self,
+        request: Request<RunTaskRequest>,
+    ) -> Result<Response<Self::RunTaskStream>, Status> {
+        let request = request.into_inner();
+        if !self.sessions.touch(&request.session) {
+            return Err(Status::failed_precondition(
+                "no session for this coordinator, Hello must be called first",
+            ));
+        }
+        let resolved = std::iter::once(&request.hash)
+            .chain(&request.dependency_hashes)
+            .try_for_each(|hash| validate_hash(hash))
+            .and_then(|_| self.resolver.resolve(&request.task_id))
+            .map_err(|err| Status::invalid_argument(err.to_string()))?;
+
+        let (events, receiver) = mpsc::unbounded_channel();
+        let task = TaskRun {
+            resolver: self.resolver.clone(),
+            cache: self.cache.clone(),
+            manager: self.manager.clone(),
+            slots: self.slots.clone(),
+        };
+
+        tokio::spawn(async move {
+            info!("running {}", request.task_id);
+            let finished = task.run(request, resolved, &events).await;
+            // The coordinator might have gone away, there's nobody to tell
+            events
+                .send(Ok(RunTaskEvent {
+                    event: Some(Event::Finished(finished)),
+                }))
+                .ok();
+        });
+
+        Ok(Response::new(UnboundedReceiverStream::new(receiver)))
+    }
+}
+
+struct TaskRun {
+    resolver: Arc<TaskResolver>,
+    cache: AsyncCache,
+    manager: ProcessManager,
+    slots: Arc<Semaphore>,
+}
+
+impl TaskRun {
+    async fn run(
+        self,
+        request: RunTaskRequest,
+        task: ResolvedTask,
+        events: &EventSender,
+    ) -> TaskFinished {
+        let _slot = self
+            .slots
+            .acquire()
+            .await
+            .expect("worker slots are never closed");
+        let start = Instant::now();
+        let finished = |exit_code, error: Option<String>| TaskFinished {
+            exit_code,
+            duration_msec: start.elapsed().as_millis() as u64,
+            error,
+        };
+
+        let repo_root = self.resolver.repo_root();
+        for hash in &request.dependency_hashes {
+            // Dependencies that aren't cached have nothing to restore
+            match self.cache.fetch(repo_root, hash).await {
+                Ok(Some(_)) => debug!("restored dependency outputs {hash}"),
+                Ok(None) => debug!("no cached outputs for dependency {hash}"),
+                Err(err) => debug!("failed to restore dependency outputs {hash}: {err}"),
+            }
+        }
+
+        let Ok(binary) = which(task.program) else {
+            return finished(
+                None,
+                Some(format!("unable to find {} on the worker", task.program)),
+            );
+        };
+
+        let mut cmd = Command::new(binary);
+        cmd.args(&task.args);
+        cmd.current_dir(task.directory.clone());
+        cmd.env_clear();
+        cmd.envs(request.env.iter());
+        // The worker's own PATH and shell are used, not the coordinator's
+        cmd.envs(DEFAULT_PASS_THROUGH_ENV.iter().filter_map(|name| {
+            let value = std::env::var(name).ok()?;
+            Some((name.to_string(), value))
+        }));
+        cmd.env("TURBO_HASH", &request.hash);
+
+        let mut process = match self.manager.spawn(cmd, ShutdownPolicy::default()) {
+            Some(Ok(process)) => process,
+            Some(Err(err)) => return finished(None, Some(err.to_string())),
+            None => return finished(None, Some("worker is shutting down".to_string())),
+        };
+
+        let mut output = OutputSender {
+            events,
+            log: Vec::new(),
+        };
+        let exit_code = match process.wait_with_piped_outputs(&mut output).await {
+            Ok(Some(ChildExit::Finished(exit_code))) => exit_code,
+            Ok(_) => return finished(None, Some("task was killed".to_string())),
+            Err(err) => return finished(None, Some(format!("unable to pipe outputs: {err}"))),
+        };
+        let duration = start.elapsed();
+
+        if exit_code != Some(0) {
+            return finished(exit_code, None);
+        }
+
+        let error = self
+            .save_outputs(&request.hash, &task, &output.log, duration)
+            .await
+            .err()
+            .map(|err| format!("failed to cache outputs: {err}"));
+
+        finished(exit_code, error)
+    }
+
+    async fn save_outputs(
+        &self,
+        hash: &str,
+        task: &ResolvedTask,
+        log: &[u8],
+        duration: Duration,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        task.log_file.ensure_dir()?;
+        task.log_file.create_with_contents(log)?;
+
+        let repo_root = self.resolver.repo_root();
+        let inclusions = task.outputs.validated_inclusions()?;
+        let exclusions = task.outputs.validated_exclusions()?;
+        let mut files = globwalk::globwalk(repo_root, &inclusions, &exclusions, WalkType::All)?
+            .into_iter()
+            .map(|path| AnchoredSystemPathBuf::relative_path_between(repo_root, &path))
+            .collect::<Vec<_>>();
+        files.sort();
+
+        self.cache
+            .put(
+                repo_root.to_owned(),
+                hash.to_string(),
+                files,
+                duration.as_millis() as u64,
+            )
+            .await?;
+        // The coordinator restores the outputs as soon as the task finishes,
+        // so they have to be written by then
+        self.cache.wait().await?;
+
+        Ok(())
+    }
+}
+
+// Streams task output to the coordinator while keeping a copy for the log file
+struct OutputSender<'a> {
+    events: &'a EventSender,
+    log: Vec<u8>,
+}
+
+impl<'a> Write for OutputSender<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.log.extend_from_slice(buf);
+        // The task keeps running if the coordinator disconnects, so its
+        // outputs still end up in the cache
+        self.events
+            .send(Ok(RunTaskEvent {
+                event: Some(Event::Output(buf.to_vec())),
+            }))
+            .ok();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
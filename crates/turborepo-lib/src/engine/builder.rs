@@ -1,11 +1,17 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+};
 
 use itertools::Itertools;
 use miette::Diagnostic;
-use turbopath::AbsoluteSystemPath;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+use turborepo_cache::CacheConfig;
+use turborepo_errors::Spanned;
 use turborepo_graph_utils as graph;
-use turborepo_repository::package_graph::{
-    PackageGraph, WorkspaceName, WorkspaceNode, ROOT_PKG_NAME,
+use turborepo_repository::{
+    package_graph::{PackageGraph, WorkspaceName, WorkspaceNode, ROOT_PKG_NAME},
+    package_json::PackageJson,
 };
 
 use super::Engine;
@@ -13,7 +19,10 @@ use crate::{
     config,
     run::task_id::{TaskId, TaskName},
     task_graph::TaskDefinition,
-    turbo_json::{validate_extends, validate_no_package_task_syntax, RawTaskDefinition, TurboJson},
+    turbo_json::{
+        resolve_variant, split_variant, validate_extends, validate_no_package_task_syntax,
+        validate_no_workspace_providers, RawTaskDefinition, TurboJson,
+    },
 };
 
 #[derive(Debug, thiserror::Error, Diagnostic)]
@@ -45,6 +54,32 @@ pub enum Error {
     InvalidTaskName { task_name: String, reason: String },
 }
 
+/// Where a turbo.json that tasks are defined in comes from: a workspace, or
+/// an npm package installed in `node_modules` that's extended from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ConfigSource {
+    Workspace(WorkspaceName),
+    Package {
+        name: String,
+        dir: AbsoluteSystemPathBuf,
+    },
+}
+
+impl From<WorkspaceName> for ConfigSource {
+    fn from(workspace: WorkspaceName) -> Self {
+        Self::Workspace(workspace)
+    }
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Workspace(workspace) => write!(f, "{workspace}"),
+            Self::Package { name, .. } => f.write_str(name),
+        }
+    }
+}
+
 pub struct EngineBuilder<'a> {
     repo_root: &'a AbsoluteSystemPath,
     package_graph: &'a PackageGraph,
@@ -114,7 +149,13 @@ impl<'a> EngineBuilder<'a> {
             return Ok(Engine::default().seal());
         }
 
-        let mut turbo_jsons = self.turbo_jsons.take().unwrap_or_default();
+        let mut turbo_jsons: HashMap<ConfigSource, TurboJson> = self
+            .turbo_jsons
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(workspace, turbo_json)| (workspace.into(), turbo_json))
+            .collect();
         let mut missing_tasks: HashSet<&TaskName<'_>, std::collections::hash_map::RandomState> =
             HashSet::from_iter(self.tasks.iter());
         let mut traversal_queue = VecDeque::with_capacity(1);
@@ -182,13 +223,11 @@ impl<'a> EngineBuilder<'a> {
                     task_id: task_id.to_string(),
                 });
             }
-            let raw_task_definition = RawTaskDefinition::from_iter(self.task_definition_chain(
+            let task_definition = self.task_definition(
                 &mut turbo_jsons,
                 &task_id,
                 &task_id.as_non_workspace_task_name(),
-            )?);
-
-            let task_definition = TaskDefinition::try_from(raw_task_definition)?;
+            )?;
 
             // Skip this iteration of the loop if we've already seen this taskID
             if visited.contains(&task_id) {
@@ -271,48 +310,106 @@ impl<'a> EngineBuilder<'a> {
 
     fn has_task_definition(
         &self,
-        turbo_jsons: &mut HashMap<WorkspaceName, TurboJson>,
+        turbo_jsons: &mut HashMap<ConfigSource, TurboJson>,
         workspace: &WorkspaceName,
         task_name: &TaskName<'static>,
         task_id: &TaskId,
     ) -> Result<bool, Error> {
-        let turbo_json = self
-            .turbo_json(turbo_jsons, workspace)
-            // If there was no turbo.json in the workspace, fallback to the root turbo.json
-            .or_else(|e| {
-                if e.is_missing_turbo_json() && !matches!(workspace, WorkspaceName::Root) {
-                    Ok(None)
-                } else {
-                    Err(e)
-                }
-            })?;
+        let mut sources = vec![ConfigSource::Workspace(WorkspaceName::Root)];
+        if !matches!(workspace, WorkspaceName::Root) {
+            sources.extend(self.extends_chain(turbo_jsons, workspace)?);
+        }
 
-        let Some(turbo_json) = turbo_json else {
-            return self.has_task_definition(turbo_jsons, &WorkspaceName::Root, task_name, task_id);
+        let task_id_as_name = task_id.as_task_name();
+        for source in &sources {
+            let Some(turbo_json) = self.turbo_json(turbo_jsons, source)? else {
+                continue;
+            };
+            if turbo_json.has_task(&task_id_as_name) || turbo_json.has_task(task_name) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Resolves the definition of a task. If no task is defined by that name,
+    /// a name like `test:ci` resolves to the `ci` variant of `test`.
+    fn task_definition(
+        &self,
+        turbo_jsons: &mut HashMap<ConfigSource, TurboJson>,
+        task_id: &TaskId,
+        task_name: &TaskName,
+    ) -> Result<TaskDefinition, Error> {
+        let (chain, script) = match self.task_definition_chain(turbo_jsons, task_id, task_name) {
+            Ok(chain) => (chain, None),
+            Err(e) if e.is_missing_task() => {
+                match self.variant_definition_chain(turbo_jsons, task_id, task_name)? {
+                    Some((chain, script)) => (chain, Some(script)),
+                    None => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
         };
 
-        let task_id_as_name = task_id.as_task_name();
-        if turbo_json.pipeline.contains_key(&task_id_as_name)
-            || turbo_json.pipeline.contains_key(task_name)
-        {
-            Ok(true)
-        } else if !matches!(workspace, WorkspaceName::Root) {
-            self.has_task_definition(turbo_jsons, &WorkspaceName::Root, task_name, task_id)
-        } else {
-            Ok(false)
+        let mut task_definition = TaskDefinition::try_from(RawTaskDefinition::from_iter(chain))?;
+        task_definition.script = script;
+        task_definition.cache_config = self.cache_config(turbo_jsons, task_id)?;
+        Ok(task_definition)
+    }
+
+    /// Resolves the caches a task can use from the `cache` settings of the
+    /// root turbo.json followed by each turbo.json in its workspace's extends
+    /// chain, with later settings overriding earlier ones.
+    fn cache_config(
+        &self,
+        turbo_jsons: &mut HashMap<ConfigSource, TurboJson>,
+        task_id: &TaskId,
+    ) -> Result<CacheConfig, Error> {
+        let mut sources = vec![ConfigSource::Workspace(WorkspaceName::Root)];
+        if !self.is_single && task_id.package() != ROOT_PKG_NAME {
+            let workspace = WorkspaceName::from(task_id.package());
+            sources.extend(self.extends_chain(turbo_jsons, &workspace)?);
         }
+
+        let mut cache_config = CacheConfig::default();
+        for source in &sources {
+            if let Some(turbo_json) = self.turbo_json(turbo_jsons, source)? {
+                turbo_json.cache.apply_to(&mut cache_config);
+            }
+        }
+        Ok(cache_config)
+    }
+
+    /// Resolves `task_name` as a variant of another task, returning the chain
+    /// of definitions and the script of the task it's a variant of
+    fn variant_definition_chain(
+        &self,
+        turbo_jsons: &mut HashMap<ConfigSource, TurboJson>,
+        task_id: &TaskId,
+        task_name: &TaskName,
+    ) -> Result<Option<(Vec<RawTaskDefinition>, String)>, Error> {
+        let Some((task, variant)) = split_variant(task_name) else {
+            return Ok(None);
+        };
+        let task_id = TaskId::new(task_id.package(), task.task());
+        let chain = match self.task_definition_chain(turbo_jsons, &task_id, &task) {
+            Ok(chain) => chain,
+            Err(e) if e.is_missing_task() => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        Ok(resolve_variant(chain, variant).map(|chain| (chain, task.task().to_string())))
     }
 
     fn task_definition_chain(
         &self,
-        turbo_jsons: &mut HashMap<WorkspaceName, TurboJson>,
+        turbo_jsons: &mut HashMap<ConfigSource, TurboJson>,
         task_id: &TaskId,
         task_name: &TaskName,
     ) -> Result<Vec<RawTaskDefinition>, Error> {
         let mut task_definitions = Vec::new();
 
         let root_turbo_json = self
-            .turbo_json(turbo_jsons, &WorkspaceName::Root)?
+            .turbo_json(turbo_jsons, &ConfigSource::Workspace(WorkspaceName::Root))?
             .ok_or(Error::Config(crate::config::Error::NoTurboJSON))?;
 
         if let Some(root_definition) = root_turbo_json.task(task_id, task_name) {
@@ -329,25 +426,24 @@ impl<'a> EngineBuilder<'a> {
         }
 
         if task_id.package() != ROOT_PKG_NAME {
-            match self.turbo_json(turbo_jsons, &WorkspaceName::from(task_id.package())) {
-                Ok(Some(workspace_json)) => {
-                    let validation_errors = workspace_json
-                        .validate(&[validate_no_package_task_syntax, validate_extends]);
-                    if !validation_errors.is_empty() {
-                        return Err(Error::Validation {
-                            errors: validation_errors,
-                        });
-                    }
-
-                    if let Some(workspace_def) = workspace_json.pipeline.get(task_name) {
-                        task_definitions.push(workspace_def.value.clone());
-                    }
+            let workspace = WorkspaceName::from(task_id.package());
+            for source in self.extends_chain(turbo_jsons, &workspace)? {
+                let Some(workspace_json) = self.turbo_json(turbo_jsons, &source)? else {
+                    continue;
+                };
+                let validation_errors = workspace_json.validate(&[
+                    validate_no_package_task_syntax,
+                    validate_extends,
+                    validate_no_workspace_providers,
+                ]);
+                if !validation_errors.is_empty() {
+                    return Err(Error::Validation {
+                        errors: validation_errors,
+                    });
                 }
-                Ok(None) => (),
-                // swallow the error where the config file doesn't exist, but bubble up other things
-                Err(e) if e.is_missing_turbo_json() => (),
-                Err(e) => {
-                    return Err(e);
+
+                if let Some(workspace_def) = workspace_json.pipeline.get(task_name) {
+                    task_definitions.push(workspace_def.value.clone());
                 }
             }
         }
@@ -362,16 +458,157 @@ impl<'a> EngineBuilder<'a> {
         Ok(task_definitions)
     }
 
-    fn turbo_json<'b>(
+    /// Returns the turbo.json files that apply to `workspace`'s tasks, in the
+    /// order their definitions are merged: each workspace or package it
+    /// extends from, depth first and in the order they're listed, followed by
+    /// the workspace itself. The root turbo.json always comes first, so it's
+    /// left out.
+    fn extends_chain(
         &self,
-        turbo_jsons: &'b mut HashMap<WorkspaceName, TurboJson>,
+        turbo_jsons: &mut HashMap<ConfigSource, TurboJson>,
         workspace: &WorkspaceName,
+    ) -> Result<Vec<ConfigSource>, Error> {
+        let mut chain = Vec::new();
+        self.resolve_extends(
+            turbo_jsons,
+            &ConfigSource::Workspace(workspace.clone()),
+            None,
+            &mut Vec::new(),
+            &mut chain,
+        )?;
+        Ok(chain)
+    }
+
+    fn resolve_extends(
+        &self,
+        turbo_jsons: &mut HashMap<ConfigSource, TurboJson>,
+        source: &ConfigSource,
+        extended_from: Option<&Spanned<String>>,
+        extended_by: &mut Vec<ConfigSource>,
+        chain: &mut Vec<ConfigSource>,
+    ) -> Result<(), Error> {
+        let extends = match self.turbo_json(turbo_jsons, source) {
+            Ok(Some(turbo_json)) => turbo_json.extends.value.clone(),
+            Ok(None) => return Ok(()),
+            // A workspace doesn't need a turbo.json, but one that's extended from does,
+            // as does a package
+            Err(e) if e.is_missing_turbo_json() => {
+                return match extended_from {
+                    Some(entry) => {
+                        let (span, text) = entry.span_and_text();
+                        Err(config::Error::ExtendsWithoutTurboJson {
+                            workspace: entry.value.clone(),
+                            path: config_path(entry),
+                            span,
+                            text,
+                        }
+                        .into())
+                    }
+                    None => Ok(()),
+                };
+            }
+            Err(e) => return Err(e),
+        };
+
+        extended_by.push(source.clone());
+        for entry in &extends {
+            // Every chain starts from the root turbo.json
+            if entry.as_str() == ROOT_PKG_NAME {
+                continue;
+            }
+            let (span, text) = entry.span_and_text();
+            let Some(extended) = self.resolve_extends_entry(source, entry.as_str()) else {
+                return Err(config::Error::ExtendsUnknownWorkspace {
+                    workspace: entry.value.clone(),
+                    path: config_path(entry),
+                    span,
+                    text,
+                }
+                .into());
+            };
+            // A workspace or package that was already extended from keeps its first
+            // position
+            if chain.contains(&extended) {
+                continue;
+            }
+
+            if let Some(start) = extended_by.iter().position(|w| w == &extended) {
+                let cycle = extended_by[start..]
+                    .iter()
+                    .chain(std::iter::once(&extended))
+                    .join(" -> ");
+                return Err(config::Error::ExtendsCycle {
+                    cycle,
+                    path: config_path(entry),
+                    span,
+                    text,
+                }
+                .into());
+            }
+
+            self.resolve_extends(turbo_jsons, &extended, Some(entry), extended_by, chain)?;
+        }
+        extended_by.pop();
+
+        chain.push(source.clone());
+        Ok(())
+    }
+
+    /// Resolves an `extends` entry found in `source`'s turbo.json to a
+    /// workspace by its name, or otherwise to an npm package installed in
+    /// `node_modules`, looked up the way Node.js would from `source`'s
+    /// directory
+    fn resolve_extends_entry(&self, source: &ConfigSource, name: &str) -> Option<ConfigSource> {
+        let workspace = WorkspaceName::from(name);
+        if self.package_graph.workspace_info(&workspace).is_some() {
+            return Some(ConfigSource::Workspace(workspace));
+        }
+
+        let package_path = npm_package_path(name)?;
+        let dir = match source {
+            ConfigSource::Workspace(workspace) => self
+                .repo_root
+                .resolve(self.package_graph.workspace_dir(workspace)?),
+            ConfigSource::Package { dir, .. } => dir.clone(),
+        };
+        // node_modules outside of the repository aren't part of its checkout
+        dir.ancestors()
+            .take_while(|ancestor| self.repo_root.contains(ancestor))
+            .map(|ancestor| {
+                let mut components = vec!["node_modules"];
+                components.extend(&package_path);
+                ancestor.join_components(&components)
+            })
+            .find(|package_dir| package_dir.exists())
+            .map(|dir| ConfigSource::Package {
+                name: name.to_string(),
+                dir,
+            })
+    }
+
+    fn turbo_json<'b>(
+        &self,
+        turbo_jsons: &'b mut HashMap<ConfigSource, TurboJson>,
+        source: &ConfigSource,
     ) -> Result<Option<&'b TurboJson>, Error> {
-        if turbo_jsons.get(workspace).is_none() {
-            let json = self.load_turbo_json(workspace)?;
-            turbo_jsons.insert(workspace.clone(), json);
+        if turbo_jsons.get(source).is_none() {
+            let json = match source {
+                ConfigSource::Workspace(workspace) => self.load_turbo_json(workspace)?,
+                ConfigSource::Package { dir, .. } => self.load_package_turbo_json(dir)?,
+            };
+            turbo_jsons.insert(source.clone(), json);
         }
-        Ok(turbo_jsons.get(workspace))
+        Ok(turbo_jsons.get(source))
+    }
+
+    fn load_package_turbo_json(&self, dir: &AbsoluteSystemPath) -> Result<TurboJson, Error> {
+        let package_dir = AnchoredSystemPathBuf::relative_path_between(self.repo_root, dir);
+        Ok(TurboJson::load(
+            self.repo_root,
+            &package_dir,
+            &PackageJson::default(),
+            false,
+        )?)
     }
 
     fn load_turbo_json(&self, workspace: &WorkspaceName) -> Result<TurboJson, Error> {
@@ -394,10 +631,43 @@ impl<'a> EngineBuilder<'a> {
     }
 }
 
+// The components of an npm package name, `name` or `@scope/name`, or None if
+// it isn't one
+fn npm_package_path(name: &str) -> Option<Vec<&str>> {
+    let components = name.split('/').collect::<Vec<_>>();
+    let is_valid = |component: &str| {
+        !component.is_empty()
+            && component != "."
+            && component != ".."
+            && !component.contains(['\\', ':'])
+    };
+    let is_package = match *components.as_slice() {
+        [name] => is_valid(name) && !name.starts_with('@'),
+        [scope, name] => scope.starts_with('@') && is_valid(scope) && is_valid(name),
+        _ => false,
+    };
+    is_package.then_some(components)
+}
+
+// The turbo.json that an `extends` entry was found in
+fn config_path(entry: &Spanned<String>) -> String {
+    entry
+        .path
+        .as_ref()
+        .map_or_else(|| "turbo.json".to_string(), |path| path.to_string())
+}
+
 impl Error {
     fn is_missing_turbo_json(&self) -> bool {
         matches!(self, Self::Config(crate::config::Error::NoTurboJSON))
     }
+
+    fn is_missing_task(&self) -> bool {
+        matches!(
+            self,
+            Self::MissingWorkspaceTask { .. } | Self::MissingTaskForRoot { .. }
+        )
+    }
 }
 
 // If/when we decide to be stricter about task names,
@@ -605,6 +875,7 @@ mod test {
             ),
         ]
         .into_iter()
+        .map(|(workspace, turbo_json)| (workspace.into(), turbo_json))
         .collect();
         let engine_builder = EngineBuilder::new(&repo_root, &package_graph, false);
         let task_name = TaskName::from(task_name);
@@ -616,6 +887,285 @@ mod test {
         assert_eq!(has_def, expected);
     }
 
+    #[test_case("//#build", false, true ; "root task")]
+    #[test_case("a#build", false, true ; "workspace without turbo json")]
+    #[test_case("b#build", true, false ; "workspace overrides")]
+    fn test_cache_config(task_id: &'static str, local_read: bool, remote_write: bool) {
+        let repo_root_dir = TempDir::new("repo").unwrap();
+        let repo_root = AbsoluteSystemPathBuf::new(repo_root_dir.path().to_str().unwrap()).unwrap();
+        let package_graph = mock_package_graph(
+            &repo_root,
+            package_jsons! {
+                repo_root,
+                "a" => [],
+                "b" => []
+            },
+        );
+        let mut turbo_jsons = vec![
+            (
+                WorkspaceName::Root,
+                turbo_json(json!({
+                    "cache": { "local": "write" },
+                    "pipeline": {
+                        "build": {},
+                    }
+                })),
+            ),
+            (
+                WorkspaceName::from("b"),
+                turbo_json(json!({
+                    "extends": ["//"],
+                    "cache": { "local": "readwrite", "remote": "read" },
+                })),
+            ),
+        ]
+        .into_iter()
+        .map(|(workspace, turbo_json)| (workspace.into(), turbo_json))
+        .collect();
+        let engine_builder = EngineBuilder::new(&repo_root, &package_graph, false);
+        let task_id = TaskId::try_from(task_id).unwrap();
+
+        let cache_config = engine_builder
+            .cache_config(&mut turbo_jsons, &task_id)
+            .unwrap();
+        assert_eq!(cache_config.local.read, local_read);
+        assert!(cache_config.local.write);
+        assert!(cache_config.remote.read);
+        assert_eq!(cache_config.remote.write, remote_write);
+    }
+
+    #[test_case("a", Ok(&["preset", "a"]) ; "extends preset")]
+    #[test_case("b", Ok(&["preset", "a", "b"]) ; "extends preset twice")]
+    #[test_case("c", Err("c -> c") ; "extends itself")]
+    #[test_case("d", Err("d -> e -> d") ; "extends cycle")]
+    #[test_case("f", Err("missing") ; "extends unknown workspace")]
+    #[test_case("g", Err("no-turbo-json") ; "extends workspace without turbo json")]
+    fn test_extends_chain(workspace: &str, expected: Result<&[&str], &str>) {
+        let repo_root_dir = TempDir::new("repo").unwrap();
+        let repo_root = AbsoluteSystemPathBuf::new(repo_root_dir.path().to_str().unwrap()).unwrap();
+        let package_graph = mock_package_graph(
+            &repo_root,
+            package_jsons! {
+                repo_root,
+                "preset" => [],
+                "no-turbo-json" => [],
+                "a" => [],
+                "b" => [],
+                "c" => [],
+                "d" => [],
+                "e" => [],
+                "f" => [],
+                "g" => []
+            },
+        );
+        let mut turbo_jsons = vec![
+            (WorkspaceName::Root, turbo_json(json!({ "pipeline": {} }))),
+            (
+                WorkspaceName::from("preset"),
+                turbo_json(json!({ "extends": ["//"], "pipeline": { "lint": {} } })),
+            ),
+            (
+                WorkspaceName::from("a"),
+                turbo_json(json!({ "extends": ["//", "preset"], "pipeline": {} })),
+            ),
+            (
+                WorkspaceName::from("b"),
+                turbo_json(json!({ "extends": ["//", "preset", "a"], "pipeline": {} })),
+            ),
+            (
+                WorkspaceName::from("c"),
+                turbo_json(json!({ "extends": ["//", "c"], "pipeline": {} })),
+            ),
+            (
+                WorkspaceName::from("d"),
+                turbo_json(json!({ "extends": ["//", "e"], "pipeline": {} })),
+            ),
+            (
+                WorkspaceName::from("e"),
+                turbo_json(json!({ "extends": ["d"], "pipeline": {} })),
+            ),
+            (
+                WorkspaceName::from("f"),
+                turbo_json(json!({ "extends": ["//", "missing"], "pipeline": {} })),
+            ),
+            (
+                WorkspaceName::from("g"),
+                turbo_json(json!({ "extends": ["//", "no-turbo-json"], "pipeline": {} })),
+            ),
+        ]
+        .into_iter()
+        .map(|(workspace, turbo_json)| (workspace.into(), turbo_json))
+        .collect();
+        let engine_builder = EngineBuilder::new(&repo_root, &package_graph, false);
+
+        let chain = engine_builder.extends_chain(&mut turbo_jsons, &WorkspaceName::from(workspace));
+        match expected {
+            Ok(expected) => {
+                let expected = expected
+                    .iter()
+                    .map(|workspace| ConfigSource::from(WorkspaceName::from(*workspace)))
+                    .collect::<Vec<_>>();
+                assert_eq!(chain.unwrap(), expected);
+            }
+            Err(expected) => match chain.unwrap_err() {
+                Error::Config(config::Error::ExtendsCycle { cycle, span, .. }) => {
+                    assert_eq!(cycle, expected);
+                    assert!(span.is_some());
+                }
+                Error::Config(
+                    config::Error::ExtendsUnknownWorkspace {
+                        workspace, span, ..
+                    }
+                    | config::Error::ExtendsWithoutTurboJson {
+                        workspace, span, ..
+                    },
+                ) => {
+                    assert_eq!(workspace, expected);
+                    assert!(span.is_some());
+                }
+                e => panic!("unexpected error: {e}"),
+            },
+        }
+    }
+
+    #[test]
+    fn test_task_definition_from_extended_workspace() {
+        let repo_root_dir = TempDir::new("repo").unwrap();
+        let repo_root = AbsoluteSystemPathBuf::new(repo_root_dir.path().to_str().unwrap()).unwrap();
+        let package_graph = mock_package_graph(
+            &repo_root,
+            package_jsons! {
+                repo_root,
+                "preset" => [],
+                "web" => []
+            },
+        );
+        let mut turbo_jsons = vec![
+            (
+                WorkspaceName::Root,
+                turbo_json(json!({ "pipeline": { "build": { "inputs": ["root"] } } })),
+            ),
+            (
+                WorkspaceName::from("preset"),
+                turbo_json(json!({
+                    "extends": ["//"],
+                    "pipeline": {
+                        "build": { "outputs": [".next/**"] },
+                        "lint": {},
+                    }
+                })),
+            ),
+            (
+                WorkspaceName::from("web"),
+                turbo_json(json!({
+                    "extends": ["//", "preset"],
+                    "pipeline": { "build": { "inputs": ["web"] } }
+                })),
+            ),
+        ]
+        .into_iter()
+        .map(|(workspace, turbo_json)| (workspace.into(), turbo_json))
+        .collect();
+        let engine_builder = EngineBuilder::new(&repo_root, &package_graph, false);
+
+        let lint_id = TaskId::new("web", "lint");
+        assert!(engine_builder
+            .has_task_definition(
+                &mut turbo_jsons,
+                &WorkspaceName::from("web"),
+                &TaskName::from("lint"),
+                &lint_id
+            )
+            .unwrap());
+
+        let build_id = TaskId::new("web", "build");
+        let task_name = TaskName::from("build");
+        let definition = TaskDefinition::try_from(RawTaskDefinition::from_iter(
+            engine_builder
+                .task_definition_chain(&mut turbo_jsons, &build_id, &task_name)
+                .unwrap(),
+        ))
+        .unwrap();
+        // web's inputs win over the root's, while the outputs come from the preset
+        assert_eq!(definition.inputs, vec!["web".to_string()]);
+        assert_eq!(definition.outputs.inclusions, vec![".next/**".to_string()]);
+    }
+
+    #[test]
+    fn test_task_definition_from_extended_package() {
+        let repo_root_dir = TempDir::new("repo").unwrap();
+        let repo_root = AbsoluteSystemPathBuf::new(repo_root_dir.path().to_str().unwrap()).unwrap();
+        let package_graph = mock_package_graph(
+            &repo_root,
+            package_jsons! {
+                repo_root,
+                "web" => []
+            },
+        );
+        let package_dir =
+            repo_root.join_components(&["node_modules", "@acme", "turbo-config-next"]);
+        package_dir.create_dir_all().unwrap();
+        package_dir
+            .join_component("turbo.json")
+            .create_with_contents(
+                r#"{"extends": ["//"], "pipeline": {"build": {"outputs": [".next/**"]}}}"#,
+            )
+            .unwrap();
+        let mut turbo_jsons = vec![
+            (
+                WorkspaceName::Root,
+                turbo_json(json!({ "pipeline": { "build": { "inputs": ["root"] } } })),
+            ),
+            (
+                WorkspaceName::from("web"),
+                turbo_json(json!({
+                    "extends": ["//", "@acme/turbo-config-next"],
+                    "pipeline": { "build": { "inputs": ["web"] } }
+                })),
+            ),
+        ]
+        .into_iter()
+        .map(|(workspace, turbo_json)| (workspace.into(), turbo_json))
+        .collect();
+        let engine_builder = EngineBuilder::new(&repo_root, &package_graph, false);
+
+        // The package is found in the node_modules of a directory above the workspace
+        let chain = engine_builder
+            .extends_chain(&mut turbo_jsons, &WorkspaceName::from("web"))
+            .unwrap();
+        assert_eq!(
+            chain,
+            vec![
+                ConfigSource::Package {
+                    name: "@acme/turbo-config-next".to_string(),
+                    dir: package_dir,
+                },
+                ConfigSource::Workspace(WorkspaceName::from("web")),
+            ]
+        );
+
+        let build_id = TaskId::new("web", "build");
+        let task_name = TaskName::from("build");
+        let definition = TaskDefinition::try_from(RawTaskDefinition::from_iter(
+            engine_builder
+                .task_definition_chain(&mut turbo_jsons, &build_id, &task_name)
+                .unwrap(),
+        ))
+        .unwrap();
+        assert_eq!(definition.inputs, vec!["web".to_string()]);
+        assert_eq!(definition.outputs.inclusions, vec![".next/**".to_string()]);
+    }
+
+    #[test_case("@acme/turbo-config", Some(&["@acme", "turbo-config"]) ; "scoped")]
+    #[test_case("turbo-config", Some(&["turbo-config"]) ; "unscoped")]
+    #[test_case("@acme", None ; "scope only")]
+    #[test_case("acme/turbo-config", None ; "unscoped with separator")]
+    #[test_case("@acme/../../etc", None ; "path traversal")]
+    #[test_case("..", None ; "parent dir")]
+    fn test_npm_package_path(name: &str, expected: Option<&[&str]>) {
+        assert_eq!(npm_package_path(name).as_deref(), expected);
+    }
+
     macro_rules! deps {
         {} => {
             HashMap::new()
@@ -650,6 +1200,78 @@ mod test {
             .collect()
     }
 
+    #[test]
+    fn test_task_variants() {
+        let repo_root_dir = TempDir::new("repo").unwrap();
+        let repo_root = AbsoluteSystemPathBuf::new(repo_root_dir.path().to_str().unwrap()).unwrap();
+        let package_graph = mock_package_graph(
+            &repo_root,
+            package_jsons! {
+                repo_root,
+                "a" => [],
+                "b" => ["a"]
+            },
+        );
+        let turbo_jsons = vec![
+            (
+                WorkspaceName::Root,
+                turbo_json(json!({
+                    "pipeline": {
+                        "build": {},
+                        "test": {
+                            "args": ["--reporter=dot"],
+                            "variants": {
+                                "ci": { "args": ["--ci"], "dependsOn": ["build:prod"] },
+                            }
+                        },
+                        "build:prod": {},
+                    }
+                })),
+            ),
+            (
+                WorkspaceName::from("b"),
+                turbo_json(json!({
+                    "extends": ["//"],
+                    "pipeline": {
+                        "test": { "variants": { "ci": { "args": ["--shard=1"] } } },
+                    }
+                })),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let engine = EngineBuilder::new(&repo_root, &package_graph, false)
+            .with_turbo_jsons(Some(turbo_jsons))
+            .with_tasks(Some(TaskName::from("test:ci")))
+            .with_workspaces(vec![WorkspaceName::from("a"), WorkspaceName::from("b")])
+            .build()
+            .unwrap();
+
+        let expected = deps! {
+            "a#test:ci" => ["a#build:prod"],
+            "a#build:prod" => ["___ROOT___"],
+            "b#test:ci" => ["b#build:prod"],
+            "b#build:prod" => ["___ROOT___"]
+        };
+        assert_eq!(all_dependencies(&engine), expected);
+
+        let a_test = engine
+            .task_definition(&TaskId::new("a", "test:ci"))
+            .unwrap();
+        assert_eq!(a_test.script.as_deref(), Some("test"));
+        assert_eq!(a_test.args, vec!["--reporter=dot", "--ci"]);
+        // b's declaration of the variant is merged last
+        let b_test = engine
+            .task_definition(&TaskId::new("b", "test:ci"))
+            .unwrap();
+        assert_eq!(b_test.args, vec!["--reporter=dot", "--shard=1"]);
+        // a task that's defined by its own name isn't a variant
+        let a_build = engine
+            .task_definition(&TaskId::new("a", "build:prod"))
+            .unwrap();
+        assert_eq!(a_build.script, None);
+    }
+
     #[test]
     fn test_default_engine() {
         let repo_root_dir = TempDir::new("repo").unwrap();
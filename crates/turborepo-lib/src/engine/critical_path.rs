@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use petgraph::{graph::NodeIndex, Direction};
+
+use super::{Built, Engine, TaskNode};
+use crate::run::task_id::TaskId;
+
+impl Engine<Built> {
+    /// Estimates how much work is left once each task starts: its own
+    /// duration plus the longest chain of dependents that has to run after
+    /// it. Scheduling the tasks with the most remaining work first keeps the
+    /// critical path moving.
+    ///
+    /// Tasks without a known duration are assumed to take as long as the
+    /// average known task, or 1 if nothing is known, which prioritizes the
+    /// longest chain of tasks.
+    pub fn remaining_durations(
+        &self,
+        durations: &HashMap<TaskId<'static>, u64>,
+    ) -> HashMap<TaskId<'static>, u64> {
+        let known = self
+            .task_lookup
+            .keys()
+            .filter_map(|task_id| durations.get(task_id))
+            .collect::<Vec<_>>();
+        let default = match known.len() {
+            0 => 1,
+            len => known.into_iter().sum::<u64>() / len as u64,
+        };
+
+        self.longest_paths(|task_id| durations.get(task_id).copied().unwrap_or(default))
+            .into_iter()
+            .filter_map(|(index, length)| match &self.task_graph[index] {
+                TaskNode::Task(task_id) => Some((task_id.clone(), length)),
+                TaskNode::Root => None,
+            })
+            .collect()
+    }
+
+    /// The chain of dependent tasks with the largest total duration, in the
+    /// order they ran, along with that total. No amount of parallelism could
+    /// have made the run finish faster than this. Tasks without a duration
+    /// are treated as if they took no time and left out of the path.
+    pub fn critical_path(
+        &self,
+        durations: &HashMap<TaskId<'static>, u64>,
+    ) -> (Vec<TaskId<'static>>, u64) {
+        let lengths =
+            self.longest_paths(|task_id| durations.get(task_id).copied().unwrap_or_default());
+
+        let mut path = Vec::new();
+        let mut current = self.longest_task(&lengths, self.task_graph.node_indices());
+        let total = current.map_or(0, |index| lengths[&index]);
+        while let Some(index) = current.filter(|index| lengths[index] > 0) {
+            if let TaskNode::Task(task_id) = &self.task_graph[index] {
+                if durations
+                    .get(task_id)
+                    .map_or(false, |duration| *duration > 0)
+                {
+                    path.push(task_id.clone());
+                }
+            }
+            current = self.longest_task(
+                &lengths,
+                self.task_graph
+                    .neighbors_directed(index, Direction::Incoming),
+            );
+        }
+
+        (path, total)
+    }
+
+    // For every node, the length of the longest path that starts at it and
+    // follows its dependents
+    fn longest_paths(&self, duration: impl Fn(&TaskId) -> u64) -> HashMap<NodeIndex, u64> {
+        // Tasks point at their dependencies, so the topological order visits
+        // every task after all of its dependents. A cyclic graph can't be
+        // walked anyway, so there's nothing to prioritize.
+        let Ok(order) = petgraph::algo::toposort(&self.task_graph, None) else {
+            return HashMap::new();
+        };
+
+        let mut lengths = HashMap::with_capacity(order.len());
+        for index in order {
+            let own = match &self.task_graph[index] {
+                TaskNode::Task(task_id) => duration(task_id),
+                TaskNode::Root => 0,
+            };
+            let after = self
+                .task_graph
+                .neighbors_directed(index, Direction::Incoming)
+                .filter_map(|dependent| lengths.get(&dependent).copied())
+                .max()
+                .unwrap_or_default();
+            lengths.insert(index, own + after);
+        }
+
+        lengths
+    }
+
+    // Ties are broken by task id so the critical path is stable between runs
+    fn longest_task(
+        &self,
+        lengths: &HashMap<NodeIndex, u64>,
+        candidates: impl Iterator<Item = NodeIndex>,
+    ) -> Option<NodeIndex> {
+        candidates
+            .filter_map(|index| match &self.task_graph[index] {
+                TaskNode::Task(task_id) => Some((lengths.get(&index)?, task_id, index)),
+                TaskNode::Root => None,
+            })
+            .max_by(|(a_length, a_id, _), (b_length, b_id, _)| {
+                a_length.cmp(b_length).then_with(|| b_id.cmp(a_id))
+            })
+            .map(|(_, _, index)| index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::engine::Building;
+
+    // Builds an engine from `(task, dependencies)` pairs
+    fn engine(tasks: &[(&'static str, &[&'static str])]) -> Engine<Built> {
+        let mut engine = Engine::<Building>::new();
+        for (task, dependencies) in tasks {
+            let task_id = TaskId::try_from(*task).unwrap();
+            let index = engine.get_index(&task_id);
+            if dependencies.is_empty() {
+                engine.connect_to_root(&task_id);
+            }
+            for dependency in *dependencies {
+                let dependency = engine.get_index(&TaskId::try_from(*dependency).unwrap());
+                engine.task_graph.add_edge(index, dependency, ());
+            }
+        }
+        engine.seal()
+    }
+
+    fn durations(durations: &[(&'static str, u64)]) -> HashMap<TaskId<'static>, u64> {
+        durations
+            .iter()
+            .map(|(task, duration)| (TaskId::try_from(*task).unwrap(), *duration))
+            .collect()
+    }
+
+    fn diamond() -> Engine<Built> {
+        engine(&[
+            ("a#build", &[]),
+            ("b#build", &["a#build"]),
+            ("c#build", &["a#build"]),
+            ("d#build", &["b#build", "c#build"]),
+            ("e#lint", &[]),
+        ])
+    }
+
+    #[test]
+    fn test_remaining_durations() {
+        let remaining = diamond().remaining_durations(&durations(&[
+            ("a#build", 10),
+            ("b#build", 20),
+            ("c#build", 50),
+            ("d#build", 5),
+            ("e#lint", 30),
+        ]));
+
+        assert_eq!(
+            remaining,
+            durations(&[
+                ("a#build", 65),
+                ("b#build", 25),
+                ("c#build", 55),
+                ("d#build", 5),
+                ("e#lint", 30),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_remaining_durations_defaults_to_average() {
+        let remaining = diamond().remaining_durations(&durations(&[
+            ("a#build", 10),
+            ("c#build", 50),
+            // Not part of the graph so it shouldn't affect the average
+            ("z#build", 1000),
+        ]));
+
+        assert_eq!(remaining[&TaskId::new("a", "build")], 10 + 50 + 30);
+        assert_eq!(remaining[&TaskId::new("b", "build")], 30 + 30);
+        assert_eq!(remaining[&TaskId::new("e", "lint")], 30);
+    }
+
+    #[test]
+    fn test_remaining_durations_without_history() {
+        let remaining = diamond().remaining_durations(&HashMap::new());
+
+        assert_eq!(remaining[&TaskId::new("a", "build")], 3);
+        assert_eq!(remaining[&TaskId::new("b", "build")], 2);
+        assert_eq!(remaining[&TaskId::new("d", "build")], 1);
+        assert_eq!(remaining[&TaskId::new("e", "lint")], 1);
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let (path, total) = diamond().critical_path(&durations(&[
+            ("a#build", 10),
+            ("b#build", 20),
+            ("c#build", 50),
+            ("d#build", 5),
+            ("e#lint", 30),
+        ]));
+
+        assert_eq!(
+            path,
+            vec![
+                TaskId::new("a", "build"),
+                TaskId::new("c", "build"),
+                TaskId::new("d", "build")
+            ]
+        );
+        assert_eq!(total, 65);
+    }
+
+    #[test]
+    fn test_critical_path_skips_tasks_that_did_not_run() {
+        let (path, total) = diamond().critical_path(&durations(&[("b#build", 20)]));
+
+        assert_eq!(path, vec![TaskId::new("b", "build")]);
+        assert_eq!(total, 20);
+
+        let (path, total) = diamond().critical_path(&HashMap::new());
+        assert!(path.is_empty());
+        assert_eq!(total, 0);
+    }
+}
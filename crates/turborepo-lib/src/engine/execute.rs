@@ -1,7 +1,11 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap},
+    sync::{Arc, Mutex},
+};
 
 use futures::{stream::FuturesUnordered, StreamExt};
-use tokio::sync::{mpsc, oneshot, Semaphore};
+use tokio::sync::{mpsc, oneshot};
 use tracing::log::debug;
 use turborepo_graph_utils::Walker;
 
@@ -18,10 +22,13 @@ pub struct Message<T, U> {
 type VisitorData = TaskId<'static>;
 type VisitorResult = Result<(), StopExecution>;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExecutionOptions {
     parallel: bool,
     concurrency: usize,
+    // How long each task is expected to take in milliseconds, used to decide
+    // which ready task gets to run first
+    task_durations: HashMap<TaskId<'static>, u64>,
 }
 
 impl ExecutionOptions {
@@ -29,8 +36,14 @@ impl ExecutionOptions {
         Self {
             parallel,
             concurrency,
+            task_durations: HashMap::new(),
         }
     }
+
+    pub fn with_task_durations(mut self, task_durations: HashMap<TaskId<'static>, u64>) -> Self {
+        self.task_durations = task_durations;
+        self
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -60,6 +73,8 @@ impl Engine {
     /// type which will stop any further execution of tasks.
     /// This will not stop any task which is currently running, simply it will
     /// stop scheduling new tasks.
+    /// When there are more ready tasks than available concurrency, the tasks
+    /// with the longest estimated path through their dependents go first.
     // (olszewski) The current impl requires that the visitor receiver is read until
     // finish even once a task sends back the stop signal. This is suboptimal
     // since it would mean the visitor would need to also track if
@@ -72,8 +87,10 @@ impl Engine {
         let ExecutionOptions {
             parallel,
             concurrency,
+            task_durations,
         } = options;
-        let sema = Arc::new(Semaphore::new(concurrency));
+        let priorities = Arc::new(self.remaining_durations(&task_durations));
+        let sema = Arc::new(PrioritySemaphore::new(concurrency));
         let mut tasks: FuturesUnordered<tokio::task::JoinHandle<Result<(), ExecuteError>>> =
             FuturesUnordered::new();
 
@@ -83,6 +100,7 @@ impl Engine {
         while let Some((node_id, done)) = nodes.recv().await {
             let visitor = visitor.clone();
             let sema = sema.clone();
+            let priorities = priorities.clone();
             let walker = walker.clone();
             let this = self.clone();
 
@@ -104,10 +122,10 @@ impl Engine {
 
                 // Acquire the semaphore unless parallel
                 let _permit = match parallel {
-                    false => Some(sema.acquire().await.expect(
-                        "Graph concurrency semaphore closed while tasks are still attempting to \
-                         acquire permits",
-                    )),
+                    false => {
+                        let priority = priorities.get(task_id).copied().unwrap_or_default();
+                        Some(sema.acquire(priority).await)
+                    }
                     true => None,
                 };
 
@@ -143,9 +161,152 @@ impl Engine {
     }
 }
 
+/// A semaphore that hands permits to the waiter with the highest priority
+/// instead of the one that has waited the longest. Waiters with the same
+/// priority are served in the order they arrived.
+struct PrioritySemaphore {
+    state: Mutex<PriorityState>,
+}
+
+struct PriorityState {
+    available: usize,
+    waiters: BinaryHeap<Waiter>,
+    arrivals: u64,
+}
+
+struct Waiter {
+    priority: u64,
+    arrival: Reverse<u64>,
+    permit: oneshot::Sender<PriorityPermit>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.priority, self.arrival).cmp(&(other.priority, other.arrival))
+    }
+}
+
+/// Returns its slot to the semaphore when dropped
+struct PriorityPermit {
+    semaphore: Arc<PrioritySemaphore>,
+}
+
+impl Drop for PriorityPermit {
+    fn drop(&mut self) {
+        self.semaphore.release();
+    }
+}
+
+impl PrioritySemaphore {
+    fn new(permits: usize) -> Self {
+        Self {
+            state: Mutex::new(PriorityState {
+                available: permits,
+                waiters: BinaryHeap::new(),
+                arrivals: 0,
+            }),
+        }
+    }
+
+    async fn acquire(self: &Arc<Self>, priority: u64) -> PriorityPermit {
+        let receiver = {
+            let mut state = self.state.lock().expect("semaphore mutex poisoned");
+            if state.available > 0 {
+                state.available -= 1;
+                return PriorityPermit {
+                    semaphore: self.clone(),
+                };
+            }
+            let (permit, receiver) = oneshot::channel();
+            let arrival = Reverse(state.arrivals);
+            state.arrivals += 1;
+            state.waiters.push(Waiter {
+                priority,
+                arrival,
+                permit,
+            });
+            receiver
+        };
+
+        receiver
+            .await
+            .expect("waiters are only removed once they've been sent a permit")
+    }
+
+    fn release(self: &Arc<Self>) {
+        let waiter = {
+            let mut state = self.state.lock().expect("semaphore mutex poisoned");
+            match state.waiters.pop() {
+                Some(waiter) => waiter,
+                None => {
+                    state.available += 1;
+                    return;
+                }
+            }
+        };
+        // If the waiter has gone away the permit gets dropped here, which
+        // passes it on to the next waiter
+        let _ = waiter.permit.send(PriorityPermit {
+            semaphore: self.clone(),
+        });
+    }
+}
+
 impl<T, U> Message<T, U> {
     pub fn new(info: T) -> (Self, oneshot::Receiver<U>) {
         let (callback, receiver) = oneshot::channel();
         (Self { info, callback }, receiver)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_priority_semaphore_order() {
+        let semaphore = Arc::new(PrioritySemaphore::new(1));
+        let permit = semaphore.acquire(0).await;
+
+        let (order_tx, mut order_rx) = mpsc::unbounded_channel();
+        let mut waiters = Vec::new();
+        for (name, priority) in [("low", 1), ("high", 10), ("medium", 5), ("also-high", 10)] {
+            let semaphore = semaphore.clone();
+            let order_tx = order_tx.clone();
+            waiters.push(tokio::spawn(async move {
+                let _permit = semaphore.acquire(priority).await;
+                order_tx.send(name).unwrap();
+            }));
+            // Let the waiter queue up before the next one arrives
+            while semaphore.state.lock().unwrap().waiters.len() < waiters.len() {
+                tokio::task::yield_now().await;
+            }
+        }
+        drop(order_tx);
+        drop(permit);
+
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        let mut order = Vec::new();
+        while let Some(name) = order_rx.recv().await {
+            order.push(name);
+        }
+        assert_eq!(order, vec!["high", "also-high", "medium", "low"]);
+        assert_eq!(semaphore.state.lock().unwrap().available, 1);
+    }
+}
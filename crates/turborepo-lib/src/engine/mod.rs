@@ -1,4 +1,5 @@
 mod builder;
+mod critical_path;
 mod execute;
 
 mod dot;
@@ -112,6 +113,25 @@ impl Engine<Built> {
         self.neighbors(task_id, petgraph::Direction::Incoming)
     }
 
+    /// Every task that has to finish before `task_id` can start, not just its
+    /// direct dependencies
+    pub fn transitive_dependencies(&self, task_id: &TaskId) -> Option<HashSet<&TaskNode>> {
+        let index = self.task_lookup.get(task_id)?;
+        let mut dfs = petgraph::visit::Dfs::new(&self.task_graph, *index);
+        let mut dependencies = HashSet::new();
+        while let Some(dependency) = dfs.next(&self.task_graph) {
+            if dependency != *index {
+                dependencies.insert(
+                    self.task_graph
+                        .node_weight(dependency)
+                        .expect("node index should be present"),
+                );
+            }
+        }
+
+        Some(dependencies)
+    }
+
     fn neighbors(
         &self,
         task_id: &TaskId,
@@ -190,7 +210,9 @@ impl Engine<Built> {
                             package: dep_id.package().to_string(),
                         })?;
                     if task_definition.persistent
-                        && package_json.scripts.contains_key(dep_id.task())
+                        && task_definition
+                            .resolved_command(dep_id, package_json)
+                            .is_some()
                     {
                         return Err(ValidateError::DependencyOnPersistentTask {
                             persistent_task: dep_id.to_string(),
@@ -204,19 +226,16 @@ impl Engine<Built> {
                     .workspace_info(&WorkspaceName::from(task_id.package().to_string()))
                     .expect("package graph should contain workspace info for task package");
 
-                let package_has_task = info
-                    .package_json
-                    .scripts
-                    .get(task_id.task())
+                let Some(task_definition) = self.task_definitions.get(task_id) else {
+                    return Ok(false);
+                };
+
+                let package_has_task = task_definition
+                    .resolved_command(task_id, &info.package_json)
                     // handle legacy behaviour from go where an empty string may appear
                     .map_or(false, |script| !script.is_empty());
 
-                let task_is_persistent = self
-                    .task_definitions
-                    .get(task_id)
-                    .map_or(false, |task_def| task_def.persistent);
-
-                Ok(task_is_persistent && package_has_task)
+                Ok(task_definition.persistent && package_has_task)
             })
             .fold((0, Vec::new()), |(mut count, mut errs), result| {
                 match result {
@@ -385,4 +404,44 @@ mod test {
         // if our limit is greater, then it should pass
         engine.validate(&graph, 4).expect("ok");
     }
+
+    #[test]
+    fn test_transitive_dependencies() {
+        let mut engine = Engine::new();
+        let util = TaskId::new("util", "build");
+        let lib = TaskId::new("lib", "build");
+        let app = TaskId::new("app", "build");
+        let util_index = engine.get_index(&util);
+        engine.connect_to_root(&util);
+        let lib_index = engine.get_index(&lib);
+        engine.task_graph.add_edge(lib_index, util_index, ());
+        let app_index = engine.get_index(&app);
+        engine.task_graph.add_edge(app_index, lib_index, ());
+        let engine = engine.seal();
+
+        let tasks = |nodes: HashSet<&TaskNode>| {
+            let mut tasks = nodes
+                .into_iter()
+                .filter_map(|node| match node {
+                    TaskNode::Task(task_id) => Some(task_id.to_string()),
+                    TaskNode::Root => None,
+                })
+                .collect::<Vec<_>>();
+            tasks.sort();
+            tasks
+        };
+
+        assert_eq!(
+            tasks(engine.dependencies(&app).unwrap()),
+            vec!["lib#build".to_string()]
+        );
+        assert_eq!(
+            tasks(engine.transitive_dependencies(&app).unwrap()),
+            vec!["lib#build".to_string(), "util#build".to_string()]
+        );
+        assert!(tasks(engine.transitive_dependencies(&util).unwrap()).is_empty());
+        assert!(engine
+            .transitive_dependencies(&TaskId::new("missing", "build"))
+            .is_none());
+    }
 }
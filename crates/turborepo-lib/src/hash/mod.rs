@@ -54,6 +54,8 @@ pub struct TaskHashable<'a> {
     pub(crate) task: &'a str,
     pub(crate) outputs: TaskOutputs,
     pub(crate) pass_through_args: &'a [String],
+    pub(crate) command: Option<&'a str>,
+    pub(crate) cwd: Option<&'a str>,
 
     // env
     pub(crate) env: &'a [String],
@@ -75,6 +77,9 @@ pub struct GlobalHashable<'a> {
     pub env_mode: EnvMode,
     pub framework_inference: bool,
     pub dot_env: &'a [turbopath::RelativeUnixPathBuf],
+    // The package manager and version pinned by `packageManager`, or the
+    // installed version if it drifted from the pin
+    pub package_manager: Option<&'a str>,
 }
 
 pub struct LockFilePackages(pub Vec<turborepo_lockfiles::Package>);
@@ -211,6 +216,12 @@ impl From<TaskHashable<'_>> for Builder<HeapAllocator> {
 
         builder.set_task(task_hashable.task);
         builder.set_env_mode(task_hashable.env_mode.into());
+        if let Some(command) = task_hashable.command {
+            builder.set_command(command);
+        }
+        if let Some(cwd) = task_hashable.cwd {
+            builder.set_cwd(cwd);
+        }
 
         {
             let output_builder: Builder<_> = task_hashable.outputs.into();
@@ -361,6 +372,10 @@ impl From<GlobalHashable<'_>> for Builder<HeapAllocator> {
             }
         }
 
+        if let Some(package_manager) = hashable.package_manager {
+            builder.set_package_manager(package_manager);
+        }
+
         // We're okay to unwrap here because we haven't hit the nesting
         // limit and the message will not have cycles.
         let size = builder
@@ -403,11 +418,14 @@ mod test {
                 exclusions: vec!["exclusions".to_string()],
             },
             pass_through_args: &["pass_thru_args".to_string()],
+            command: None,
+            cwd: None,
             env: &["env".to_string()],
             resolved_env_vars: vec![],
             pass_through_env: &["pass_thru_env".to_string()],
             env_mode: ResolvedEnvMode::Loose,
             dot_env: &[turbopath::RelativeUnixPathBuf::new("dotenv".to_string()).unwrap()],
+            package_manager: None,
         };
 
         assert_eq!(task_hashable.hash(), "ff765ee2f83bc034");
@@ -16,6 +16,7 @@ mod cli;
 mod commands;
 mod config;
 mod daemon;
+mod distributed;
 mod engine;
 
 mod framework;
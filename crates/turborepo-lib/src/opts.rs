@@ -5,7 +5,10 @@ use turbopath::AnchoredSystemPathBuf;
 use turborepo_cache::CacheOpts;
 
 use crate::{
-    cli::{Command, DryRunMode, EnvMode, LogOrder, LogPrefix, OutputLogsMode, RunArgs},
+    cli::{
+        Command, DryRunMode, EnvMode, LogFormat, LogOrder, LogPrefix, OutputLogsMode, RunArgs,
+        RunOutputFormat, SummarizeMode,
+    },
     run::task_id::TaskId,
     Args,
 };
@@ -105,6 +108,8 @@ pub struct RunCacheOpts {
     pub(crate) skip_reads: bool,
     pub(crate) skip_writes: bool,
     pub(crate) task_output_mode_override: Option<OutputLogsMode>,
+    pub(crate) log_format: LogFormat,
+    pub(crate) lazy_restore: bool,
 }
 
 impl<'a> From<&'a RunArgs> for RunCacheOpts {
@@ -113,6 +118,8 @@ impl<'a> From<&'a RunArgs> for RunCacheOpts {
             skip_reads: args.force.flatten().is_some_and(|f| f),
             skip_writes: args.no_cache,
             task_output_mode_override: args.output_logs,
+            log_format: args.log_format,
+            lazy_restore: args.lazy_restore,
         }
     }
 }
@@ -135,9 +142,12 @@ pub struct RunOpts {
     pub(crate) single_package: bool,
     pub log_prefix: ResolvedLogPrefix,
     pub log_order: ResolvedLogOrder,
-    pub summarize: Option<Option<bool>>,
+    pub summarize: Option<Option<SummarizeMode>>,
     pub(crate) experimental_space_id: Option<String>,
     pub is_github_actions: bool,
+    pub(crate) workers: Vec<String>,
+    pub(crate) worker_token: Option<String>,
+    pub(crate) output: RunOutputFormat,
 }
 
 impl RunOpts {
@@ -227,6 +237,9 @@ impl<'a> TryFrom<&'a RunArgs> for RunOpts {
             graph,
             dry_run: args.dry_run,
             is_github_actions,
+            workers: args.workers.clone(),
+            worker_token: args.worker_token.clone(),
+            output: args.output,
         })
     }
 }
@@ -378,7 +391,7 @@ mod test {
 
     use super::{LegacyFilter, RunOpts};
     use crate::{
-        cli::DryRunMode,
+        cli::{DryRunMode, RunOutputFormat},
         opts::{Opts, RunCacheOpts, ScopeOpts},
     };
 
@@ -532,6 +545,9 @@ mod test {
             summarize: None,
             experimental_space_id: None,
             is_github_actions: false,
+            workers: vec![],
+            worker_token: None,
+            output: RunOutputFormat::Text,
         };
         let cache_opts = CacheOpts::default();
         let runcache_opts = RunCacheOpts::default();
@@ -25,6 +25,8 @@ use std::{
 };
 
 use portable_pty::{native_pty_system, Child as PtyChild, MasterPty as PtyController};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use tokio::{
     io::{AsyncBufRead, AsyncBufReadExt, BufReader},
     join,
@@ -32,6 +34,7 @@ use tokio::{
     sync::{mpsc, watch, RwLock},
 };
 use tracing::debug;
+use turborepo_ui::{LogStream, StreamWrite};
 
 use super::Command;
 
@@ -53,6 +56,9 @@ impl ChildState {
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum ChildExit {
     Finished(Option<i32>),
+    /// The child process exited within its grace period after being asked to
+    /// stop
+    Interrupted,
     Killed,
     /// The child process was killed by someone else. Note that on
     /// windows, it is not possible to distinguish between whether
@@ -64,13 +70,93 @@ pub enum ChildExit {
 #[derive(Debug, Clone)]
 pub enum ShutdownStyle {
     /// On windows this will immediately kill, and on posix systems it
-    /// will send a SIGINT. If `Duration` elapses, we then follow up with a
-    /// `Kill`.
-    Graceful(Duration),
+    /// will send the policy's stop signal. If the grace period elapses, we then
+    /// follow up with a `Kill`.
+    Graceful(ShutdownPolicy),
 
     Kill,
 }
 
+/// The signal that a child process is sent when it is asked to stop
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum StopSignal {
+    #[default]
+    #[serde(rename = "SIGINT")]
+    Interrupt,
+    #[serde(rename = "SIGTERM")]
+    Terminate,
+    #[serde(rename = "SIGHUP")]
+    Hangup,
+}
+
+impl StopSignal {
+    pub const ALL: [Self; 3] = [Self::Interrupt, Self::Terminate, Self::Hangup];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StopSignal::Interrupt => "SIGINT",
+            StopSignal::Terminate => "SIGTERM",
+            StopSignal::Hangup => "SIGHUP",
+        }
+    }
+
+    #[cfg(unix)]
+    fn as_raw(&self) -> libc::c_int {
+        match self {
+            StopSignal::Interrupt => libc::SIGINT,
+            StopSignal::Terminate => libc::SIGTERM,
+            StopSignal::Hangup => libc::SIGHUP,
+        }
+    }
+}
+
+/// How a child process is asked to stop before it gets killed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub struct ShutdownPolicy {
+    pub signal: StopSignal,
+    /// How long the child has to exit after it is sent `signal`
+    pub grace_period: Duration,
+    /// Whether the signal, and the kill that may follow it, are sent to the
+    /// child's whole process group instead of just the child
+    pub process_group: bool,
+}
+
+impl ShutdownPolicy {
+    pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_millis(500);
+}
+
+impl Default for ShutdownPolicy {
+    fn default() -> Self {
+        Self {
+            signal: StopSignal::default(),
+            grace_period: Self::DEFAULT_GRACE_PERIOD,
+            process_group: true,
+        }
+    }
+}
+
+impl From<Duration> for ShutdownPolicy {
+    fn from(grace_period: Duration) -> Self {
+        Self {
+            grace_period,
+            ..Default::default()
+        }
+    }
+}
+
+/// Sends `signal` to the process `pid`, or to the process group that it leads
+#[cfg(unix)]
+fn send_signal(pid: u32, process_group: bool, signal: libc::c_int) {
+    // kill takes negative pid to indicate that you want to use gpid
+    let target = match process_group {
+        true => -(pid as i32),
+        false => pid as i32,
+    };
+    unsafe {
+        libc::kill(target, signal);
+    }
+}
+
 /// Child process stopped.
 #[derive(Debug)]
 pub struct ShutdownFailed;
@@ -329,18 +415,14 @@ impl ShutdownStyle {
             // Windows doesn't give the ability to send a signal to a process so we
             // can't make use of the graceful shutdown timeout.
             #[allow(unused)]
-            ShutdownStyle::Graceful(timeout) => {
+            ShutdownStyle::Graceful(policy) => {
                 // try ro run the command for the given timeout
                 #[cfg(unix)]
                 {
                     let fut = async {
                         if let Some(pid) = child.pid() {
-                            debug!("sending SIGINT to child {}", pid);
-                            // kill takes negative pid to indicate that you want to use gpid
-                            let pgid = -(pid as i32);
-                            unsafe {
-                                libc::kill(pgid, libc::SIGINT);
-                            }
+                            debug!("sending {} to child {}", policy.signal.as_str(), pid);
+                            send_signal(pid, policy.process_group, policy.signal.as_raw());
                             debug!("waiting for child {}", pid);
                             child.wait().await
                         } else {
@@ -351,15 +433,18 @@ impl ShutdownStyle {
 
                     debug!("starting shutdown");
 
-                    let result = tokio::time::timeout(*timeout, fut).await;
+                    let result = tokio::time::timeout(policy.grace_period, fut).await;
                     match result {
-                        // We ignore the exit code and mark it as killed since we sent a SIGINT
-                        // This avoids reliance on an underlying process exiting with
+                        // We ignore the exit code and mark it as interrupted since we sent a
+                        // signal. This avoids reliance on an underlying process exiting with
                         // no exit code or a non-zero in order for turbo to operate correctly.
-                        Ok(Ok(_exit_code)) => ChildState::Exited(ChildExit::Killed),
+                        Ok(Ok(_exit_code)) => ChildState::Exited(ChildExit::Interrupted),
                         Ok(Err(_)) => ChildState::Exited(ChildExit::Failed),
                         Err(_) => {
                             debug!("graceful shutdown timed out, killing child");
+                            if let Some(pid) = child.pid().filter(|_| policy.process_group) {
+                                send_signal(pid, true, libc::SIGKILL);
+                            }
                             match child.kill().await {
                                 Ok(_) => ChildState::Exited(ChildExit::Killed),
                                 Err(_) => ChildState::Exited(ChildExit::Failed),
@@ -406,6 +491,8 @@ pub struct Child {
     stdin: Arc<Mutex<Option<ChildInput>>>,
     output: Arc<Mutex<Option<ChildOutput>>>,
     label: String,
+    forward_signals: bool,
+    process_group: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -441,6 +528,11 @@ impl Child {
         use_pty: bool,
     ) -> io::Result<Self> {
         let label = command.label();
+        let forward_signals = command.will_forward_signals();
+        let process_group = match &shutdown_style {
+            ShutdownStyle::Graceful(policy) => policy.process_group,
+            ShutdownStyle::Kill => true,
+        };
         let SpawnResult {
             handle: mut child,
             io: ChildIO { stdin, output },
@@ -495,6 +587,8 @@ impl Child {
             stdin: Arc::new(Mutex::new(stdin)),
             output: Arc::new(Mutex::new(output)),
             label,
+            forward_signals,
+            process_group,
         })
     }
 
@@ -562,6 +656,21 @@ impl Child {
         code
     }
 
+    /// Sends `signal` to the `Child` if it was spawned to have signals
+    /// forwarded to it. Nothing is sent once the process has exited.
+    #[cfg(unix)]
+    pub async fn forward_signal(&self, signal: libc::c_int) {
+        if !self.forward_signals {
+            return;
+        }
+        // hold the lock so that the process can't be reaped while we signal it
+        let state = self.state.read().await;
+        if let (ChildState::Running(_), Some(pid)) = (&*state, self.pid) {
+            debug!("forwarding signal {} to child {}", signal, pid);
+            send_signal(pid, self.process_group, signal);
+        }
+    }
+
     fn pid(&self) -> Option<u32> {
         self.pid
     }
@@ -580,6 +689,17 @@ impl Child {
     pub async fn wait_with_piped_outputs<W: Write>(
         &mut self,
         stdout_pipe: W,
+    ) -> Result<Option<ChildExit>, std::io::Error> {
+        self.wait_with_piped_streams(MergedStreams(stdout_pipe))
+            .await
+    }
+
+    /// Like `wait_with_piped_outputs`, but the pipe is told which stream each
+    /// line came from. When the child is attached to a pty its streams can't
+    /// be told apart, so all output is reported as stdout.
+    pub async fn wait_with_piped_streams<W: StreamWrite>(
+        &mut self,
+        stdout_pipe: W,
     ) -> Result<Option<ChildExit>, std::io::Error> {
         match self.outputs() {
             Some(ChildOutput::Std { stdout, stderr }) => {
@@ -601,7 +721,7 @@ impl Child {
     #[tracing::instrument(skip_all)]
     async fn wait_with_piped_sync_output<R: BufRead + Send + 'static>(
         &mut self,
-        mut stdout_pipe: impl Write,
+        mut stdout_pipe: impl StreamWrite,
         mut stdout_lines: R,
     ) -> Result<Option<ChildExit>, std::io::Error> {
         // TODO: in order to not impose that a stdout_pipe is Send we send the bytes
@@ -633,7 +753,7 @@ impl Child {
             let mut result = Ok(());
             while let Some(mut bytes) = byte_rx.recv().await {
                 add_trailing_newline(&mut bytes);
-                if let Err(err) = stdout_pipe.write_all(&bytes) {
+                if let Err(err) = stdout_pipe.write_stream(LogStream::Stdout, &bytes) {
                     result = Err(err);
                     break;
                 }
@@ -650,7 +770,7 @@ impl Child {
     #[tracing::instrument(skip_all)]
     async fn wait_with_piped_async_outputs<R1: AsyncBufRead + Unpin, R2: AsyncBufRead + Unpin>(
         &mut self,
-        mut stdout_pipe: impl Write,
+        mut stdout_pipe: impl StreamWrite,
         mut stdout_lines: Option<R1>,
         mut stderr_lines: Option<R2>,
     ) -> Result<Option<ChildExit>, std::io::Error> {
@@ -676,13 +796,13 @@ impl Child {
                 Some(result) = next_line(&mut stdout_lines, &mut stdout_buffer) => {
                     result?;
                     add_trailing_newline(&mut stdout_buffer);
-                    stdout_pipe.write_all(&stdout_buffer)?;
+                    stdout_pipe.write_stream(LogStream::Stdout, &stdout_buffer)?;
                     stdout_buffer.clear();
                 }
                 Some(result) = next_line(&mut stderr_lines, &mut stderr_buffer) => {
                     result?;
                     add_trailing_newline(&mut stderr_buffer);
-                    stdout_pipe.write_all(&stderr_buffer)?;
+                    stdout_pipe.write_stream(LogStream::Stderr, &stderr_buffer)?;
                     stderr_buffer.clear();
                 }
                 else => {
@@ -692,12 +812,12 @@ impl Child {
                     // We check and flush the buffers to avoid missing the last line of output.
                     if !stdout_buffer.is_empty() {
                         add_trailing_newline(&mut stdout_buffer);
-                        stdout_pipe.write_all(&stdout_buffer)?;
+                        stdout_pipe.write_stream(LogStream::Stdout, &stdout_buffer)?;
                         stdout_buffer.clear();
                     }
                     if !stderr_buffer.is_empty() {
                         add_trailing_newline(&mut stderr_buffer);
-                        stdout_pipe.write_all(&stderr_buffer)?;
+                        stdout_pipe.write_stream(LogStream::Stderr, &stderr_buffer)?;
                         stderr_buffer.clear();
                     }
                     break;
@@ -715,6 +835,21 @@ impl Child {
     }
 }
 
+// Sends every stream of a child to the same pipe
+struct MergedStreams<W>(W);
+
+impl<W: Write> Write for MergedStreams<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl<W: Write> StreamWrite for MergedStreams<W> {}
+
 // Adds a trailing newline if necessary to the buffer
 fn add_trailing_newline(buffer: &mut Vec<u8>) {
     // If the line doesn't end with a newline, that indicates we hit a EOF.
@@ -793,9 +928,10 @@ mod test {
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
     use tracing_test::traced_test;
     use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_ui::{LogStream, StreamWrite};
 
     use super::{Child, ChildInput, ChildOutput, ChildState, Command};
-    use crate::process::child::{ChildExit, ShutdownStyle};
+    use crate::process::child::{ChildExit, ShutdownPolicy, ShutdownStyle, StopSignal};
 
     const STARTUP_DELAY: Duration = Duration::from_millis(500);
     // We skip testing PTY usage on Windows
@@ -951,7 +1087,7 @@ mod test {
 
         let mut child = Child::spawn(
             cmd,
-            ShutdownStyle::Graceful(Duration::from_millis(500)),
+            ShutdownStyle::Graceful(Duration::from_millis(500).into()),
             use_pty,
         )
         .unwrap();
@@ -988,7 +1124,7 @@ mod test {
 
         let mut child = Child::spawn(
             cmd,
-            ShutdownStyle::Graceful(Duration::from_millis(500)),
+            ShutdownStyle::Graceful(Duration::from_millis(500).into()),
             use_pty,
         )
         .unwrap();
@@ -1000,8 +1136,9 @@ mod test {
 
         let state = child.state.read().await;
 
-        // We should ignore the exit code of the process and always treat it as killed
-        assert_matches!(&*state, &ChildState::Exited(ChildExit::Killed));
+        // We should ignore the exit code of the process and always treat it as
+        // interrupted
+        assert_matches!(&*state, &ChildState::Exited(ChildExit::Interrupted));
     }
 
     #[test_case(false)]
@@ -1018,7 +1155,7 @@ mod test {
 
         let mut child = Child::spawn(
             cmd,
-            ShutdownStyle::Graceful(Duration::from_millis(500)),
+            ShutdownStyle::Graceful(Duration::from_millis(500).into()),
             use_pty,
         )
         .unwrap();
@@ -1104,6 +1241,48 @@ mod test {
         assert_matches!(exit, Some(ChildExit::Finished(Some(0))));
     }
 
+    #[tokio::test]
+    async fn test_wait_with_streams() {
+        #[derive(Default)]
+        struct Streams(Vec<(LogStream, String)>);
+
+        impl std::io::Write for Streams {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.write_stream(LogStream::Stdout, buf)?;
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl StreamWrite for Streams {
+            fn write_stream(&mut self, stream: LogStream, buf: &[u8]) -> std::io::Result<()> {
+                self.0
+                    .push((stream, String::from_utf8_lossy(buf).trim().to_string()));
+                Ok(())
+            }
+        }
+
+        let script = find_script_dir().join_component("hello_world_hello_moon.js");
+        let mut cmd = Command::new("node");
+        cmd.args([script.as_std_path()]);
+        let mut child = Child::spawn(cmd, ShutdownStyle::Kill, false).unwrap();
+
+        let mut streams = Streams::default();
+
+        let exit = child.wait_with_piped_streams(&mut streams).await.unwrap();
+
+        assert!(streams
+            .0
+            .contains(&(LogStream::Stdout, "hello world".to_string())));
+        assert!(streams
+            .0
+            .contains(&(LogStream::Stderr, "hello moon".to_string())));
+        assert_matches!(exit, Some(ChildExit::Finished(Some(0))));
+    }
+
     #[test_case(false)]
     #[test_case(TEST_PTY)]
     #[tokio::test]
@@ -1158,7 +1337,7 @@ mod test {
         cmd.args(["-c", "while true; do sleep 0.2; done"]);
         let mut child = Child::spawn(
             cmd,
-            ShutdownStyle::Graceful(Duration::from_millis(100)),
+            ShutdownStyle::Graceful(Duration::from_millis(100).into()),
             use_pty,
         )
         .unwrap();
@@ -1167,7 +1346,64 @@ mod test {
 
         let exit = child.stop().await;
 
-        assert_matches!(exit, Some(ChildExit::Killed));
+        assert_matches!(exit, Some(ChildExit::Interrupted | ChildExit::Killed));
+    }
+
+    #[cfg(unix)]
+    #[test_case(false)]
+    #[test_case(TEST_PTY)]
+    #[tokio::test]
+    async fn test_stop_signal(use_pty: bool) {
+        // only exits promptly if it gets a SIGTERM
+        let mut cmd = Command::new("sh");
+        cmd.args([
+            "-c",
+            "trap '' INT; trap 'exit 0' TERM; while true; do sleep 0.1; done",
+        ]);
+        let mut child = Child::spawn(
+            cmd,
+            ShutdownStyle::Graceful(ShutdownPolicy {
+                signal: StopSignal::Terminate,
+                grace_period: Duration::from_secs(5),
+                process_group: true,
+            }),
+            use_pty,
+        )
+        .unwrap();
+
+        tokio::time::sleep(STARTUP_DELAY).await;
+
+        let exit = tokio::time::timeout(Duration::from_secs(2), child.stop())
+            .await
+            .expect("child should exit before the grace period ends");
+
+        assert_matches!(exit, Some(ChildExit::Interrupted));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_forward_signal() {
+        let mut cmd = Command::new("sh");
+        cmd.args([
+            "-c",
+            "trap 'echo usr1' USR1; while true; do sleep 0.1; done",
+        ]);
+        cmd.forward_signals();
+        let mut child = Child::spawn(cmd, ShutdownStyle::Kill, false).unwrap();
+
+        tokio::time::sleep(STARTUP_DELAY).await;
+        child.forward_signal(libc::SIGUSR1).await;
+
+        let mut buf = vec![0; 4];
+        match child.outputs().unwrap() {
+            ChildOutput::Std { mut stdout, .. } => {
+                stdout.read_exact(&mut buf).await.unwrap();
+            }
+            ChildOutput::Pty(_) => unreachable!("spawned without a pty"),
+        };
+        assert_eq!(buf, b"usr1");
+
+        child.kill().await;
     }
 
     #[test_case(false)]
@@ -15,6 +15,7 @@ pub struct Command {
     cwd: Option<AbsoluteSystemPathBuf>,
     env: BTreeMap<OsString, OsString>,
     open_stdin: bool,
+    forward_signals: bool,
     env_clear: bool,
 }
 
@@ -27,6 +28,7 @@ impl Command {
             cwd: None,
             env: BTreeMap::new(),
             open_stdin: false,
+            forward_signals: false,
             env_clear: false,
         }
     }
@@ -77,6 +79,13 @@ impl Command {
         self
     }
 
+    /// Configure the child process to receive the signals that turbo forwards
+    /// to long running tasks
+    pub fn forward_signals(&mut self) -> &mut Self {
+        self.forward_signals = true;
+        self
+    }
+
     /// Clears the environment variables for the child process
     pub fn env_clear(&mut self) -> &mut Self {
         self.env_clear = true;
@@ -100,6 +109,11 @@ impl Command {
     pub fn will_open_stdin(&self) -> bool {
         self.open_stdin
     }
+
+    /// If forwarded signals should be sent to the process
+    pub fn will_forward_signals(&self) -> bool {
+        self.forward_signals
+    }
 }
 
 impl From<Command> for tokio::process::Command {
@@ -111,6 +125,7 @@ impl From<Command> for tokio::process::Command {
             env,
             open_stdin,
             env_clear,
+            ..
         } = value;
 
         let mut cmd = tokio::process::Command::new(program);
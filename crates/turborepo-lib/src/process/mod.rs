@@ -23,7 +23,7 @@ use futures::Future;
 use tokio::task::JoinSet;
 use tracing::{debug, trace};
 
-pub use self::child::{Child, ChildExit};
+pub use self::child::{Child, ChildExit, ShutdownPolicy, StopSignal};
 
 /// A process manager that is responsible for spawning and managing child
 /// processes. When the manager is Open, new child processes can be spawned
@@ -73,7 +73,7 @@ impl ProcessManager {
     pub fn spawn(
         &self,
         command: Command,
-        stop_timeout: Duration,
+        shutdown_policy: ShutdownPolicy,
     ) -> Option<io::Result<child::Child>> {
         let mut lock = self.state.lock().unwrap();
         if lock.is_closing {
@@ -81,7 +81,7 @@ impl ProcessManager {
         }
         let child = child::Child::spawn(
             command,
-            child::ShutdownStyle::Graceful(stop_timeout),
+            child::ShutdownStyle::Graceful(shutdown_policy),
             self.use_pty,
         );
         if let Ok(child) = &child {
@@ -91,12 +91,22 @@ impl ProcessManager {
     }
 
     /// Stop the process manager, closing all child processes. On posix
-    /// systems this will send a SIGINT, and on windows it will just kill
-    /// the process immediately.
+    /// systems this will send each child the stop signal from its shutdown
+    /// policy, and on windows it will just kill the process immediately.
     pub async fn stop(&self) {
         self.close(|mut c| async move { c.stop().await }).await
     }
 
+    /// Forward `signal` to the running children that were spawned to receive
+    /// forwarded signals.
+    #[cfg(unix)]
+    pub async fn forward_signal(&self, signal: libc::c_int) {
+        let children = self.state.lock().expect("not poisoned").children.clone();
+        for child in children {
+            child.forward_signal(signal).await;
+        }
+    }
+
     /// Stop the process manager, waiting for all child processes to exit.
     ///
     /// If you want to set a timeout, use `tokio::time::timeout` and
@@ -162,13 +172,16 @@ mod test {
         cmd
     }
 
-    const STOPPED_EXIT: Option<ChildExit> = Some(ChildExit::Killed);
+    const STOPPED_EXIT: Option<ChildExit> = Some(ChildExit::Interrupted);
 
     #[tokio::test]
     async fn test_basic() {
         let manager = ProcessManager::new(false);
         let mut child = manager
-            .spawn(get_script_command("hello_world.js"), Duration::from_secs(2))
+            .spawn(
+                get_script_command("hello_world.js"),
+                Duration::from_secs(2).into(),
+            )
             .unwrap()
             .unwrap();
         let mut out = Vec::new();
@@ -184,7 +197,7 @@ mod test {
         let children = (0..2)
             .map(|_| {
                 manager
-                    .spawn(get_command(), Duration::from_secs(2))
+                    .spawn(get_command(), Duration::from_secs(2).into())
                     .unwrap()
                     .unwrap()
             })
@@ -204,7 +217,7 @@ mod test {
     async fn test_closed() {
         let manager = ProcessManager::new(false);
         let mut child = manager
-            .spawn(get_command(), Duration::from_secs(2))
+            .spawn(get_command(), Duration::from_secs(2).into())
             .unwrap()
             .unwrap();
         let mut out = Vec::new();
@@ -221,7 +234,7 @@ mod test {
 
         // Verify that we can't start new child processes
         assert!(manager
-            .spawn(get_command(), Duration::from_secs(2))
+            .spawn(get_command(), Duration::from_secs(2).into())
             .is_none());
 
         manager.stop().await;
@@ -231,7 +244,10 @@ mod test {
     async fn test_exit_code() {
         let manager = ProcessManager::new(false);
         let mut child = manager
-            .spawn(get_script_command("hello_world.js"), Duration::from_secs(2))
+            .spawn(
+                get_script_command("hello_world.js"),
+                Duration::from_secs(2).into(),
+            )
             .unwrap()
             .unwrap();
 
@@ -248,7 +264,10 @@ mod test {
     async fn test_message_after_stop() {
         let manager = ProcessManager::new(false);
         let mut child = manager
-            .spawn(get_script_command("hello_world.js"), Duration::from_secs(2))
+            .spawn(
+                get_script_command("hello_world.js"),
+                Duration::from_secs(2).into(),
+            )
             .unwrap()
             .unwrap();
 
@@ -270,7 +289,7 @@ mod test {
     #[tokio::test]
     async fn test_reuse_manager() {
         let manager = ProcessManager::new(false);
-        manager.spawn(get_command(), Duration::from_secs(2));
+        manager.spawn(get_command(), Duration::from_secs(2).into());
 
         sleep(Duration::from_millis(100)).await;
 
@@ -299,7 +318,7 @@ mod test {
             let command = get_script_command(script);
             tasks.push(tokio::spawn(async move {
                 manager
-                    .spawn(command, Duration::from_secs(1))
+                    .spawn(command, Duration::from_secs(1).into())
                     .unwrap()
                     .unwrap()
                     .wait()
@@ -330,7 +349,7 @@ mod test {
 
         let mut out = Vec::new();
         let mut child = manager
-            .spawn(get_command(), Duration::from_secs(1))
+            .spawn(get_command(), Duration::from_secs(1).into())
             .unwrap()
             .unwrap();
 
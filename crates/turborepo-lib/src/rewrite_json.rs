@@ -63,10 +63,12 @@ pub fn set_path(
     // Figure out what we should be generating:
     // - An object to be assigned to an existing member. ("object")
     // - A member to add to an existing object. ("member")
-    let generate_type: GenerateType = if !closest_path.is_empty() {
-        GenerateType::Object
-    } else {
+    let missing_path_segments = &path[closest_path.len()..];
+    let is_object = matches!(closest_node, jsonc_parser::ast::Value::Object(_));
+    let generate_type: GenerateType = if is_object && !missing_path_segments.is_empty() {
         GenerateType::Member
+    } else {
+        GenerateType::Object
     };
 
     // Identify the token replacement metadata: start, end, and possible trailing
@@ -88,7 +90,6 @@ pub fn set_path(
 
     // Generate the serialized JSON to insert into the document.
     // We synthesize objects for missing path segments.
-    let missing_path_segments = &path[closest_path.len()..];
     let computed_object = match generate_type {
         GenerateType::Object => generate_object(missing_path_segments, json_value),
         GenerateType::Member => generate_member(missing_path_segments, json_value, separator),
@@ -105,7 +106,9 @@ pub fn set_path(
  * get_root returns the document root, or information on the error
  * encountered with the input json_document_string.
  */
-fn get_root(json_document_string: &str) -> Result<jsonc_parser::ast::Value, RewriteError> {
+pub(crate) fn get_root(
+    json_document_string: &str,
+) -> Result<jsonc_parser::ast::Value, RewriteError> {
     let parse_result_result = parse_to_ast(
         json_document_string,
         &Default::default(),
@@ -339,9 +342,72 @@ fn find_all_paths<'a>(
     ranges
 }
 
+/**
+ * Given a JSONC document, an object traversal path, and a new key,
+ * `rename_path` will return a minimally-mutated JSONC document with all
+ * occurrences of the last key in the path renamed to the new key. Values
+ * are left untouched.
+ */
+pub fn rename_path(
+    json_document_string: &str,
+    path: &[&str],
+    new_key: &str,
+) -> Result<Option<String>, RewriteError> {
+    let root = get_root(json_document_string)?;
+
+    let mut name_ranges = vec![];
+    find_all_names(&root, path, &mut name_ranges);
+
+    if name_ranges.is_empty() {
+        return Ok(None);
+    }
+
+    // We iterate in reverse since we're mutating the string.
+    name_ranges.sort_by_key(|range| range.start);
+    let replacement = format!("\"{new_key}\"");
+    let mut output: String = json_document_string.to_owned();
+    for range in name_ranges.iter().rev() {
+        output.replace_range(range.start..range.end, &replacement);
+    }
+
+    Ok(Some(output))
+}
+
+/**
+ * find_all_names returns the ranges of the names of every property at the
+ * specified path.
+ */
+fn find_all_names(
+    current_node: &jsonc_parser::ast::Value<'_>,
+    target_path: &[&str],
+    name_ranges: &mut Vec<jsonc_parser::common::Range>,
+) {
+    let Some((key, remaining_path)) = target_path.split_first() else {
+        return;
+    };
+
+    // We can only find paths on objects.
+    if let jsonc_parser::ast::Value::Object(obj) = current_node {
+        for property in obj
+            .properties
+            .iter()
+            .filter(|property| property.name.as_str() == *key)
+        {
+            if remaining_path.is_empty() {
+                name_ranges.push(match &property.name {
+                    jsonc_parser::ast::ObjectPropName::String(literal) => literal.range,
+                    jsonc_parser::ast::ObjectPropName::Word(literal) => literal.range,
+                });
+            } else {
+                find_all_names(&property.value, remaining_path, name_ranges);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::rewrite_json::{set_path, unset_path};
+    use crate::rewrite_json::{rename_path, set_path, unset_path};
 
     macro_rules! set_tests {
         ($($name:ident: $value:expr,)*) => {
@@ -393,12 +459,29 @@ mod test {
             "{ \"parent\": { \"child\": \"Jerry\" } }",
             "{ \"parent\": { \"child\": \"Junior\" } }"
         ),
+        existing_populated_object: (
+            "{ \"parent\": { \"sibling\": \"Jerry\" } }",
+            "{ \"parent\": {\"child\":\"Junior\", \"sibling\": \"Jerry\" } }"
+        ),
         existing_bonus_child: (
             "{ \"parent\": { \"child\": { \"grandchild\": \"Morty\" } } }",
             "{ \"parent\": { \"child\": \"Junior\" } }"
         ),
     }
 
+    macro_rules! rename_tests {
+        ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (json_document_string, path, expected) = $value;
+                let output_option = rename_path(json_document_string, path, "renamed").unwrap();
+                assert_eq!(output_option.as_deref(), expected);
+            }
+        )*
+        }
+    }
+
     unset_tests! {
         nonexistent_path: (
             r#"{ "before": {}, "experimentalSpaces": { "id": "one" }, "experimentalSpaces": { "id": "two" }, "after": {} }"#,
@@ -432,4 +515,22 @@ mod test {
             None
         ),
     }
+
+    rename_tests! {
+        rename_nonexistent_path: (
+            r#"{ "parent": { "child": 1 } }"#,
+            &["parent", "nope"],
+            None
+        ),
+        rename_leaf_node: (
+            "{\n  // a comment\n  \"parent\": { \"child\": [1, 2] }\n}",
+            &["parent", "child"],
+            Some("{\n  // a comment\n  \"parent\": { \"renamed\": [1, 2] }\n}")
+        ),
+        rename_duplicate_keys: (
+            r#"{ "parent": { "child": 1 }, "parent": { "child": 2 } }"#,
+            &["parent", "child"],
+            Some(r#"{ "parent": { "renamed": 1 }, "parent": { "renamed": 2 } }"#)
+        ),
+    }
 }
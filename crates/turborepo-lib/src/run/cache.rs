@@ -1,20 +1,28 @@
-use std::{io::Write, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    io::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use console::StyledObject;
+use futures::future::{join_all, try_join_all};
+use tokio::sync::OnceCell;
 use tracing::debug;
 use turbopath::{
     AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
 };
-use turborepo_cache::{AsyncCache, CacheError, CacheHitMetadata, CacheSource};
+use turborepo_cache::{AsyncCache, CacheConfig, CacheError, CacheHitMetadata, CacheSource};
 use turborepo_repository::package_graph::WorkspaceInfo;
 use turborepo_scm::SCM;
 use turborepo_telemetry::events::{task::PackageTaskEventBuilder, TrackedErrors};
 use turborepo_ui::{
-    color, replay_logs, ColorSelector, LogWriter, PrefixedUI, PrefixedWriter, GREY, UI,
+    color, replay_logs, replay_stderr_logs, ColorSelector, LogWriter, PrefixedUI, PrefixedWriter,
+    GREY, UI,
 };
 
 use crate::{
-    cli::OutputLogsMode,
+    cli::{LogFormat, OutputLogsMode},
     daemon::{DaemonClient, DaemonConnector},
     hash::{FileHashes, TurboHash},
     opts::RunCacheOpts,
@@ -40,10 +48,13 @@ pub enum Error {
     Scm(#[from] turborepo_scm::Error),
     #[error(transparent)]
     Path(#[from] turbopath::PathError),
+    #[error("outputs of {task_id} are no longer in the cache")]
+    DeferredOutputsMissing { task_id: TaskId<'static> },
 }
 
 pub struct RunCache {
     task_output_mode: Option<OutputLogsMode>,
+    log_format: LogFormat,
     cache: AsyncCache,
     reads_disabled: bool,
     writes_disabled: bool,
@@ -51,6 +62,42 @@ pub struct RunCache {
     color_selector: ColorSelector,
     daemon_client: Option<DaemonClient<DaemonConnector>>,
     ui: UI,
+    is_dry_run: bool,
+    // Cache statuses of every task hash, queried before execution starts
+    preflight_statuses: Mutex<HashMap<String, Option<CacheHitMetadata>>>,
+    lazy_restore: bool,
+    // Cache hits whose outputs haven't been written to disk yet
+    deferred_restores: Mutex<HashMap<TaskId<'static>, Arc<DeferredRestore>>>,
+}
+
+// The outputs of a cache hit that only get written to disk once a dependent
+// task executes or the run finishes
+struct DeferredRestore {
+    cache: AsyncCache,
+    hash: String,
+    restored_files: OnceCell<Vec<AnchoredSystemPathBuf>>,
+}
+
+impl DeferredRestore {
+    // Only the first call restores the outputs, later calls wait for it to finish
+    async fn restore(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+        task_id: &TaskId<'static>,
+    ) -> Result<&[AnchoredSystemPathBuf], Error> {
+        let restored_files = self
+            .restored_files
+            .get_or_try_init(|| async {
+                match self.cache.fetch(repo_root, &self.hash).await? {
+                    Some((_, restored_files)) => Ok(restored_files),
+                    None => Err(Error::DeferredOutputsMissing {
+                        task_id: task_id.clone(),
+                    }),
+                }
+            })
+            .await?;
+        Ok(restored_files)
+    }
 }
 
 impl RunCache {
@@ -70,6 +117,7 @@ impl RunCache {
         };
         RunCache {
             task_output_mode,
+            log_format: opts.log_format,
             cache,
             reads_disabled: opts.skip_reads,
             writes_disabled: opts.skip_writes,
@@ -77,9 +125,78 @@ impl RunCache {
             color_selector,
             daemon_client,
             ui,
+            is_dry_run,
+            preflight_statuses: Mutex::new(HashMap::new()),
+            lazy_restore: opts.lazy_restore,
+            deferred_restores: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Why the remote cache was disabled partway through the run, if it was.
+    pub fn remote_cache_disabled_reason(&self) -> Option<String> {
+        self.cache.remote_cache_disabled_reason()
+    }
+
+    pub fn reads_disabled(&self) -> bool {
+        self.reads_disabled
+    }
+
+    /// Whether outputs of cache hits are only restored once they're needed
+    pub fn lazy_restore(&self) -> bool {
+        self.lazy_restore
+    }
+
+    /// Queries the cache status of every given task hash at once so that
+    /// tasks don't each need their own existence check. Unless this is a
+    /// dry run, remote hits start downloading in the background right away.
+    /// Hashes are queried with the cache config of their task's workspace.
+    #[tracing::instrument(skip_all)]
+    pub async fn preflight(&self, hashes: &[(&str, CacheConfig)]) {
+        if self.reads_disabled || hashes.is_empty() {
+            return;
+        }
+
+        let mut hashes_by_config: HashMap<CacheConfig, Vec<&str>> = HashMap::new();
+        for (hash, config) in hashes {
+            hashes_by_config.entry(*config).or_default().push(*hash);
+        }
+
+        let mut statuses = HashMap::with_capacity(hashes.len());
+        for (config, hashes) in hashes_by_config {
+            let cache = self.cache.with_config(config);
+            let config_statuses = match cache.exists_many(&hashes).await {
+                Ok(statuses) => statuses,
+                Err(err) => {
+                    // The per-task checks will still run, so this isn't fatal
+                    debug!("failed to query cache statuses: {err}");
+                    continue;
+                }
+            };
+            if !self.is_dry_run {
+                prefetch_remote_hits(&cache, &config_statuses);
+            }
+            statuses.extend(config_statuses);
+        }
+
+        *self
+            .preflight_statuses
+            .lock()
+            .expect("preflight lock poisoned") = statuses;
+    }
+
+    /// Whether the preflight query found an artifact for the given hash
+    pub fn preflight_hit(&self, hash: &str) -> bool {
+        matches!(self.preflight_status(hash), Some(Some(_)))
+    }
+
+    fn preflight_status(&self, hash: &str) -> Option<Option<CacheHitMetadata>> {
+        self.preflight_statuses
+            .lock()
+            .expect("preflight lock poisoned")
+            .get(hash)
+            .copied()
+    }
+
     pub fn task_cache(
         self: &Arc<Self>,
         // TODO: Group these in a struct
@@ -101,9 +218,13 @@ impl RunCache {
         }
 
         let caching_disabled = !task_definition.cache;
+        // The workspace's cache config can only narrow the caches the run uses
+        let cache_config = task_definition.cache_config;
 
         TaskCache {
             expanded_outputs: Vec::new(),
+            cache: self.cache.with_config(cache_config),
+            cache_config,
             run_cache: self.clone(),
             repo_relative_globs,
             hash: hash.to_owned(),
@@ -116,14 +237,105 @@ impl RunCache {
         }
     }
 
+    fn defer_restore(&self, task_id: TaskId<'static>, cache: AsyncCache, hash: String) {
+        self.deferred_restores
+            .lock()
+            .expect("deferred restores lock poisoned")
+            .insert(
+                task_id,
+                Arc::new(DeferredRestore {
+                    cache,
+                    hash,
+                    restored_files: OnceCell::new(),
+                }),
+            );
+    }
+
+    fn deferred_restores<'a>(
+        &self,
+        task_ids: impl IntoIterator<Item = &'a TaskId<'static>>,
+    ) -> Vec<(TaskId<'static>, Arc<DeferredRestore>)> {
+        let deferred_restores = self
+            .deferred_restores
+            .lock()
+            .expect("deferred restores lock poisoned");
+        task_ids
+            .into_iter()
+            .filter_map(|task_id| {
+                let restore = deferred_restores.get(task_id)?;
+                Some((task_id.clone(), restore.clone()))
+            })
+            .collect()
+    }
+
+    /// Writes the outputs of the given tasks to disk if their restore was
+    /// deferred. Tasks that were restored normally are skipped.
+    pub async fn restore_deferred_outputs<'a>(
+        &self,
+        task_ids: impl IntoIterator<Item = &'a TaskId<'static>>,
+    ) -> Result<(), Error> {
+        let restores = self.deferred_restores(task_ids);
+        try_join_all(
+            restores
+                .iter()
+                .map(|(task_id, restore)| restore.restore(&self.repo_root, task_id)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Writes every output whose restore is still deferred to disk. Returns
+    /// the files restored for each task whose restore was deferred during the
+    /// run, including the ones a dependent already needed.
+    pub async fn finish_deferred_outputs(
+        &self,
+    ) -> Vec<(TaskId<'static>, Result<Vec<AnchoredSystemPathBuf>, Error>)> {
+        let restores = self
+            .deferred_restores
+            .lock()
+            .expect("deferred restores lock poisoned")
+            .iter()
+            .map(|(task_id, restore)| (task_id.clone(), restore.clone()))
+            .collect::<Vec<_>>();
+        join_all(restores.into_iter().map(|(task_id, restore)| async move {
+            let restored_files = restore
+                .restore(&self.repo_root, &task_id)
+                .await
+                .map(|files| files.to_vec());
+            (task_id, restored_files)
+        }))
+        .await
+    }
+
     pub async fn shutdown_cache(&self) {
         // Ignore errors coming from cache already shutting down
         self.cache.shutdown().await.ok();
     }
 }
 
+// Starts downloading the artifacts that were found in the remote cache
+fn prefetch_remote_hits(cache: &AsyncCache, statuses: &HashMap<String, Option<CacheHitMetadata>>) {
+    let remote_hits = statuses
+        .iter()
+        .filter(|(_, status)| {
+            matches!(
+                status,
+                Some(CacheHitMetadata {
+                    source: CacheSource::Remote,
+                    ..
+                })
+            )
+        })
+        .map(|(hash, _)| hash.clone())
+        .collect::<Vec<_>>();
+    debug!("prefetching {} remote cache hits", remote_hits.len());
+    cache.prefetch(remote_hits);
+}
+
 pub struct TaskCache {
     expanded_outputs: Vec<AnchoredSystemPathBuf>,
+    cache: AsyncCache,
+    cache_config: CacheConfig,
     run_cache: Arc<RunCache>,
     repo_relative_globs: TaskOutputs,
     hash: String,
@@ -150,7 +362,10 @@ impl TaskCache {
                 "cache miss, executing {}",
                 color!(self.ui, GREY, "{}", self.hash)
             ));
-            self.replay_log_file(prefixed_ui)?;
+            // Structured logs let us leave out everything but the errors
+            if self.log_file_path.exists() {
+                replay_stderr_logs(prefixed_ui, &self.log_file_path)?;
+            }
         }
 
         Ok(())
@@ -164,12 +379,15 @@ impl TaskCache {
         let mut log_writer = LogWriter::default();
         let prefixed_writer = PrefixedWriter::new(self.run_cache.ui, prefix, writer);
 
-        if self.caching_disabled || self.run_cache.writes_disabled {
+        if self.caching_disabled || self.writes_disabled() {
             log_writer.with_prefixed_writer(prefixed_writer);
             return Ok(log_writer);
         }
 
-        log_writer.with_log_file(&self.log_file_path)?;
+        match self.run_cache.log_format {
+            LogFormat::Raw => log_writer.with_log_file(&self.log_file_path)?,
+            LogFormat::Structured => log_writer.with_structured_log_file(&self.log_file_path)?,
+        }
 
         if !matches!(
             self.task_output_mode,
@@ -181,8 +399,19 @@ impl TaskCache {
         Ok(log_writer)
     }
 
+    fn reads_disabled(&self) -> bool {
+        self.run_cache.reads_disabled || !self.cache_config.can_read()
+    }
+
+    fn writes_disabled(&self) -> bool {
+        self.run_cache.writes_disabled || !self.cache_config.can_write()
+    }
+
     pub async fn exists(&self) -> Result<Option<CacheHitMetadata>, CacheError> {
-        self.run_cache.cache.exists(&self.hash).await
+        if let Some(status) = self.run_cache.preflight_status(&self.hash) {
+            return Ok(status);
+        }
+        self.cache.exists(&self.hash).await
     }
 
     pub async fn restore_outputs(
@@ -190,7 +419,7 @@ impl TaskCache {
         prefixed_ui: &mut PrefixedUI<impl Write>,
         telemetry: &PackageTaskEventBuilder,
     ) -> Result<Option<CacheHitMetadata>, Error> {
-        if self.caching_disabled || self.run_cache.reads_disabled {
+        if self.caching_disabled || self.reads_disabled() {
             if !matches!(
                 self.task_output_mode,
                 OutputLogsMode::None | OutputLogsMode::ErrorsOnly
@@ -228,28 +457,31 @@ impl TaskCache {
         };
 
         let has_changed_outputs = changed_output_count > 0;
+        let defer_restore = has_changed_outputs && self.can_defer_restore();
+
+        let cache_status = if defer_restore {
+            let Some(cache_hit_metadata) = self.exists().await? else {
+                self.on_miss(prefixed_ui);
+                return Ok(None);
+            };
+            self.run_cache.defer_restore(
+                self.task_id.clone(),
+                self.cache.clone(),
+                self.hash.clone(),
+            );
 
-        let cache_status = if has_changed_outputs {
+            Some(cache_hit_metadata)
+        } else if has_changed_outputs {
             // Note that we currently don't use the output globs when restoring, but we
             // could in the future to avoid doing unnecessary file I/O. We also
             // need to pass along the exclusion globs as well.
             let cache_status = self
-                .run_cache
                 .cache
                 .fetch(&self.run_cache.repo_root, &self.hash)
                 .await?;
 
             let Some((cache_hit_metadata, restored_files)) = cache_status else {
-                if !matches!(
-                    self.task_output_mode,
-                    OutputLogsMode::None | OutputLogsMode::ErrorsOnly
-                ) {
-                    prefixed_ui.output(format!(
-                        "cache miss, executing {}",
-                        color!(self.ui, GREY, "{}", self.hash)
-                    ));
-                }
-
+                self.on_miss(prefixed_ui);
                 return Ok(None);
             };
 
@@ -281,7 +513,9 @@ impl TaskCache {
             })
         };
 
-        let more_context = if has_changed_outputs {
+        let more_context = if defer_restore {
+            " (outputs restored when needed)"
+        } else if has_changed_outputs {
             ""
         } else {
             " (outputs already on disk)"
@@ -312,12 +546,30 @@ impl TaskCache {
         Ok(cache_status)
     }
 
+    fn on_miss(&self, prefixed_ui: &mut PrefixedUI<impl Write>) {
+        if !matches!(
+            self.task_output_mode,
+            OutputLogsMode::None | OutputLogsMode::ErrorsOnly
+        ) {
+            prefixed_ui.output(format!(
+                "cache miss, executing {}",
+                color!(self.ui, GREY, "{}", self.hash)
+            ));
+        }
+    }
+
+    // The log file is part of the cached outputs, so outputs can only be
+    // restored later if the logs don't need to be replayed now
+    fn can_defer_restore(&self) -> bool {
+        self.run_cache.lazy_restore && self.task_output_mode != OutputLogsMode::Full
+    }
+
     pub async fn save_outputs(
         &mut self,
         duration: Duration,
         telemetry: &PackageTaskEventBuilder,
     ) -> Result<(), Error> {
-        if self.caching_disabled || self.run_cache.writes_disabled {
+        if self.caching_disabled || self.writes_disabled() {
             return Ok(());
         }
 
@@ -337,8 +589,7 @@ impl TaskCache {
             })
             .collect::<Vec<_>>();
         relative_paths.sort();
-        self.run_cache
-            .cache
+        self.cache
             .put(
                 self.run_cache.repo_root.clone(),
                 self.hash.clone(),
@@ -370,6 +621,32 @@ impl TaskCache {
         Ok(())
     }
 
+    /// Restores outputs that another process put in the cache while this run
+    /// was executing, without replaying logs. Returns false if the outputs
+    /// aren't in the cache.
+    pub async fn fetch_outputs(&mut self) -> Result<bool, Error> {
+        let Some((_, restored_files)) = self
+            .cache
+            .fetch(&self.run_cache.repo_root, &self.hash)
+            .await?
+        else {
+            return Ok(false);
+        };
+        self.expanded_outputs = restored_files;
+
+        Ok(true)
+    }
+
+    /// Whether outputs of this task get saved to the cache
+    pub fn is_cacheable(&self) -> bool {
+        !self.caching_disabled && !self.writes_disabled()
+    }
+
+    /// The caches this task's workspace is allowed to use
+    pub fn cache_config(&self) -> CacheConfig {
+        self.cache_config
+    }
+
     pub fn expanded_outputs(&self) -> &[AnchoredSystemPathBuf] {
         &self.expanded_outputs
     }
@@ -459,3 +736,195 @@ impl ConfigCache {
         Ok(FileHashes(hash_object).hash())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use tempfile::TempDir;
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+    use turborepo_api_client::APIClient;
+    use turborepo_cache::{AsyncCache, CacheOpts};
+    use turborepo_repository::package_graph::WorkspaceInfo;
+    use turborepo_telemetry::events::task::PackageTaskEventBuilder;
+    use turborepo_ui::{ColorSelector, PrefixedUI, UI};
+
+    use super::{Error, RunCache};
+    use crate::{
+        cli::OutputLogsMode, opts::RunCacheOpts, run::task_id::TaskId, task_graph::TaskDefinition,
+    };
+
+    const HASH: &str = "deferred-hash";
+
+    struct Fixture {
+        _dir: TempDir,
+        repo_root: AbsoluteSystemPathBuf,
+        run_cache: Arc<RunCache>,
+        output: AbsoluteSystemPathBuf,
+    }
+
+    // A repo where the outputs of `web#build` are in the local cache, but not
+    // on disk
+    async fn setup() -> Fixture {
+        let dir = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(dir.path()).unwrap();
+        let opts = CacheOpts {
+            skip_remote: true,
+            workers: 10,
+            ..CacheOpts::default()
+        };
+        let api_client = APIClient::new("http://localhost:1", 200, "2.0.0", true).unwrap();
+        let cache = AsyncCache::new(&opts, &repo_root, api_client, None, None).unwrap();
+
+        let output = repo_root.join_components(&["apps", "web", "dist", "out.txt"]);
+        output.ensure_dir().unwrap();
+        output.create_with_contents("built").unwrap();
+        cache
+            .put(
+                repo_root.clone(),
+                HASH.to_string(),
+                vec![AnchoredSystemPathBuf::relative_path_between(
+                    &repo_root, &output,
+                )],
+                100,
+            )
+            .await
+            .unwrap();
+        cache.wait().await.unwrap();
+        output.remove_file().unwrap();
+
+        let opts = RunCacheOpts {
+            task_output_mode_override: Some(OutputLogsMode::None),
+            lazy_restore: true,
+            ..RunCacheOpts::default()
+        };
+        let run_cache = Arc::new(RunCache::new(
+            cache,
+            &repo_root,
+            &opts,
+            ColorSelector::default(),
+            None,
+            UI::new(true),
+            false,
+        ));
+
+        Fixture {
+            _dir: dir,
+            repo_root,
+            run_cache,
+            output,
+        }
+    }
+
+    impl Fixture {
+        // Checks the cache for `web#build`, which defers restoring its outputs
+        async fn defer_web_build(&self) -> TaskId<'static> {
+            let task_id = TaskId::new("web", "build");
+            let workspace_info = WorkspaceInfo {
+                package_json_path: AnchoredSystemPathBuf::relative_path_between(
+                    &self.repo_root,
+                    &self
+                        .repo_root
+                        .join_components(&["apps", "web", "package.json"]),
+                ),
+                ..WorkspaceInfo::default()
+            };
+            let mut task_cache = self.run_cache.task_cache(
+                &TaskDefinition::default(),
+                &workspace_info,
+                task_id.clone(),
+                HASH,
+            );
+
+            let mut prefixed_ui = PrefixedUI::new(UI::new(true), Vec::<u8>::new(), Vec::new());
+            let hit = task_cache
+                .restore_outputs(
+                    &mut prefixed_ui,
+                    &PackageTaskEventBuilder::new("web", "build"),
+                )
+                .await
+                .unwrap();
+            assert!(hit.is_some());
+            // The hit is reported without writing anything
+            assert!(!self.output.exists());
+            assert!(task_cache.expanded_outputs().is_empty());
+
+            task_id
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deferred_outputs_restored_before_dependents() {
+        let fixture = setup().await;
+        let task_id = fixture.defer_web_build().await;
+
+        // Tasks that don't depend on `web#build` don't restore its outputs
+        let other_task_id = TaskId::new("docs", "build");
+        fixture
+            .run_cache
+            .restore_deferred_outputs([&other_task_id])
+            .await
+            .unwrap();
+        assert!(!fixture.output.exists());
+
+        fixture
+            .run_cache
+            .restore_deferred_outputs([&other_task_id, &task_id])
+            .await
+            .unwrap();
+        assert_eq!(fixture.output.read_to_string().unwrap(), "built");
+    }
+
+    #[tokio::test]
+    async fn test_deferred_outputs_restored_at_end_of_run() {
+        let fixture = setup().await;
+        let task_id = fixture.defer_web_build().await;
+        let expected_file =
+            AnchoredSystemPathBuf::relative_path_between(&fixture.repo_root, &fixture.output);
+
+        let finished = fixture.run_cache.finish_deferred_outputs().await;
+        assert_eq!(finished.len(), 1);
+        let (finished_task_id, restored_files) = &finished[0];
+        assert_eq!(finished_task_id, &task_id);
+        assert!(restored_files.as_ref().unwrap().contains(&expected_file));
+        assert_eq!(fixture.output.read_to_string().unwrap(), "built");
+
+        // Outputs that were already restored are still reported, but aren't
+        // written again
+        fixture.output.remove_file().unwrap();
+        let finished = fixture.run_cache.finish_deferred_outputs().await;
+        assert!(finished[0].1.as_ref().unwrap().contains(&expected_file));
+        assert!(!fixture.output.exists());
+    }
+
+    #[tokio::test]
+    async fn test_deferred_outputs_missing() {
+        let fixture = setup().await;
+        let task_id = fixture.defer_web_build().await;
+
+        // The artifact is evicted after the hit was reported
+        fixture
+            .repo_root
+            .join_components(&[
+                "node_modules",
+                ".cache",
+                "turbo",
+                &format!("{HASH}.tar.zst"),
+            ])
+            .remove_file()
+            .unwrap();
+
+        let result = fixture.run_cache.restore_deferred_outputs([&task_id]).await;
+        assert!(matches!(
+            result,
+            Err(Error::DeferredOutputsMissing { task_id: missing }) if missing == task_id
+        ));
+
+        let finished = fixture.run_cache.finish_deferred_outputs().await;
+        assert!(matches!(
+            &finished[..],
+            [(_, Err(Error::DeferredOutputsMissing { .. }))]
+        ));
+        assert!(!fixture.output.exists());
+    }
+}
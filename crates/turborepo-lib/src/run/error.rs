@@ -4,7 +4,7 @@ use turborepo_repository::package_graph;
 
 use super::graph_visualizer;
 use crate::{
-    config, daemon, engine, opts,
+    config, daemon, distributed, engine, opts,
     run::{global_hash, scope},
     task_graph, task_hash,
 };
@@ -47,4 +47,6 @@ pub enum Error {
     Visitor(#[from] task_graph::VisitorError),
     #[error("error registering signal handler: {0}")]
     SignalHandler(std::io::Error),
+    #[error(transparent)]
+    Distributed(#[from] distributed::Error),
 }
@@ -48,6 +48,8 @@ pub struct GlobalHashableInputs<'a> {
     pub env_mode: EnvMode,
     pub framework_inference: bool,
     pub dot_env: Option<&'a [RelativeUnixPathBuf]>,
+    // This is `None` if the root package.json has no `packageManager` field
+    pub package_manager_version: Option<&'a str>,
     pub env_at_execution_start: &'a EnvironmentVariableMap,
 }
 
@@ -56,6 +58,7 @@ pub fn get_global_hash_inputs<'a, L: ?Sized + Lockfile>(
     root_external_dependencies_hash: Option<&'a str>,
     root_path: &AbsoluteSystemPath,
     package_manager: &PackageManager,
+    package_manager_version: Option<&'a str>,
     lockfile: Option<&L>,
     global_file_dependencies: &'a [String],
     env_at_execution_start: &'a EnvironmentVariableMap,
@@ -119,6 +122,7 @@ pub fn get_global_hash_inputs<'a, L: ?Sized + Lockfile>(
         env_mode,
         framework_inference,
         dot_env,
+        package_manager_version,
         env_at_execution_start,
     })
 }
@@ -209,6 +213,7 @@ impl<'a> GlobalHashableInputs<'a> {
             env_mode: self.env_mode,
             framework_inference: self.framework_inference,
             dot_env: self.dot_env.unwrap_or_default(),
+            package_manager: self.package_manager_version,
         };
 
         global_hashable.hash()
@@ -251,6 +256,7 @@ mod tests {
             None,
             &root,
             &PackageManager::Pnpm,
+            None,
             lockfile,
             &file_deps,
             &env_var_map,
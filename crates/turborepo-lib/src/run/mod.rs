@@ -5,7 +5,7 @@ mod error;
 pub(crate) mod global_hash;
 mod graph_visualizer;
 pub(crate) mod package_discovery;
-mod scope;
+pub(crate) mod scope;
 pub(crate) mod summary;
 pub mod task_access;
 pub mod task_id;
@@ -21,16 +21,17 @@ pub use cache::{ConfigCache, RunCache, TaskCache};
 use chrono::{DateTime, Local};
 use itertools::Itertools;
 use rayon::iter::ParallelBridge;
-use tracing::debug;
+use tracing::{debug, warn};
 use turbopath::AnchoredSystemPath;
 use turborepo_analytics::{start_analytics, AnalyticsHandle, AnalyticsSender};
 use turborepo_api_client::{APIAuth, APIClient};
-use turborepo_cache::{AsyncCache, RemoteCacheOpts};
+use turborepo_cache::{AsyncCache, CacheOpts, RemoteCacheOpts};
 use turborepo_ci::Vendor;
-use turborepo_env::EnvironmentVariableMap;
+use turborepo_env::{DotEnvMap, EnvironmentVariableMap, DOT_ENV_MODE_VAR};
 use turborepo_repository::{
     package_graph::{PackageGraph, WorkspaceName},
     package_json::PackageJson,
+    package_manager::{PackageManagerVersion, PackageManagerVersionMismatch},
 };
 use turborepo_scm::SCM;
 use turborepo_telemetry::events::{
@@ -52,18 +53,24 @@ use {
 use self::task_id::TaskName;
 pub use crate::run::error::Error;
 use crate::{
-    cli::{DryRunMode, EnvMode},
+    cli::{DryRunMode, EnvMode, RunOutputFormat},
     commands::CommandBase,
+    config::ConfigurationOptions,
     daemon::DaemonConnector,
+    distributed::WorkerPool,
     engine::{Engine, EngineBuilder},
     opts::Opts,
     process::ProcessManager,
-    run::{global_hash::get_global_hash_inputs, summary::RunTracker, task_access::TaskAccess},
+    run::{
+        global_hash::get_global_hash_inputs,
+        summary::{RunEvents, RunTracker},
+        task_access::TaskAccess,
+    },
     shim::TurboState,
     signal::{SignalHandler, SignalSubscriber},
-    task_graph::Visitor,
+    task_graph::{expand_dot_env_files, Visitor},
     task_hash::{get_external_deps_hash, PackageInputsHashes},
-    turbo_json::TurboJson,
+    turbo_json::{package_graph_builder, TurboJson},
 };
 
 pub struct Run {
@@ -73,28 +80,57 @@ pub struct Run {
     api_auth: Option<APIAuth>,
 }
 
+/// Whether any of `tasks` is defined in the root turbo.json as a root task,
+/// in which case the root workspace has to be part of the run
+pub(crate) fn has_root_task<'a>(
+    root_turbo_json: &TurboJson,
+    tasks: impl IntoIterator<Item = &'a String>,
+) -> bool {
+    tasks.into_iter().any(|task| {
+        let mut task_name = TaskName::from(task.as_str());
+        // If it's not a package task, we convert to a root task
+        if !task_name.is_package_task() {
+            task_name = task_name.into_root_task()
+        }
+
+        root_turbo_json.pipeline.contains_key(&task_name)
+    })
+}
+
+/// Applies the remote cache settings from the config, skipping the remote
+/// cache when the repo isn't linked
+pub(crate) fn configure_remote_cache(
+    cache_opts: &mut CacheOpts,
+    config: &ConfigurationOptions,
+    api_auth: &Option<APIAuth>,
+) {
+    let is_linked = turborepo_api_client::is_linked(api_auth);
+    if !is_linked {
+        cache_opts.skip_remote = true;
+    } else if let Some(enabled) = config.enabled {
+        // We're linked, but if the user has explicitly enabled or disabled, use that
+        // value
+        cache_opts.skip_remote = !enabled;
+    }
+    cache_opts.remote_cache_max_failures = config.max_failures();
+    // Note that we don't currently use the team_id value here. In the future, we
+    // should probably verify that we only use the signature value when the
+    // configured team_id matches the final resolved team_id.
+    let unused_remote_cache_opts_team_id = config.team_id().map(|team_id| team_id.to_string());
+    let signature = config.signature();
+    let encryption = config.encryption();
+    cache_opts.remote_cache_opts = Some(RemoteCacheOpts::new(
+        unused_remote_cache_opts_team_id,
+        signature,
+        encryption,
+    ));
+}
+
 impl Run {
     pub fn new(base: CommandBase, api_auth: Option<APIAuth>) -> Result<Self, Error> {
         let processes = ProcessManager::infer();
         let mut opts: Opts = base.args().try_into()?;
-        let config = base.config()?;
-        let is_linked = turborepo_api_client::is_linked(&api_auth);
-        if !is_linked {
-            opts.cache_opts.skip_remote = true;
-        } else if let Some(enabled) = config.enabled {
-            // We're linked, but if the user has explicitly enabled or disabled, use that
-            // value
-            opts.cache_opts.skip_remote = !enabled;
-        }
-        // Note that we don't currently use the team_id value here. In the future, we
-        // should probably verify that we only use the signature value when the
-        // configured team_id matches the final resolved team_id.
-        let unused_remote_cache_opts_team_id = config.team_id().map(|team_id| team_id.to_string());
-        let signature = config.signature();
-        opts.cache_opts.remote_cache_opts = Some(RemoteCacheOpts::new(
-            unused_remote_cache_opts_team_id,
-            signature,
-        ));
+        configure_remote_cache(&mut opts.cache_opts, base.config()?, &api_auth);
         Ok(Self {
             base,
             processes,
@@ -111,6 +147,32 @@ impl Run {
         });
     }
 
+    // Persistent tasks can be told to e.g. reload by sending SIGUSR1 or SIGUSR2
+    // to turbo, which passes them on to every task spawned to receive them.
+    #[cfg(unix)]
+    fn forward_user_signals(&self) -> tokio::task::JoinHandle<()> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let manager = self.processes.clone();
+        tokio::spawn(async move {
+            let (Ok(mut usr1), Ok(mut usr2)) = (
+                signal(SignalKind::user_defined1()),
+                signal(SignalKind::user_defined2()),
+            ) else {
+                debug!("unable to listen for user signals, they will not be forwarded");
+                return;
+            };
+            loop {
+                let forwarded = tokio::select! {
+                    Some(()) = usr1.recv() => libc::SIGUSR1,
+                    Some(()) = usr2.recv() => libc::SIGUSR2,
+                    else => return,
+                };
+                manager.forward_signal(forwarded).await;
+            }
+        })
+    }
+
     fn targets(&self) -> &[String] {
         self.base.args().get_tasks()
     }
@@ -175,6 +237,8 @@ impl Run {
         if let Some(subscriber) = signal_handler.subscribe() {
             self.connect_process_manager(subscriber);
         }
+        #[cfg(unix)]
+        let forward_user_signals = self.forward_user_signals();
 
         let (analytics_sender, analytics_handle) =
             Self::initialize_analytics(self.api_auth.clone(), api_client.clone()).unzip();
@@ -189,6 +253,9 @@ impl Run {
             )
             .await;
 
+        #[cfg(unix)]
+        forward_user_signals.abort();
+
         if let Some(analytics_handle) = analytics_handle {
             analytics_handle.close_with_timeout().await;
         }
@@ -251,6 +318,7 @@ impl Run {
                     can_kill_server: true,
                     pid_file: self.base.daemon_file_root().join_component("turbod.pid"),
                     sock_file: self.base.daemon_file_root().join_component("turbod.sock"),
+                    repo_root: self.base.repo_root.clone(),
                 };
 
                 match (connector.connect().await, self.opts.run_opts.daemon) {
@@ -279,8 +347,32 @@ impl Run {
             }
         };
 
+        let scm = scm.await.expect("detecting scm panicked");
+        let async_cache = AsyncCache::new(
+            &self.opts.cache_opts,
+            &self.base.repo_root,
+            api_client.clone(),
+            self.api_auth.clone(),
+            analytics_sender,
+        )?;
+
+        // restore config from task access trace if it's enabled
+        let task_access = TaskAccess::new(self.base.repo_root.clone(), async_cache.clone(), &scm);
+        task_access.restore_config().await;
+
+        let root_turbo_json = TurboJson::load(
+            &self.base.repo_root,
+            AnchoredSystemPath::empty(),
+            &root_package_json,
+            is_single_package,
+        )?;
+
+        if self.opts.run_opts.experimental_space_id.is_none() {
+            self.opts.run_opts.experimental_space_id = root_turbo_json.space_id.clone();
+        }
+
         let mut pkg_dep_graph = {
-            let builder = PackageGraph::builder(&self.base.repo_root, root_package_json.clone())
+            let builder = package_graph_builder(&self.base.repo_root, root_package_json.clone())?
                 .with_single_package_mode(self.opts.run_opts.single_package);
 
             #[cfg(feature = "daemon-package-discovery")]
@@ -316,31 +408,22 @@ impl Run {
         repo_telemetry.track_size(pkg_dep_graph.len());
         run_telemetry.track_run_type(self.opts.run_opts.dry_run.is_some());
 
-        let scm = scm.await.expect("detecting scm panicked");
-        let async_cache = AsyncCache::new(
-            &self.opts.cache_opts,
-            &self.base.repo_root,
-            api_client.clone(),
-            self.api_auth.clone(),
-            analytics_sender,
-        )?;
-
-        // restore config from task access trace if it's enabled
-        let task_access = TaskAccess::new(self.base.repo_root.clone(), async_cache.clone(), &scm);
-        task_access.restore_config().await;
+        pkg_dep_graph.validate()?;
 
-        let root_turbo_json = TurboJson::load(
+        let package_manager_version = PackageManagerVersion::detect(
+            pkg_dep_graph.package_manager(),
             &self.base.repo_root,
-            AnchoredSystemPath::empty(),
             &root_package_json,
-            is_single_package,
         )?;
-
-        if self.opts.run_opts.experimental_space_id.is_none() {
-            self.opts.run_opts.experimental_space_id = root_turbo_json.space_id.clone();
+        if let Some(PackageManagerVersionMismatch { pinned, installed }) =
+            package_manager_version.mismatch()
+        {
+            warn!(
+                "package.json pins {pinned} in \"packageManager\" but {installed} is installed. \
+                 Tasks run with {installed} will not share cache with {pinned}."
+            );
         }
-
-        pkg_dep_graph.validate()?;
+        let package_manager_version = package_manager_version.resolved();
 
         let filtered_pkgs = {
             let (mut filtered_pkgs, is_all_packages) = scope::resolve_packages(
@@ -350,19 +433,8 @@ impl Run {
                 &scm,
             )?;
 
-            if is_all_packages {
-                for target in self.targets() {
-                    let mut task_name = TaskName::from(target.as_str());
-                    // If it's not a package task, we convert to a root task
-                    if !task_name.is_package_task() {
-                        task_name = task_name.into_root_task()
-                    }
-
-                    if root_turbo_json.pipeline.contains_key(&task_name) {
-                        filtered_pkgs.insert(WorkspaceName::Root);
-                        break;
-                    }
-                }
+            if is_all_packages && has_root_task(&root_turbo_json, self.targets()) {
+                filtered_pkgs.insert(WorkspaceName::Root);
             };
 
             filtered_pkgs
@@ -371,7 +443,11 @@ impl Run {
         let env_at_execution_start = EnvironmentVariableMap::infer();
         let mut engine = self.build_engine(&pkg_dep_graph, &root_turbo_json, &filtered_pkgs)?;
 
-        if self.opts.run_opts.dry_run.is_none() && self.opts.run_opts.graph.is_none() {
+        let emit_events = self.opts.run_opts.output == RunOutputFormat::Ndjson;
+        if self.opts.run_opts.dry_run.is_none()
+            && self.opts.run_opts.graph.is_none()
+            && !emit_events
+        {
             self.print_run_prelude(&filtered_pkgs);
         }
 
@@ -384,10 +460,17 @@ impl Run {
         let root_external_dependencies_hash =
             is_monorepo.then(|| get_external_deps_hash(&root_workspace.transitive_dependencies));
 
+        let dot_env_mode = env_at_execution_start.get(DOT_ENV_MODE_VAR).cloned();
+        let global_dot_env_files = root_turbo_json
+            .global_dot_env
+            .as_deref()
+            .map(|files| expand_dot_env_files(files, dot_env_mode.as_deref()));
+
         let mut global_hash_inputs = get_global_hash_inputs(
             root_external_dependencies_hash.as_deref(),
             &self.base.repo_root,
             pkg_dep_graph.package_manager(),
+            package_manager_version.as_deref(),
             pkg_dep_graph.lockfile(),
             &root_turbo_json.global_deps,
             &env_at_execution_start,
@@ -395,7 +478,7 @@ impl Run {
             root_turbo_json.global_pass_through_env.as_deref(),
             self.opts.run_opts.env_mode,
             self.opts.run_opts.framework_inference,
-            root_turbo_json.global_dot_env.as_deref(),
+            global_dot_env_files.as_deref(),
             &scm,
         )?;
 
@@ -431,6 +514,15 @@ impl Run {
             global_env_mode = EnvMode::Strict;
         }
 
+        let global_dot_env = DotEnvMap::load(global_dot_env_files.iter().flatten().map(|file| {
+            (
+                self.base
+                    .repo_root
+                    .resolve(&file.to_anchored_system_path_buf()),
+                file.to_string(),
+            )
+        }))?;
+
         let workspaces = pkg_dep_graph.workspaces().collect();
         let package_inputs_hashes = PackageInputsHashes::calculate_file_hashes(
             &scm,
@@ -439,6 +531,8 @@ impl Run {
             engine.task_definitions(),
             &self.base.repo_root,
             &run_telemetry,
+            dot_env_mode.as_deref(),
+            &global_dot_env,
         )?;
 
         if self.opts.run_opts.parallel {
@@ -470,7 +564,7 @@ impl Run {
             env
         };
 
-        let run_tracker = RunTracker::new(
+        let mut run_tracker = RunTracker::new(
             start_at,
             self.opts.synthesize_command(),
             self.opts.scope_opts.pkg_inference_root.as_deref(),
@@ -483,6 +577,9 @@ impl Run {
             Vendor::get_user(),
             &scm,
         );
+        if emit_events {
+            run_tracker.with_events(RunEvents::stdout());
+        }
 
         let mut visitor = Visitor::new(
             pkg_dep_graph.clone(),
@@ -503,6 +600,25 @@ impl Run {
 
         if self.opts.run_opts.dry_run.is_some() {
             visitor.dry_run();
+        } else if !self.opts.run_opts.workers.is_empty() {
+            let workers = WorkerPool::connect(
+                &self.opts.run_opts.workers,
+                self.opts
+                    .run_opts
+                    .worker_token
+                    .as_deref()
+                    .unwrap_or_default(),
+            )
+            .await?;
+            if !emit_events {
+                cprintln!(
+                    self.base.ui,
+                    GREY,
+                    "• Distributing tasks to {} workers",
+                    workers.worker_count()
+                );
+            }
+            visitor.distribute(Arc::new(workers));
         }
 
         // we look for this log line to mark the start of the run
@@ -0,0 +1,169 @@
+//! Reports a finished run to the CI vendor it ran on. Failed tasks become
+//! the vendor's native annotations and the run is written up as a markdown
+//! job summary.
+use std::{
+    env,
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+use chrono::Duration;
+use tracing::warn;
+use turbopath::AnchoredSystemPath;
+use turborepo_ci::{Annotation, JobSummary, TaskFailure, VendorBehavior};
+
+use super::{task::TaskSummary, Error, RunSummary, TurboDuration};
+
+// Enough of the end of a failed task's output to see what went wrong
+const STDERR_TAIL_LINES: usize = 20;
+
+impl<'a> RunSummary<'a> {
+    pub(super) fn report_to_vendor(&self, behavior: &VendorBehavior) {
+        if let Some(error_annotation) = behavior.error_annotation {
+            for task in self.get_failed_tasks() {
+                let task_id = task.task_id.to_string();
+                let error = task
+                    .shared
+                    .execution
+                    .as_ref()
+                    .and_then(|execution| execution.error.as_deref())
+                    .unwrap_or("task failed");
+                let stderr_tail = self.stderr_tail(task);
+                let annotation = error_annotation(&TaskFailure {
+                    task_id: &task_id,
+                    log_file: &task.shared.log_file,
+                    error,
+                    stderr_tail: &stderr_tail,
+                });
+                if let Err(err) = self.annotate(annotation) {
+                    warn!("unable to annotate failure of {task_id}: {err}");
+                }
+            }
+        }
+
+        if let Some(job_summary) = &behavior.job_summary {
+            if let Err(err) = self.write_job_summary(job_summary) {
+                warn!("unable to write job summary: {err}");
+            }
+        }
+    }
+
+    fn stderr_tail(&self, task: &TaskSummary) -> String {
+        // A task can fail before it writes a log
+        let Some(log) = AnchoredSystemPath::new(&task.shared.log_file)
+            .ok()
+            .and_then(|log_file| {
+                turborepo_ui::read_stderr_logs(&self.repo_root.resolve(log_file)).ok()
+            })
+        else {
+            return String::new();
+        };
+        let log = console::strip_ansi_codes(&String::from_utf8_lossy(&log)).into_owned();
+        let lines = log.lines().collect::<Vec<_>>();
+        lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n")
+    }
+
+    fn annotate(&self, annotation: Annotation) -> io::Result<()> {
+        match annotation {
+            // stdout is reserved for run events
+            Annotation::Log(log) if self.events.is_some() => io::stderr().write_all(log.as_bytes()),
+            Annotation::Log(log) => io::stdout().write_all(log.as_bytes()),
+            Annotation::Command {
+                program,
+                args,
+                input,
+            } => {
+                let mut child = Command::new(program)
+                    .args(args)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .spawn()?;
+                child
+                    .stdin
+                    .take()
+                    .expect("stdin is piped")
+                    .write_all(input.as_bytes())?;
+                let status = child.wait()?;
+                if !status.success() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("{program} exited with {status}"),
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn write_job_summary(&self, job_summary: &JobSummary) -> Result<(), Error> {
+        let markdown = self.format_markdown();
+        match job_summary {
+            JobSummary::AppendToFile { env_var } => {
+                // Only set inside of a job step
+                let Ok(path) = env::var(env_var) else {
+                    return Ok(());
+                };
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)?
+                    .write_all(markdown.as_bytes())?;
+            }
+            JobSummary::Upload(upload) => {
+                let path =
+                    self.repo_root
+                        .join_components(&[".turbo", "runs", &format!("{}.md", self.id)]);
+                path.ensure_dir()?;
+                path.create_with_contents(markdown)?;
+                self.annotate(Annotation::Log(upload(path.as_str())))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn format_markdown(&self) -> String {
+        let mut markdown = String::from("## Turborepo run summary\n\n");
+        if let Some(execution) = &self.execution {
+            markdown.push_str(&execution.format_markdown());
+            markdown.push('\n');
+        }
+
+        let mut tasks = self.tasks.iter().collect::<Vec<_>>();
+        tasks.sort_by(|a, b| a.task_id.cmp(&b.task_id));
+        markdown.push_str("| Task | Status | Cache | Duration |\n");
+        markdown.push_str("| --- | --- | --- | --- |\n");
+        for task in tasks {
+            let cache = &task.shared.cache;
+            let cache = match cache.source_name() {
+                Some(source) => format!("{} ({source})", cache.status_name()),
+                None => cache.status_name().to_string(),
+            };
+            let (status, duration) = match &task.shared.execution {
+                None => ("not run".to_string(), String::new()),
+                Some(execution) => {
+                    let status = if execution.stopped.is_some() {
+                        "stopped".to_string()
+                    } else if !execution.is_failure() {
+                        "succeeded".to_string()
+                    } else if let Some(exit_code) = execution.exit_code {
+                        format!("failed ({exit_code})")
+                    } else {
+                        "failed".to_string()
+                    };
+                    let duration = TurboDuration::from(Duration::milliseconds(
+                        execution.end_time - execution.start_time,
+                    ));
+                    (status, duration.to_string())
+                }
+            };
+            let _ = writeln!(
+                markdown,
+                "| `{}` | {status} | {cache} | {duration} |",
+                task.task_id
+            );
+        }
+        markdown
+    }
+}
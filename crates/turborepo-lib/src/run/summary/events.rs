@@ -0,0 +1,330 @@
+//! The machine-readable event stream printed by `turbo run --output=ndjson`.
+//! Each event is a single line of JSON with a `type` and a `timestamp` in
+//! milliseconds since the unix epoch.
+
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+use chrono::Local;
+use serde::{Serialize, Serializer};
+use tracing::debug;
+use turborepo_cache::CacheSource;
+use turborepo_ui::{LogStream, StreamWrite};
+
+use super::execution::{TaskExecutionSummary, TaskStop};
+use crate::run::task_id::TaskId;
+
+/// Emits run events as newline delimited JSON. Clones share the same writer,
+/// so events from concurrent tasks are never interleaved mid-line.
+#[derive(Clone)]
+pub struct RunEvents {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl fmt::Debug for RunEvents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RunEvents").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    timestamp: i64,
+    #[serde(flatten)]
+    event: RunEvent<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum RunEvent<'a> {
+    #[serde(rename_all = "camelCase")]
+    RunStart { command: &'a str },
+    #[serde(rename_all = "camelCase")]
+    TaskQueued {
+        #[serde(serialize_with = "display")]
+        task_id: &'a TaskId<'a>,
+    },
+    #[serde(rename_all = "camelCase")]
+    TaskStarted {
+        #[serde(serialize_with = "display")]
+        task_id: &'a TaskId<'a>,
+    },
+    #[serde(rename_all = "camelCase")]
+    CacheHit {
+        #[serde(serialize_with = "display")]
+        task_id: &'a TaskId<'a>,
+        source: &'static str,
+    },
+    #[serde(rename_all = "camelCase")]
+    CacheMiss {
+        #[serde(serialize_with = "display")]
+        task_id: &'a TaskId<'a>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Log {
+        #[serde(serialize_with = "display")]
+        task_id: &'a TaskId<'a>,
+        stream: LogStream,
+        line: &'a str,
+    },
+    #[serde(rename_all = "camelCase")]
+    TaskFinished {
+        #[serde(serialize_with = "display")]
+        task_id: &'a TaskId<'a>,
+        exit_code: Option<i32>,
+        duration_ms: i64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        stopped: Option<TaskStop>,
+    },
+    #[serde(rename_all = "camelCase")]
+    RunFinished {
+        exit_code: i32,
+        attempted: usize,
+        success: usize,
+        failed: usize,
+        cached: usize,
+    },
+}
+
+fn display<T: fmt::Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+impl RunEvents {
+    pub fn stdout() -> Self {
+        Self::new(io::stdout())
+    }
+
+    fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Arc::new(Mutex::new(Box::new(writer))),
+        }
+    }
+
+    fn emit(&self, event: RunEvent) {
+        let record = Record {
+            timestamp: Local::now().timestamp_millis(),
+            event,
+        };
+        let mut writer = self.writer.lock().expect("lock poisoned");
+        // A consumer that stopped reading shouldn't fail the run
+        let result: io::Result<()> = try {
+            serde_json::to_writer(&mut *writer, &record)?;
+            writer.write_all(b"\n")?;
+            writer.flush()?;
+        };
+        if let Err(e) = result {
+            debug!("unable to write run event: {e}");
+        }
+    }
+
+    pub fn run_start(&self, command: &str) {
+        self.emit(RunEvent::RunStart { command });
+    }
+
+    pub fn task_queued(&self, task_id: &TaskId) {
+        self.emit(RunEvent::TaskQueued { task_id });
+    }
+
+    pub fn task_started(&self, task_id: &TaskId) {
+        self.emit(RunEvent::TaskStarted { task_id });
+    }
+
+    pub fn cache_hit(&self, task_id: &TaskId, source: CacheSource) {
+        let source = match source {
+            CacheSource::Local => "local",
+            CacheSource::Remote => "remote",
+        };
+        self.emit(RunEvent::CacheHit { task_id, source });
+    }
+
+    pub fn cache_miss(&self, task_id: &TaskId) {
+        self.emit(RunEvent::CacheMiss { task_id });
+    }
+
+    pub fn task_finished(&self, task_id: &TaskId, execution: &TaskExecutionSummary) {
+        self.emit(RunEvent::TaskFinished {
+            task_id,
+            exit_code: execution.exit_code,
+            duration_ms: execution.end_time - execution.start_time,
+            error: execution.error.as_deref(),
+            stopped: execution.stopped,
+        });
+    }
+
+    pub(super) fn run_finished(
+        &self,
+        exit_code: i32,
+        attempted: usize,
+        success: usize,
+        failed: usize,
+        cached: usize,
+    ) {
+        self.emit(RunEvent::RunFinished {
+            exit_code,
+            attempted,
+            success,
+            failed,
+            cached,
+        });
+    }
+
+    /// Wraps the writer that receives a task's output so that every line is
+    /// also emitted as a log event.
+    pub fn task_logs<W: StreamWrite>(&self, task_id: TaskId<'static>, writer: W) -> TaskLogs<W> {
+        TaskLogs {
+            events: self.clone(),
+            task_id,
+            writer,
+        }
+    }
+}
+
+/// Forwards a task's output to its writer while emitting a log event for each
+/// line.
+pub struct TaskLogs<W> {
+    events: RunEvents,
+    task_id: TaskId<'static>,
+    writer: W,
+}
+
+impl<W> TaskLogs<W> {
+    fn emit_lines(&self, stream: LogStream, buf: &[u8]) {
+        let output = String::from_utf8_lossy(buf);
+        for line in output.lines() {
+            self.events.emit(RunEvent::Log {
+                task_id: &self.task_id,
+                stream,
+                line,
+            });
+        }
+    }
+}
+
+impl<W: Write> Write for TaskLogs<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.emit_lines(LogStream::Stdout, &buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<W: StreamWrite> StreamWrite for TaskLogs<W> {
+    fn write_stream(&mut self, stream: LogStream, buf: &[u8]) -> io::Result<()> {
+        self.writer.write_stream(stream, buf)?;
+        self.emit_lines(stream, buf);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Output {
+        // the parsed events, without their timestamps
+        fn events(&self) -> Vec<Value> {
+            let output = self.0.lock().unwrap();
+            std::str::from_utf8(&output)
+                .unwrap()
+                .lines()
+                .map(|line| {
+                    let mut event: Value = serde_json::from_str(line).unwrap();
+                    let timestamp = event.as_object_mut().unwrap().remove("timestamp");
+                    assert!(timestamp.unwrap().is_i64());
+                    event
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_task_events() {
+        let output = Output::default();
+        let events = RunEvents::new(output.clone());
+        let task_id = TaskId::new("web", "build");
+
+        events.task_queued(&task_id);
+        events.cache_hit(&task_id, CacheSource::Remote);
+        events.task_finished(
+            &task_id,
+            &TaskExecutionSummary {
+                start_time: 100,
+                end_time: 150,
+                exit_code: Some(1),
+                error: Some("exit code 1".into()),
+                stopped: None,
+            },
+        );
+        events.run_finished(1, 1, 0, 1, 0);
+
+        assert_eq!(
+            output.events(),
+            vec![
+                json!({ "type": "taskQueued", "taskId": "web#build" }),
+                json!({ "type": "cacheHit", "taskId": "web#build", "source": "remote" }),
+                json!({
+                    "type": "taskFinished",
+                    "taskId": "web#build",
+                    "exitCode": 1,
+                    "durationMs": 50,
+                    "error": "exit code 1",
+                }),
+                json!({
+                    "type": "runFinished",
+                    "exitCode": 1,
+                    "attempted": 1,
+                    "success": 0,
+                    "failed": 1,
+                    "cached": 0,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_task_logs() {
+        let output = Output::default();
+        let events = RunEvents::new(output.clone());
+        let mut task_output = Vec::new();
+        let mut logs = events.task_logs(TaskId::new("web", "dev"), &mut task_output);
+
+        logs.write_stream(LogStream::Stdout, b"ready\n").unwrap();
+        logs.write_stream(LogStream::Stderr, b"warn 1\nwarn 2\n")
+            .unwrap();
+
+        assert_eq!(task_output, b"ready\nwarn 1\nwarn 2\n");
+        assert_eq!(
+            output.events(),
+            vec![
+                json!({ "type": "log", "taskId": "web#dev", "stream": "stdout", "line": "ready" }),
+                json!({ "type": "log", "taskId": "web#dev", "stream": "stderr", "line": "warn 1" }),
+                json!({ "type": "log", "taskId": "web#dev", "stream": "stderr", "line": "warn 2" }),
+            ]
+        );
+    }
+}
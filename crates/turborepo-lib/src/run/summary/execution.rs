@@ -1,13 +1,16 @@
-use std::fmt;
+use std::{collections::HashMap, fmt};
 
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Duration, Local};
 use serde::Serialize;
 use tokio::sync::mpsc;
 use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath};
 use turborepo_ui::{color, cprintln, BOLD, BOLD_GREEN, BOLD_RED, MAGENTA, UI, YELLOW};
 
-use super::TurboDuration;
-use crate::run::{summary::task::TaskSummary, task_id::TaskId};
+use super::{events::RunEvents, TurboDuration};
+use crate::{
+    engine::Engine,
+    run::{summary::task::TaskSummary, task_id::TaskId},
+};
 
 // Just used to make changing the type that gets passed to the state management
 // thread easy
@@ -20,13 +23,14 @@ pub struct ExecutionTracker {
     // this thread handles the state management
     state_thread: tokio::task::JoinHandle<SummaryState>,
     sender: mpsc::Sender<Message>,
+    events: Option<RunEvents>,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExecutionSummary<'a> {
     // a synthesized turbo command to produce this invocation
-    command: String,
+    pub(crate) command: String,
     // the (possibly empty) path from the turborepo root to where the command was run
     #[serde(rename = "repoPath")]
     repo_path: &'a AnchoredSystemPath,
@@ -43,6 +47,58 @@ pub struct ExecutionSummary<'a> {
     #[serde(skip)]
     duration: TurboDuration,
     pub(crate) exit_code: i32,
+    // why the remote cache stopped being used during the run, if it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remote_cache_disabled_reason: Option<String>,
+    // the longest chain of dependent tasks, which bounds how fast the run could be
+    #[serde(skip_serializing_if = "Option::is_none")]
+    critical_path: Option<CriticalPathSummary>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CriticalPathSummary {
+    tasks: Vec<String>,
+    // combined duration of the tasks on the critical path in milliseconds
+    duration: u64,
+    // how many tasks were running at once on average
+    achieved_parallelism: f64,
+    // how many tasks would have been running at once on average if the run
+    // had only taken as long as its critical path
+    possible_parallelism: f64,
+}
+
+impl CriticalPathSummary {
+    /// Returns `None` if no tasks were executed
+    pub fn new(
+        engine: &Engine,
+        task_durations: &HashMap<TaskId<'static>, u64>,
+        run_duration: u64,
+        single_package: bool,
+    ) -> Option<Self> {
+        if task_durations.is_empty() {
+            return None;
+        }
+        let (tasks, duration) = engine.critical_path(task_durations);
+
+        let total_task_time = task_durations.values().sum::<u64>() as f64;
+        // Rounded to keep the summary readable
+        let parallelism =
+            |duration: u64| (total_task_time / duration.max(1) as f64 * 100.0).round() / 100.0;
+
+        Some(Self {
+            tasks: tasks
+                .into_iter()
+                .map(|task_id| match single_package {
+                    true => task_id.task().to_string(),
+                    false => task_id.to_string(),
+                })
+                .collect(),
+            duration,
+            achieved_parallelism: parallelism(run_duration),
+            possible_parallelism: parallelism(duration),
+        })
+    }
 }
 
 impl<'a> ExecutionSummary<'a> {
@@ -53,6 +109,8 @@ impl<'a> ExecutionSummary<'a> {
         exit_code: i32,
         start_time: DateTime<Local>,
         end_time: DateTime<Local>,
+        remote_cache_disabled_reason: Option<String>,
+        critical_path: Option<CriticalPathSummary>,
     ) -> Self {
         let duration = TurboDuration::new(&start_time, &end_time);
         Self {
@@ -67,6 +125,8 @@ impl<'a> ExecutionSummary<'a> {
             end_time: end_time.timestamp_millis(),
             duration,
             exit_code,
+            remote_cache_disabled_reason,
+            critical_path,
         }
     }
 
@@ -112,6 +172,36 @@ impl<'a> ExecutionSummary<'a> {
 
         if path.exists() {
             line_data.push(("Summary", path.to_string()));
+
+            // A single task on the critical path has nothing to compare against
+            if let Some(critical_path) = self
+                .critical_path
+                .as_ref()
+                .filter(|critical_path| critical_path.tasks.len() > 1)
+            {
+                line_data.push((
+                    "Critical path",
+                    format!(
+                        "{} ({})",
+                        critical_path.tasks.join(" → "),
+                        TurboDuration::from(Duration::milliseconds(critical_path.duration as i64))
+                    ),
+                ));
+                line_data.push((
+                    "Parallelism",
+                    format!(
+                        "{}x of {}x possible",
+                        critical_path.achieved_parallelism, critical_path.possible_parallelism
+                    ),
+                ));
+            }
+        }
+
+        if let Some(reason) = &self.remote_cache_disabled_reason {
+            line_data.push((
+                "Remote cache",
+                color!(ui, YELLOW, "disabled, {}", reason).to_string(),
+            ));
         }
 
         if !failed_tasks.is_empty() {
@@ -159,6 +249,34 @@ impl<'a> ExecutionSummary<'a> {
     fn successful(&self) -> usize {
         self.success + self.cached
     }
+
+    /// The totals as markdown, for CI job summaries
+    pub(super) fn format_markdown(&self) -> String {
+        let mut markdown = format!("`{}`\n\n", self.command);
+        markdown.push_str("| Tasks | Cached | Failed | Time |\n");
+        markdown.push_str("| --- | --- | --- | --- |\n");
+        markdown.push_str(&format!(
+            "| {} successful, {} total | {} cached, {} total | {} | {} |\n",
+            self.successful(),
+            self.attempted,
+            self.cached,
+            self.attempted,
+            self.failed,
+            self.duration
+        ));
+        markdown
+    }
+
+    /// Reports the totals as the final event of the run
+    pub(super) fn emit_finished(&self, events: &RunEvents) {
+        events.run_finished(
+            self.exit_code,
+            self.attempted,
+            self.success,
+            self.failed,
+            self.cached,
+        );
+    }
 }
 
 /// The final states of all task executions
@@ -195,6 +313,7 @@ pub struct TaskTracker<T> {
     sender: mpsc::Sender<Message>,
     started_at: T,
     task_id: TaskId<'static>,
+    events: Option<RunEvents>,
 }
 
 #[derive(Debug, Clone)]
@@ -222,10 +341,28 @@ pub struct TaskExecutionSummary {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
     pub exit_code: Option<i32>,
+    // set if the task was shut down by turbo instead of exiting on its own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stopped: Option<TaskStop>,
+}
+
+/// How a task that turbo shut down came to exit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskStop {
+    // exited within its grace period after receiving its stop signal
+    Interrupted,
+    // had to be killed after its grace period elapsed
+    Killed,
 }
 
 impl TaskExecutionSummary {
     pub fn is_failure(&self) -> bool {
+        // A task that was shut down didn't fail, the run was stopped
+        // around it.
+        if self.stopped.is_some() {
+            return false;
+        }
         // We consider None as a failure as it indicates the task failed to start
         // or was killed in a manner where we didn't collect an exit code.
         !matches!(self.exit_code, Some(0))
@@ -255,15 +392,25 @@ impl ExecutionTracker {
         Self {
             state_thread,
             sender,
+            events: None,
         }
     }
 
+    // Report task events as they happen in addition to recording them
+    pub fn set_events(&mut self, events: RunEvents) {
+        self.events = Some(events);
+    }
+
     // Produce a tracker for the task
     pub fn task_tracker(&self, task_id: TaskId<'static>) -> TaskTracker<()> {
+        if let Some(events) = &self.events {
+            events.task_queued(&task_id);
+        }
         TaskTracker {
             sender: self.sender.clone(),
             task_id,
             started_at: (),
+            events: self.events.clone(),
         }
     }
 
@@ -288,9 +435,15 @@ impl TaskTracker<()> {
     // Start the tracker
     pub async fn start(self) -> TaskTracker<DateTime<Local>> {
         let TaskTracker {
-            sender, task_id, ..
+            sender,
+            task_id,
+            events,
+            ..
         } = self;
         let started_at = Local::now();
+        if let Some(events) = &events {
+            events.task_started(&task_id);
+        }
         sender
             .send(TrackerMessage {
                 event: Event::Building,
@@ -302,6 +455,7 @@ impl TaskTracker<()> {
             sender,
             started_at,
             task_id,
+            events,
         }
     }
 
@@ -329,11 +483,48 @@ impl TaskTracker<chrono::DateTime<Local>> {
     // internal turbo error
     pub fn cancel(self) {}
 
+    // Unlike a cancellation the task was running when turbo shut it down, so
+    // we record how it exited
+    pub async fn stopped(self, how: TaskStop) -> TaskExecutionSummary {
+        let Self {
+            sender,
+            started_at,
+            task_id,
+            events,
+        } = self;
+
+        let ended_at = Local::now();
+        let execution = TaskExecutionSummary {
+            start_time: started_at.timestamp_millis(),
+            end_time: ended_at.timestamp_millis(),
+            exit_code: None,
+            error: None,
+            stopped: Some(how),
+        };
+
+        if let Some(events) = &events {
+            events.task_finished(&task_id, &execution);
+        }
+        let state = TaskState {
+            task_id,
+            execution: Some(execution.clone()),
+        };
+        sender
+            .send(TrackerMessage {
+                event: Event::Canceled,
+                state: Some(state),
+            })
+            .await
+            .expect("summary state thread finished");
+        execution
+    }
+
     pub async fn cached(self) -> TaskExecutionSummary {
         let Self {
             sender,
             started_at,
             task_id,
+            events,
         } = self;
 
         let ended_at = Local::now();
@@ -343,8 +534,12 @@ impl TaskTracker<chrono::DateTime<Local>> {
             // Go synthesizes a zero exit code on cache hits
             exit_code: Some(0),
             error: None,
+            stopped: None,
         };
 
+        if let Some(events) = &events {
+            events.task_finished(&task_id, &execution);
+        }
         let state = TaskState {
             task_id,
             execution: Some(execution.clone()),
@@ -364,6 +559,7 @@ impl TaskTracker<chrono::DateTime<Local>> {
             sender,
             started_at,
             task_id,
+            events,
         } = self;
 
         let ended_at = Local::now();
@@ -372,8 +568,12 @@ impl TaskTracker<chrono::DateTime<Local>> {
             end_time: ended_at.timestamp_millis(),
             exit_code: Some(exit_code),
             error: None,
+            stopped: None,
         };
 
+        if let Some(events) = &events {
+            events.task_finished(&task_id, &execution);
+        }
         let state = TaskState {
             task_id,
             execution: Some(execution.clone()),
@@ -397,6 +597,7 @@ impl TaskTracker<chrono::DateTime<Local>> {
             sender,
             started_at,
             task_id,
+            events,
         } = self;
 
         let ended_at = Local::now();
@@ -405,8 +606,12 @@ impl TaskTracker<chrono::DateTime<Local>> {
             end_time: ended_at.timestamp_millis(),
             exit_code,
             error: Some(error.to_string()),
+            stopped: None,
         };
 
+        if let Some(events) = &events {
+            events.task_finished(&task_id, &execution);
+        }
         let state = TaskState {
             task_id,
             execution: Some(execution.clone()),
@@ -437,6 +642,7 @@ mod test {
         let bar = TaskId::new("bar", "build");
         let baz = TaskId::new("baz", "build");
         let boo = TaskId::new("boo", "build");
+        let qux = TaskId::new("qux", "dev");
         let mut tasks = Vec::new();
         {
             let tracker = summary.task_tracker(foo.clone());
@@ -466,12 +672,19 @@ mod test {
                 tracker.cancel();
             }));
         }
+        {
+            let tracker = summary.task_tracker(qux.clone());
+            tasks.push(tokio::spawn(async move {
+                let tracker = tracker.start().await;
+                tracker.stopped(TaskStop::Interrupted).await;
+            }));
+        }
         for task in tasks {
             task.await.unwrap();
         }
 
         let state = summary.finish().await.unwrap();
-        assert_eq!(state.attempted, 4);
+        assert_eq!(state.attempted, 5);
         assert_eq!(state.cached, 1);
         assert_eq!(state.failed, 1);
         assert_eq!(state.success, 1);
@@ -486,6 +699,10 @@ mod test {
             boo_state.is_none(),
             "canceling doesn't produce execution data"
         );
+        let qux_state = state.tasks.iter().find(|task| task.task_id == qux).unwrap();
+        let qux_execution = qux_state.execution.as_ref().unwrap();
+        assert_eq!(qux_execution.stopped, Some(TaskStop::Interrupted));
+        assert!(!qux_execution.is_failure());
     }
 
     #[tokio::test]
@@ -518,7 +735,8 @@ mod test {
             start_time: 123,
             end_time: 234,
             exit_code: Some(0),
-            error: None
+            error: None,
+            stopped: None,
         },
         json!({ "startTime": 123, "endTime": 234, "exitCode": 0 })
         ; "success"
@@ -529,10 +747,22 @@ mod test {
             end_time: 234,
             exit_code: Some(1),
             error: Some("cannot find anything".into()),
+            stopped: None,
         },
         json!({ "startTime": 123, "endTime": 234, "exitCode": 1, "error": "cannot find anything" })
         ; "failure"
     )]
+    #[test_case(
+        TaskExecutionSummary {
+            start_time: 123,
+            end_time: 234,
+            exit_code: None,
+            error: None,
+            stopped: Some(TaskStop::Killed),
+        },
+        json!({ "startTime": 123, "endTime": 234, "exitCode": null, "stopped": "killed" })
+        ; "stopped"
+    )]
     fn test_serialization(value: impl serde::Serialize, expected: serde_json::Value) {
         assert_eq!(serde_json::to_value(value).unwrap(), expected);
     }
@@ -0,0 +1,179 @@
+//! Reads task durations back out of the run summaries saved by previous
+//! `--summarize` runs so the engine can schedule long chains of tasks first.
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use tracing::debug;
+use turbopath::AbsoluteSystemPath;
+use turborepo_repository::package_graph::ROOT_PKG_NAME;
+
+use crate::run::task_id::TaskId;
+
+// Only the most recent summaries are read, older durations are likely stale
+const MAX_SUMMARIES: usize = 10;
+
+#[derive(Deserialize)]
+struct PastRun {
+    #[serde(default)]
+    tasks: Vec<PastTask>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PastTask {
+    task_id: String,
+    cache: PastCache,
+    execution: Option<PastExecution>,
+}
+
+#[derive(Deserialize)]
+struct PastCache {
+    status: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PastExecution {
+    start_time: i64,
+    end_time: i64,
+    exit_code: Option<i32>,
+}
+
+/// How long each task took in milliseconds the last time it ran to
+/// completion without hitting the cache. Summaries that can't be read are
+/// skipped, this is only used as an estimate.
+pub fn historical_task_durations(repo_root: &AbsoluteSystemPath) -> HashMap<TaskId<'static>, u64> {
+    let runs_dir = repo_root.join_components(&[".turbo", "runs"]);
+    let Ok(entries) = std::fs::read_dir(runs_dir.as_std_path()) else {
+        return HashMap::new();
+    };
+
+    // Summaries are named by their KSUID, which sorts by creation time
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "json")
+        })
+        .collect::<Vec<_>>();
+    paths.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut durations = HashMap::new();
+    for path in paths.into_iter().take(MAX_SUMMARIES) {
+        let run = match std::fs::read(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                serde_json::from_slice::<PastRun>(&contents).map_err(|err| err.to_string())
+            }) {
+            Ok(run) => run,
+            Err(err) => {
+                debug!("skipping run summary {}: {err}", path.display());
+                continue;
+            }
+        };
+
+        for task in run.tasks {
+            let Some(duration) = task.duration() else {
+                continue;
+            };
+            // Newer summaries are read first, so they take precedence
+            durations.entry(task.id()).or_insert(duration);
+        }
+    }
+
+    durations
+}
+
+impl PastTask {
+    fn duration(&self) -> Option<u64> {
+        let execution = self.execution.as_ref()?;
+        if self.cache.status != "MISS" || execution.exit_code != Some(0) {
+            return None;
+        }
+
+        u64::try_from(execution.end_time - execution.start_time).ok()
+    }
+
+    fn id(&self) -> TaskId<'static> {
+        match TaskId::try_from(self.task_id.as_str()) {
+            Ok(task_id) => task_id.into_owned(),
+            // Single package summaries leave the package out of the task id
+            Err(_) => TaskId::new(ROOT_PKG_NAME, &self.task_id).into_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use tempdir::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+
+    fn task(task_id: &str, status: &str, duration: i64, exit_code: i32) -> serde_json::Value {
+        json!({
+            "taskId": task_id,
+            "cache": { "status": status },
+            "execution": { "startTime": 1000, "endTime": 1000 + duration, "exitCode": exit_code },
+        })
+    }
+
+    #[test]
+    fn test_historical_task_durations() {
+        let dir = TempDir::new("history").unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(dir.path()).unwrap();
+        let runs_dir = repo_root.join_components(&[".turbo", "runs"]);
+        runs_dir.create_dir_all().unwrap();
+
+        runs_dir
+            .join_component("2ZAAAA.json")
+            .create_with_contents(
+                json!({ "tasks": [
+                    task("web#build", "MISS", 100, 0),
+                    task("docs#build", "MISS", 200, 0),
+                    task("docs#test", "MISS", 300, 0),
+                ] })
+                .to_string(),
+            )
+            .unwrap();
+        runs_dir
+            .join_component("2ZBBBB.json")
+            .create_with_contents(
+                json!({ "tasks": [
+                    task("web#build", "MISS", 150, 0),
+                    task("docs#build", "HIT", 1, 0),
+                    task("docs#test", "MISS", 10, 1),
+                    task("lint", "MISS", 50, 0),
+                ] })
+                .to_string(),
+            )
+            .unwrap();
+        runs_dir
+            .join_component("2ZCCCC.html")
+            .create_with_contents("<html></html>")
+            .unwrap();
+        runs_dir
+            .join_component("2ZDDDD.json")
+            .create_with_contents("{")
+            .unwrap();
+
+        assert_eq!(
+            historical_task_durations(&repo_root),
+            HashMap::from([
+                (TaskId::new("web", "build"), 150),
+                (TaskId::new("docs", "build"), 200),
+                (TaskId::new("docs", "test"), 300),
+                (TaskId::new("//", "lint"), 50),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_no_history() {
+        let dir = TempDir::new("history").unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(dir.path()).unwrap();
+
+        assert!(historical_task_durations(&repo_root).is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+//! Renders a run summary as a single, self-contained HTML file with a Gantt
+//! chart of task execution. Everything the page needs, including the task
+//! logs, is inlined so the report can be opened offline or attached to CI
+//! artifacts.
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use turbopath::AnchoredSystemPath;
+
+use super::{Error, RunSummary};
+
+const REPORT_DATA_PLACEHOLDER: &str = "__TURBO_RUN_REPORT_DATA__";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportData<'a, 'b> {
+    summary: &'b RunSummary<'a>,
+    logs: BTreeMap<String, String>,
+}
+
+impl<'a> RunSummary<'a> {
+    pub(super) fn format_html(&mut self) -> Result<String, Error> {
+        self.normalize();
+
+        // Logs are best effort, a task might not have produced any
+        let logs = self
+            .tasks
+            .iter()
+            .filter_map(|task| {
+                let log_file = AnchoredSystemPath::new(&task.shared.log_file).ok()?;
+                let log = turborepo_ui::read_logs(&self.repo_root.resolve(log_file)).ok()?;
+                Some((
+                    task.task_id.to_string(),
+                    console::strip_ansi_codes(&String::from_utf8_lossy(&log)).into_owned(),
+                ))
+            })
+            .collect();
+
+        render_report(&ReportData {
+            summary: self,
+            logs,
+        })
+    }
+}
+
+fn render_report(data: &ReportData) -> Result<String, Error> {
+    let data = serde_json::to_string(data)?
+        // Keep logs containing `</script>` from ending the data block early
+        .replace("</", "<\\/");
+
+    Ok(REPORT_TEMPLATE.replace(REPORT_DATA_PLACEHOLDER, &data))
+}
+
+const REPORT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>Turborepo Run Report</title>
+  <style>
+    body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; margin: 0; color: #111; }
+    header { padding: 16px 24px; border-bottom: 1px solid #ddd; }
+    header h1 { font-size: 18px; margin: 0 0 8px; }
+    header code { background: #f4f4f4; padding: 2px 6px; border-radius: 4px; }
+    .stats span { margin-right: 16px; color: #555; }
+    .legend { padding: 8px 24px; font-size: 12px; color: #555; }
+    .legend i { display: inline-block; width: 12px; height: 12px; border-radius: 2px; margin: 0 4px 0 12px; vertical-align: middle; }
+    main { display: flex; align-items: flex-start; }
+    #chart { flex: 3; padding: 8px 24px; overflow-x: auto; }
+    #details { flex: 2; padding: 8px 24px; border-left: 1px solid #ddd; min-height: 100vh; overflow-x: auto; }
+    .row { display: flex; align-items: center; height: 24px; cursor: pointer; }
+    .row:hover, .row.selected { background: #f0f4ff; }
+    .label { width: 260px; flex-shrink: 0; font-size: 12px; white-space: nowrap; overflow: hidden; text-overflow: ellipsis; }
+    .track { position: relative; flex: 1; height: 16px; }
+    .bar { position: absolute; height: 16px; min-width: 2px; border-radius: 3px; }
+    .miss { background: #3b82f6; }
+    .hit-local { background: #22c55e; }
+    .hit-remote { background: #14b8a6; }
+    .failed { background: #ef4444; }
+    .critical { outline: 2px solid #f59e0b; outline-offset: 1px; }
+    #details h2 { font-size: 16px; }
+    #details table { border-collapse: collapse; font-size: 12px; width: 100%; }
+    #details td { border-bottom: 1px solid #eee; padding: 2px 8px 2px 0; vertical-align: top; word-break: break-all; }
+    #details pre { background: #111; color: #eee; padding: 8px; font-size: 12px; overflow-x: auto; white-space: pre-wrap; }
+  </style>
+</head>
+<body>
+  <header>
+    <h1>Turborepo Run Report</h1>
+    <div><code id="command"></code></div>
+    <div class="stats" id="stats"></div>
+  </header>
+  <div class="legend">
+    <i class="miss"></i>cache miss<i class="hit-local"></i>local cache hit<i class="hit-remote"></i>remote cache hit<i class="failed"></i>failed<i class="critical" style="background: #fff"></i>critical path
+  </div>
+  <main>
+    <div id="chart"></div>
+    <div id="details"><p>Select a task to see its details.</p></div>
+  </main>
+  <script type="application/json" id="report-data">__TURBO_RUN_REPORT_DATA__</script>
+  <script>
+    const data = JSON.parse(document.getElementById("report-data").textContent);
+    const summary = data.summary;
+    const execution = summary.execution || { startTime: 0, endTime: 0 };
+    const criticalPath = execution.criticalPath || { tasks: [] };
+    const criticalTasks = new Set(criticalPath.tasks);
+    const runDuration = Math.max(execution.endTime - execution.startTime, 1);
+    const tasks = summary.tasks
+      .filter((task) => task.execution)
+      .sort((a, b) => a.execution.startTime - b.execution.startTime || a.taskId.localeCompare(b.taskId));
+
+    const el = (tag, props = {}, children = []) => {
+      const node = Object.assign(document.createElement(tag), props);
+      node.append(...children);
+      return node;
+    };
+    const formatDuration = (ms) => (ms < 1000 ? `${ms}ms` : `${(ms / 1000).toFixed(2)}s`);
+    const statusClass = (task) => {
+      if (task.execution.exitCode !== 0) return "failed";
+      if (task.cache.status === "HIT") return task.cache.source === "REMOTE" ? "hit-remote" : "hit-local";
+      return "miss";
+    };
+
+    document.getElementById("command").textContent = execution.command || "turbo run";
+    document.getElementById("stats").append(
+      el("span", { textContent: `${tasks.length} tasks` }),
+      el("span", { textContent: `${execution.cached || 0} cached` }),
+      el("span", { textContent: `${execution.failed || 0} failed` }),
+      el("span", { textContent: `duration ${formatDuration(execution.endTime - execution.startTime)}` }),
+      el("span", { textContent: `critical path ${criticalPath.tasks.join(" → ") || "n/a"}` }),
+      el("span", {
+        textContent: criticalPath.possibleParallelism
+          ? `parallelism ${criticalPath.achievedParallelism}x of ${criticalPath.possibleParallelism}x possible`
+          : "",
+      })
+    );
+
+    const table = (rows) =>
+      el("table", {}, rows.map(([key, value]) => el("tr", {}, [el("td", { textContent: key }), el("td", { textContent: value })])));
+
+    const showDetails = (task, row) => {
+      document.querySelectorAll(".row.selected").forEach((selected) => selected.classList.remove("selected"));
+      row.classList.add("selected");
+      const env = task.environmentVariables;
+      const details = document.getElementById("details");
+      details.replaceChildren(
+        el("h2", { textContent: task.taskId }),
+        table([
+          ["Hash", task.hash],
+          ["Command", task.command],
+          ["Cache", task.cache.status + (task.cache.source ? ` (${task.cache.source.toLowerCase()})` : "")],
+          ["Time saved", formatDuration(task.cache.timeSaved)],
+          ["Duration", formatDuration(task.execution.endTime - task.execution.startTime)],
+          ["Exit code", String(task.execution.exitCode)],
+          ["Error", task.execution.error || ""],
+          ["Dependencies", task.dependencies.join(", ")],
+          ["Dependents", task.dependents.join(", ")],
+        ]),
+        el("h3", { textContent: "Environment variables" }),
+        table([
+          ["Specified", env.specified.env.join(", ")],
+          ["Configured", env.configured.join(", ")],
+          ["Inferred", env.inferred.join(", ")],
+          ["Passthrough", (env.passthrough || []).join(", ")],
+        ]),
+        el("h3", { textContent: `Inputs (${Object.keys(task.inputs).length})` }),
+        table(Object.entries(task.inputs)),
+        el("h3", { textContent: "Logs" }),
+        el("pre", { textContent: data.logs[task.taskId] || "No logs" })
+      );
+    };
+
+    const chart = document.getElementById("chart");
+    for (const task of tasks) {
+      const left = ((task.execution.startTime - execution.startTime) / runDuration) * 100;
+      const width = ((task.execution.endTime - task.execution.startTime) / runDuration) * 100;
+      const bar = el("div", {
+        className: `bar ${statusClass(task)}${criticalTasks.has(task.taskId) ? " critical" : ""}`,
+        title: `${task.taskId}: ${formatDuration(task.execution.endTime - task.execution.startTime)}`,
+      });
+      bar.style.left = `${left}%`;
+      bar.style.width = `${width}%`;
+      const row = el("div", { className: "row" }, [
+        el("div", { className: "label", textContent: task.taskId }),
+        el("div", { className: "track" }, [bar]),
+      ]);
+      row.addEventListener("click", () => showDetails(task, row));
+      chart.append(row);
+    }
+  </script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod test {
+    use svix_ksuid::{Ksuid, KsuidLike};
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+    use crate::{
+        cli::SummarizeMode,
+        run::summary::{
+            global_hash::{GlobalEnvConfiguration, GlobalEnvVarSummary, GlobalHashSummary},
+            EnvMode, RunType,
+        },
+    };
+
+    const DATA_BLOCK_START: &str = r#"<script type="application/json" id="report-data">"#;
+
+    #[test]
+    fn test_log_cannot_end_data_block() -> Result<(), Error> {
+        let repo_root = AbsoluteSystemPathBuf::cwd().unwrap();
+        let summary = RunSummary {
+            id: Ksuid::new(None, None),
+            version: "1".to_string(),
+            turbo_version: "1.0.0",
+            monorepo: true,
+            global_hash_summary: GlobalHashSummary {
+                root_key: "root key",
+                files: Default::default(),
+                hash_of_external_dependencies: "",
+                global_dot_env: None,
+                environment_variables: GlobalEnvVarSummary {
+                    specified: GlobalEnvConfiguration {
+                        env: &[],
+                        pass_through_env: None,
+                    },
+                    configured: None,
+                    inferred: None,
+                    pass_through: None,
+                },
+            },
+            execution: None,
+            packages: Vec::new(),
+            env_mode: EnvMode::Strict,
+            framework_inference: true,
+            tasks: Vec::new(),
+            user: "user".to_string(),
+            scm: serde_json::from_value(serde_json::json!({ "type": "git" }))?,
+            repo_root: &repo_root,
+            summarize_mode: SummarizeMode::Html,
+            run_type: RunType::Real,
+            spaces_client_handle: None,
+            events: None,
+        };
+        let log = "<script>alert(1)</script>\n</SCRIPT> done";
+
+        let html = render_report(&ReportData {
+            summary: &summary,
+            logs: [("web#build".to_string(), log.to_string())].into(),
+        })?;
+
+        assert!(!html.contains(REPORT_DATA_PLACEHOLDER));
+        // The data block runs until the first `</script>`, so it must hold
+        // the whole report
+        let (_, rest) = html.split_once(DATA_BLOCK_START).unwrap();
+        let (data, rest) = rest.split_once("</script>").unwrap();
+        assert!(!data.contains("</"));
+        assert!(rest.trim_start().starts_with("<script>"));
+
+        let data: serde_json::Value = serde_json::from_str(data)?;
+        assert_eq!(data["logs"]["web#build"], log);
+        assert_eq!(data["summary"]["turboVersion"], "1.0.0");
+
+        Ok(())
+    }
+}
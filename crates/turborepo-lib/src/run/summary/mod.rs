@@ -3,20 +3,31 @@
 //! A tracker tracks the live data and then gets turned into a summary for
 //! displaying it We have this split because the tracker representation is not
 //! exactly what we want to display to the user.
+mod ci;
 #[allow(dead_code)]
 mod duration;
+mod events;
 mod execution;
 mod global_hash;
+mod history;
+mod html;
+mod otel;
 mod scm;
 mod spaces;
 mod task;
 mod task_factory;
-use std::{collections::HashSet, io, io::Write};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    io::Write,
+};
 
 use chrono::{DateTime, Local};
 pub use duration::TurboDuration;
-pub use execution::{TaskExecutionSummary, TaskTracker};
+pub use events::RunEvents;
+pub use execution::{TaskExecutionSummary, TaskStop, TaskTracker};
 pub use global_hash::GlobalHashSummary;
+pub use history::historical_task_durations;
 use itertools::Itertools;
 use serde::Serialize;
 pub use spaces::{SpacesTaskClient, SpacesTaskInformation};
@@ -26,7 +37,8 @@ use thiserror::Error;
 use tracing::{error, log::warn};
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath};
 use turborepo_api_client::{spaces::CreateSpaceRunPayload, APIAuth, APIClient};
-use turborepo_env::EnvironmentVariableMap;
+use turborepo_ci::Vendor;
+use turborepo_env::{EnvironmentVariableMap, ResolvedEnvMode};
 use turborepo_repository::package_graph::{PackageGraph, WorkspaceName};
 use turborepo_scm::SCM;
 use turborepo_ui::{color, cprintln, cwriteln, BOLD, BOLD_CYAN, GREY, UI};
@@ -37,11 +49,12 @@ use self::{
 use super::task_id::TaskId;
 use crate::{
     cli,
-    cli::DryRunMode,
+    cli::{DryRunMode, SummarizeMode},
     engine::Engine,
     opts::RunOpts,
     run::summary::{
-        execution::{ExecutionSummary, ExecutionTracker},
+        execution::{CriticalPathSummary, ExecutionSummary, ExecutionTracker},
+        otel::OtlpExporter,
         scm::SCMState,
         spaces::{SpaceRequest, SpacesClient, SpacesClientHandle},
         task::TaskSummary,
@@ -69,6 +82,8 @@ pub enum Error {
     Env(#[source] turborepo_env::Error),
     #[error("failed to construct task summary: {0}")]
     TaskSummary(#[from] task_factory::Error),
+    #[error("failed to export run trace: {0}")]
+    OtlpExport(#[from] reqwest::Error),
 }
 
 // NOTE: When changing this, please ensure that the server side is updated to
@@ -103,6 +118,15 @@ impl From<cli::EnvMode> for EnvMode {
     }
 }
 
+impl From<ResolvedEnvMode> for EnvMode {
+    fn from(env_mode: ResolvedEnvMode) -> Self {
+        match env_mode {
+            ResolvedEnvMode::Loose => EnvMode::Loose,
+            ResolvedEnvMode::Strict => EnvMode::Strict,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RunSummary<'a> {
@@ -123,11 +147,13 @@ pub struct RunSummary<'a> {
     #[serde(skip)]
     repo_root: &'a AbsoluteSystemPath,
     #[serde(skip)]
-    should_save: bool,
+    summarize_mode: SummarizeMode,
     #[serde(skip)]
     run_type: RunType,
     #[serde(skip)]
     spaces_client_handle: Option<SpacesClientHandle>,
+    #[serde(skip)]
+    events: Option<RunEvents>,
 }
 
 /// We use this to track the run, so it's constructed before the run.
@@ -140,6 +166,8 @@ pub struct RunTracker {
     spaces_client_handle: Option<SpacesClientHandle>,
     user: String,
     synthesized_command: String,
+    remote_cache_disabled_reason: Option<String>,
+    events: Option<RunEvents>,
 }
 
 impl RunTracker {
@@ -183,9 +211,29 @@ impl RunTracker {
             user,
             synthesized_command,
             spaces_client_handle,
+            remote_cache_disabled_reason: None,
+            events: None,
         }
     }
 
+    /// Reports the run as it happens through `events` instead of printing a
+    /// summary at the end.
+    pub fn with_events(&mut self, events: RunEvents) {
+        events.run_start(&self.synthesized_command);
+        self.execution_tracker.set_events(events.clone());
+        self.events = Some(events);
+    }
+
+    pub fn events(&self) -> Option<&RunEvents> {
+        self.events.as_ref()
+    }
+
+    /// Records that the remote cache was disabled partway through the run so
+    /// it's reported in the summary.
+    pub fn remote_cache_disabled(&mut self, reason: String) {
+        self.remote_cache_disabled_reason = Some(reason);
+    }
+
     #[allow(clippy::too_many_arguments)]
     #[tracing::instrument(skip(
         repo_root,
@@ -193,6 +241,7 @@ impl RunTracker {
         run_opts,
         packages,
         global_hash_summary,
+        engine,
         task_factory,
     ))]
     pub async fn to_summary<'a>(
@@ -205,10 +254,14 @@ impl RunTracker {
         packages: HashSet<WorkspaceName>,
         global_hash_summary: GlobalHashSummary<'a>,
         global_env_mode: EnvMode,
+        engine: &'a Engine,
         task_factory: TaskSummaryFactory<'a>,
     ) -> Result<RunSummary<'a>, Error> {
         let single_package = run_opts.single_package;
-        let should_save = run_opts.summarize.flatten().is_some_and(|s| s);
+        let summarize_mode = run_opts
+            .summarize
+            .flatten()
+            .unwrap_or(SummarizeMode::Disabled);
 
         let run_type = match run_opts.dry_run {
             None => RunType::Real,
@@ -224,6 +277,21 @@ impl RunTracker {
             .cloned()
             .map(|TaskState { task_id, execution }| task_factory.task_summary(task_id, execution))
             .collect::<Result<Vec<_>, task_factory::Error>>()?;
+        let task_durations = summary_state
+            .tasks
+            .iter()
+            .filter_map(|TaskState { task_id, execution }| {
+                let execution = execution.as_ref()?;
+                let duration = u64::try_from(execution.end_time - execution.start_time).ok()?;
+                Some((task_id.clone(), duration))
+            })
+            .collect::<HashMap<_, _>>();
+        let critical_path = CriticalPathSummary::new(
+            engine,
+            &task_durations,
+            u64::try_from((end_time - self.started_at).num_milliseconds()).unwrap_or_default(),
+            single_package,
+        );
         let execution_summary = ExecutionSummary::new(
             self.synthesized_command.clone(),
             summary_state,
@@ -231,6 +299,8 @@ impl RunTracker {
             exit_code,
             self.started_at,
             end_time,
+            self.remote_cache_disabled_reason,
+            critical_path,
         );
 
         Ok(RunSummary {
@@ -247,9 +317,10 @@ impl RunTracker {
             user: self.user,
             monorepo: !single_package,
             repo_root,
-            should_save,
+            summarize_mode,
             run_type,
             spaces_client_handle: self.spaces_client_handle,
+            events: self.events,
         })
     }
 
@@ -300,6 +371,7 @@ impl RunTracker {
                 packages,
                 global_hash_summary,
                 global_env_mode.into(),
+                engine,
                 task_factory,
             )
             .await?;
@@ -385,16 +457,33 @@ impl<'a> RunSummary<'a> {
             return self.close_dry_run(pkg_dep_graph, ui);
         }
 
-        if self.should_save {
+        if self.summarize_mode != SummarizeMode::Disabled {
             if let Err(err) = self.save() {
                 warn!("Error writing run summary: {}", err)
             }
         }
 
         if let Some(execution) = &self.execution {
-            let path = self.get_path();
-            let failed_tasks = self.get_failed_tasks();
-            execution.print(ui, path, failed_tasks);
+            if let Some(events) = &self.events {
+                execution.emit_finished(events);
+            } else {
+                let path = self.get_path();
+                let failed_tasks = self.get_failed_tasks();
+                execution.print(ui, path, failed_tasks);
+            }
+        }
+
+        if let Some(behavior) = Vendor::infer().and_then(|vendor| vendor.behavior.as_ref()) {
+            self.report_to_vendor(behavior);
+        }
+
+        if let Some(exporter) = OtlpExporter::from_env() {
+            if let Some(trace) = self.to_trace() {
+                // Failing to export the trace shouldn't fail the run
+                if let Err(err) = exporter.export(&trace).await {
+                    warn!("Error exporting run trace: {}", err);
+                }
+            }
         }
 
         if let Some(spaces_client_handle) = self.spaces_client_handle.take() {
@@ -779,7 +868,11 @@ impl<'a> RunSummary<'a> {
     }
 
     fn get_path(&self) -> AbsoluteSystemPathBuf {
-        let filename = format!("{}.json", self.id);
+        let extension = match self.summarize_mode {
+            SummarizeMode::Html => "html",
+            SummarizeMode::Json | SummarizeMode::Disabled => "json",
+        };
+        let filename = format!("{}.{extension}", self.id);
 
         self.repo_root
             .join_components(&[".turbo", "runs", &filename])
@@ -798,11 +891,14 @@ impl<'a> RunSummary<'a> {
     }
 
     fn save(&mut self) -> Result<(), Error> {
-        let json = self.format_json()?;
+        let contents = match self.summarize_mode {
+            SummarizeMode::Html => self.format_html()?,
+            SummarizeMode::Json | SummarizeMode::Disabled => self.format_json()?,
+        };
 
         let summary_path = self.get_path();
         summary_path.ensure_dir()?;
 
-        Ok(summary_path.create_with_contents(json)?)
+        Ok(summary_path.create_with_contents(contents)?)
     }
 }
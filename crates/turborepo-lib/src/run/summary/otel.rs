@@ -0,0 +1,547 @@
+//! Exports a finished run as an OpenTelemetry trace, so `turbo run` shows up
+//! in an existing observability stack. The run is a single root span with a
+//! child span per task. Each task span links to the spans of the tasks it
+//! depends on.
+//!
+//! Traces are sent with OTLP over HTTP using the JSON encoding, configured
+//! with the standard `OTEL_EXPORTER_OTLP_*` environment variables.
+use std::{collections::HashMap, env, time::Duration};
+
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use super::{Error, RunSummary};
+
+const TRACES_PATH: &str = "/v1/traces";
+const DEFAULT_SERVICE_NAME: &str = "turbo";
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+const SCOPE_NAME: &str = "turborepo";
+
+// https://opentelemetry.io/docs/specs/otel/trace/api/#spankind
+const SPAN_KIND_INTERNAL: u8 = 1;
+// https://opentelemetry.io/docs/specs/otel/trace/api/#set-status
+const STATUS_CODE_OK: u8 = 1;
+const STATUS_CODE_ERROR: u8 = 2;
+
+pub struct OtlpExporter {
+    client: reqwest::Client,
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    service_name: String,
+}
+
+/// The parts of a run summary that end up in the exported trace
+#[derive(Debug, Clone)]
+pub struct RunTrace {
+    pub run_id: String,
+    pub command: String,
+    pub turbo_version: String,
+    pub exit_code: i32,
+    // Milliseconds since the epoch
+    pub start_time: i64,
+    pub end_time: i64,
+    pub scm_branch: Option<String>,
+    pub scm_sha: Option<String>,
+    pub tasks: Vec<TaskTrace>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskTrace {
+    pub task_id: String,
+    pub task: String,
+    pub package: String,
+    pub hash: String,
+    pub cache_status: &'static str,
+    pub cache_source: Option<&'static str>,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+    // Milliseconds since the epoch
+    pub start_time: i64,
+    pub end_time: i64,
+    pub dependencies: Vec<String>,
+}
+
+impl OtlpExporter {
+    /// Creates an exporter if an OTLP endpoint is configured in the
+    /// environment.
+    pub fn from_env() -> Option<Self> {
+        Self::from_env_vars(|key| env::var(key).ok().filter(|value| !value.is_empty()))
+    }
+
+    fn from_env_vars(var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let endpoint = var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT").or_else(|| {
+            var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                .map(|endpoint| format!("{}{TRACES_PATH}", endpoint.trim_end_matches('/')))
+        })?;
+
+        let protocol = var("OTEL_EXPORTER_OTLP_TRACES_PROTOCOL")
+            .or_else(|| var("OTEL_EXPORTER_OTLP_PROTOCOL"));
+        if protocol.as_deref() == Some("grpc") {
+            warn!("OTLP over gRPC is not supported, run traces will not be exported");
+            return None;
+        }
+
+        let headers = var("OTEL_EXPORTER_OTLP_TRACES_HEADERS")
+            .or_else(|| var("OTEL_EXPORTER_OTLP_HEADERS"))
+            .map(|headers| parse_headers(&headers))
+            .unwrap_or_default();
+
+        Some(Self {
+            client: reqwest::Client::new(),
+            endpoint,
+            headers,
+            service_name: var("OTEL_SERVICE_NAME")
+                .unwrap_or_else(|| DEFAULT_SERVICE_NAME.to_string()),
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn export(&self, trace: &RunTrace) -> Result<(), Error> {
+        let request = self.build_request(trace);
+
+        let mut request_builder = self
+            .client
+            .post(&self.endpoint)
+            .timeout(EXPORT_TIMEOUT)
+            .json(&request);
+        for (key, value) in &self.headers {
+            request_builder = request_builder.header(key, value);
+        }
+
+        request_builder.send().await?.error_for_status()?;
+        debug!("exported run trace to {}", self.endpoint);
+
+        Ok(())
+    }
+
+    fn build_request(&self, trace: &RunTrace) -> ExportTraceServiceRequest {
+        let trace_id = hex::encode(rand::random::<[u8; 16]>());
+        let run_span_id = new_span_id();
+        let task_span_ids: HashMap<&str, String> = trace
+            .tasks
+            .iter()
+            .map(|task| (task.task_id.as_str(), new_span_id()))
+            .collect();
+
+        let mut run_attributes = vec![
+            KeyValue::string("turbo.run.id", &trace.run_id),
+            KeyValue::string("turbo.run.command", &trace.command),
+            KeyValue::int("turbo.run.exit_code", trace.exit_code.into()),
+        ];
+        if let Some(branch) = &trace.scm_branch {
+            run_attributes.push(KeyValue::string("vcs.branch", branch));
+        }
+        if let Some(sha) = &trace.scm_sha {
+            run_attributes.push(KeyValue::string("vcs.sha", sha));
+        }
+
+        let mut spans = vec![Span {
+            trace_id: trace_id.clone(),
+            span_id: run_span_id.clone(),
+            parent_span_id: None,
+            name: "turbo run".to_string(),
+            kind: SPAN_KIND_INTERNAL,
+            start_time_unix_nano: millis_to_nanos(trace.start_time),
+            end_time_unix_nano: millis_to_nanos(trace.end_time),
+            attributes: run_attributes,
+            links: Vec::new(),
+            status: Status::from_success(trace.exit_code == 0),
+        }];
+
+        for task in &trace.tasks {
+            let mut attributes = vec![
+                KeyValue::string("turbo.task.id", &task.task_id),
+                KeyValue::string("turbo.task.name", &task.task),
+                KeyValue::string("turbo.task.package", &task.package),
+                KeyValue::string("turbo.task.hash", &task.hash),
+                KeyValue::string("turbo.task.cache.status", task.cache_status),
+            ];
+            if let Some(source) = task.cache_source {
+                attributes.push(KeyValue::string("turbo.task.cache.source", source));
+            }
+            if let Some(exit_code) = task.exit_code {
+                attributes.push(KeyValue::int("turbo.task.exit_code", exit_code.into()));
+            }
+            if let Some(error) = &task.error {
+                attributes.push(KeyValue::string("turbo.task.error", error));
+            }
+
+            // Dependencies that weren't part of the run don't have a span to link to
+            let links = task
+                .dependencies
+                .iter()
+                .filter_map(|dependency| task_span_ids.get(dependency.as_str()))
+                .map(|span_id| Link {
+                    trace_id: trace_id.clone(),
+                    span_id: span_id.clone(),
+                    attributes: vec![KeyValue::string("turbo.link.type", "dependency")],
+                })
+                .collect();
+
+            spans.push(Span {
+                trace_id: trace_id.clone(),
+                span_id: task_span_ids[task.task_id.as_str()].clone(),
+                parent_span_id: Some(run_span_id.clone()),
+                name: task.task_id.clone(),
+                kind: SPAN_KIND_INTERNAL,
+                start_time_unix_nano: millis_to_nanos(task.start_time),
+                end_time_unix_nano: millis_to_nanos(task.end_time),
+                attributes,
+                links,
+                status: Status::from_success(task.exit_code == Some(0)),
+            });
+        }
+
+        ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Resource {
+                    attributes: vec![
+                        KeyValue::string("service.name", &self.service_name),
+                        KeyValue::string("service.version", &trace.turbo_version),
+                    ],
+                },
+                scope_spans: vec![ScopeSpans {
+                    scope: InstrumentationScope {
+                        name: SCOPE_NAME.to_string(),
+                        version: trace.turbo_version.clone(),
+                    },
+                    spans,
+                }],
+            }],
+        }
+    }
+}
+
+impl<'a> RunSummary<'a> {
+    /// Returns `None` for runs that didn't execute anything
+    pub(super) fn to_trace(&self) -> Option<RunTrace> {
+        let execution = self.execution.as_ref()?;
+        let tasks = self
+            .tasks
+            .iter()
+            .filter_map(|task| {
+                let task_execution = task.shared.execution.as_ref()?;
+                Some(TaskTrace {
+                    task_id: task.task_id.to_string(),
+                    task: task.task.clone(),
+                    package: task.package.clone(),
+                    hash: task.shared.hash.clone(),
+                    cache_status: task.shared.cache.status_name(),
+                    cache_source: task.shared.cache.source_name(),
+                    exit_code: task_execution.exit_code,
+                    error: task_execution.error.clone(),
+                    start_time: task_execution.start_time,
+                    end_time: task_execution.end_time,
+                    dependencies: task
+                        .shared
+                        .dependencies
+                        .iter()
+                        .map(|dependency| dependency.to_string())
+                        .collect(),
+                })
+            })
+            .collect();
+
+        Some(RunTrace {
+            run_id: self.id.to_string(),
+            command: execution.command.clone(),
+            turbo_version: self.turbo_version.to_string(),
+            exit_code: execution.exit_code,
+            start_time: execution.start_time,
+            end_time: execution.end_time,
+            scm_branch: self.scm.branch.clone(),
+            scm_sha: self.scm.sha.clone(),
+            tasks,
+        })
+    }
+}
+
+// Parses the `key1=value1,key2=value2` format used by
+// `OTEL_EXPORTER_OTLP_HEADERS`
+fn parse_headers(headers: &str) -> Vec<(String, String)> {
+    headers
+        .split(',')
+        .filter_map(|header| {
+            let (key, value) = header.split_once('=')?;
+            Some((key.trim().to_string(), value.trim().to_string()))
+        })
+        .filter(|(key, _)| !key.is_empty())
+        .collect()
+}
+
+fn new_span_id() -> String {
+    hex::encode(rand::random::<[u8; 8]>())
+}
+
+// OTLP/JSON encodes 64 bit integers as strings
+fn millis_to_nanos(millis: i64) -> String {
+    (i128::from(millis) * 1_000_000).to_string()
+}
+
+// The types below mirror the OTLP protobuf messages, following the protobuf
+// JSON mapping: https://opentelemetry.io/docs/specs/otlp/#json-protobuf-encoding
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportTraceServiceRequest {
+    resource_spans: Vec<ResourceSpans>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceSpans {
+    resource: Resource,
+    scope_spans: Vec<ScopeSpans>,
+}
+
+#[derive(Debug, Serialize)]
+struct Resource {
+    attributes: Vec<KeyValue>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScopeSpans {
+    scope: InstrumentationScope,
+    spans: Vec<Span>,
+}
+
+#[derive(Debug, Serialize)]
+struct InstrumentationScope {
+    name: String,
+    version: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Span {
+    trace_id: String,
+    span_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_span_id: Option<String>,
+    name: String,
+    kind: u8,
+    start_time_unix_nano: String,
+    end_time_unix_nano: String,
+    attributes: Vec<KeyValue>,
+    links: Vec<Link>,
+    status: Status,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Link {
+    trace_id: String,
+    span_id: String,
+    attributes: Vec<KeyValue>,
+}
+
+#[derive(Debug, Serialize)]
+struct Status {
+    code: u8,
+}
+
+impl Status {
+    fn from_success(success: bool) -> Self {
+        Self {
+            code: if success {
+                STATUS_CODE_OK
+            } else {
+                STATUS_CODE_ERROR
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct KeyValue {
+    key: &'static str,
+    value: AnyValue,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum AnyValue {
+    StringValue(String),
+    IntValue(String),
+}
+
+impl KeyValue {
+    fn string(key: &'static str, value: impl Into<String>) -> Self {
+        Self {
+            key,
+            value: AnyValue::StringValue(value.into()),
+        }
+    }
+
+    fn int(key: &'static str, value: i64) -> Self {
+        Self {
+            key,
+            value: AnyValue::IntValue(value.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, net::SocketAddr};
+
+    use axum::{routing::post, Json, Router};
+    use serde_json::Value;
+    use tokio::sync::mpsc;
+
+    use super::{parse_headers, OtlpExporter, RunTrace, TaskTrace};
+
+    fn task(task_id: &str, dependencies: &[&str], exit_code: i32) -> TaskTrace {
+        let (package, task) = task_id.split_once('#').unwrap();
+        TaskTrace {
+            task_id: task_id.to_string(),
+            task: task.to_string(),
+            package: package.to_string(),
+            hash: format!("{package}-hash"),
+            cache_status: "MISS",
+            cache_source: None,
+            exit_code: Some(exit_code),
+            error: None,
+            start_time: 1_700_000_000_100,
+            end_time: 1_700_000_000_200,
+            dependencies: dependencies.iter().map(|dep| dep.to_string()).collect(),
+        }
+    }
+
+    fn attribute<'a>(span: &'a Value, key: &str) -> Option<&'a Value> {
+        span["attributes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|attribute| attribute["key"] == key)
+            .map(|attribute| &attribute["value"])
+    }
+
+    #[test]
+    fn test_endpoint_from_env() {
+        let env = HashMap::from([
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://localhost:4318/"),
+            (
+                "OTEL_EXPORTER_OTLP_HEADERS",
+                "x-api-key=secret, x-team = turbo",
+            ),
+        ]);
+        let exporter =
+            OtlpExporter::from_env_vars(|key| env.get(key).map(|value| value.to_string())).unwrap();
+        assert_eq!(exporter.endpoint, "http://localhost:4318/v1/traces");
+        assert_eq!(exporter.service_name, "turbo");
+        assert_eq!(
+            exporter.headers,
+            vec![
+                ("x-api-key".to_string(), "secret".to_string()),
+                ("x-team".to_string(), "turbo".to_string())
+            ]
+        );
+
+        // The signal specific endpoint is used as is
+        let env = HashMap::from([
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://localhost:4318"),
+            (
+                "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+                "http://collector/traces",
+            ),
+        ]);
+        let exporter =
+            OtlpExporter::from_env_vars(|key| env.get(key).map(|value| value.to_string())).unwrap();
+        assert_eq!(exporter.endpoint, "http://collector/traces");
+
+        let env = HashMap::from([
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://localhost:4317"),
+            ("OTEL_EXPORTER_OTLP_PROTOCOL", "grpc"),
+        ]);
+        assert!(
+            OtlpExporter::from_env_vars(|key| env.get(key).map(|value| value.to_string()))
+                .is_none()
+        );
+        assert!(OtlpExporter::from_env_vars(|_| None).is_none());
+    }
+
+    #[test]
+    fn test_parse_headers() {
+        assert_eq!(
+            parse_headers("a=1,b=x=y,,=c,d"),
+            vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "x=y".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_to_collector() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/v1/traces",
+            post(move |Json(body): Json<Value>| {
+                tx.send(body).unwrap();
+                async { Json(serde_json::json!({})) }
+            }),
+        );
+        let port = port_scanner::request_open_port().unwrap();
+        let addr = SocketAddr::from(([127, 0, 0, 1], port));
+        let server = tokio::spawn(axum_server::bind(addr).serve(app.into_make_service()));
+
+        let exporter = OtlpExporter::from_env_vars(|key| {
+            (key == "OTEL_EXPORTER_OTLP_ENDPOINT").then(|| format!("http://{addr}"))
+        })
+        .unwrap();
+        let trace = RunTrace {
+            run_id: "run-id".to_string(),
+            command: "turbo run build".to_string(),
+            turbo_version: "1.0.0".to_string(),
+            exit_code: 1,
+            start_time: 1_700_000_000_000,
+            end_time: 1_700_000_001_000,
+            scm_branch: Some("main".to_string()),
+            scm_sha: None,
+            tasks: vec![
+                task("lib#build", &[], 0),
+                task("app#build", &["lib#build", "missing#build"], 1),
+            ],
+        };
+        exporter.export(&trace).await.unwrap();
+
+        let body = rx.recv().await.unwrap();
+        server.abort();
+
+        let resource_spans = &body["resourceSpans"][0];
+        assert_eq!(
+            attribute(&resource_spans["resource"], "service.name").unwrap()["stringValue"],
+            "turbo"
+        );
+        let spans = resource_spans["scopeSpans"][0]["spans"].as_array().unwrap();
+        assert_eq!(spans.len(), 3);
+
+        let run_span = &spans[0];
+        assert_eq!(run_span["name"], "turbo run");
+        assert_eq!(run_span["startTimeUnixNano"], "1700000000000000000");
+        assert_eq!(run_span["status"]["code"], 2);
+        assert!(run_span.get("parentSpanId").is_none());
+
+        let lib_span = &spans[1];
+        let app_span = &spans[2];
+        for span in [lib_span, app_span] {
+            assert_eq!(span["traceId"], run_span["traceId"]);
+            assert_eq!(span["parentSpanId"], run_span["spanId"]);
+        }
+        assert_eq!(
+            attribute(app_span, "turbo.task.package").unwrap()["stringValue"],
+            "app"
+        );
+        assert_eq!(
+            attribute(app_span, "turbo.task.exit_code").unwrap()["intValue"],
+            "1"
+        );
+        assert_eq!(lib_span["status"]["code"], 1);
+
+        // Only dependencies that ran are linked
+        let links = app_span["links"].as_array().unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0]["spanId"], lib_span["spanId"]);
+    }
+}
@@ -2,12 +2,12 @@ use std::collections::BTreeMap;
 
 use serde::Serialize;
 use turbopath::{AnchoredSystemPathBuf, RelativeUnixPathBuf};
-use turborepo_cache::CacheHitMetadata;
-use turborepo_env::{DetailedMap, EnvironmentVariableMap};
+use turborepo_cache::{CacheConfig, CacheHitMetadata};
+use turborepo_env::{DetailedMap, DotEnvMap, EnvironmentVariableMap};
 
 use super::{execution::TaskExecutionSummary, EnvMode};
 use crate::{
-    cli::OutputLogsMode,
+    cli::{self, OutputLogsMode},
     run::task_id::TaskId,
     task_graph::{TaskDefinition, TaskOutputs},
 };
@@ -98,11 +98,16 @@ pub struct TaskEnvConfiguration {
 pub struct TaskSummaryTaskDefinition {
     outputs: Vec<String>,
     cache: bool,
+    // Only shown when the workspace restricts which caches can be used
+    #[serde(skip_serializing_if = "CacheConfig::is_default")]
+    cache_config: CacheConfig,
     depends_on: Vec<String>,
     inputs: Vec<String>,
     output_mode: OutputLogsMode,
     persistent: bool,
     env: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    env_mode: Option<cli::EnvMode>,
     pass_through_env: Option<Vec<String>>,
     dot_env: Option<Vec<RelativeUnixPathBuf>>,
 }
@@ -115,9 +120,26 @@ pub struct TaskEnvVarSummary {
     pub inferred: Vec<String>,
     #[serde(rename = "passthrough")]
     pub pass_through: Option<Vec<String>>,
+    // The .env files that were loaded and which file each variable came from
+    #[serde(skip_serializing_if = "DotEnvMap::is_empty")]
+    pub dot_env: DotEnvMap,
 }
 
 impl TaskCacheSummary {
+    pub fn status_name(&self) -> &'static str {
+        match self.status {
+            CacheStatus::Hit => "HIT",
+            CacheStatus::Miss => "MISS",
+        }
+    }
+
+    pub fn source_name(&self) -> Option<&'static str> {
+        self.source.map(|source| match source {
+            CacheSource::Local => "LOCAL",
+            CacheSource::Remote => "REMOTE",
+        })
+    }
+
     pub fn cache_miss() -> Self {
         Self {
             local: false,
@@ -195,6 +217,7 @@ impl TaskEnvVarSummary {
             configured: env_vars.by_source.explicit.to_secret_hashable(),
             inferred: env_vars.by_source.matching.to_secret_hashable(),
             pass_through,
+            dot_env: env_vars.by_source.dot_env,
         })
     }
 }
@@ -274,7 +297,9 @@ impl From<TaskDefinition> for TaskSummaryTaskDefinition {
                     exclusions,
                 },
             cache,
+            cache_config,
             mut env,
+            env_mode,
             pass_through_env,
             dot_env,
             topological_dependencies,
@@ -282,6 +307,11 @@ impl From<TaskDefinition> for TaskSummaryTaskDefinition {
             mut inputs,
             output_mode,
             persistent,
+            shutdown: _,
+            args: _,
+            script: _,
+            command: _,
+            cwd: _,
         } = value;
 
         let mut outputs = inclusions;
@@ -309,11 +339,13 @@ impl From<TaskDefinition> for TaskSummaryTaskDefinition {
         Self {
             outputs,
             cache,
+            cache_config,
             depends_on,
             inputs,
             output_mode,
             persistent,
             env,
+            env_mode,
             pass_through_env,
             // This should _not_ be sorted.
             dot_env,
@@ -378,6 +410,35 @@ mod test {
         })
         ; "resolved task definition"
     )]
+    #[test_case(
+        TaskSummaryTaskDefinition {
+            cache: true,
+            cache_config: CacheConfig {
+                remote: turborepo_cache::CacheActions {
+                read: true,
+                write: false,
+            },
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        json!({
+            "outputs": [],
+            "cache": true,
+            "cacheConfig": {
+                "local": { "read": true, "write": true },
+                "remote": { "read": true, "write": false },
+            },
+            "dependsOn": [],
+            "inputs": [],
+            "outputMode": "full",
+            "persistent": false,
+            "env": [],
+            "passThroughEnv": null,
+            "dotEnv": null,
+        })
+        ; "restricted cache config"
+    )]
     fn test_serialization(value: impl serde::Serialize, expected: serde_json::Value) {
         assert_eq!(serde_json::to_value(value).unwrap(), expected);
     }
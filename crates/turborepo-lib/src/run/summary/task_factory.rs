@@ -6,7 +6,7 @@ use turborepo_repository::package_graph::{PackageGraph, WorkspaceInfo, Workspace
 use super::{
     execution::TaskExecutionSummary,
     task::{SharedTaskSummary, TaskEnvVarSummary},
-    EnvMode, SinglePackageTaskSummary, TaskSummary,
+    SinglePackageTaskSummary, TaskSummary,
 };
 use crate::{
     cli,
@@ -109,16 +109,14 @@ impl<'a> TaskSummaryFactory<'a> {
         workspace_info: &WorkspaceInfo,
         display_task: impl Fn(&TaskNode) -> Option<T> + Copy,
     ) -> Result<SharedTaskSummary<T>, Error> {
+        let task_definition = self.task_definition(task_id)?;
+
         // TODO: command should be optional
-        let command = workspace_info
-            .package_json
-            .scripts
-            .get(task_id.task())
-            .cloned()
+        let command = task_definition
+            .resolved_command(task_id, &workspace_info.package_json)
+            .map(|command| command.to_string())
             .unwrap_or_else(|| "<NONEXISTENT>".to_string());
 
-        let task_definition = self.task_definition(task_id)?;
-
         let expanded_outputs = self
             .hash_tracker
             .expanded_outputs(task_id)
@@ -175,22 +173,9 @@ impl<'a> TaskSummaryFactory<'a> {
             framework,
             dependencies,
             dependents,
-            // TODO: this is some very messy code that appears in a few places
-            // we should attempt to calculate this once and reuse it
-            env_mode: match self.global_env_mode {
-                cli::EnvMode::Infer => {
-                    if task_definition.pass_through_env.is_some() {
-                        EnvMode::Strict
-                    } else {
-                        // If we're in infer mode we have just detected non-usage of strict env
-                        // vars. But our behavior's actual meaning of this
-                        // state is `loose`.
-                        EnvMode::Loose
-                    }
-                }
-                cli::EnvMode::Strict => EnvMode::Strict,
-                cli::EnvMode::Loose => EnvMode::Loose,
-            },
+            env_mode: task_definition
+                .resolved_env_mode(self.global_env_mode)
+                .into(),
             environment_variables: TaskEnvVarSummary::new(
                 task_definition,
                 env_vars,
@@ -0,0 +1,348 @@
+use thiserror::Error;
+use turborepo_repository::package_manager::PackageManager;
+
+/// Arguments that can't be passed to a task's `command` without the shell
+/// changing what they mean
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("arguments can't be passed to `{command}`: {reason}")]
+pub struct ArgsError {
+    command: String,
+    reason: String,
+}
+
+impl ArgsError {
+    fn new(command: &str, reason: impl Into<String>) -> Self {
+        Self {
+            command: command.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// The binary to spawn for a task and the arguments to pass it: the
+/// turbo.json `command` run through the shell if there is one, otherwise the
+/// package.json script run through the package manager.
+///
+/// Like a package.json script, `args` are appended to the end of the
+/// `command`, so with `a && b` only `b` gets them. Commands that would run
+/// the args as a command of their own, or drop them, are rejected.
+pub(crate) fn program_and_args(
+    command: Option<&str>,
+    script: &str,
+    args: &[String],
+    package_manager: &PackageManager,
+) -> Result<(&'static str, Vec<String>), ArgsError> {
+    match command {
+        #[cfg(unix)]
+        Some(command) => Ok(("sh", sh_args(command, args)?)),
+        #[cfg(windows)]
+        Some(command) => Ok(("cmd", cmd_args(command, args)?)),
+        None => {
+            let mut run_args = vec!["run".to_string(), script.to_string()];
+            if !args.is_empty() {
+                run_args.extend(package_manager.arg_separator(args).map(|s| s.to_string()));
+                run_args.extend(args.iter().cloned());
+            }
+            Ok((package_manager.command(), run_args))
+        }
+    }
+}
+
+#[cfg(unix)]
+fn sh_args(command: &str, args: &[String]) -> Result<Vec<String>, ArgsError> {
+    if args.is_empty() {
+        return Ok(vec!["-c".to_string(), command.to_string()]);
+    }
+
+    let command = command.trim_end();
+    if let Some(reason) = sh_args_problem(command) {
+        return Err(ArgsError::new(command, reason));
+    }
+    // Args are forwarded as positional parameters so the shell doesn't
+    // interpret them a second time
+    let mut sh_args = vec![
+        "-c".to_string(),
+        format!("{command} \"$@\""),
+        "sh".to_string(),
+    ];
+    sh_args.extend(args.iter().cloned());
+    Ok(sh_args)
+}
+
+/// Why appending `"$@"` to `command` wouldn't pass arguments to its last
+/// command. This is a rough scan of the command rather than a full parse, it
+/// only needs to find what the command ends with.
+#[cfg(unix)]
+fn sh_args_problem(command: &str) -> Option<&'static str> {
+    enum Ending {
+        Word,
+        Separator,
+        Compound,
+    }
+    enum Open {
+        Substitution,
+        Parameter,
+        Compound,
+    }
+
+    let mut ending = Ending::Separator;
+    let mut open = Vec::new();
+    // Whether the next character starts a word, and whether that word is in
+    // command position where keywords like `fi` are recognised
+    let mut word_start = true;
+    let mut command_position = true;
+    let mut word = String::new();
+
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        if matches!(c, ' ' | '\t' | '\n' | ';' | '&' | '|' | '(' | ')') && !word.is_empty() {
+            if command_position && matches!(word.as_str(), "fi" | "done" | "esac") {
+                ending = Ending::Compound;
+            }
+            // Reserved words leave the next word in command position
+            command_position = matches!(word.as_str(), "then" | "do" | "else" | "elif" | "!");
+            word.clear();
+        }
+        let starts_word = word_start;
+        word_start = false;
+        match c {
+            '\\' => {
+                if chars.next().is_none() {
+                    return Some("it ends with a line continuation");
+                }
+                word.push('\\');
+                ending = Ending::Word;
+            }
+            '\'' => {
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                }
+                word.push('\'');
+                ending = Ending::Word;
+            }
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => (),
+                    }
+                }
+                word.push('"');
+                ending = Ending::Word;
+            }
+            '#' if starts_word => {
+                if !chars.by_ref().any(|c| c == '\n') {
+                    return Some("it ends with a comment");
+                }
+                ending = Ending::Separator;
+                word_start = true;
+                command_position = true;
+            }
+            '$' if matches!(chars.peek(), Some('(' | '{')) => {
+                let kind = match chars.next() {
+                    Some('(') => Open::Substitution,
+                    _ => Open::Parameter,
+                };
+                open.push(kind);
+                word.push('$');
+                ending = Ending::Word;
+            }
+            '(' => {
+                open.push(Open::Compound);
+                ending = Ending::Separator;
+                word_start = true;
+                command_position = true;
+            }
+            ')' => {
+                ending = match open.pop() {
+                    Some(Open::Substitution) => Ending::Word,
+                    _ => Ending::Compound,
+                };
+                word_start = true;
+            }
+            '{' if starts_word && matches!(chars.peek(), Some(' ' | '\t' | '\n')) => {
+                open.push(Open::Compound);
+                ending = Ending::Separator;
+                word_start = true;
+                command_position = true;
+            }
+            '}' if matches!(open.last(), Some(Open::Parameter)) => {
+                open.pop();
+                ending = Ending::Word;
+            }
+            '}' if starts_word && matches!(open.last(), Some(Open::Compound)) => {
+                open.pop();
+                ending = Ending::Compound;
+                word_start = true;
+            }
+            ';' | '&' | '|' | '\n' => {
+                ending = Ending::Separator;
+                word_start = true;
+                command_position = true;
+            }
+            ' ' | '\t' => word_start = true,
+            c => {
+                word.push(c);
+                ending = Ending::Word;
+            }
+        }
+    }
+    if command_position && matches!(word.as_str(), "fi" | "done" | "esac") {
+        ending = Ending::Compound;
+    }
+
+    match ending {
+        Ending::Word => None,
+        Ending::Separator => Some("it ends with a separator, so they would run as a command"),
+        Ending::Compound => Some("it ends with a compound command, which can't take arguments"),
+    }
+}
+
+#[cfg(windows)]
+fn cmd_args(command: &str, args: &[String]) -> Result<Vec<String>, ArgsError> {
+    // An argument containing whitespace is quoted when the command line is
+    // built, and cmd would strip that quote along with the last one on the
+    // line. The command is passed word by word so it never gets quoted.
+    let mut cmd_args = vec!["/D".to_string(), "/V:OFF".to_string(), "/C".to_string()];
+    cmd_args.extend(
+        command
+            .split([' ', '\t'])
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_string()),
+    );
+    for arg in args {
+        cmd_args.push(escape_cmd_arg(arg).map_err(|reason| ArgsError::new(command, reason))?);
+    }
+    Ok(cmd_args)
+}
+
+/// Escapes `arg` so cmd passes it on unchanged. Arguments containing
+/// whitespace are quoted when the command line is built, which already stops
+/// cmd from interpreting them, the rest have cmd's special characters escaped.
+#[cfg(windows)]
+fn escape_cmd_arg(arg: &str) -> Result<String, String> {
+    if let Some(c) = arg.chars().find(|c| matches!(c, '"' | '%' | '\r' | '\n')) {
+        return Err(format!("`{arg}` contains {c:?}, which cmd would interpret"));
+    }
+    if arg.is_empty() || arg.contains([' ', '\t']) {
+        return Ok(arg.to_string());
+    }
+
+    let mut escaped = String::with_capacity(arg.len());
+    for c in arg.chars() {
+        if matches!(c, '^' | '&' | '|' | '<' | '>' | '(' | ')') {
+            escaped.push('^');
+        }
+        escaped.push(c);
+    }
+    Ok(escaped)
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(unix)]
+    mod unix {
+        use test_case::test_case;
+
+        use super::super::{sh_args, sh_args_problem};
+
+        fn run(command: &str, args: &[&str]) -> String {
+            let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+            let output = std::process::Command::new("sh")
+                .args(sh_args(command, &args).unwrap())
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        }
+
+        #[test_case("echo", &[], "\n" ; "no args")]
+        #[test_case("echo", &["a", "b c"], "a b c\n" ; "plain")]
+        #[test_case("echo first && echo", &["a"], "first\na\n" ; "compound")]
+        #[test_case("echo first\necho\n", &["a"], "first\na\n" ; "trailing newline")]
+        #[test_case("# setup\necho", &["a"], "a\n" ; "earlier comment")]
+        #[test_case("echo $(echo a)", &["b"], "a b\n" ; "substitution")]
+        #[test_case("echo done", &["a"], "done a\n" ; "keyword as argument")]
+        #[test_case("echo a;", &[], "a\n" ; "trailing separator without args")]
+        #[test_case(
+            "echo",
+            &["a;b", "$HOME", "`id`", "*", "&&", "'", "\"", "#"],
+            "a;b $HOME `id` * && ' \" #\n" ;
+            "metacharacters"
+        )]
+        fn test_sh_args(command: &str, args: &[&str], expected: &str) {
+            assert_eq!(run(command, args), expected);
+        }
+
+        #[test_case("echo a" ; "plain")]
+        #[test_case("echo a && echo b" ; "compound")]
+        #[test_case("echo 'a;' \"b&\" c\\;" ; "quoted separators")]
+        #[test_case("echo ${HOME}" ; "parameter")]
+        #[test_case("echo a#b" ; "hash in word")]
+        #[test_case("echo a 2>&1" ; "redirect")]
+        fn test_supported(command: &str) {
+            assert_eq!(sh_args_problem(command), None);
+        }
+
+        #[test_case("echo a;" ; "semicolon")]
+        #[test_case("echo a &" ; "background")]
+        #[test_case("echo a &&" ; "and")]
+        #[test_case("echo a |" ; "pipe")]
+        #[test_case("echo a # comment" ; "comment")]
+        #[test_case("echo a \\" ; "continuation")]
+        #[test_case("(echo a)" ; "subshell")]
+        #[test_case("{ echo a; }" ; "group")]
+        #[test_case("if true; then echo a; fi" ; "if")]
+        #[test_case("for x in a; do echo $x; done" ; "for")]
+        fn test_unsupported(command: &str) {
+            assert!(sh_args_problem(command).is_some());
+            assert!(sh_args(command, &["a".to_string()]).is_err());
+        }
+    }
+
+    #[cfg(windows)]
+    mod windows {
+        use test_case::test_case;
+
+        use super::super::{cmd_args, escape_cmd_arg};
+
+        #[test_case("a", "a" ; "plain")]
+        #[test_case("a&b", "a^&b" ; "ampersand")]
+        #[test_case("a|b^c", "a^|b^^c" ; "pipe and caret")]
+        #[test_case("<(a)>", "^<^(a^)^>" ; "redirects and parens")]
+        #[test_case("a & b", "a & b" ; "quoted")]
+        #[test_case("", "" ; "empty")]
+        fn test_escape_cmd_arg(arg: &str, expected: &str) {
+            assert_eq!(escape_cmd_arg(arg).unwrap(), expected);
+        }
+
+        #[test_case("50%" ; "percent")]
+        #[test_case("%PATH%" ; "variable")]
+        #[test_case("say \"hi\"" ; "quote")]
+        #[test_case("a\nb" ; "newline")]
+        fn test_escape_cmd_arg_rejected(arg: &str) {
+            assert!(escape_cmd_arg(arg).is_err());
+        }
+
+        #[test_case("echo", &["a", "b c"], "a \"b c\"" ; "plain")]
+        #[test_case("echo first && echo", &["a"], "first \r\na" ; "compound")]
+        #[test_case("echo", &["a&b", "c|d", "e^f"], "a&b c|d e^f" ; "metacharacters")]
+        fn test_cmd_args(command: &str, args: &[&str], expected: &str) {
+            let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+            let output = std::process::Command::new("cmd")
+                .args(cmd_args(command, &args).unwrap())
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            assert_eq!(
+                String::from_utf8(output.stdout).unwrap().trim_end(),
+                expected
+            );
+        }
+    }
+}
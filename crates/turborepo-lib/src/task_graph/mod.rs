@@ -1,14 +1,20 @@
+mod command;
 mod visitor;
 
 use std::str::FromStr;
 
+pub(crate) use command::{program_and_args, ArgsError};
 use globwalk::{GlobError, ValidatedGlob};
 use serde::{Deserialize, Serialize};
 use turbopath::{AnchoredSystemPath, AnchoredSystemPathBuf, RelativeUnixPathBuf};
+use turborepo_cache::CacheConfig;
+use turborepo_env::ResolvedEnvMode;
+use turborepo_repository::package_json::PackageJson;
 pub use visitor::{Error as VisitorError, Visitor};
 
 use crate::{
-    cli::OutputLogsMode,
+    cli::{EnvMode, OutputLogsMode},
+    process::ShutdownPolicy,
     run::task_id::{TaskId, TaskName},
     turbo_json::RawTaskDefinition,
 };
@@ -43,13 +49,23 @@ pub struct TaskDefinition {
     pub outputs: TaskOutputs,
     pub(crate) cache: bool,
 
+    // The caches the task can use, as configured by its workspace's turbo.json
+    pub(crate) cache_config: CacheConfig,
+
     // This field is custom-marshalled from `env` and `depends_on``
     pub(crate) env: Vec<String>,
 
+    // Overrides the run's env mode for this task
+    pub(crate) env_mode: Option<EnvMode>,
+
     pub(crate) pass_through_env: Option<Vec<String>>,
 
     pub(crate) dot_env: Option<Vec<RelativeUnixPathBuf>>,
 
+    // Whether the workspace's `.env`, `.env.local`, `.env.{mode}` and
+    // `.env.{mode}.local` files are loaded after `dot_env`
+    pub(crate) dot_env_cascade: bool,
+
     // TopologicalDependencies are tasks from package dependencies.
     // E.g. "build" is a topological dependency in:
     // dependsOn: ['^build'].
@@ -72,21 +88,44 @@ pub struct TaskDefinition {
     // Persistent indicates whether the Task is expected to exit or not
     // Tasks marked Persistent do not exit (e.g. --watch mode or dev servers)
     pub persistent: bool,
+
+    // How the task's process is stopped when turbo shuts down the run
+    pub(crate) shutdown: ShutdownPolicy,
+
+    // Args passed to the task's script, before any pass through args
+    pub(crate) args: Vec<String>,
+
+    // The script that's run when this task is a variant of another task
+    pub(crate) script: Option<String>,
+
+    // A command that's run in place of a package.json script
+    pub(crate) command: Option<String>,
+
+    // The directory the task runs in, relative to its workspace
+    pub(crate) cwd: Option<RelativeUnixPathBuf>,
 }
 
 impl Default for TaskDefinition {
     fn default() -> Self {
         Self {
             cache: true,
+            cache_config: Default::default(),
             outputs: Default::default(),
             env: Default::default(),
+            env_mode: Default::default(),
             pass_through_env: Default::default(),
             topological_dependencies: Default::default(),
             task_dependencies: Default::default(),
             inputs: Default::default(),
             output_mode: Default::default(),
             persistent: Default::default(),
+            shutdown: ShutdownPolicy::default(),
             dot_env: Default::default(),
+            dot_env_cascade: Default::default(),
+            args: Default::default(),
+            script: Default::default(),
+            command: Default::default(),
+            cwd: Default::default(),
         }
     }
 }
@@ -161,6 +200,70 @@ impl TaskDefinition {
 
         repo_relative_globs
     }
+
+    /// Resolves the env mode this task runs in. A mode set on the task takes
+    /// precedence over the run's mode, and `infer` resolves to strict only
+    /// when the task declares `passThroughEnv`.
+    pub fn resolved_env_mode(&self, global_env_mode: EnvMode) -> ResolvedEnvMode {
+        match self.env_mode.unwrap_or(global_env_mode) {
+            EnvMode::Infer if self.pass_through_env.is_some() => ResolvedEnvMode::Strict,
+            // If we're in infer mode we have just detected non-usage of strict env vars.
+            // But our behavior's actual meaning of this state is `loose`.
+            EnvMode::Infer => ResolvedEnvMode::Loose,
+            EnvMode::Strict => ResolvedEnvMode::Strict,
+            EnvMode::Loose => ResolvedEnvMode::Loose,
+        }
+    }
+
+    /// The `.env` files for this task, relative to its workspace, with any
+    /// `{mode}` placeholder expanded. Highest priority first: the task's
+    /// `dotEnv` files, then the workspace's cascade if it's enabled.
+    pub fn dot_env_files(&self, mode: Option<&str>) -> Vec<RelativeUnixPathBuf> {
+        let mut files = expand_dot_env_files(self.dot_env.as_deref().unwrap_or_default(), mode);
+        if self.dot_env_cascade {
+            let cascade = turborepo_env::dot_env_cascade(mode)
+                .into_iter()
+                .filter_map(|file| RelativeUnixPathBuf::new(file).ok());
+            for file in cascade {
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+        files
+    }
+
+    /// The package.json script that runs this task
+    pub fn script<'a>(&'a self, task_id: &'a TaskId) -> &'a str {
+        self.script.as_deref().unwrap_or(task_id.task())
+    }
+
+    /// The command this task runs in a workspace: the `command` from
+    /// turbo.json if there is one, otherwise its package.json script
+    pub fn resolved_command<'a>(
+        &'a self,
+        task_id: &TaskId,
+        package_json: &'a PackageJson,
+    ) -> Option<&'a str> {
+        match &self.command {
+            Some(command) => Some(command),
+            None => package_json
+                .scripts
+                .get(self.script(task_id))
+                .map(|script| script.as_str()),
+        }
+    }
+}
+
+pub fn expand_dot_env_files(
+    files: &[RelativeUnixPathBuf],
+    mode: Option<&str>,
+) -> Vec<RelativeUnixPathBuf> {
+    turborepo_env::expand_dot_env_mode(files.iter().map(|file| file.as_str()), mode)
+        .into_iter()
+        // A mode could in theory turn `{mode}` into an absolute path, skip those
+        .filter_map(|file| RelativeUnixPathBuf::new(file).ok())
+        .collect()
 }
 
 fn task_log_filename(task_name: &str) -> String {
@@ -172,9 +275,81 @@ mod test {
     use std::path::MAIN_SEPARATOR_STR;
 
     use pretty_assertions::assert_eq;
+    use test_case::test_case;
 
     use super::*;
 
+    #[test_case(None, false, EnvMode::Infer, ResolvedEnvMode::Loose ; "infer")]
+    #[test_case(None, true, EnvMode::Infer, ResolvedEnvMode::Strict ; "infer with pass through")]
+    #[test_case(None, false, EnvMode::Strict, ResolvedEnvMode::Strict ; "global strict")]
+    #[test_case(Some(EnvMode::Loose), true, EnvMode::Strict, ResolvedEnvMode::Loose ; "task overrides global")]
+    #[test_case(Some(EnvMode::Strict), false, EnvMode::Loose, ResolvedEnvMode::Strict ; "task strict")]
+    #[test_case(Some(EnvMode::Infer), true, EnvMode::Loose, ResolvedEnvMode::Strict ; "task infer")]
+    fn test_resolved_env_mode(
+        env_mode: Option<EnvMode>,
+        has_pass_through: bool,
+        global_env_mode: EnvMode,
+        expected: ResolvedEnvMode,
+    ) {
+        let task_defn = TaskDefinition {
+            env_mode,
+            pass_through_env: has_pass_through.then(Vec::new),
+            ..Default::default()
+        };
+        assert_eq!(task_defn.resolved_env_mode(global_env_mode), expected);
+    }
+
+    #[test_case(None, None, None ; "no command or script")]
+    #[test_case(None, Some("tsc"), Some("tsc") ; "script")]
+    #[test_case(Some("tsc --noEmit"), None, Some("tsc --noEmit") ; "command")]
+    #[test_case(Some("tsc --noEmit"), Some("tsc"), Some("tsc --noEmit") ; "command over script")]
+    fn test_resolved_command(command: Option<&str>, script: Option<&str>, expected: Option<&str>) {
+        let task_defn = TaskDefinition {
+            command: command.map(|command| command.to_string()),
+            ..Default::default()
+        };
+        let package_json = PackageJson {
+            scripts: script
+                .map(|script| ("lint".to_string(), script.to_string()))
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let task_id = TaskId::new("web", "lint");
+        assert_eq!(
+            task_defn.resolved_command(&task_id, &package_json),
+            expected
+        );
+    }
+
+    #[test_case(&[], false, Some("production"), &[] ; "nothing")]
+    #[test_case(&[".env.{mode}"], false, Some("test"), &[".env.test"] ; "configured")]
+    #[test_case(&[], true, Some("production"), &[".env.production.local", ".env.production", ".env.local", ".env"] ; "cascade")]
+    #[test_case(&[], true, None, &[".env.local", ".env"] ; "cascade without mode")]
+    #[test_case(&["config/.env", ".env"], true, None, &["config/.env", ".env", ".env.local"] ; "configured before cascade")]
+    fn test_dot_env_files(
+        dot_env: &[&str],
+        dot_env_cascade: bool,
+        mode: Option<&str>,
+        expected: &[&str],
+    ) {
+        let task_defn = TaskDefinition {
+            dot_env: Some(
+                dot_env
+                    .iter()
+                    .map(|file| RelativeUnixPathBuf::new(*file).unwrap())
+                    .collect(),
+            ),
+            dot_env_cascade,
+            ..Default::default()
+        };
+        let files = task_defn.dot_env_files(mode);
+        assert_eq!(
+            files.iter().map(|file| file.as_str()).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
     #[test]
     fn test_relative_output_globs() {
         let task_defn = TaskDefinition {
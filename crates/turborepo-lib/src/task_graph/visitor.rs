@@ -1,9 +1,9 @@
 use std::{
     borrow::Cow,
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     io::Write,
     sync::{Arc, Mutex, OnceLock},
-    time::{Duration, Instant},
+    time::Instant,
 };
 
 use console::{Style, StyledObject};
@@ -12,8 +12,9 @@ use regex::Regex;
 use tokio::sync::{mpsc, oneshot};
 use tracing::{debug, error, Instrument, Span};
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath};
+use turborepo_cache::CacheActions;
 use turborepo_ci::{Vendor, VendorBehavior};
-use turborepo_env::{EnvironmentVariableMap, ResolvedEnvMode};
+use turborepo_env::EnvironmentVariableMap;
 use turborepo_repository::{
     package_graph::{PackageGraph, WorkspaceName, ROOT_PKG_NAME},
     package_manager::PackageManager,
@@ -25,20 +26,22 @@ use turborepo_ui::{ColorSelector, OutputClient, OutputSink, OutputWriter, Prefix
 use which::which;
 
 use crate::{
-    cli::EnvMode,
-    engine::{Engine, ExecutionOptions, StopExecution},
+    cli::{EnvMode, RunOutputFormat},
+    distributed::{proto::RunTaskRequest, WorkerPool},
+    engine::{Engine, ExecutionOptions, StopExecution, TaskNode},
     opts::RunOpts,
-    process::{ChildExit, Command, ProcessManager},
+    process::{ChildExit, Command, ProcessManager, ShutdownPolicy},
     run::{
         global_hash::GlobalHashableInputs,
         summary::{
-            self, GlobalHashSummary, RunTracker, SpacesTaskClient, SpacesTaskInformation,
-            TaskExecutionSummary, TaskTracker,
+            self, GlobalHashSummary, RunEvents, RunTracker, SpacesTaskClient,
+            SpacesTaskInformation, TaskExecutionSummary, TaskStop, TaskTracker,
         },
         task_access::TaskAccess,
         task_id::TaskId,
         RunCache, TaskCache,
     },
+    task_graph::{program_and_args, ArgsError, TaskDefinition},
     task_hash::{self, PackageInputsHashes, TaskHashTracker, TaskHashTrackerState, TaskHasher},
 };
 
@@ -58,6 +61,7 @@ pub struct Visitor<'a> {
     sink: OutputSink<StdWriter>,
     task_hasher: TaskHasher<'a>,
     ui: UI,
+    workers: Option<Arc<WorkerPool>>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -79,6 +83,8 @@ pub enum Error {
     TaskHash(#[from] task_hash::Error),
     #[error(transparent)]
     RunSummary(#[from] summary::Error),
+    #[error(transparent)]
+    Path(#[from] turbopath::PathError),
 }
 
 impl<'a> Visitor<'a> {
@@ -126,6 +132,7 @@ impl<'a> Visitor<'a> {
             task_hasher,
             ui,
             global_env,
+            workers: None,
         }
     }
 
@@ -135,11 +142,25 @@ impl<'a> Visitor<'a> {
         engine: Arc<Engine>,
         telemetry: &GenericEventBuilder,
     ) -> Result<Vec<TaskError>, Error> {
-        let concurrency = self.run_opts.concurrency as usize;
+        let mut task_durations = summary::historical_task_durations(self.repo_root);
+        if !self.run_cache.reads_disabled() {
+            // Restoring outputs is quick no matter how long the task took to run
+            for task_id in self.preflight_cache(&engine, telemetry).await? {
+                task_durations.insert(task_id, 0);
+            }
+        }
+
+        let mut concurrency = self.run_opts.concurrency as usize;
+        if let Some(workers) = &self.workers {
+            // Tasks waiting on a worker still hold a slot in the engine
+            concurrency = concurrency.max(workers.concurrency());
+        }
         let (node_sender, mut node_stream) = mpsc::channel(concurrency);
         let engine_handle = {
             let engine = engine.clone();
-            tokio::spawn(engine.execute(ExecutionOptions::new(false, concurrency), node_sender))
+            let options =
+                ExecutionOptions::new(false, concurrency).with_task_durations(task_durations);
+            tokio::spawn(engine.execute(options, node_sender))
         };
         let mut tasks = FuturesUnordered::new();
         let errors = Arc::new(Mutex::new(Vec::new()));
@@ -163,11 +184,14 @@ impl<'a> Visitor<'a> {
 
             let package_task_event =
                 PackageTaskEventBuilder::new(info.package(), info.task()).with_parent(telemetry);
-            let command = workspace_info
-                .package_json
-                .scripts
-                .get(info.task())
-                .cloned();
+
+            let task_definition = engine
+                .task_definition(&info)
+                .ok_or(Error::MissingDefinition)?;
+
+            let command = task_definition
+                .resolved_command(&info, &workspace_info.package_json)
+                .map(|command| command.to_string());
 
             match command {
                 Some(cmd) if info.package() == ROOT_PKG_NAME && turbo_regex().is_match(&cmd) => {
@@ -180,35 +204,26 @@ impl<'a> Visitor<'a> {
                 _ => (),
             }
 
-            let task_definition = engine
-                .task_definition(&info)
-                .ok_or(Error::MissingDefinition)?;
-
-            let task_env_mode = match self.global_env_mode {
-                // Task env mode is only independent when global env mode is `infer`.
-                EnvMode::Infer if task_definition.pass_through_env.is_some() => {
-                    ResolvedEnvMode::Strict
-                }
-                // If we're in infer mode we have just detected non-usage of strict env vars.
-                // But our behavior's actual meaning of this state is `loose`.
-                EnvMode::Infer => ResolvedEnvMode::Loose,
-                // Otherwise we just use the global env mode.
-                EnvMode::Strict => ResolvedEnvMode::Strict,
-                EnvMode::Loose => ResolvedEnvMode::Loose,
-            };
+            let task_env_mode = task_definition.resolved_env_mode(self.global_env_mode);
             package_task_event.track_env_mode(&task_env_mode.to_string());
 
             let dependency_set = engine.dependencies(&info).ok_or(Error::MissingDefinition)?;
 
-            let task_hash_telemetry = package_task_event.child();
-            let task_hash = self.task_hasher.calculate_task_hash(
-                &info,
-                task_definition,
-                task_env_mode,
-                workspace_info,
-                dependency_set,
-                task_hash_telemetry,
-            )?;
+            // The hash may have already been calculated during the cache preflight
+            let task_hash = match self.task_hasher.task_hash_tracker().hash(&info) {
+                Some(task_hash) => task_hash,
+                None => {
+                    let task_hash_telemetry = package_task_event.child();
+                    self.task_hasher.calculate_task_hash(
+                        &info,
+                        task_definition,
+                        task_env_mode,
+                        workspace_info,
+                        dependency_set,
+                        task_hash_telemetry,
+                    )?
+                }
+            };
 
             debug!("task {} hash is {}", info, task_hash);
             // We do this calculation earlier than we do in Go due to the `task_hasher`
@@ -217,6 +232,15 @@ impl<'a> Visitor<'a> {
             let execution_env =
                 self.task_hasher
                     .env(&info, task_env_mode, task_definition, &self.global_env)?;
+            // Workers only get the env vars the task declares, not the whole
+            // environment of this machine
+            let declared_env = match &self.workers {
+                Some(_) => {
+                    self.task_hasher
+                        .declared_env(&info, task_definition, &self.global_env)?
+                }
+                None => EnvironmentVariableMap::default(),
+            };
 
             let task_cache = self.run_cache.task_cache(
                 task_definition,
@@ -248,16 +272,20 @@ impl<'a> Visitor<'a> {
                         continue;
                     }
 
-                    let workspace_directory = self.repo_root.resolve(workspace_info.package_path());
+                    let mut workspace_directory =
+                        self.repo_root.resolve(workspace_info.package_path());
+                    if let Some(cwd) = &task_definition.cwd {
+                        workspace_directory = workspace_directory.join_unix_path(cwd)?;
+                    }
 
-                    let persistent = task_definition.persistent;
                     let mut exec_context = factory.exec_context(
                         info.clone(),
                         task_hash,
                         task_cache,
                         workspace_directory,
                         execution_env,
-                        persistent,
+                        declared_env,
+                        task_definition,
                         self.task_access.clone(),
                     );
 
@@ -287,13 +315,21 @@ impl<'a> Visitor<'a> {
         }
 
         // Wait for the engine task to finish and for all of our tasks to finish
-        engine_handle.await.expect("engine execution panicked")?;
+        if let Err(e) = engine_handle.await.expect("engine execution panicked") {
+            // Cache hits were already reported, so their outputs have to end up
+            // on disk even though the run stops here
+            self.finish_deferred_outputs(&errors).await;
+            return Err(e.into());
+        }
         // This will poll the futures until they are all completed
         while let Some(result) = tasks.next().await {
             result.expect("task executor panicked");
         }
         drop(factory);
 
+        // Outputs that no task needed still have to end up on disk
+        self.finish_deferred_outputs(&errors).await;
+
         // Write out the traced-config.json file if we have one
         self.task_access.save().await;
 
@@ -305,6 +341,94 @@ impl<'a> Visitor<'a> {
         Ok(errors)
     }
 
+    // Writes the outputs of cache hits whose restore is still deferred to disk
+    async fn finish_deferred_outputs(&self, errors: &Mutex<Vec<TaskError>>) {
+        for (task_id, restored_files) in self.run_cache.finish_deferred_outputs().await {
+            match restored_files {
+                Ok(restored_files) => self
+                    .task_hasher
+                    .task_hash_tracker()
+                    .insert_expanded_outputs(task_id, restored_files),
+                Err(e) => errors.lock().expect("lock poisoned").push(TaskError {
+                    task_id: self.display_task_id(&task_id),
+                    cause: TaskErrorCause::Restore { msg: e.to_string() },
+                }),
+            }
+        }
+    }
+
+    /// Walks the task graph without executing anything to calculate every
+    /// task hash up front, then checks the cache for all of them at once.
+    /// This lets remote cache hits download while earlier tasks execute.
+    /// Returns the tasks that are expected to be cache hits.
+    #[tracing::instrument(skip_all)]
+    async fn preflight_cache(
+        &self,
+        engine: &Arc<Engine>,
+        telemetry: &GenericEventBuilder,
+    ) -> Result<Vec<TaskId<'static>>, Error> {
+        let concurrency = self.run_opts.concurrency as usize;
+        let (node_sender, mut node_stream) = mpsc::channel(concurrency);
+        let engine_handle = tokio::spawn(
+            engine
+                .clone()
+                .execute(ExecutionOptions::new(false, concurrency), node_sender),
+        );
+
+        let mut hashes = Vec::new();
+        while let Some(message) = node_stream.recv().await {
+            let crate::engine::Message { info, callback } = message;
+            let package_name = WorkspaceName::from(info.package());
+            let workspace_info = self
+                .package_graph
+                .workspace_info(&package_name)
+                .ok_or_else(|| Error::MissingPackage {
+                    package_name: package_name.clone(),
+                    task_id: info.clone(),
+                })?;
+            let task_definition = engine
+                .task_definition(&info)
+                .ok_or(Error::MissingDefinition)?;
+            let task_env_mode = task_definition.resolved_env_mode(self.global_env_mode);
+            let dependency_set = engine.dependencies(&info).ok_or(Error::MissingDefinition)?;
+
+            let package_task_event =
+                PackageTaskEventBuilder::new(info.package(), info.task()).with_parent(telemetry);
+            let task_hash = self.task_hasher.calculate_task_hash(
+                &info,
+                task_definition,
+                task_env_mode,
+                workspace_info,
+                dependency_set,
+                package_task_event.child(),
+            )?;
+
+            if task_definition.cache {
+                hashes.push((info, task_hash, task_definition.cache_config));
+            }
+            // Nothing runs during the preflight, so dependents can be visited
+            // right away
+            callback.send(Ok(())).ok();
+        }
+        engine_handle.await.expect("engine execution panicked")?;
+
+        debug!("querying cache for {} tasks", hashes.len());
+        self.run_cache
+            .preflight(
+                &hashes
+                    .iter()
+                    .map(|(_, hash, cache_config)| (hash.as_str(), *cache_config))
+                    .collect::<Vec<_>>(),
+            )
+            .await;
+
+        Ok(hashes
+            .into_iter()
+            .filter(|(_, hash, _)| self.run_cache.preflight_hit(hash))
+            .map(|(task_id, ..)| task_id)
+            .collect())
+    }
+
     /// Finishes visiting the tasks, creates the run summary, and either
     /// prints, saves, or sends it to spaces.
     #[tracing::instrument(skip(
@@ -330,13 +454,18 @@ impl<'a> Visitor<'a> {
             repo_root,
             global_env_mode,
             task_hasher,
+            run_cache,
+            mut run_tracker,
             ..
         } = self;
 
         let global_hash_summary = GlobalHashSummary::try_from(global_hash_inputs)?;
 
-        Ok(self
-            .run_tracker
+        if let Some(reason) = run_cache.remote_cache_disabled_reason() {
+            run_tracker.remote_cache_disabled(reason);
+        }
+
+        Ok(run_tracker
             .finish(
                 exit_code,
                 &package_graph,
@@ -357,6 +486,9 @@ impl<'a> Visitor<'a> {
     fn sink(run_opts: &RunOpts, silent: bool) -> OutputSink<StdWriter> {
         let (out, err) = if silent {
             (std::io::sink().into(), std::io::sink().into())
+        } else if run_opts.output == RunOutputFormat::Ndjson {
+            // stdout is reserved for run events
+            (std::io::sink().into(), std::io::stderr().into())
         } else if run_opts.should_redirect_stderr_to_stdout() {
             (std::io::stdout().into(), std::io::stdout().into())
         } else {
@@ -446,6 +578,11 @@ impl<'a> Visitor<'a> {
     pub fn dry_run(&mut self) {
         self.dry = true;
     }
+
+    /// Run tasks that miss the cache on remote workers
+    pub fn distribute(&mut self, workers: Arc<WorkerPool>) {
+        self.workers = Some(workers);
+    }
 }
 
 // A tiny enum that allows us to use the same type for stdout and stderr without
@@ -514,6 +651,8 @@ enum TaskErrorCause {
     Spawn { msg: String },
     #[error("command {command} exited ({exit_code})")]
     Exit { command: String, exit_code: i32 },
+    #[error("unable to restore outputs: {msg}")]
+    Restore { msg: String },
 }
 
 impl TaskError {
@@ -583,11 +722,20 @@ impl<'a> ExecContextFactory<'a> {
         task_cache: TaskCache,
         workspace_directory: AbsoluteSystemPathBuf,
         execution_env: EnvironmentVariableMap,
-        persistent: bool,
+        declared_env: EnvironmentVariableMap,
+        task_definition: &TaskDefinition,
         task_access: TaskAccess,
     ) -> ExecContext {
         let task_id_for_display = self.visitor.display_task_id(&task_id);
-        let pass_through_args = self.visitor.run_opts.args_for_task(&task_id);
+        let script = task_definition.script(&task_id).to_string();
+        let pass_through_args = self
+            .visitor
+            .run_opts
+            .args_for_task(&task_id)
+            .unwrap_or_default();
+        let has_pass_through_args = !pass_through_args.is_empty();
+        let mut args = task_definition.args.clone();
+        args.extend(pass_through_args);
         ExecContext {
             engine: self.engine.clone(),
             ui: self.visitor.ui,
@@ -599,17 +747,25 @@ impl<'a> ExecContextFactory<'a> {
             task_id,
             task_id_for_display,
             task_cache,
+            run_cache: self.visitor.run_cache.clone(),
             hash_tracker: self.visitor.task_hasher.task_hash_tracker(),
             package_manager: *self.visitor.package_graph.package_manager(),
             workspace_directory,
             manager: self.manager.clone(),
             task_hash,
             execution_env,
+            declared_env,
             continue_on_error: self.visitor.run_opts.continue_on_error,
-            pass_through_args,
+            script,
+            command: task_definition.command.clone(),
+            args,
+            has_pass_through_args,
             errors: self.errors.clone(),
-            persistent,
+            persistent: task_definition.persistent,
+            shutdown: task_definition.shutdown,
             task_access,
+            workers: self.visitor.workers.clone(),
+            events: self.visitor.run_tracker.events().cloned(),
         }
     }
 
@@ -634,17 +790,29 @@ struct ExecContext {
     task_id: TaskId<'static>,
     task_id_for_display: String,
     task_cache: TaskCache,
+    run_cache: Arc<RunCache>,
     hash_tracker: TaskHashTracker,
     package_manager: PackageManager,
     workspace_directory: AbsoluteSystemPathBuf,
     manager: ProcessManager,
     task_hash: String,
     execution_env: EnvironmentVariableMap,
+    // The subset of the env that's sent to remote workers
+    declared_env: EnvironmentVariableMap,
     continue_on_error: bool,
-    pass_through_args: Option<Vec<String>>,
+    script: String,
+    // A command from turbo.json that's run in place of the script
+    command: Option<String>,
+    // The task's own args followed by any pass through args
+    args: Vec<String>,
+    // Workers resolve args from turbo.json, so these tasks can't run remotely
+    has_pass_through_args: bool,
     errors: Arc<Mutex<Vec<TaskError>>>,
     persistent: bool,
+    shutdown: ShutdownPolicy,
     task_access: TaskAccess,
+    workers: Option<Arc<WorkerPool>>,
+    events: Option<RunEvents>,
 }
 
 enum ExecOutcome {
@@ -652,6 +820,8 @@ enum ExecOutcome {
     Success(SuccessOutcome),
     // An internal error that indicates a shutdown should be performed
     Internal,
+    // The task was shut down while it was still running
+    Stopped(TaskStop),
     // An error with the task execution
     Task {
         exit_code: Option<i32>,
@@ -718,6 +888,11 @@ impl ExecContext {
                 callback.send(Err(StopExecution)).ok();
                 self.manager.stop().await;
             }
+            ExecOutcome::Stopped(how) => {
+                tracker.stopped(how).await;
+                callback.send(Err(StopExecution)).ok();
+                self.manager.stop().await;
+            }
             ExecOutcome::Task { exit_code, message } => {
                 let task_summary = tracker.build_failed(exit_code, message).await;
                 callback
@@ -771,6 +946,9 @@ impl ExecContext {
             .await
         {
             Ok(Some(status)) => {
+                if let Some(events) = &self.events {
+                    events.cache_hit(&self.task_id, status.source);
+                }
                 // we need to set expanded outputs
                 self.hash_tracker.insert_expanded_outputs(
                     self.task_id.clone(),
@@ -780,28 +958,71 @@ impl ExecContext {
                     .insert_cache_status(self.task_id.clone(), status);
                 return ExecOutcome::Success(SuccessOutcome::CacheHit);
             }
-            Ok(None) => (),
+            Ok(None) => {
+                if let Some(events) = &self.events {
+                    events.cache_miss(&self.task_id);
+                }
+            }
             Err(e) => {
                 telemetry.track_error(TrackedErrors::ErrorFetchingFromCache);
                 prefixed_ui.error(format!("error fetching from cache: {e}"));
+                if let Some(events) = &self.events {
+                    events.cache_miss(&self.task_id);
+                }
             }
         }
 
-        let Ok(package_manager_binary) = which(self.package_manager.command()) else {
+        // Dependencies that were cache hits might not have written their
+        // outputs yet, and this task could read any of them
+        if self.run_cache.lazy_restore() {
+            let dependencies = self
+                .engine
+                .transitive_dependencies(&self.task_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|node| match node {
+                    TaskNode::Task(task_id) => Some(task_id.clone()),
+                    TaskNode::Root => None,
+                })
+                .collect::<Vec<_>>();
+            if let Err(e) = self.run_cache.restore_deferred_outputs(&dependencies).await {
+                telemetry.track_error(TrackedErrors::ErrorFetchingFromCache);
+                let error = TaskErrorCause::Restore { msg: e.to_string() };
+                let message = error.to_string();
+                prefixed_ui.error(&message);
+                self.errors.lock().expect("lock poisoned").push(TaskError {
+                    task_id: self.task_id_for_display.clone(),
+                    cause: error,
+                });
+                return ExecOutcome::Task {
+                    exit_code: None,
+                    message,
+                };
+            }
+        }
+
+        let (program, args) = match self.program_and_args() {
+            Ok(program_and_args) => program_and_args,
+            Err(e) => {
+                let error = TaskErrorCause::Spawn { msg: e.to_string() };
+                let message = error.to_string();
+                prefixed_ui.error(&message);
+                self.errors.lock().expect("lock poisoned").push(TaskError {
+                    task_id: self.task_id_for_display.clone(),
+                    cause: error,
+                });
+                return ExecOutcome::Task {
+                    exit_code: None,
+                    message,
+                };
+            }
+        };
+        let Ok(binary) = which(program) else {
             return ExecOutcome::Internal;
         };
 
-        let mut cmd = Command::new(package_manager_binary);
-        let mut args = vec!["run".to_string(), self.task_id.task().to_string()];
-        if let Some(pass_through_args) = &self.pass_through_args {
-            args.extend(
-                self.package_manager
-                    .arg_separator(pass_through_args.as_slice())
-                    .map(|s| s.to_string()),
-            );
-            args.extend(pass_through_args.iter().cloned());
-        }
-        cmd.args(args);
+        let mut cmd = Command::new(binary);
+        cmd.args(&args);
         cmd.current_dir(self.workspace_directory.clone());
 
         // We clear the env before populating it with variables we expect
@@ -821,8 +1042,26 @@ impl ExecContext {
         // Many persistent tasks if started hooked up to a pseudoterminal
         // will shut down if stdin is closed, so we open it even if we don't pass
         // anything to it.
+        // They are also the tasks that users need to poke while they are
+        // running, e.g. to reload config on SIGUSR1.
         if self.persistent {
             cmd.open_stdin();
+            cmd.forward_signals();
+        }
+
+        if let Some(workers) = self.remote_workers() {
+            if let Some(outcome) = self
+                .execute_remote(
+                    &workers,
+                    cmd.label(),
+                    output_client,
+                    &mut prefixed_ui,
+                    telemetry,
+                )
+                .await
+            {
+                return outcome;
+            }
         }
 
         let mut stdout_writer = match self
@@ -837,7 +1076,7 @@ impl ExecContext {
             }
         };
 
-        let mut process = match self.manager.spawn(cmd, Duration::from_millis(500)) {
+        let mut process = match self.manager.spawn(cmd, self.shutdown) {
             Some(Ok(child)) => child,
             // Turbo was unable to spawn a process
             Some(Err(e)) => {
@@ -859,7 +1098,17 @@ impl ExecContext {
             }
         };
 
-        let exit_status = match process.wait_with_piped_outputs(&mut stdout_writer).await {
+        let piped = match &self.events {
+            Some(events) => {
+                process
+                    .wait_with_piped_streams(
+                        events.task_logs(self.task_id.clone(), &mut stdout_writer),
+                    )
+                    .await
+            }
+            None => process.wait_with_piped_streams(&mut stdout_writer).await,
+        };
+        let exit_status = match piped {
             Ok(Some(exit_status)) => exit_status,
             Err(e) => {
                 telemetry.track_error(TrackedErrors::FailedToPipeOutputs);
@@ -906,30 +1155,171 @@ impl ExecContext {
                 if let Err(e) = stdout_writer.flush() {
                     error!("error flushing logs: {e}");
                 }
-                if let Err(e) = self.task_cache.on_error(&mut prefixed_ui) {
-                    error!("error reading logs: {e}");
+                self.task_failed(&mut prefixed_ui, process.label().to_string(), code)
+            }
+            ChildExit::Interrupted => ExecOutcome::Stopped(TaskStop::Interrupted),
+            ChildExit::Killed => ExecOutcome::Stopped(TaskStop::Killed),
+            // All of these indicate a failure where we don't know how to recover
+            ChildExit::Finished(None) | ChildExit::KilledExternal | ChildExit::Failed => {
+                ExecOutcome::Internal
+            }
+        }
+    }
+
+    fn task_failed(
+        &mut self,
+        prefixed_ui: &mut PrefixedUI<impl Write>,
+        command: String,
+        exit_code: i32,
+    ) -> ExecOutcome {
+        if let Err(e) = self.task_cache.on_error(prefixed_ui) {
+            error!("error reading logs: {e}");
+        }
+        let error = TaskErrorCause::from_execution(command, exit_code);
+        let message = error.to_string();
+        if self.continue_on_error {
+            prefixed_ui.warn("command finished with error, but continuing...");
+        } else {
+            prefixed_ui.error(format!("command finished with error: {error}"));
+        }
+        self.errors.lock().expect("lock poisoned").push(TaskError {
+            task_id: self.task_id_for_display.clone(),
+            cause: error,
+        });
+        ExecOutcome::Task {
+            exit_code: Some(exit_code),
+            message,
+        }
+    }
+
+    // Persistent tasks never finish and the outputs of tasks that aren't
+    // cached can't make it back from a worker, so those always run locally.
+    // Outputs come back through the remote cache, so that has to be fully
+    // usable by the task's workspace too.
+    fn remote_workers(&self) -> Option<Arc<WorkerPool>> {
+        let workers = self.workers.as_ref()?;
+        (!self.persistent
+            && !self.has_pass_through_args
+            && self.task_cache.is_cacheable()
+            && self.task_cache.cache_config().remote == CacheActions::default()
+            && !self.task_access.is_enabled())
+        .then(|| workers.clone())
+    }
+
+    /// Runs the task on a worker and restores its outputs from the cache.
+    /// Returns `None` if the task couldn't be run remotely, in which case it
+    /// should run locally instead.
+    async fn execute_remote(
+        &mut self,
+        workers: &WorkerPool,
+        command: String,
+        output_client: &OutputClient<impl std::io::Write>,
+        prefixed_ui: &mut PrefixedUI<impl Write>,
+        telemetry: &PackageTaskEventBuilder,
+    ) -> Option<ExecOutcome> {
+        let request = self.run_task_request();
+        let mut stdout_writer = match self
+            .task_cache
+            .output_writer(self.pretty_prefix.clone(), output_client.stdout())
+        {
+            Ok(w) => w,
+            Err(e) => {
+                telemetry.track_error(TrackedErrors::FailedToCaptureOutputs);
+                error!("failed to capture outputs for \"{}\": {e}", self.task_id);
+                return Some(ExecOutcome::Internal);
+            }
+        };
+
+        let finished = match &self.events {
+            Some(events) => {
+                workers
+                    .run_task(
+                        request,
+                        events.task_logs(self.task_id.clone(), &mut stdout_writer),
+                    )
+                    .await
+            }
+            None => workers.run_task(request, &mut stdout_writer).await,
+        };
+        let finished = match finished {
+            Ok(finished) => finished,
+            Err(e) => {
+                prefixed_ui.warn(format!("{e}, running task locally"));
+                return None;
+            }
+        };
+        if let Err(e) = stdout_writer.flush() {
+            error!("error flushing logs: {e}");
+        }
+
+        // Tasks a worker couldn't run are retried by the pool, so the exit
+        // code is the task's own
+        match finished.exit_code {
+            Some(0) => match self.task_cache.fetch_outputs().await {
+                Ok(true) => {
+                    self.hash_tracker.insert_expanded_outputs(
+                        self.task_id.clone(),
+                        self.task_cache.expanded_outputs().to_vec(),
+                    );
+                    Some(ExecOutcome::Success(SuccessOutcome::Run))
                 }
-                let error = TaskErrorCause::from_execution(process.label().to_string(), code);
-                let message = error.to_string();
-                if self.continue_on_error {
-                    prefixed_ui.warn("command finished with error, but continuing...");
-                } else {
-                    prefixed_ui.error(format!("command finished with error: {error}"));
+                Ok(false) => {
+                    prefixed_ui.warn(
+                        "outputs from the worker are missing from the cache, running task locally",
+                    );
+                    None
                 }
-                self.errors.lock().expect("lock poisoned").push(TaskError {
-                    task_id: self.task_id_for_display.clone(),
-                    cause: error,
-                });
-                ExecOutcome::Task {
-                    exit_code: Some(code),
-                    message,
+                Err(e) => {
+                    prefixed_ui.warn(format!(
+                        "unable to restore outputs from the worker, running task locally: {e}"
+                    ));
+                    None
                 }
-            }
-            // All of these indicate a failure where we don't know how to recover
-            ChildExit::Finished(None)
-            | ChildExit::Killed
-            | ChildExit::KilledExternal
-            | ChildExit::Failed => ExecOutcome::Internal,
+            },
+            Some(code) => Some(self.task_failed(prefixed_ui, command, code)),
+            None => Some(ExecOutcome::Internal),
+        }
+    }
+
+    /// The program that runs this task and the arguments it's called with.
+    /// Tasks with a `command` in turbo.json run it through the shell, and the
+    /// rest run their package.json script through the package manager.
+    fn program_and_args(&self) -> Result<(&'static str, Vec<String>), ArgsError> {
+        program_and_args(
+            self.command.as_deref(),
+            &self.script,
+            &self.args,
+            &self.package_manager,
+        )
+    }
+
+    // The worker resolves everything else about the task from its own checkout
+    fn run_task_request(&self) -> RunTaskRequest {
+        let env = self
+            .declared_env
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect::<HashMap<_, _>>();
+        // Workers restore the outputs of every upstream task, a dependency's
+        // outputs can refer to outputs of its own dependencies
+        let dependency_hashes = self
+            .engine
+            .transitive_dependencies(&self.task_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|node| match node {
+                TaskNode::Task(task_id) => self.hash_tracker.hash(task_id),
+                TaskNode::Root => None,
+            })
+            .collect();
+
+        RunTaskRequest {
+            // Filled in by the worker pool for the worker the task runs on
+            session: String::new(),
+            task_id: self.task_id.to_string(),
+            hash: self.task_hash.clone(),
+            env,
+            dependency_hashes,
         }
     }
 
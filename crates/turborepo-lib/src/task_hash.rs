@@ -9,7 +9,7 @@ use thiserror::Error;
 use tracing::{debug, Span};
 use turbopath::{AbsoluteSystemPath, AnchoredSystemPath, AnchoredSystemPathBuf};
 use turborepo_cache::CacheHitMetadata;
-use turborepo_env::{BySource, DetailedMap, EnvironmentVariableMap, ResolvedEnvMode};
+use turborepo_env::{BySource, DetailedMap, DotEnvMap, EnvironmentVariableMap, ResolvedEnvMode};
 use turborepo_repository::package_graph::{WorkspaceInfo, WorkspaceName};
 use turborepo_scm::SCM;
 use turborepo_telemetry::events::{
@@ -25,6 +25,18 @@ use crate::{
     task_graph::TaskDefinition,
 };
 
+/// Env vars that tasks always get in strict mode so that they can find
+/// binaries and a shell
+pub(crate) const DEFAULT_PASS_THROUGH_ENV: &[&str] = &[
+    "SHELL",
+    // Command Prompt casing of env variables
+    "PATH",
+    "SYSTEMROOT",
+    // Powershell casing of env variables
+    "Path",
+    "SystemRoot",
+];
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("missing pipeline entry {0}")]
@@ -63,10 +75,19 @@ impl TaskHashable<'_> {
 pub struct PackageInputsHashes {
     hashes: HashMap<TaskId<'static>, String>,
     expanded_hashes: HashMap<TaskId<'static>, FileHashes>,
+    dot_env: HashMap<TaskId<'static>, DotEnvMap>,
 }
 
 impl PackageInputsHashes {
-    #[tracing::instrument(skip(all_tasks, workspaces, task_definitions, repo_root, scm))]
+    #[tracing::instrument(skip(
+        all_tasks,
+        workspaces,
+        task_definitions,
+        repo_root,
+        scm,
+        global_dot_env
+    ))]
+    #[allow(clippy::too_many_arguments)]
     pub fn calculate_file_hashes<'a>(
         scm: &SCM,
         all_tasks: impl ParallelIterator<Item = &'a TaskNode>,
@@ -74,12 +95,14 @@ impl PackageInputsHashes {
         task_definitions: &HashMap<TaskId<'static>, TaskDefinition>,
         repo_root: &AbsoluteSystemPath,
         telemetry: &GenericEventBuilder,
+        dot_env_mode: Option<&str>,
+        global_dot_env: &DotEnvMap,
     ) -> Result<PackageInputsHashes, Error> {
         tracing::trace!(scm_manual=%scm.is_manual(), "scm running in {} mode", if scm.is_manual() { "manual" } else { "git" });
 
         let span = Span::current();
 
-        let (hashes, expanded_hashes): (HashMap<_, _>, HashMap<_, _>) = all_tasks
+        let (hashes, (expanded_hashes, dot_env)) = all_tasks
             .filter_map(|task| {
                 let span = tracing::info_span!(parent: &span, "calculate_file_hash", ?task);
                 let _enter = span.enter();
@@ -124,36 +147,51 @@ impl PackageInputsHashes {
                     Ok(hash_object) => hash_object,
                     Err(err) => return Some(Err(err.into())),
                 };
-                if let Some(dot_env) = &task_definition.dot_env {
-                    if !dot_env.is_empty() {
-                        let absolute_package_path = repo_root.resolve(package_path);
-                        let dot_env_object = match scm.hash_existing_of(
-                            &absolute_package_path,
-                            dot_env.iter().map(|p| p.to_anchored_system_path_buf()),
-                        ) {
-                            Ok(dot_env_object) => dot_env_object,
-                            Err(err) => return Some(Err(err.into())),
-                        };
-
-                        for (key, value) in dot_env_object {
-                            hash_object.insert(key, value);
-                        }
+                let dot_env_files = task_definition.dot_env_files(dot_env_mode);
+                if !dot_env_files.is_empty() {
+                    let absolute_package_path = repo_root.resolve(package_path);
+                    let dot_env_object = match scm.hash_existing_of(
+                        &absolute_package_path,
+                        dot_env_files
+                            .iter()
+                            .map(|p| p.to_anchored_system_path_buf()),
+                    ) {
+                        Ok(dot_env_object) => dot_env_object,
+                        Err(err) => return Some(Err(err.into())),
+                    };
+
+                    for (key, value) in dot_env_object {
+                        hash_object.insert(key, value);
                     }
                 }
 
+                // The task's own files take priority over the global ones
+                let dot_env = match DotEnvMap::load(dot_env_files.iter().map(|file| {
+                    let mut repo_relative = package_path.to_owned();
+                    repo_relative.push(file.to_anchored_system_path_buf());
+                    (
+                        repo_root.resolve(&repo_relative),
+                        repo_relative.to_unix().to_string(),
+                    )
+                })) {
+                    Ok(dot_env) => dot_env.with_base(global_dot_env),
+                    Err(err) => return Some(Err(err.into())),
+                };
+
                 let file_hashes = FileHashes(hash_object);
                 let hash = file_hashes.clone().hash();
 
                 Some(Ok((
                     (task_id.clone(), hash),
-                    (task_id.clone(), file_hashes),
+                    ((task_id.clone(), file_hashes), (task_id.clone(), dot_env)),
                 )))
             })
-            .collect::<Result<_, _>>()?;
+            .collect::<Result<(HashMap<_, _>, (HashMap<_, _>, HashMap<_, _>)), _>>()?;
 
         Ok(PackageInputsHashes {
             hashes,
             expanded_hashes,
+            dot_env,
         })
     }
 }
@@ -181,6 +219,7 @@ pub struct TaskHashTrackerState {
 /// Caches package-inputs hashes, and package-task hashes.
 pub struct TaskHasher<'a> {
     hashes: HashMap<TaskId<'static>, String>,
+    dot_env: HashMap<TaskId<'static>, DotEnvMap>,
     run_opts: &'a RunOpts,
     env_at_execution_start: &'a EnvironmentVariableMap,
     global_hash: &'a str,
@@ -197,9 +236,11 @@ impl<'a> TaskHasher<'a> {
         let PackageInputsHashes {
             hashes,
             expanded_hashes,
+            dot_env,
         } = package_inputs_hashes;
         Self {
             hashes,
+            dot_env,
             run_opts,
             env_at_execution_start,
             global_hash,
@@ -297,6 +338,7 @@ impl<'a> TaskHasher<'a> {
             by_source: BySource {
                 explicit: explicit_env_var_map,
                 matching: matching_env_var_map,
+                dot_env: self.dot_env.get(task_id).cloned().unwrap_or_default(),
             },
         };
 
@@ -318,6 +360,15 @@ impl<'a> TaskHasher<'a> {
         // We wrap in an Option to mimic Go's serialization of nullable values
         let optional_package_dir = (!is_root_package).then_some(package_dir);
 
+        // The task's own args are passed to its script before any pass through
+        // args, and are hashed the same way
+        let args = task_definition
+            .args
+            .iter()
+            .chain(&self.run_opts.pass_through_args)
+            .cloned()
+            .collect::<Vec<_>>();
+
         let task_hashable = TaskHashable {
             global_hash: self.global_hash,
             task_dependency_hashes,
@@ -327,7 +378,9 @@ impl<'a> TaskHasher<'a> {
             task: task_id.task(),
             outputs,
 
-            pass_through_args: &self.run_opts.pass_through_args,
+            pass_through_args: &args,
+            command: task_definition.command.as_deref(),
+            cwd: task_definition.cwd.as_ref().map(|cwd| cwd.as_str()),
             env: &task_definition.env,
             resolved_env_vars: hashable_env_pairs,
             pass_through_env: task_definition
@@ -393,48 +446,97 @@ impl<'a> TaskHasher<'a> {
         self.task_hash_tracker.clone()
     }
 
+    /// The environment the task's process is spawned with. Variables loaded
+    /// from `.env` files are added unless the environment already sets them.
     pub fn env(
         &self,
         task_id: &TaskId,
         task_env_mode: ResolvedEnvMode,
         task_definition: &TaskDefinition,
         global_env: &EnvironmentVariableMap,
+    ) -> Result<EnvironmentVariableMap, Error> {
+        let mut env =
+            self.env_without_dot_env(task_id, task_env_mode, task_definition, global_env)?;
+        if let Some(dot_env) = self.dot_env.get(task_id) {
+            for (key, value) in dot_env.vars.iter() {
+                env.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+
+        Ok(env)
+    }
+
+    fn env_without_dot_env(
+        &self,
+        task_id: &TaskId,
+        task_env_mode: ResolvedEnvMode,
+        task_definition: &TaskDefinition,
+        global_env: &EnvironmentVariableMap,
     ) -> Result<EnvironmentVariableMap, Error> {
         match task_env_mode {
             ResolvedEnvMode::Strict => {
                 let mut pass_through_env = EnvironmentVariableMap::default();
-                let default_env_var_pass_through_map =
-                    self.env_at_execution_start.from_wildcards(&[
-                        "SHELL",
-                        // Command Prompt casing of env variables
-                        "PATH",
-                        "SYSTEMROOT",
-                        // Powershell casing of env variables
-                        "Path",
-                        "SystemRoot",
-                    ])?;
-                let tracker_env = self
-                    .task_hash_tracker
-                    .env_vars(task_id)
-                    .ok_or_else(|| Error::MissingEnvVars(task_id.clone().into_owned()))?;
-
+                let default_env_var_pass_through_map = self
+                    .env_at_execution_start
+                    .from_wildcards(DEFAULT_PASS_THROUGH_ENV)?;
                 pass_through_env.union(&default_env_var_pass_through_map);
-                pass_through_env.union(global_env);
-                pass_through_env.union(&tracker_env.all);
-
-                let env_var_pass_through_map = self.env_at_execution_start.from_wildcards(
-                    task_definition
-                        .pass_through_env
-                        .as_deref()
-                        .unwrap_or_default(),
-                )?;
-                pass_through_env.union(&env_var_pass_through_map);
+                pass_through_env.union(&self.declared_env_without_dot_env(
+                    task_id,
+                    task_definition,
+                    global_env,
+                )?);
 
                 Ok(pass_through_env)
             }
             ResolvedEnvMode::Loose => Ok(self.env_at_execution_start.clone()),
         }
     }
+
+    /// The env vars a task declares in turbo.json, or that are inferred from
+    /// its framework, along with its dotEnv files. Unlike strict mode, this
+    /// leaves out the shell variables such as PATH. Only these are sent to
+    /// remote workers.
+    pub fn declared_env(
+        &self,
+        task_id: &TaskId,
+        task_definition: &TaskDefinition,
+        global_env: &EnvironmentVariableMap,
+    ) -> Result<EnvironmentVariableMap, Error> {
+        let mut env = self.declared_env_without_dot_env(task_id, task_definition, global_env)?;
+        if let Some(dot_env) = self.dot_env.get(task_id) {
+            for (key, value) in dot_env.vars.iter() {
+                env.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+
+        Ok(env)
+    }
+
+    fn declared_env_without_dot_env(
+        &self,
+        task_id: &TaskId,
+        task_definition: &TaskDefinition,
+        global_env: &EnvironmentVariableMap,
+    ) -> Result<EnvironmentVariableMap, Error> {
+        let tracker_env = self
+            .task_hash_tracker
+            .env_vars(task_id)
+            .ok_or_else(|| Error::MissingEnvVars(task_id.clone().into_owned()))?;
+
+        let mut env = EnvironmentVariableMap::default();
+        env.union(global_env);
+        env.union(&tracker_env.all);
+
+        let env_var_pass_through_map = self.env_at_execution_start.from_wildcards(
+            task_definition
+                .pass_through_env
+                .as_deref()
+                .unwrap_or_default(),
+        )?;
+        env.union(&env_var_pass_through_map);
+
+        Ok(env)
+    }
 }
 
 pub fn get_external_deps_hash(
@@ -0,0 +1,407 @@
+//! Versioned migrations for turbo.json files. Migrations rewrite the text of
+//! the file in place using `rewrite_json`, so any comments and formatting
+//! outside of the keys they touch are preserved. Every migration is a no-op
+//! on a file that has already been migrated, so they can safely be applied
+//! more than once.
+
+use jsonc_parser::ast::{Object, Value};
+use semver::Version;
+
+use crate::rewrite_json::{self, RewriteError};
+
+pub struct Migration {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// The version of turbo that made the change this migration applies
+    pub introduced_in: Version,
+    migrate: fn(&str) -> Result<String, RewriteError>,
+}
+
+impl Migration {
+    /// Applies the migration, returning `None` if it didn't change anything
+    pub fn apply(&self, text: &str) -> Result<Option<String>, RewriteError> {
+        let migrated = (self.migrate)(text)?;
+        Ok((migrated != text).then_some(migrated))
+    }
+}
+
+/// Every migration, in the order they were introduced
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        name: "migrate-env-var-dependencies",
+        description: "Move environment variables in \"dependsOn\" and \"globalDependencies\" to \
+                      \"env\" and \"globalEnv\"",
+        introduced_in: Version::new(1, 5, 0),
+        migrate: migrate_env_var_dependencies,
+    },
+    Migration {
+        name: "stabilize-env-mode",
+        description: "Rename \"experimentalPassThroughEnv\" and \
+                      \"experimentalGlobalPassThroughEnv\"",
+        introduced_in: Version::new(1, 10, 0),
+        migrate: stabilize_env_mode,
+    },
+];
+
+/// The migrations for changes made after the `from` version of turbo, or all
+/// of them if no version is given
+pub fn migrations_since(from: Option<&Version>) -> impl Iterator<Item = &'static Migration> + '_ {
+    MIGRATIONS
+        .iter()
+        .filter(move |migration| from.map_or(true, |from| migration.introduced_in > *from))
+}
+
+fn migrate_env_var_dependencies(text: &str) -> Result<String, RewriteError> {
+    let root = rewrite_json::get_root(text)?;
+    // Workspace configs can't set global options
+    let is_root_config = find_value(&root, &["extends"]).is_none();
+    let tasks = task_names(&root);
+
+    let mut text = text.to_string();
+    if is_root_config {
+        text = move_env_vars(&text, &["globalDependencies"], &["globalEnv"])?;
+    }
+    for task in &tasks {
+        text = move_env_vars(
+            &text,
+            &["pipeline", task.as_str(), "dependsOn"],
+            &["pipeline", task.as_str(), "env"],
+        )?;
+    }
+    Ok(text)
+}
+
+/// Moves any `$`-prefixed entries of the array at `dependencies` to the array
+/// at `env`, without the prefix. `env` must be a sibling of `dependencies`,
+/// which is removed if nothing else is left in it.
+fn move_env_vars(text: &str, dependencies: &[&str], env: &[&str]) -> Result<String, RewriteError> {
+    let root = rewrite_json::get_root(text)?;
+    let Some(current_dependencies) = find_value(&root, dependencies).and_then(strings) else {
+        return Ok(text.to_string());
+    };
+    let (env_vars, remaining_dependencies): (Vec<_>, Vec<_>) = current_dependencies
+        .into_iter()
+        .partition(|dependency| dependency.starts_with('$'));
+    if env_vars.is_empty() {
+        return Ok(text.to_string());
+    }
+
+    let mut new_env = find_value(&root, env).and_then(strings).unwrap_or_default();
+    for env_var in env_vars {
+        let env_var = env_var.trim_start_matches('$').to_string();
+        if !new_env.contains(&env_var) {
+            new_env.push(env_var);
+        }
+    }
+
+    let text = match find_value(&root, env) {
+        Some(_) => rewrite_json::set_path(text, env, &to_json(&new_env))?,
+        None => {
+            let (env_key, _) = env.split_last().expect("path must not be empty");
+            insert_member_after(text, dependencies, env_key, &to_json(&new_env))?
+        }
+    };
+    if remaining_dependencies.is_empty() {
+        remove_member(&text, dependencies)
+    } else {
+        rewrite_json::set_path(&text, dependencies, &to_json(&remaining_dependencies))
+    }
+}
+
+fn stabilize_env_mode(text: &str) -> Result<String, RewriteError> {
+    let tasks = task_names(&rewrite_json::get_root(text)?);
+
+    let mut text = rename_key(
+        text,
+        &["experimentalGlobalPassThroughEnv"],
+        "globalPassThroughEnv",
+    )?;
+    for task in &tasks {
+        text = rename_key(
+            &text,
+            &["pipeline", task.as_str(), "experimentalPassThroughEnv"],
+            "passThroughEnv",
+        )?;
+    }
+    Ok(text)
+}
+
+/// Renames the last key of `path`. If the new key is already set, the arrays
+/// under both keys are merged into the new key instead.
+fn rename_key(text: &str, path: &[&str], new_key: &str) -> Result<String, RewriteError> {
+    let root = rewrite_json::get_root(text)?;
+    let Some(old_value) = find_value(&root, path) else {
+        return Ok(text.to_string());
+    };
+    let (_, parent) = path.split_last().expect("path must not be empty");
+    let mut new_path = parent.to_vec();
+    new_path.push(new_key);
+    let Some(new_value) = find_value(&root, &new_path) else {
+        return Ok(
+            rewrite_json::rename_path(text, path, new_key)?.unwrap_or_else(|| text.to_string())
+        );
+    };
+
+    let mut merged = strings(old_value)
+        .into_iter()
+        .chain(strings(new_value))
+        .flatten()
+        .collect::<Vec<_>>();
+    merged.sort();
+    merged.dedup();
+
+    let text = rewrite_json::set_path(text, &new_path, &to_json(&merged))?;
+    Ok(rewrite_json::unset_path(&text, path, true)?.unwrap_or(text))
+}
+
+/// Finds the value at `path`, using the last occurrence of any duplicated keys
+/// like the turbo.json parser does
+fn find_value<'a>(node: &'a Value<'a>, path: &[&str]) -> Option<&'a Value<'a>> {
+    let Some((key, remaining_path)) = path.split_first() else {
+        return Some(node);
+    };
+    let Value::Object(object) = node else {
+        return None;
+    };
+    let property = object
+        .properties
+        .iter()
+        .rev()
+        .find(|property| property.name.as_str() == *key)?;
+    find_value(&property.value, remaining_path)
+}
+
+/// Finds the object holding the member at `path` and the index of that
+/// member, using the last occurrence of any duplicated keys like `find_value`
+fn find_member<'a>(root: &'a Value<'a>, path: &[&str]) -> Option<(&'a Object<'a>, usize)> {
+    let (key, parent) = path.split_last()?;
+    let Some(Value::Object(object)) = find_value(root, parent) else {
+        return None;
+    };
+    let index = object
+        .properties
+        .iter()
+        .rposition(|property| property.name.as_str() == *key)?;
+    Some((object, index))
+}
+
+/// Adds a `key` member right after the member at `sibling`. If the sibling is
+/// on its own line the new member gets its own line with the same indentation.
+fn insert_member_after(
+    text: &str,
+    sibling: &[&str],
+    key: &str,
+    value: &str,
+) -> Result<String, RewriteError> {
+    let root = rewrite_json::get_root(text)?;
+    let Some((object, index)) = find_member(&root, sibling) else {
+        return Ok(text.to_string());
+    };
+    let sibling = &object.properties[index];
+
+    let line_start = text[..sibling.range.start]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1);
+    let indentation = &text[line_start..sibling.range.start];
+    let separator = if indentation.trim().is_empty() {
+        let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        format!("{newline}{indentation}")
+    } else {
+        " ".to_string()
+    };
+
+    let mut output = text.to_string();
+    output.insert_str(
+        sibling.range.end,
+        &format!(",{separator}\"{key}\": {value}"),
+    );
+    Ok(output)
+}
+
+/// Removes the member at `path` along with the comma and whitespace that
+/// separate it from its neighbors
+fn remove_member(text: &str, path: &[&str]) -> Result<String, RewriteError> {
+    let root = rewrite_json::get_root(text)?;
+    let Some((object, index)) = find_member(&root, path) else {
+        return Ok(text.to_string());
+    };
+    let member = &object.properties[index];
+    let previous = index
+        .checked_sub(1)
+        .map(|previous| &object.properties[previous]);
+    let range = match (previous, object.properties.get(index + 1)) {
+        // The next member moves into this one's place
+        (_, Some(next)) => member.range.start..next.range.start,
+        (Some(previous), None) => previous.range.end..member.range.end,
+        (None, None) => object.range.start + 1..object.range.end - 1,
+    };
+
+    let mut output = text.to_string();
+    output.replace_range(range, "");
+    Ok(output)
+}
+
+fn task_names(root: &Value) -> Vec<String> {
+    match find_value(root, &["pipeline"]) {
+        Some(Value::Object(pipeline)) => pipeline
+            .properties
+            .iter()
+            .map(|property| property.name.as_str().to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn strings(value: &Value) -> Option<Vec<String>> {
+    let Value::Array(array) = value else {
+        return None;
+    };
+    Some(
+        array
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                Value::StringLit(literal) => Some(literal.value.to_string()),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+fn to_json(values: &[String]) -> String {
+    serde_json::to_string(values).expect("strings are always serializable")
+}
+
+#[cfg(test)]
+mod test {
+    use semver::Version;
+    use test_case::test_case;
+
+    use super::{migrations_since, MIGRATIONS};
+
+    fn migrate(name: &str, text: &str) -> Option<String> {
+        MIGRATIONS
+            .iter()
+            .find(|migration| migration.name == name)
+            .unwrap()
+            .apply(text)
+            .unwrap()
+    }
+
+    #[test_case(None, &["migrate-env-var-dependencies", "stabilize-env-mode"] ; "all")]
+    #[test_case(Some("1.5.0"), &["stabilize-env-mode"] ; "excludes the from version")]
+    #[test_case(Some("1.10.0"), &[] ; "up to date")]
+    fn test_migrations_since(from: Option<&str>, expected: &[&str]) {
+        let from = from.map(|from| Version::parse(from).unwrap());
+        let names = migrations_since(from.as_ref())
+            .map(|migration| migration.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn test_stabilize_env_mode() {
+        let text = r#"{
+  // comments are kept
+  "experimentalGlobalPassThroughEnv": ["AWS_TOKEN"],
+  "pipeline": {
+    "build": {
+      "experimentalPassThroughEnv": ["NODE_ENV"],
+      "outputs": ["dist/**"]
+    },
+    "test": {
+      "experimentalPassThroughEnv": ["CI", "SECRET"],
+      "passThroughEnv": ["CI", "ANOTHER"]
+    }
+  }
+}"#;
+        let expected = r#"{
+  // comments are kept
+  "globalPassThroughEnv": ["AWS_TOKEN"],
+  "pipeline": {
+    "build": {
+      "passThroughEnv": ["NODE_ENV"],
+      "outputs": ["dist/**"]
+    },
+    "test": {
+      "passThroughEnv": ["ANOTHER","CI","SECRET"]
+    }
+  }
+}"#;
+        let migrated = migrate("stabilize-env-mode", text).unwrap();
+        assert_eq!(migrated, expected);
+        assert_eq!(migrate("stabilize-env-mode", &migrated), None);
+    }
+
+    #[test]
+    fn test_migrate_env_var_dependencies() {
+        let text = r#"{
+  "globalDependencies": ["$GLOBAL_ENV", "tsconfig.json"],
+  "globalEnv": ["OTHER"],
+  "pipeline": {
+    // build needs its env
+    "build": {
+      "dependsOn": ["^build", "$NODE_ENV"],
+      "env": ["NODE_ENV"]
+    },
+    "lint": {
+      "dependsOn": ["$LINT_MODE"]
+    }
+  }
+}"#;
+        let expected = r#"{
+  "globalDependencies": ["tsconfig.json"],
+  "globalEnv": ["OTHER","GLOBAL_ENV"],
+  "pipeline": {
+    // build needs its env
+    "build": {
+      "dependsOn": ["^build"],
+      "env": ["NODE_ENV"]
+    },
+    "lint": {
+      "env": ["LINT_MODE"]
+    }
+  }
+}"#;
+        let migrated = migrate("migrate-env-var-dependencies", text).unwrap();
+        assert_eq!(migrated, expected);
+        assert_eq!(migrate("migrate-env-var-dependencies", &migrated), None);
+    }
+
+    #[test_case(
+        r#"{ "globalDependencies": ["$A", "$B"], "pipeline": {} }"#,
+        r#"{ "globalEnv": ["A","B"], "pipeline": {} }"# ;
+        "only env vars"
+    )]
+    #[test_case(
+        r#"{ "pipeline": { "build": { "dependsOn": ["$A"], "outputs": [] } } }"#,
+        r#"{ "pipeline": { "build": { "env": ["A"], "outputs": [] } } }"# ;
+        "single line"
+    )]
+    #[test_case(
+        r#"{ "pipeline": { "build": { "outputs": [], "dependsOn": ["$A"], "env": ["B"] } } }"#,
+        r#"{ "pipeline": { "build": { "outputs": [], "env": ["B","A"] } } }"# ;
+        "between existing keys"
+    )]
+    #[test_case(
+        r#"{ "pipeline": { "build": { "env": ["B"], "dependsOn": ["$A"] } } }"#,
+        r#"{ "pipeline": { "build": { "env": ["B","A"] } } }"# ;
+        "last key"
+    )]
+    #[test_case(
+        "{\r\n  \"pipeline\": {\r\n    \"build\": {\r\n      \"dependsOn\": [\"^build\", \"$A\"]\r\n    }\r\n  }\r\n}",
+        "{\r\n  \"pipeline\": {\r\n    \"build\": {\r\n      \"dependsOn\": [\"^build\"],\r\n      \"env\": [\"A\"]\r\n    }\r\n  }\r\n}" ;
+        "keeps line endings"
+    )]
+    fn test_migrate_env_var_dependencies_layout(text: &str, expected: &str) {
+        let migrated = migrate("migrate-env-var-dependencies", text).unwrap();
+        assert_eq!(migrated, expected);
+        assert_eq!(migrate("migrate-env-var-dependencies", &migrated), None);
+    }
+
+    #[test]
+    fn test_workspace_globals_untouched() {
+        let text = r#"{ "extends": ["//"], "globalDependencies": ["$ENV"] }"#;
+        assert_eq!(migrate("migrate-env-var-dependencies", text), None);
+    }
+}
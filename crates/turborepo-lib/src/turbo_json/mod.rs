@@ -1,38 +1,114 @@
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
+    io,
     ops::{Deref, DerefMut},
     sync::Arc,
+    time::Duration,
 };
 
 use camino::Utf8Path;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use struct_iterable::Iterable;
 use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AnchoredSystemPath, RelativeUnixPathBuf};
+use turborepo_cache::{CacheActions, CacheConfig};
 use turborepo_errors::Spanned;
-use turborepo_repository::{package_graph::ROOT_PKG_NAME, package_json::PackageJson};
+use turborepo_repository::{
+    discovery::LocalPackageDiscoveryBuilder,
+    package_graph::{PackageGraph, PackageGraphBuilder, ROOT_PKG_NAME},
+    package_json::PackageJson,
+    workspace_provider::WorkspaceProviderKind,
+};
 
 use crate::{
-    cli::OutputLogsMode,
+    cli::{EnvMode, OutputLogsMode},
     config::{ConfigurationOptions, Error},
+    process::{ShutdownPolicy, StopSignal},
     run::{
         task_access::{TaskAccessTraceFile, TASK_ACCESS_CONFIG_PATH},
-        task_id::{TaskId, TaskName},
+        task_id::{TaskId, TaskName, TASK_DELIMITER},
     },
     task_graph::{TaskDefinition, TaskOutputs},
     unescape::UnescapedString,
 };
 
+pub mod migrations;
 pub mod parser;
+mod schema;
+
+pub use schema::turbo_json_schema;
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SpacesJson {
     pub id: Option<UnescapedString>,
     #[serde(flatten)]
+    #[schemars(skip)]
     pub other: Option<serde_json::Value>,
 }
 
+/// How a workspace's tasks can use a cache: `false`, `"read"`, `"write"` or
+/// `"readwrite"`. `true` is the same as `"readwrite"`.
+#[derive(Serialize, JsonSchema, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(untagged)]
+pub enum CacheActionsJson {
+    Enabled(bool),
+    Actions(CacheActionsName),
+}
+
+#[derive(Serialize, JsonSchema, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheActionsName {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl From<CacheActionsJson> for CacheActions {
+    fn from(value: CacheActionsJson) -> Self {
+        match value {
+            CacheActionsJson::Enabled(true)
+            | CacheActionsJson::Actions(CacheActionsName::ReadWrite) => CacheActions::default(),
+            CacheActionsJson::Enabled(false) => CacheActions::DISABLED,
+            CacheActionsJson::Actions(CacheActionsName::Read) => CacheActions {
+                read: true,
+                write: false,
+            },
+            CacheActionsJson::Actions(CacheActionsName::Write) => CacheActions {
+                read: false,
+                write: true,
+            },
+        }
+    }
+}
+
+/// The caches the tasks of a workspace can use. Settings that aren't set are
+/// inherited from the root turbo.json, and otherwise both caches can be read
+/// from and written to. These only narrow what the run allows, e.g.
+/// `--remote-only` still skips the local cache.
+#[derive(Serialize, JsonSchema, Debug, Default, PartialEq, Eq, Clone, Copy, Iterable)]
+pub struct CacheJson {
+    /// How the local filesystem cache can be used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local: Option<CacheActionsJson>,
+    /// How the remote cache can be used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<CacheActionsJson>,
+}
+
+impl CacheJson {
+    /// Overrides the parts of `config` that are set in this turbo.json
+    pub fn apply_to(&self, config: &mut CacheConfig) {
+        if let Some(local) = self.local {
+            config.local = local.into();
+        }
+        if let Some(remote) = self.remote {
+            config.remote = remote.into();
+        }
+    }
+}
+
 // A turbo.json config that is synthesized but not yet resolved.
 // This means that we've done the work to synthesize the config from
 // package.json, but we haven't yet resolved the workspace
@@ -43,17 +119,19 @@ pub struct SpacesJson {
 pub struct TurboJson {
     text: Option<Arc<str>>,
     path: Option<Arc<str>>,
-    pub(crate) extends: Spanned<Vec<String>>,
+    pub(crate) extends: Spanned<Vec<Spanned<String>>>,
     pub(crate) global_deps: Vec<String>,
     pub(crate) global_dot_env: Option<Vec<RelativeUnixPathBuf>>,
     pub(crate) global_env: Vec<String>,
     pub(crate) global_pass_through_env: Option<Vec<String>>,
     pub(crate) pipeline: Pipeline,
+    pub(crate) cache: CacheJson,
     pub(crate) remote_cache: Option<ConfigurationOptions>,
     pub(crate) space_id: Option<String>,
+    pub(crate) workspace_providers: Vec<WorkspaceProviderKind>,
 }
 
-#[derive(Serialize, Default, Debug, PartialEq, Clone, Iterable)]
+#[derive(Serialize, JsonSchema, Default, Debug, PartialEq, Clone, Iterable)]
 #[serde(rename_all = "camelCase")]
 // The raw deserialized turbo.json file.
 pub struct RawTurboJson {
@@ -68,28 +146,40 @@ pub struct RawTurboJson {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub experimental_spaces: Option<SpacesJson>,
+    /// The configurations this workspace turbo.json extends. Currently only
+    /// `["//"]`, the root turbo.json, is supported.
     #[serde(skip_serializing_if = "Option::is_none")]
-    extends: Option<Spanned<Vec<UnescapedString>>>,
-    // Global root filesystem dependencies
+    extends: Option<Spanned<Vec<Spanned<UnescapedString>>>>,
+    /// Global root filesystem dependencies
     #[serde(skip_serializing_if = "Option::is_none")]
     global_dependencies: Option<Vec<Spanned<UnescapedString>>>,
+    /// Environment variables that affect the hash of every task
     #[serde(skip_serializing_if = "Option::is_none")]
     global_env: Option<Vec<Spanned<UnescapedString>>>,
+    /// Environment variables that are available to every task in strict env
+    /// mode without affecting their hashes
     #[serde(skip_serializing_if = "Option::is_none")]
     global_pass_through_env: Option<Vec<Spanned<UnescapedString>>>,
-    // .env files to consider, in order.
+    /// .env files to consider, in order.
     #[serde(skip_serializing_if = "Option::is_none")]
     global_dot_env: Option<Vec<UnescapedString>>,
-    // Pipeline is a map of Turbo pipeline entries which define the task graph
-    // and cache behavior on a per task or per package-task basis.
+    /// Pipeline is a map of Turbo pipeline entries which define the task graph
+    /// and cache behavior on a per task or per package-task basis.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pipeline: Option<Pipeline>,
-    // Configuration options when interfacing with the remote cache
+    /// The caches this workspace's tasks can use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) cache: Option<CacheJson>,
+    /// Configuration options when interfacing with the remote cache
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) remote_cache: Option<ConfigurationOptions>,
+    /// Other tools whose workspaces are included alongside package.json
+    /// workspaces
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workspace_providers: Option<Vec<Spanned<UnescapedString>>>,
 }
 
-#[derive(Serialize, Default, Debug, PartialEq, Clone)]
+#[derive(Serialize, JsonSchema, Default, Debug, PartialEq, Clone)]
 #[serde(transparent)]
 pub struct Pipeline(BTreeMap<TaskName<'static>, Spanned<RawTaskDefinition>>);
 
@@ -117,27 +207,83 @@ impl DerefMut for Pipeline {
     }
 }
 
-#[derive(Serialize, Default, Debug, PartialEq, Clone, Iterable)]
+#[derive(Serialize, JsonSchema, Default, Debug, PartialEq, Clone, Iterable)]
 #[serde(rename_all = "camelCase")]
 pub struct RawTaskDefinition {
+    /// Arguments passed to the task's script
+    #[serde(skip_serializing_if = "Option::is_none")]
+    args: Option<Spanned<Vec<UnescapedString>>>,
+    /// Whether the task's outputs and logs should be cached
     #[serde(skip_serializing_if = "Spanned::is_none")]
+    #[schemars(with = "Option<bool>")]
     cache: Spanned<Option<bool>>,
+    /// A command to run instead of a package.json script
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<Spanned<UnescapedString>>,
+    /// The directory `command` is run in, relative to the package
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cwd: Option<Spanned<UnescapedString>>,
+    /// The tasks that must complete before this one runs. A `^` prefix refers
+    /// to the task in the package's dependencies.
     #[serde(skip_serializing_if = "Option::is_none")]
     depends_on: Option<Spanned<Vec<UnescapedString>>>,
+    /// .env files that affect the task's hash, in order.
     #[serde(skip_serializing_if = "Option::is_none")]
     dot_env: Option<Spanned<Vec<UnescapedString>>>,
+    /// Whether the package's `.env`, `.env.local`, `.env.{mode}` and
+    /// `.env.{mode}.local` files are loaded after the `dotEnv` files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dot_env_cascade: Option<Spanned<bool>>,
+    /// Environment variables that affect the task's hash
     #[serde(skip_serializing_if = "Option::is_none")]
     env: Option<Vec<Spanned<UnescapedString>>>,
+    /// Which environment variables are available to the task
+    #[serde(skip_serializing_if = "Option::is_none")]
+    env_mode: Option<Spanned<EnvMode>>,
+    /// Globs of the files that affect the task's hash. Defaults to every file
+    /// in the package that isn't ignored by git.
     #[serde(skip_serializing_if = "Option::is_none")]
     inputs: Option<Vec<Spanned<UnescapedString>>>,
+    /// Environment variables that are available to the task in strict env
+    /// mode without affecting its hash
     #[serde(skip_serializing_if = "Option::is_none")]
     pass_through_env: Option<Vec<Spanned<UnescapedString>>>,
+    /// Whether the task is long-running, like a dev server. Other tasks can't
+    /// depend on persistent tasks.
     #[serde(skip_serializing_if = "Option::is_none")]
     persistent: Option<Spanned<bool>>,
+    /// Globs of the files the task produces, which are cached
     #[serde(skip_serializing_if = "Option::is_none")]
     outputs: Option<Vec<Spanned<UnescapedString>>>,
+    /// Which logs of the task are printed
     #[serde(skip_serializing_if = "Option::is_none")]
     output_mode: Option<Spanned<OutputLogsMode>>,
+    /// The signal the task is sent when it's asked to stop
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_signal: Option<Spanned<StopSignal>>,
+    /// How long to wait after `stopSignal` before killing the task, in
+    /// milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_grace_period: Option<Spanned<u64>>,
+    /// Whether `stopSignal` is sent to the task's whole process group
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_process_group: Option<Spanned<bool>>,
+    /// Named variants of the task that are run as `<task>:<variant>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variants: Option<TaskVariants>,
+}
+
+// Named variants of a task, that are run as `<task>:<variant>`
+#[derive(Serialize, JsonSchema, Default, Debug, PartialEq, Clone)]
+#[serde(transparent)]
+pub struct TaskVariants(BTreeMap<String, Spanned<RawTaskDefinition>>);
+
+impl Deref for TaskVariants {
+    type Target = BTreeMap<String, Spanned<RawTaskDefinition>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
 }
 
 macro_rules! set_field {
@@ -162,12 +308,95 @@ impl RawTaskDefinition {
         set_field!(self, other, output_mode);
         set_field!(self, other, persistent);
         set_field!(self, other, env);
+        set_field!(self, other, env_mode);
         set_field!(self, other, pass_through_env);
         set_field!(self, other, dot_env);
+        set_field!(self, other, dot_env_cascade);
+        set_field!(self, other, stop_signal);
+        set_field!(self, other, stop_grace_period);
+        set_field!(self, other, stop_process_group);
+        set_field!(self, other, args);
+        set_field!(self, other, variants);
+        set_field!(self, other, command);
+        set_field!(self, other, cwd);
+    }
+
+    fn has_variant(&self, variant: &str) -> bool {
+        self.variants
+            .as_ref()
+            .is_some_and(|variants| variants.contains_key(variant))
+    }
+
+    /// Whether the task runs its own `command` rather than a package.json
+    /// script
+    pub(crate) fn has_command(&self) -> bool {
+        self.command.is_some()
     }
+
+    /// The `inputs` and `outputs` globs of the task along with the key each
+    /// glob is listed under
+    pub(crate) fn globs(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        let inputs = self
+            .inputs
+            .iter()
+            .flatten()
+            .map(|glob| ("inputs", &*glob.value));
+        let outputs = self
+            .outputs
+            .iter()
+            .flatten()
+            .map(|glob| ("outputs", &*glob.value));
+        inputs.chain(outputs)
+    }
+}
+
+/// Splits a task name like `test:ci` into the task it's a variant of and the
+/// name of the variant
+pub fn split_variant<'a>(task_name: &'a TaskName) -> Option<(TaskName<'static>, &'a str)> {
+    let (task, variant) = task_name.task().rsplit_once(':')?;
+    let base = match task_name.package() {
+        Some(package) => TaskName::from(format!("{package}{TASK_DELIMITER}{task}")),
+        None => TaskName::from(task.to_string()),
+    };
+    Some((base, variant))
+}
+
+/// Resolves a variant from the definitions of the task it's a variant of, in
+/// the order they are merged. Each declaration of the variant is merged after
+/// all of them, with its `args` appended to the ones of the task. Returns
+/// `None` if none of the definitions declare the variant.
+pub fn resolve_variant(
+    definitions: Vec<RawTaskDefinition>,
+    variant: &str,
+) -> Option<Vec<RawTaskDefinition>> {
+    let task_args = definitions
+        .iter()
+        .rev()
+        .find_map(|definition| definition.args.as_ref())
+        .map(|args| args.value.clone())
+        .unwrap_or_default();
+    let variants = definitions
+        .iter()
+        .filter_map(|definition| definition.variants.as_ref()?.get(variant))
+        .map(|variant| {
+            let mut variant = variant.value.clone();
+            if let Some(args) = &mut variant.args {
+                args.value = task_args
+                    .iter()
+                    .cloned()
+                    .chain(args.value.drain(..))
+                    .collect();
+            }
+            variant
+        })
+        .collect::<Vec<_>>();
+    if variants.is_empty() {
+        return None;
+    }
+    Some(definitions.into_iter().chain(variants).collect())
 }
 
-const CONFIG_FILE: &str = "turbo.json";
+pub(crate) const CONFIG_FILE: &str = "turbo.json";
 const ENV_PIPELINE_DELIMITER: &str = "$";
 const TOPOLOGICAL_PIPELINE_DELIMITER: &str = "^";
 
@@ -278,6 +507,27 @@ impl TryFrom<RawTaskDefinition> for TaskDefinition {
             })
             .collect::<Result<Vec<_>, _>>()?;
 
+        let cwd = raw_task
+            .cwd
+            .map(|cwd| {
+                let (span, text) = cwd.span_and_text();
+                let cwd = cwd.into_inner();
+                // The task runs in this directory, so it has to stay inside of the workspace
+                if cwd.split(['/', '\\']).any(|component| component == "..") {
+                    return Err(Error::ParentDirInConfig {
+                        field: "cwd",
+                        span,
+                        text,
+                    });
+                }
+                RelativeUnixPathBuf::new(cwd).map_err(|_| Error::AbsolutePathInConfig {
+                    field: "cwd",
+                    span,
+                    text,
+                })
+            })
+            .transpose()?;
+
         let pass_through_env = raw_task
             .pass_through_env
             .map(|env| -> Result<Vec<String>, Error> {
@@ -304,17 +554,47 @@ impl TryFrom<RawTaskDefinition> for TaskDefinition {
             })
             .transpose()?;
 
+        let default_shutdown = ShutdownPolicy::default();
+        let shutdown = ShutdownPolicy {
+            signal: raw_task
+                .stop_signal
+                .map_or(default_shutdown.signal, |signal| signal.into_inner()),
+            grace_period: raw_task
+                .stop_grace_period
+                .map_or(default_shutdown.grace_period, |millis| {
+                    Duration::from_millis(millis.into_inner())
+                }),
+            process_group: raw_task
+                .stop_process_group
+                .map_or(default_shutdown.process_group, |process_group| {
+                    process_group.into_inner()
+                }),
+        };
+
         Ok(TaskDefinition {
             outputs,
             cache: cache.into_inner().unwrap_or(true),
+            // Cache config is set per workspace rather than per task, so it's
+            // resolved when the task graph is built
+            cache_config: Default::default(),
             topological_dependencies,
             task_dependencies,
             env,
+            env_mode: raw_task.env_mode.map(|env_mode| env_mode.into_inner()),
             inputs,
             pass_through_env,
             dot_env,
+            dot_env_cascade: *raw_task.dot_env_cascade.unwrap_or_default(),
             output_mode: *raw_task.output_mode.unwrap_or_default(),
             persistent: *raw_task.persistent.unwrap_or_default(),
+            shutdown,
+            args: raw_task
+                .args
+                .map(|args| args.into_inner().into_iter().map(String::from).collect())
+                .unwrap_or_default(),
+            script: None,
+            command: raw_task.command.map(|command| command.into_inner().into()),
+            cwd,
         })
     }
 }
@@ -459,17 +739,33 @@ impl TryFrom<RawTurboJson> for TurboJson {
                 })
                 .transpose()?,
             pipeline: raw_turbo.pipeline.unwrap_or_default(),
+            cache: raw_turbo.cache.unwrap_or_default(),
             // copy these over, we don't need any changes here.
             remote_cache: raw_turbo.remote_cache,
             extends: raw_turbo
                 .extends
                 .unwrap_or_default()
-                .map(|s| s.into_iter().map(|s| s.into()).collect()),
+                .map(|s| s.into_iter().map(|s| s.map(|s| s.into())).collect()),
             // Directly to space_id, we don't need to keep the struct
             space_id: raw_turbo
                 .experimental_spaces
                 .and_then(|s| s.id)
                 .map(|s| s.into()),
+            workspace_providers: raw_turbo
+                .workspace_providers
+                .into_iter()
+                .flatten()
+                .map(|provider| {
+                    provider.parse::<WorkspaceProviderKind>().map_err(|_| {
+                        let (span, text) = provider.span_and_text();
+                        Error::UnknownWorkspaceProvider {
+                            provider: provider.into_inner().into(),
+                            span,
+                            text,
+                        }
+                    })
+                })
+                .collect::<Result<_, _>>()?,
         })
     }
 }
@@ -583,6 +879,32 @@ impl TurboJson {
         raw_turbo_json.try_into()
     }
 
+    /// Reads the `workspaceProviders` of the root turbo.json. A repository
+    /// without a root turbo.json has none.
+    pub fn load_workspace_providers(
+        repo_root: &AbsoluteSystemPath,
+    ) -> Result<Vec<WorkspaceProviderKind>, Error> {
+        match Self::read(
+            repo_root,
+            &AnchoredSystemPath::empty().join_component(CONFIG_FILE),
+        ) {
+            Ok(turbo_json) => Ok(turbo_json.workspace_providers),
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether `task_name` is defined, either directly or as a variant of a
+    /// task that declares it
+    pub fn has_task(&self, task_name: &TaskName) -> bool {
+        self.pipeline.contains_key(task_name)
+            || split_variant(task_name).is_some_and(|(task, variant)| {
+                self.pipeline
+                    .get(&task)
+                    .is_some_and(|definition| definition.has_variant(variant))
+            })
+    }
+
     pub fn task(&self, task_id: &TaskId, task_name: &TaskName) -> Option<RawTaskDefinition> {
         match self.pipeline.get(&task_id.as_task_name()) {
             Some(entry) => Some(entry.value.clone()),
@@ -603,6 +925,24 @@ impl TurboJson {
 
 type TurboJSONValidation = fn(&TurboJson) -> Vec<Error>;
 
+/// Starts building the package graph of the repository at `repo_root`. Besides
+/// the workspaces of its package manager, the graph has the ones found by the
+/// `workspaceProviders` listed in its root turbo.json.
+pub fn package_graph_builder(
+    repo_root: &AbsoluteSystemPath,
+    root_package_json: PackageJson,
+) -> Result<PackageGraphBuilder<'_, LocalPackageDiscoveryBuilder>, Error> {
+    let workspace_providers = TurboJson::load_workspace_providers(repo_root)?;
+    Ok(
+        PackageGraph::builder(repo_root, root_package_json).with_workspace_providers(
+            workspace_providers
+                .iter()
+                .map(|kind| kind.provider())
+                .collect(),
+        ),
+    )
+}
+
 pub fn validate_no_package_task_syntax(turbo_json: &TurboJson) -> Vec<Error> {
     turbo_json
         .pipeline
@@ -620,19 +960,31 @@ pub fn validate_no_package_task_syntax(turbo_json: &TurboJson) -> Vec<Error> {
         .collect()
 }
 
+// The workspaces that are extended from are resolved by the engine builder,
+// once the package graph is known
 pub fn validate_extends(turbo_json: &TurboJson) -> Vec<Error> {
-    match turbo_json.extends.first() {
-        Some(package_name) if package_name != ROOT_PKG_NAME || turbo_json.extends.len() > 1 => {
-            let (span, text) = turbo_json.extends.span_and_text();
-            vec![Error::ExtendFromNonRoot { span, text }]
-        }
-        None => vec![Error::NoExtends {
+    match turbo_json.extends.is_empty() {
+        true => vec![Error::NoExtends {
+            path: turbo_json
+                .path
+                .as_ref()
+                .map_or_else(|| "turbo.json".to_string(), |p| p.to_string()),
+        }],
+        false => vec![],
+    }
+}
+
+// Workspaces are discovered before any workspace's turbo.json is read, so
+// only the root turbo.json can add more of them
+pub fn validate_no_workspace_providers(turbo_json: &TurboJson) -> Vec<Error> {
+    match turbo_json.workspace_providers.is_empty() {
+        true => vec![],
+        false => vec![Error::WorkspaceProvidersOutsideRoot {
             path: turbo_json
                 .path
                 .as_ref()
                 .map_or_else(|| "turbo.json".to_string(), |p| p.to_string()),
         }],
-        _ => vec![],
     }
 }
 
@@ -664,7 +1016,7 @@ fn gather_env_vars(
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
+    use std::{fs, time::Duration};
 
     use anyhow::Result;
     use biome_deserialize::json::deserialize_from_json_str;
@@ -674,11 +1026,18 @@ mod tests {
     use tempfile::tempdir;
     use test_case::test_case;
     use turbopath::{AbsoluteSystemPath, AnchoredSystemPath, RelativeUnixPathBuf};
-    use turborepo_repository::package_json::PackageJson;
+    use turborepo_cache::{CacheActions, CacheConfig};
+    use turborepo_repository::{
+        package_json::PackageJson, workspace_provider::WorkspaceProviderKind,
+    };
 
-    use super::{Pipeline, RawTurboJson, Spanned};
+    use super::{
+        resolve_variant, split_variant, validate_no_workspace_providers, CacheActionsJson,
+        CacheActionsName, CacheJson, Pipeline, RawTurboJson, Spanned,
+    };
     use crate::{
-        cli::OutputLogsMode,
+        cli::{EnvMode, OutputLogsMode},
+        process::{ShutdownPolicy, StopSignal},
         run::task_id::TaskName,
         task_graph::{TaskDefinition, TaskOutputs},
         turbo_json::{RawTaskDefinition, TurboJson},
@@ -704,6 +1063,21 @@ mod tests {
             ..TurboJson::default()
         }
     )]
+    #[test_case(r#"{ "workspaceProviders": ["cargo", "go"] }"#,
+        TurboJson {
+            workspace_providers: vec![WorkspaceProviderKind::Cargo, WorkspaceProviderKind::Go],
+            ..TurboJson::default()
+        }
+    ; "workspace providers")]
+    #[test_case(r#"{ "cache": { "remote": "read", "local": false } }"#,
+        TurboJson {
+            cache: CacheJson {
+                local: Some(CacheActionsJson::Enabled(false)),
+                remote: Some(CacheActionsJson::Actions(CacheActionsName::Read)),
+            },
+            ..TurboJson::default()
+        }
+    ; "cache")]
     fn test_get_root_turbo_no_synthesizing(
         turbo_json_content: &str,
         expected_turbo_json: TurboJson,
@@ -842,6 +1216,74 @@ mod tests {
         }
         ; "empty dotenv"
     )]
+    #[test_case(
+        r#"{ "dotEnvCascade": true }"#,
+        RawTaskDefinition {
+            dot_env_cascade: Some(Spanned::new(true).with_range(19..23)),
+            ..RawTaskDefinition::default()
+        },
+        TaskDefinition {
+            dot_env_cascade: true,
+            ..Default::default()
+        }
+        ; "dotenv cascade"
+    )]
+    #[test_case(
+        r#"{ "envMode": "strict" }"#,
+        RawTaskDefinition {
+            env_mode: Some(Spanned::new(EnvMode::Strict).with_range(13..21)),
+            ..RawTaskDefinition::default()
+        },
+        TaskDefinition {
+            env_mode: Some(EnvMode::Strict),
+            ..Default::default()
+        }
+        ; "env mode"
+    )]
+    #[test_case(
+        r#"{ "stopSignal": "SIGTERM", "stopGracePeriod": 5000, "stopProcessGroup": false }"#,
+        RawTaskDefinition {
+            stop_signal: Some(Spanned::new(StopSignal::Terminate).with_range(16..25)),
+            stop_grace_period: Some(Spanned::new(5000).with_range(46..50)),
+            stop_process_group: Some(Spanned::new(false).with_range(72..77)),
+            ..RawTaskDefinition::default()
+        },
+        TaskDefinition {
+            shutdown: ShutdownPolicy {
+                signal: StopSignal::Terminate,
+                grace_period: Duration::from_secs(5),
+                process_group: false,
+            },
+            ..Default::default()
+        }
+        ; "shutdown policy"
+    )]
+    #[test_case(
+        r#"{ "args": ["--ci"] }"#,
+        RawTaskDefinition {
+            args: Some(Spanned::new(vec!["--ci".into()]).with_range(10..18)),
+            ..RawTaskDefinition::default()
+        },
+        TaskDefinition {
+            args: vec!["--ci".to_string()],
+            ..Default::default()
+        }
+        ; "args"
+    )]
+    #[test_case(
+        r#"{ "command": "tsc --noEmit", "cwd": "src" }"#,
+        RawTaskDefinition {
+            command: Some(Spanned::new(UnescapedString::from("tsc --noEmit")).with_range(13..27)),
+            cwd: Some(Spanned::new(UnescapedString::from("src")).with_range(36..41)),
+            ..RawTaskDefinition::default()
+        },
+        TaskDefinition {
+            command: Some("tsc --noEmit".to_string()),
+            cwd: Some(RelativeUnixPathBuf::new("src").unwrap()),
+            ..Default::default()
+        }
+        ; "command"
+    )]
     #[test_case(
         r#"{
           "dependsOn": ["cli#build"],
@@ -864,6 +1306,15 @@ mod tests {
             inputs: Some(vec![Spanned::<UnescapedString>::new("package/a/src/**".into()).with_range(241..259)]),
             output_mode: Some(Spanned::new(OutputLogsMode::Full).with_range(286..292)),
             persistent: Some(Spanned::new(true).with_range(318..322)),
+            env_mode: None,
+            dot_env_cascade: None,
+            stop_signal: None,
+            stop_grace_period: None,
+            stop_process_group: None,
+            args: None,
+            variants: None,
+            command: None,
+            cwd: None,
         },
         TaskDefinition {
           dot_env: Some(vec![RelativeUnixPathBuf::new("package/a/.env").unwrap()]),
@@ -879,6 +1330,13 @@ mod tests {
           task_dependencies: vec!["cli#build".into()],
           topological_dependencies: vec![],
           persistent: true,
+          env_mode: None,
+          dot_env_cascade: false,
+          shutdown: ShutdownPolicy::default(),
+          args: vec![],
+          script: None,
+          command: None,
+          cwd: None,
         }
       ; "full"
     )]
@@ -904,6 +1362,15 @@ mod tests {
             inputs: Some(vec![Spanned::<UnescapedString>::new("package\\a\\src\\**".into()).with_range(273..294)]),
             output_mode: Some(Spanned::new(OutputLogsMode::Full).with_range(325..331)),
             persistent: Some(Spanned::new(true).with_range(361..365)),
+            env_mode: None,
+            dot_env_cascade: None,
+            stop_signal: None,
+            stop_grace_period: None,
+            stop_process_group: None,
+            args: None,
+            variants: None,
+            command: None,
+            cwd: None,
         },
         TaskDefinition {
             dot_env: Some(vec![RelativeUnixPathBuf::new("package\\a\\.env").unwrap()]),
@@ -919,6 +1386,13 @@ mod tests {
             task_dependencies: vec!["cli#build".into()],
             topological_dependencies: vec![],
             persistent: true,
+            env_mode: None,
+            dot_env_cascade: false,
+            shutdown: ShutdownPolicy::default(),
+            args: vec![],
+            script: None,
+            command: None,
+            cwd: None,
         }
       ; "full (windows)"
     )]
@@ -941,6 +1415,25 @@ mod tests {
         Ok(())
     }
 
+    #[test_case(r#"{ "cwd": "src/.." }"#, true ; "trailing parent dir")]
+    #[test_case(r#"{ "cwd": "../other" }"#, true ; "parent dir")]
+    #[test_case(r#"{ "cwd": "..\\other" }"#, true ; "parent dir (windows)")]
+    #[test_case(r#"{ "cwd": "src/..config" }"#, false ; "dots in name")]
+    fn test_cwd_parent_dir(task_definition_content: &str, is_rejected: bool) {
+        let raw_task_definition: RawTaskDefinition = deserialize_from_json_str(
+            task_definition_content,
+            JsonParserOptions::default().with_allow_comments(),
+        )
+        .into_deserialized()
+        .unwrap();
+
+        let task_definition = TaskDefinition::try_from(raw_task_definition);
+        assert_eq!(
+            matches!(task_definition, Err(Error::ParentDirInConfig { .. })),
+            is_rejected
+        );
+    }
+
     #[test_case("[]", TaskOutputs::default() ; "empty")]
     #[test_case(r#"["target/**"]"#, TaskOutputs { inclusions: vec!["target/**".to_string()], exclusions: vec![] })]
     #[test_case(
@@ -974,6 +1467,78 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_load_workspace_providers() -> Result<()> {
+        let root_dir = tempdir()?;
+        let repo_root = AbsoluteSystemPath::from_std_path(root_dir.path())?;
+        assert!(TurboJson::load_workspace_providers(repo_root)?.is_empty());
+
+        fs::write(
+            repo_root.join_component("turbo.json"),
+            r#"{ "workspaceProviders": ["go"] }"#,
+        )?;
+        assert_eq!(
+            TurboJson::load_workspace_providers(repo_root)?,
+            vec![WorkspaceProviderKind::Go]
+        );
+
+        Ok(())
+    }
+
+    #[test_case(json!({ "extends": ["//"] }), false ; "without providers")]
+    #[test_case(json!({ "extends": ["//"], "workspaceProviders": ["cargo"] }), true ; "with providers")]
+    fn test_validate_no_workspace_providers(
+        workspace_json: serde_json::Value,
+        is_rejected: bool,
+    ) -> Result<()> {
+        let turbo_json = TurboJson::try_from(RawTurboJson::parse_from_serde(workspace_json)?)?;
+        let errors = turbo_json.validate(&[validate_no_workspace_providers]);
+        assert_eq!(!errors.is_empty(), is_rejected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_variant() -> Result<()> {
+        let turbo_json = TurboJson::try_from(RawTurboJson::parse_from_serde(json!({
+            "pipeline": {
+                "test": {
+                    "args": ["--reporter=dot"],
+                    "outputs": ["coverage/**"],
+                    "variants": {
+                        "ci": { "args": ["--ci"], "cache": false },
+                        "watch": { "persistent": true },
+                    }
+                },
+            }
+        }))?)?;
+        assert!(turbo_json.has_task(&TaskName::from("test:ci")));
+        assert!(turbo_json.has_task(&TaskName::from("web#test:watch")));
+        assert!(!turbo_json.has_task(&TaskName::from("test:e2e")));
+
+        let (task, variant) = split_variant(&TaskName::from("web#test:ci")).unwrap();
+        assert_eq!(task, TaskName::from("web#test"));
+        assert_eq!(variant, "ci");
+
+        let test = turbo_json.pipeline.get(&TaskName::from("test")).unwrap();
+        let resolve = |variant| -> Result<TaskDefinition> {
+            let chain = resolve_variant(vec![test.value.clone()], variant).unwrap();
+            Ok(RawTaskDefinition::from_iter(chain).try_into()?)
+        };
+
+        let ci = resolve("ci")?;
+        assert_eq!(ci.args, vec!["--reporter=dot", "--ci"]);
+        assert_eq!(ci.outputs.inclusions, vec!["coverage/**"]);
+        assert!(!ci.cache);
+
+        let watch = resolve("watch")?;
+        assert_eq!(watch.args, vec!["--reporter=dot"]);
+        assert!(watch.persistent);
+
+        assert!(resolve_variant(vec![test.value.clone()], "e2e").is_none());
+        Ok(())
+    }
+
     #[test]
     fn test_turbo_task_pruning() {
         let json = RawTurboJson::parse_from_serde(json!({
@@ -1034,4 +1599,51 @@ mod tests {
             .map(|mode| mode.into_inner());
         assert_eq!(actual, expected);
     }
+
+    #[test_case(json!(false), Some(CacheActions::DISABLED) ; "disabled")]
+    #[test_case(json!(true), Some(CacheActions::default()) ; "enabled")]
+    #[test_case(json!("read"), Some(CacheActions { read: true, write: false }) ; "read")]
+    #[test_case(json!("write"), Some(CacheActions { read: false, write: true }) ; "write")]
+    #[test_case(json!("readwrite"), Some(CacheActions::default()) ; "readwrite")]
+    #[test_case(json!("junk"), None ; "invalid value")]
+    fn test_parsing_cache_actions(remote: serde_json::Value, expected: Option<CacheActions>) {
+        let json = RawTurboJson::parse_from_serde(json!({
+            "cache": {
+                "remote": remote,
+            }
+        }));
+
+        let actual = json
+            .ok()
+            .and_then(|json| json.cache)
+            .and_then(|cache| cache.remote)
+            .map(CacheActions::from);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_cache_json_overrides() {
+        let mut config = CacheConfig::default();
+        CacheJson {
+            local: None,
+            remote: Some(CacheActionsJson::Actions(CacheActionsName::Read)),
+        }
+        .apply_to(&mut config);
+        CacheJson {
+            local: Some(CacheActionsJson::Enabled(false)),
+            remote: None,
+        }
+        .apply_to(&mut config);
+
+        assert_eq!(
+            config,
+            CacheConfig {
+                local: CacheActions::DISABLED,
+                remote: CacheActions {
+                    read: true,
+                    write: false,
+                },
+            }
+        );
+    }
 }
@@ -21,10 +21,14 @@ use turbopath::AnchoredSystemPath;
 use turborepo_errors::WithMetadata;
 
 use crate::{
-    cli::OutputLogsMode,
+    cli::{EnvMode, OutputLogsMode},
     config::ConfigurationOptions,
+    process::StopSignal,
     run::task_id::TaskName,
-    turbo_json::{Pipeline, RawTaskDefinition, RawTurboJson, SpacesJson, Spanned},
+    turbo_json::{
+        CacheActionsJson, CacheActionsName, CacheJson, Pipeline, RawTaskDefinition, RawTurboJson,
+        SpacesJson, Spanned, TaskVariants,
+    },
     unescape::UnescapedString,
 };
 
@@ -121,6 +125,66 @@ impl Deserializable for OutputLogsMode {
     }
 }
 
+impl Deserializable for EnvMode {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        let env_mode_str = String::deserialize(value, name, diagnostics)?;
+        match EnvMode::from_str(&env_mode_str, false) {
+            Ok(result) => Some(result),
+            Err(_) => {
+                let allowed_variants: Vec<_> = EnvMode::value_variants()
+                    .iter()
+                    .map(|s| serde_json::to_string(s).unwrap())
+                    .collect();
+
+                let allowed_variants_borrowed = allowed_variants
+                    .iter()
+                    .map(|s| s.as_str())
+                    .collect::<Vec<_>>();
+
+                diagnostics.push(DeserializationDiagnostic::new_unknown_value(
+                    &env_mode_str,
+                    value.range(),
+                    &allowed_variants_borrowed,
+                ));
+                None
+            }
+        }
+    }
+}
+
+impl Deserializable for StopSignal {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        let signal_str = String::deserialize(value, name, diagnostics)?;
+        match StopSignal::ALL
+            .iter()
+            .find(|signal| signal.as_str() == signal_str)
+        {
+            Some(signal) => Some(*signal),
+            None => {
+                let allowed_variants: Vec<_> = StopSignal::ALL
+                    .iter()
+                    .map(|signal| signal.as_str())
+                    .collect();
+
+                diagnostics.push(DeserializationDiagnostic::new_unknown_value(
+                    &signal_str,
+                    value.range(),
+                    &allowed_variants,
+                ));
+                None
+            }
+        }
+    }
+}
+
 impl Deserializable for TaskName<'static> {
     fn deserialize(
         value: &impl DeserializableValue,
@@ -186,6 +250,45 @@ impl Deserializable for RawTaskDefinition {
 
 struct RawTaskDefinitionVisitor;
 
+impl Deserializable for TaskVariants {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        value.deserialize(TaskVariantsVisitor, name, diagnostics)
+    }
+}
+
+struct TaskVariantsVisitor;
+
+impl DeserializationVisitor for TaskVariantsVisitor {
+    type Output = TaskVariants;
+
+    const EXPECTED_TYPE: VisitableType = VisitableType::MAP;
+
+    fn visit_map(
+        self,
+        members: impl Iterator<Item = Option<(impl DeserializableValue, impl DeserializableValue)>>,
+        _range: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        let mut result = BTreeMap::new();
+        for (key, value) in members.flatten() {
+            let range = value.range();
+            let variant: String = UnescapedString::deserialize(&key, "", diagnostics)?.into();
+            result.insert(
+                variant,
+                Spanned::new(RawTaskDefinition::deserialize(&value, "", diagnostics)?)
+                    .with_range(range),
+            );
+        }
+
+        Some(TaskVariants(result))
+    }
+}
+
 impl DeserializationVisitor for RawTaskDefinitionVisitor {
     type Output = RawTaskDefinition;
 
@@ -207,11 +310,29 @@ impl DeserializationVisitor for RawTaskDefinitionVisitor {
             };
             let range = value.range();
             match key_text.text() {
+                "args" => {
+                    if let Some(args) = Vec::deserialize(&value, &key_text, diagnostics) {
+                        result.args = Some(Spanned::new(args).with_range(range));
+                    }
+                }
                 "cache" => {
                     if let Some(cache) = bool::deserialize(&value, &key_text, diagnostics) {
                         result.cache = Spanned::new(Some(cache)).with_range(range);
                     }
                 }
+                "command" => {
+                    if let Some(command) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.command = Some(Spanned::new(command).with_range(range));
+                    }
+                }
+                "cwd" => {
+                    if let Some(cwd) = UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.cwd = Some(Spanned::new(cwd).with_range(range));
+                    }
+                }
                 "dependsOn" => {
                     if let Some(depends_on) = Vec::deserialize(&value, &key_text, diagnostics) {
                         result.depends_on = Some(Spanned::new(depends_on).with_range(range));
@@ -222,11 +343,23 @@ impl DeserializationVisitor for RawTaskDefinitionVisitor {
                         result.dot_env = Some(Spanned::new(dot_env).with_range(range));
                     }
                 }
+                "dotEnvCascade" => {
+                    if let Some(dot_env_cascade) = bool::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.dot_env_cascade =
+                            Some(Spanned::new(dot_env_cascade).with_range(range));
+                    }
+                }
                 "env" => {
                     if let Some(env) = Vec::deserialize(&value, &key_text, diagnostics) {
                         result.env = Some(env);
                     }
                 }
+                "envMode" => {
+                    if let Some(env_mode) = EnvMode::deserialize(&value, &key_text, diagnostics) {
+                        result.env_mode = Some(Spanned::new(env_mode).with_range(range));
+                    }
+                }
                 "inputs" => {
                     if let Some(inputs) = Vec::deserialize(&value, &key_text, diagnostics) {
                         result.inputs = Some(inputs);
@@ -255,6 +388,32 @@ impl DeserializationVisitor for RawTaskDefinitionVisitor {
                         result.output_mode = Some(Spanned::new(output_mode).with_range(range));
                     }
                 }
+                "stopSignal" => {
+                    if let Some(stop_signal) =
+                        StopSignal::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.stop_signal = Some(Spanned::new(stop_signal).with_range(range));
+                    }
+                }
+                "stopGracePeriod" => {
+                    if let Some(grace_period) = u64::deserialize(&value, &key_text, diagnostics) {
+                        result.stop_grace_period =
+                            Some(Spanned::new(grace_period).with_range(range));
+                    }
+                }
+                "stopProcessGroup" => {
+                    if let Some(process_group) = bool::deserialize(&value, &key_text, diagnostics) {
+                        result.stop_process_group =
+                            Some(Spanned::new(process_group).with_range(range));
+                    }
+                }
+                "variants" => {
+                    if let Some(variants) =
+                        TaskVariants::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.variants = Some(variants);
+                    }
+                }
                 unknown_key => {
                     diagnostics.push(create_unknown_key_diagnostic_from_struct(
                         &result,
@@ -310,6 +469,104 @@ impl DeserializationVisitor for SpacesJsonVisitor {
     }
 }
 
+impl Deserializable for CacheJson {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        value.deserialize(CacheJsonVisitor, name, diagnostics)
+    }
+}
+
+struct CacheJsonVisitor;
+
+impl DeserializationVisitor for CacheJsonVisitor {
+    type Output = CacheJson;
+
+    const EXPECTED_TYPE: VisitableType = VisitableType::MAP;
+
+    fn visit_map(
+        self,
+        members: impl Iterator<Item = Option<(impl DeserializableValue, impl DeserializableValue)>>,
+        _range: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        let mut result = CacheJson::default();
+        for (key, value) in members.flatten() {
+            let Some(key_text) = Text::deserialize(&key, "", diagnostics) else {
+                continue;
+            };
+            match key_text.text() {
+                "local" => {
+                    result.local = CacheActionsJson::deserialize(&value, &key_text, diagnostics);
+                }
+                "remote" => {
+                    result.remote = CacheActionsJson::deserialize(&value, &key_text, diagnostics);
+                }
+                unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
+                    &result,
+                    unknown_key,
+                    key.range(),
+                )),
+            }
+        }
+
+        Some(result)
+    }
+}
+
+impl Deserializable for CacheActionsJson {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        value.deserialize(CacheActionsJsonVisitor, name, diagnostics)
+    }
+}
+
+struct CacheActionsJsonVisitor;
+
+impl DeserializationVisitor for CacheActionsJsonVisitor {
+    type Output = CacheActionsJson;
+
+    const EXPECTED_TYPE: VisitableType = VisitableType::BOOL.union(VisitableType::STR);
+
+    fn visit_bool(
+        self,
+        value: bool,
+        _range: TextRange,
+        _name: &str,
+        _diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        Some(CacheActionsJson::Enabled(value))
+    }
+
+    fn visit_str(
+        self,
+        value: Text,
+        range: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        match value.text() {
+            "read" => Some(CacheActionsJson::Actions(CacheActionsName::Read)),
+            "write" => Some(CacheActionsJson::Actions(CacheActionsName::Write)),
+            "readwrite" => Some(CacheActionsJson::Actions(CacheActionsName::ReadWrite)),
+            unknown_value => {
+                diagnostics.push(DeserializationDiagnostic::new_unknown_value(
+                    unknown_value,
+                    range,
+                    &["read", "write", "readwrite"],
+                ));
+                None
+            }
+        }
+    }
+}
+
 impl Deserializable for ConfigurationOptions {
     fn deserialize(
         value: &impl DeserializableValue,
@@ -385,6 +642,11 @@ impl DeserializationVisitor for ConfigurationOptionsVisitor {
                         result.signature = Some(signature);
                     }
                 }
+                "encryption" => {
+                    if let Some(encryption) = bool::deserialize(&value, &key_text, diagnostics) {
+                        result.encryption = Some(encryption);
+                    }
+                }
                 "preflight" => {
                     if let Some(preflight) = bool::deserialize(&value, &key_text, diagnostics) {
                         result.preflight = Some(preflight);
@@ -395,6 +657,16 @@ impl DeserializationVisitor for ConfigurationOptionsVisitor {
                         result.timeout = Some(timeout);
                     }
                 }
+                "retries" => {
+                    if let Some(retries) = u32::deserialize(&value, &key_text, diagnostics) {
+                        result.retries = Some(retries);
+                    }
+                }
+                "maxFailures" => {
+                    if let Some(max_failures) = u32::deserialize(&value, &key_text, diagnostics) {
+                        result.max_failures = Some(max_failures);
+                    }
+                }
                 "enabled" => {
                     if let Some(enabled) = bool::deserialize(&value, &key_text, diagnostics) {
                         result.enabled = Some(enabled);
@@ -493,6 +765,18 @@ impl DeserializationVisitor for RawTurboJsonVisitor {
                         result.pipeline = Some(pipeline);
                     }
                 }
+                "workspaceProviders" => {
+                    if let Some(workspace_providers) =
+                        Vec::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.workspace_providers = Some(workspace_providers);
+                    }
+                }
+                "cache" => {
+                    if let Some(cache) = CacheJson::deserialize(&value, &key_text, diagnostics) {
+                        result.cache = Some(cache);
+                    }
+                }
                 "remoteCache" => {
                     if let Some(remote_cache) =
                         ConfigurationOptions::deserialize(&value, &key_text, diagnostics)
@@ -516,19 +800,27 @@ impl DeserializationVisitor for RawTurboJsonVisitor {
 impl WithMetadata for RawTurboJson {
     fn add_text(&mut self, text: Arc<str>) {
         self.text = Some(text.clone());
-        self.extends.add_text(text.clone());
+        if let Some(extends) = &mut self.extends {
+            extends.add_text(text.clone());
+            extends.value.add_text(text.clone());
+        }
         self.global_dependencies.add_text(text.clone());
         self.global_env.add_text(text.clone());
         self.global_pass_through_env.add_text(text.clone());
+        self.workspace_providers.add_text(text.clone());
         self.pipeline.add_text(text);
     }
 
     fn add_path(&mut self, path: Arc<str>) {
         self.path = Some(path.clone());
-        self.extends.add_path(path.clone());
+        if let Some(extends) = &mut self.extends {
+            extends.add_path(path.clone());
+            extends.value.add_path(path.clone());
+        }
         self.global_dependencies.add_path(path.clone());
         self.global_env.add_path(path.clone());
         self.global_pass_through_env.add_path(path.clone());
+        self.workspace_providers.add_path(path.clone());
         self.pipeline.add_path(path);
     }
 }
@@ -549,27 +841,61 @@ impl WithMetadata for Pipeline {
     }
 }
 
+impl WithMetadata for TaskVariants {
+    fn add_text(&mut self, text: Arc<str>) {
+        for (_, entry) in self.0.iter_mut() {
+            entry.add_text(text.clone());
+            entry.value.add_text(text.clone());
+        }
+    }
+
+    fn add_path(&mut self, path: Arc<str>) {
+        for (_, entry) in self.0.iter_mut() {
+            entry.add_path(path.clone());
+            entry.value.add_path(path.clone());
+        }
+    }
+}
+
 impl WithMetadata for RawTaskDefinition {
     fn add_text(&mut self, text: Arc<str>) {
+        self.args.add_text(text.clone());
+        self.command.add_text(text.clone());
+        self.cwd.add_text(text.clone());
         self.depends_on.add_text(text.clone());
         self.dot_env.add_text(text.clone());
+        self.dot_env_cascade.add_text(text.clone());
         self.env.add_text(text.clone());
+        self.env_mode.add_text(text.clone());
         self.inputs.add_text(text.clone());
         self.pass_through_env.add_text(text.clone());
         self.persistent.add_text(text.clone());
         self.outputs.add_text(text.clone());
-        self.output_mode.add_text(text);
+        self.output_mode.add_text(text.clone());
+        self.stop_signal.add_text(text.clone());
+        self.stop_grace_period.add_text(text.clone());
+        self.stop_process_group.add_text(text.clone());
+        self.variants.add_text(text);
     }
 
     fn add_path(&mut self, path: Arc<str>) {
+        self.args.add_path(path.clone());
+        self.command.add_path(path.clone());
+        self.cwd.add_path(path.clone());
         self.depends_on.add_path(path.clone());
         self.dot_env.add_path(path.clone());
+        self.dot_env_cascade.add_path(path.clone());
         self.env.add_path(path.clone());
+        self.env_mode.add_path(path.clone());
         self.inputs.add_path(path.clone());
         self.pass_through_env.add_path(path.clone());
         self.persistent.add_path(path.clone());
         self.outputs.add_path(path.clone());
-        self.output_mode.add_path(path);
+        self.output_mode.add_path(path.clone());
+        self.stop_signal.add_path(path.clone());
+        self.stop_grace_period.add_path(path.clone());
+        self.stop_process_group.add_path(path.clone());
+        self.variants.add_path(path);
     }
 }
 
@@ -0,0 +1,78 @@
+use schemars::{gen::SchemaSettings, schema::RootSchema};
+
+use crate::turbo_json::RawTurboJson;
+
+/// The JSON schema for turbo.json. It's generated from the types turbo.json is
+/// parsed into, so it always matches what this version of turbo accepts.
+pub fn turbo_json_schema() -> RootSchema {
+    let generator = SchemaSettings::draft07()
+        .with(|settings| {
+            // Options can be omitted, but turbo doesn't accept `null` for them
+            settings.option_nullable = false;
+            settings.option_add_null_type = false;
+        })
+        .into_generator();
+    let mut schema = generator.into_root_schema_for::<RawTurboJson>();
+    schema.schema.metadata().title = Some("turbo.json".to_string());
+    schema
+}
+
+#[cfg(test)]
+mod test {
+    use convert_case::{Case, Casing};
+    use serde_json::Value;
+    use struct_iterable::Iterable;
+
+    use super::turbo_json_schema;
+    use crate::turbo_json::RawTaskDefinition;
+
+    fn schema() -> Value {
+        serde_json::to_value(turbo_json_schema()).unwrap()
+    }
+
+    #[test]
+    fn test_task_definition_keys_match_parser() {
+        // The parser reports the camel cased field names as the allowed keys
+        let expected = RawTaskDefinition::default()
+            .iter()
+            .map(|(field, _)| field.to_case(Case::Camel))
+            .collect::<Vec<_>>();
+        let schema = schema();
+        let mut actual = schema["definitions"]["RawTaskDefinition"]["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        actual.sort();
+        let mut expected = expected;
+        expected.sort();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_root_keys() {
+        let schema = schema();
+        let properties = schema["properties"].as_object().unwrap();
+        for key in ["$schema", "extends", "globalEnv", "pipeline", "remoteCache"] {
+            assert!(properties.contains_key(key), "missing {key}");
+        }
+        // Internal fields aren't part of the file
+        assert!(!properties.contains_key("text"));
+        assert!(!properties.contains_key("path"));
+        assert_eq!(schema["required"], Value::Null);
+    }
+
+    #[test]
+    fn test_task_definition_values() {
+        let schema = schema();
+        let task = &schema["definitions"]["RawTaskDefinition"];
+        assert_eq!(task["required"], Value::Null);
+        assert_eq!(task["properties"]["cache"]["type"], "boolean");
+        assert_eq!(task["properties"]["outputs"]["type"], "array");
+        assert_eq!(
+            schema["definitions"]["EnvMode"]["enum"],
+            serde_json::json!(["infer", "loose", "strict"])
+        );
+    }
+}
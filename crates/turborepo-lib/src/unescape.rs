@@ -4,7 +4,17 @@ use biome_deserialize::{Deserializable, DeserializableValue, DeserializationDiag
 
 // We're using a newtype here because biome currently doesn't
 // handle escapes and we can't override the String deserializer
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Debug,
+    Clone,
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
 #[serde(transparent)]
 pub struct UnescapedString(String);
 
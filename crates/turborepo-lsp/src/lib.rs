@@ -65,7 +65,7 @@ impl LanguageServer for Backend {
             let (_, daemon) = tokio::join!(
                 self.client.log_message(
                     MessageType::INFO,
-                    format!("root uri: {}", hasher.sock_path()),
+                    format!("root uri: {}", DaemonRootHasher::sock_path()),
                 ),
                 tokio_retry::Retry::spawn(
                     tokio_retry::strategy::FixedInterval::from_millis(100).take(5),
@@ -73,8 +73,9 @@ impl LanguageServer for Backend {
                         let connector = DaemonConnector {
                             can_start_server: true,
                             can_kill_server: false,
-                            pid_file: hasher.lock_path(),
-                            sock_file: hasher.sock_path(),
+                            pid_file: DaemonRootHasher::lock_path(),
+                            sock_file: DaemonRootHasher::sock_path(),
+                            repo_root: repo_root.clone(),
                         };
                         connector.connect()
                     },
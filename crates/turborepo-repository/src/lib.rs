@@ -7,3 +7,4 @@ pub mod inference;
 pub mod package_graph;
 pub mod package_json;
 pub mod package_manager;
+pub mod workspace_provider;
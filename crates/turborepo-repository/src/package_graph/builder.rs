@@ -21,6 +21,7 @@ use crate::{
     },
     package_graph::{PackageName, PackageVersion},
     package_json::PackageJson,
+    workspace_provider::{ProvidedWorkspace, WorkspaceProvider, WorkspaceProviderKind},
 };
 
 pub struct PackageGraphBuilder<'a, T> {
@@ -30,6 +31,7 @@ pub struct PackageGraphBuilder<'a, T> {
     package_jsons: Option<HashMap<AbsoluteSystemPathBuf, PackageJson>>,
     lockfile: Option<Box<dyn Lockfile>>,
     package_discovery: T,
+    workspace_providers: Vec<Box<dyn WorkspaceProvider>>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -60,6 +62,12 @@ pub enum Error {
     Lockfile(#[from] turborepo_lockfiles::Error),
     #[error(transparent)]
     Discovery(#[from] crate::discovery::Error),
+    #[error("unable to discover {kind} workspaces: {source}")]
+    WorkspaceProvider {
+        kind: WorkspaceProviderKind,
+        #[source]
+        source: crate::workspace_provider::Error,
+    },
 }
 
 impl<'a> PackageGraphBuilder<'a, LocalPackageDiscoveryBuilder> {
@@ -75,6 +83,7 @@ impl<'a> PackageGraphBuilder<'a, LocalPackageDiscoveryBuilder> {
             is_single_package: false,
             package_jsons: None,
             lockfile: None,
+            workspace_providers: Vec::new(),
         }
     }
 }
@@ -100,6 +109,16 @@ impl<'a, P> PackageGraphBuilder<'a, P> {
         self
     }
 
+    /// Add workspaces found by other providers, like the crates in a Cargo
+    /// workspace, alongside the ones defined by `package.json` files.
+    pub fn with_workspace_providers(
+        mut self,
+        workspace_providers: Vec<Box<dyn WorkspaceProvider>>,
+    ) -> Self {
+        self.workspace_providers = workspace_providers;
+        self
+    }
+
     /// Set the package discovery strategy to use. Note that whatever strategy
     /// selected here will be wrapped in a `CachingPackageDiscovery` to
     /// prevent unnecessary work during building.
@@ -114,6 +133,7 @@ impl<'a, P> PackageGraphBuilder<'a, P> {
             package_jsons: self.package_jsons,
             lockfile: self.lockfile,
             package_discovery: discovery,
+            workspace_providers: self.workspace_providers,
         }
     }
 }
@@ -149,6 +169,7 @@ struct BuildState<'a, S, T> {
     node_lookup: HashMap<WorkspaceNode, NodeIndex>,
     lockfile: Option<Box<dyn Lockfile>>,
     package_jsons: Option<HashMap<AbsoluteSystemPathBuf, PackageJson>>,
+    workspace_providers: Vec<Box<dyn WorkspaceProvider>>,
     state: std::marker::PhantomData<S>,
     package_discovery: T,
 }
@@ -197,6 +218,7 @@ where
             package_jsons,
             lockfile,
             package_discovery,
+            workspace_providers,
         } = builder;
         let mut workspaces = HashMap::new();
         workspaces.insert(
@@ -215,6 +237,7 @@ where
             workspaces,
             lockfile,
             package_jsons,
+            workspace_providers,
             workspace_graph: Graph::new(),
             node_lookup: HashMap::new(),
             state: std::marker::PhantomData,
@@ -243,6 +266,48 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedPackageManager, T> {
             package_json_path: relative_json_path,
             ..Default::default()
         };
+        self.insert_workspace(name, entry)
+    }
+
+    fn add_provided_workspace(
+        &mut self,
+        kind: WorkspaceProviderKind,
+        workspace: ProvidedWorkspace,
+    ) -> Result<(), Error> {
+        let ProvidedWorkspace {
+            name,
+            version,
+            manifest_path,
+            internal_dependencies,
+            external_dependencies,
+        } = workspace;
+        // Dependencies on other workspaces are recorded in a synthesized
+        // package.json so they're connected the same way as JS workspaces
+        let package_json = PackageJson {
+            name: Some(name.clone()),
+            version,
+            dependencies: Some(
+                internal_dependencies
+                    .into_iter()
+                    .map(|dependency| (dependency, "*".to_string()))
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let entry = WorkspaceInfo {
+            package_json,
+            package_json_path: AnchoredSystemPathBuf::relative_path_between(
+                self.repo_root,
+                &manifest_path,
+            ),
+            transitive_dependencies: external_dependencies,
+            provider: Some(kind),
+            ..Default::default()
+        };
+        self.insert_workspace(WorkspaceName::Other(name), entry)
+    }
+
+    fn insert_workspace(&mut self, name: WorkspaceName, entry: WorkspaceInfo) -> Result<(), Error> {
         if let Some(existing) = self.workspaces.insert(name.clone(), entry) {
             let path = self
                 .workspaces
@@ -294,6 +359,16 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedPackageManager, T> {
             }
         }
 
+        for provider in std::mem::take(&mut self.workspace_providers) {
+            let kind = provider.kind();
+            let workspaces = provider
+                .discover(self.repo_root)
+                .map_err(|source| Error::WorkspaceProvider { kind, source })?;
+            for workspace in workspaces {
+                self.add_provided_workspace(kind, workspace)?;
+            }
+        }
+
         let Self {
             repo_root,
             single,
@@ -313,6 +388,7 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedPackageManager, T> {
             lockfile,
             package_discovery,
             package_jsons: None,
+            workspace_providers: Vec::new(),
             state: std::marker::PhantomData,
         })
     }
@@ -386,7 +462,10 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedWorkspaces, T> {
                 self.workspace_graph
                     .add_edge(*node_idx, *dependency_idx, ());
             }
-            entry.unresolved_external_dependencies = Some(external);
+            // Other providers resolve their external dependencies themselves
+            if entry.provider.is_none() {
+                entry.unresolved_external_dependencies = Some(external);
+            }
         }
 
         Ok(())
@@ -449,6 +528,7 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedWorkspaces, T> {
             node_lookup,
             lockfile,
             package_jsons: None,
+            workspace_providers: Vec::new(),
             state: std::marker::PhantomData,
             package_discovery,
         })
@@ -459,6 +539,7 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedLockfile, T> {
     fn all_external_dependencies(&self) -> Result<HashMap<String, HashMap<String, String>>, Error> {
         self.workspaces
             .values()
+            .filter(|entry| entry.provider.is_none())
             .map(|entry| {
                 let workspace_path = entry
                     .package_json_path
@@ -490,7 +571,11 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedLockfile, T> {
             lockfile,
             self.all_external_dependencies()?,
         )?;
-        for (_, entry) in self.workspaces.iter_mut() {
+        for (_, entry) in self
+            .workspaces
+            .iter_mut()
+            .filter(|(_, entry)| entry.provider.is_none())
+        {
             entry.transitive_dependencies = closures.remove(&entry.unix_dir_str()?);
         }
         Ok(())
@@ -747,6 +832,7 @@ mod test {
                     package_json_path: AnchoredSystemPathBuf::from_raw("unused").unwrap(),
                     unresolved_external_dependencies: None,
                     transitive_dependencies: None,
+                    provider: None,
                 },
             );
             map
@@ -808,4 +894,87 @@ mod test {
         }));
         assert_matches!(builder.build().await, Err(Error::DuplicateWorkspace { .. }))
     }
+
+    struct MockProvider;
+    impl WorkspaceProvider for MockProvider {
+        fn kind(&self) -> WorkspaceProviderKind {
+            WorkspaceProviderKind::Cargo
+        }
+
+        fn discover(
+            &self,
+            repo_root: &AbsoluteSystemPath,
+        ) -> Result<Vec<ProvidedWorkspace>, crate::workspace_provider::Error> {
+            Ok(vec![
+                ProvidedWorkspace {
+                    name: "cli".into(),
+                    version: Some("1.0.0".into()),
+                    manifest_path: repo_root.join_components(&["crates", "cli", "Cargo.toml"]),
+                    internal_dependencies: ["core".to_string()].into_iter().collect(),
+                    external_dependencies: Some(HashSet::new()),
+                },
+                ProvidedWorkspace {
+                    name: "core".into(),
+                    version: None,
+                    manifest_path: repo_root.join_components(&["crates", "core", "Cargo.toml"]),
+                    internal_dependencies: Default::default(),
+                    external_dependencies: Some(
+                        [turborepo_lockfiles::Package::new("serde", "1.0.197")]
+                            .into_iter()
+                            .collect(),
+                    ),
+                },
+            ])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_workspace_providers() {
+        let root =
+            AbsoluteSystemPathBuf::new(if cfg!(windows) { r"C:\repo" } else { "/repo" }).unwrap();
+        let package_graph = PackageGraphBuilder::new(
+            &root,
+            PackageJson {
+                name: Some("root".into()),
+                ..Default::default()
+            },
+        )
+        .with_package_discovery(MockDiscovery)
+        .with_package_jsons(Some(HashMap::new()))
+        .with_workspace_providers(vec![Box::new(MockProvider)])
+        .build()
+        .await
+        .unwrap();
+
+        assert_eq!(
+            package_graph.immediate_dependencies(&WorkspaceNode::Workspace("cli".into())),
+            Some(
+                [&WorkspaceNode::Workspace("core".into())]
+                    .into_iter()
+                    .collect()
+            )
+        );
+
+        let core = package_graph
+            .workspace_info(&WorkspaceName::from("core"))
+            .unwrap();
+        assert_eq!(core.provider, Some(WorkspaceProviderKind::Cargo));
+        assert_eq!(
+            core.package_path(),
+            AnchoredSystemPath::new(if cfg!(windows) {
+                r"crates\core"
+            } else {
+                "crates/core"
+            })
+            .unwrap()
+        );
+        assert_eq!(
+            core.transitive_dependencies,
+            Some(
+                [turborepo_lockfiles::Package::new("serde", "1.0.197")]
+                    .into_iter()
+                    .collect()
+            )
+        );
+    }
 }
@@ -11,7 +11,7 @@ use turborepo_lockfiles::Lockfile;
 
 use crate::{
     discovery::LocalPackageDiscoveryBuilder, package_json::PackageJson,
-    package_manager::PackageManager,
+    package_manager::PackageManager, workspace_provider::WorkspaceProviderKind,
 };
 
 pub mod builder;
@@ -36,6 +36,9 @@ pub struct WorkspaceInfo {
     pub package_json_path: AnchoredSystemPathBuf,
     pub unresolved_external_dependencies: Option<BTreeMap<PackageName, PackageVersion>>,
     pub transitive_dependencies: Option<HashSet<turborepo_lockfiles::Package>>,
+    /// The provider that discovered this workspace, or `None` if it's defined
+    /// by a `package.json`
+    pub provider: Option<WorkspaceProviderKind>,
 }
 
 impl WorkspaceInfo {
@@ -336,9 +339,11 @@ impl PackageGraph {
         } else {
             self.workspaces
                 .iter()
+                // Other providers' workspaces don't depend on the JS lockfile
                 .filter(|(_name, info)| {
-                    closures.get(info.package_path().to_unix().as_str())
-                        != info.transitive_dependencies.as_ref()
+                    info.provider.is_none()
+                        && closures.get(info.package_path().to_unix().as_str())
+                            != info.transitive_dependencies.as_ref()
                 })
                 .map(|(name, _info)| match name {
                     WorkspaceName::Other(n) => Some(WorkspaceName::Other(n.to_owned())),
@@ -1,6 +1,7 @@
 mod bun;
 mod npm;
 mod pnpm;
+mod version;
 mod yarn;
 
 use std::{
@@ -19,6 +20,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, PathError, RelativeUnixPath};
 use turborepo_lockfiles::Lockfile;
+pub use version::{PackageManagerVersion, PackageManagerVersionMismatch, PinnedPackageManager};
 use wax::{Any, Glob, Program};
 use which::which;
 
@@ -301,8 +303,9 @@ impl From<std::convert::Infallible> for Error {
     }
 }
 
-static PACKAGE_MANAGER_PATTERN: Lazy<Regex> =
-    lazy_regex!(r"(?P<manager>bun|npm|pnpm|yarn)@(?P<version>\d+\.\d+\.\d+(-.+)?)");
+static PACKAGE_MANAGER_PATTERN: Lazy<Regex> = lazy_regex!(
+    r"(?P<manager>bun|npm|pnpm|yarn)@(?P<version>\d+\.\d+\.\d+(-[^+]+)?)(\+(?P<hash>.+))?"
+);
 
 impl PackageManager {
     pub fn command(&self) -> &'static str {
@@ -737,6 +740,13 @@ mod tests {
                 expected_version: "1.2.3-alpha.1".to_owned(),
                 expected_error: false,
             },
+            TestCase {
+                name: "ignores the corepack hash".to_owned(),
+                package_manager: "pnpm@8.6.0-rc.1+sha256.abc123".to_owned(),
+                expected_manager: "pnpm".to_owned(),
+                expected_version: "8.6.0-rc.1".to_owned(),
+                expected_error: false,
+            },
             TestCase {
                 name: "only supports specified package managers".to_owned(),
                 package_manager: "pip@1.2.3".to_owned(),
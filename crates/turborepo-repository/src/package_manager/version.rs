@@ -0,0 +1,221 @@
+use std::process::Command;
+
+use node_semver::Version;
+use serde::Serialize;
+use tracing::debug;
+use turbopath::AbsoluteSystemPath;
+use which::which;
+
+use crate::{
+    package_json::PackageJson,
+    package_manager::{Error, PackageManager, PACKAGE_MANAGER_PATTERN},
+};
+
+/// The package manager pinned by the `packageManager` field of the root
+/// `package.json`. Following corepack, the field must name an exact version
+/// and can be followed by a hash of the package manager's archive, e.g.
+/// `pnpm@8.6.0+sha256.abc123`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedPackageManager {
+    pub name: String,
+    pub version: Version,
+    pub hash: Option<String>,
+}
+
+impl PinnedPackageManager {
+    pub fn read(package_json: &PackageJson) -> Result<Option<Self>, Error> {
+        package_json
+            .package_manager
+            .as_deref()
+            .map(Self::parse)
+            .transpose()
+    }
+
+    pub fn parse(package_manager: &str) -> Result<Self, Error> {
+        let captures = PACKAGE_MANAGER_PATTERN
+            .captures(package_manager)
+            .ok_or_else(|| {
+                Error::InvalidPackageManager(
+                    PACKAGE_MANAGER_PATTERN.to_string(),
+                    package_manager.to_string(),
+                )
+            })?;
+        Ok(Self {
+            name: captures["manager"].to_string(),
+            version: captures["version"].parse()?,
+            hash: captures.name("hash").map(|hash| hash.as_str().to_string()),
+        })
+    }
+}
+
+/// The version of the package manager pinned in `package.json` alongside the
+/// version of the binary that is actually installed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageManagerVersion {
+    pub name: &'static str,
+    pub pinned: Option<PinnedPackageManager>,
+    // `None` if nothing is pinned, in which case the binary isn't asked, or if
+    // the binary couldn't be found or didn't report a version
+    pub installed: Option<Version>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageManagerVersionMismatch {
+    pub pinned: String,
+    pub installed: String,
+}
+
+impl PackageManagerVersion {
+    pub fn detect(
+        package_manager: &PackageManager,
+        repo_root: &AbsoluteSystemPath,
+        root_package_json: &PackageJson,
+    ) -> Result<Self, Error> {
+        let pinned = PinnedPackageManager::read(root_package_json)?;
+        // Spawning the binary is only worth it when there's a pin to compare with
+        let installed = pinned.as_ref().and_then(|_| {
+            package_manager
+                .installed_version(repo_root)
+                .map_err(|e| debug!("unable to get {package_manager} version: {e}"))
+                .ok()
+        });
+        Ok(Self {
+            name: package_manager.command(),
+            pinned,
+            installed,
+        })
+    }
+
+    /// Returns the pinned and installed versions if the installed binary
+    /// doesn't match the `packageManager` field
+    pub fn mismatch(&self) -> Option<PackageManagerVersionMismatch> {
+        let pinned = self.pinned.as_ref()?;
+        let installed = self.installed.as_ref()?;
+        (pinned.name != self.name || pinned.version != *installed).then(|| {
+            PackageManagerVersionMismatch {
+                pinned: format!("{}@{}", pinned.name, pinned.version),
+                installed: format!("{}@{}", self.name, installed),
+            }
+        })
+    }
+
+    /// The package manager that will run tasks, e.g. `pnpm@8.6.0`. This is the
+    /// installed binary if it drifted from the `packageManager` field, or the
+    /// pinned version otherwise. Without a `packageManager` field there is
+    /// nothing to resolve against, so this is `None`.
+    pub fn resolved(&self) -> Option<String> {
+        let pinned = self.pinned.as_ref()?;
+        Some(match self.mismatch() {
+            Some(PackageManagerVersionMismatch { installed, .. }) => installed,
+            None => format!("{}@{}", pinned.name, pinned.version),
+        })
+    }
+}
+
+impl PackageManager {
+    /// Asks the package manager binary for its version. If corepack is
+    /// enabled its shims report the version pinned by `packageManager`, so
+    /// we run the binary from the repository root and forbid corepack from
+    /// downloading a missing version just to answer.
+    pub fn installed_version(&self, repo_root: &AbsoluteSystemPath) -> Result<Version, Error> {
+        let binary = which(self.command())?;
+        let output = Command::new(binary)
+            .arg("--version")
+            .current_dir(repo_root)
+            .env("COREPACK_ENABLE_NETWORK", "0")
+            .env("COREPACK_ENABLE_DOWNLOAD_PROMPT", "0")
+            .output()?;
+        let version = String::from_utf8(output.stdout)?;
+        Ok(version.trim().parse()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn version(
+        pinned: Option<&str>,
+        name: &'static str,
+        installed: Option<&str>,
+    ) -> PackageManagerVersion {
+        PackageManagerVersion {
+            name,
+            pinned: pinned.map(|pinned| PinnedPackageManager::parse(pinned).unwrap()),
+            installed: installed.map(|installed| installed.parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_parse_pinned_package_manager() {
+        assert_eq!(
+            PinnedPackageManager::parse("pnpm@8.6.0+sha256.abc123").unwrap(),
+            PinnedPackageManager {
+                name: "pnpm".to_string(),
+                version: "8.6.0".parse().unwrap(),
+                hash: Some("sha256.abc123".to_string()),
+            }
+        );
+        assert_eq!(
+            PinnedPackageManager::parse("yarn@4.0.0-rc.1").unwrap(),
+            PinnedPackageManager {
+                name: "yarn".to_string(),
+                version: "4.0.0-rc.1".parse().unwrap(),
+                hash: None,
+            }
+        );
+        assert!(PinnedPackageManager::parse("pnpm@^8").is_err());
+    }
+
+    #[test]
+    fn test_detect_without_pin() {
+        let repo_root = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPath::from_std_path(repo_root.path()).unwrap();
+        // Whether or not npm is installed, it isn't asked for its version
+        let version =
+            PackageManagerVersion::detect(&PackageManager::Npm, repo_root, &PackageJson::default())
+                .unwrap();
+        assert_eq!(version.pinned, None);
+        assert_eq!(version.installed, None);
+    }
+
+    #[test_case(Some("pnpm@8.6.0"), "pnpm", Some("8.6.0"), None ; "matching")]
+    #[test_case(Some("pnpm@8.6.0+sha256.abc123"), "pnpm", Some("8.6.0"), None ; "matching with hash")]
+    #[test_case(Some("pnpm@8.6.0"), "pnpm", Some("8.7.1"), Some(("pnpm@8.6.0", "pnpm@8.7.1")) ; "drifted")]
+    #[test_case(Some("yarn@1.22.19"), "npm", Some("10.2.0"), Some(("yarn@1.22.19", "npm@10.2.0")) ; "different manager")]
+    #[test_case(Some("pnpm@8.6.0"), "pnpm", None, None ; "not installed")]
+    #[test_case(None, "npm", Some("10.2.0"), None ; "not pinned")]
+    fn test_mismatch(
+        pinned: Option<&str>,
+        name: &'static str,
+        installed: Option<&str>,
+        expected: Option<(&str, &str)>,
+    ) {
+        assert_eq!(
+            version(pinned, name, installed).mismatch(),
+            expected.map(|(pinned, installed)| PackageManagerVersionMismatch {
+                pinned: pinned.to_string(),
+                installed: installed.to_string(),
+            })
+        );
+    }
+
+    #[test_case(Some("pnpm@8.6.0"), "pnpm", Some("8.6.0"), Some("pnpm@8.6.0") ; "matching")]
+    #[test_case(Some("pnpm@8.6.0"), "pnpm", Some("8.7.1"), Some("pnpm@8.7.1") ; "drifted")]
+    #[test_case(Some("pnpm@8.6.0"), "pnpm", None, Some("pnpm@8.6.0") ; "not installed")]
+    #[test_case(None, "npm", Some("10.2.0"), None ; "not pinned")]
+    fn test_resolved(
+        pinned: Option<&str>,
+        name: &'static str,
+        installed: Option<&str>,
+        expected: Option<&str>,
+    ) {
+        assert_eq!(
+            version(pinned, name, installed).resolved().as_deref(),
+            expected
+        );
+    }
+}
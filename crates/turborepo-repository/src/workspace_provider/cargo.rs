@@ -0,0 +1,398 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    str::FromStr,
+};
+
+use globwalk::ValidatedGlob;
+use serde::Deserialize;
+use turbopath::AbsoluteSystemPath;
+use turborepo_lockfiles::Package;
+
+use super::{
+    parse_error, read_to_string, Error, ProvidedWorkspace, WorkspaceProvider, WorkspaceProviderKind,
+};
+
+pub const MANIFEST: &str = "Cargo.toml";
+pub const LOCKFILE: &str = "Cargo.lock";
+
+/// Discovers the members of a Cargo workspace defined in the root
+/// `Cargo.toml`. The root package of a workspace is skipped since it's
+/// already covered by the root workspace.
+pub struct CargoProvider;
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    package: Option<ManifestPackage>,
+    workspace: Option<ManifestWorkspace>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, Dependency>,
+    #[serde(default, rename = "dev-dependencies")]
+    dev_dependencies: BTreeMap<String, Dependency>,
+    #[serde(default, rename = "build-dependencies")]
+    build_dependencies: BTreeMap<String, Dependency>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestPackage {
+    name: String,
+    // Either a version or `{ workspace = true }`
+    version: Option<toml::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestWorkspace {
+    #[serde(default)]
+    members: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+    package: Option<WorkspacePackage>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, Dependency>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkspacePackage {
+    version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Dependency {
+    Version(String),
+    Detailed {
+        // Set when the dependency is renamed
+        package: Option<String>,
+        #[serde(default)]
+        workspace: bool,
+    },
+}
+
+impl Manifest {
+    fn load(path: &AbsoluteSystemPath) -> Result<Option<Self>, Error> {
+        let Some(contents) = read_to_string(path)? else {
+            return Ok(None);
+        };
+        toml::from_str(&contents)
+            .map(Some)
+            .map_err(|e| parse_error(path, e))
+    }
+
+    /// The names of the crates this crate depends on
+    fn dependency_names<'a>(
+        &'a self,
+        workspace: &'a ManifestWorkspace,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.dependencies
+            .iter()
+            .chain(&self.dev_dependencies)
+            .chain(&self.build_dependencies)
+            .map(|(name, dependency)| {
+                let dependency = match dependency {
+                    Dependency::Detailed {
+                        workspace: true, ..
+                    } => workspace.dependencies.get(name).unwrap_or(dependency),
+                    _ => dependency,
+                };
+                match dependency {
+                    Dependency::Detailed {
+                        package: Some(package),
+                        ..
+                    } => package.as_str(),
+                    _ => name.as_str(),
+                }
+            })
+    }
+}
+
+impl WorkspaceProvider for CargoProvider {
+    fn kind(&self) -> WorkspaceProviderKind {
+        WorkspaceProviderKind::Cargo
+    }
+
+    fn discover(&self, repo_root: &AbsoluteSystemPath) -> Result<Vec<ProvidedWorkspace>, Error> {
+        let Some(workspace) =
+            Manifest::load(&repo_root.join_component(MANIFEST))?.and_then(|root| root.workspace)
+        else {
+            return Ok(Vec::new());
+        };
+
+        let inclusions = workspace
+            .members
+            .iter()
+            .map(|member| ValidatedGlob::from_str(&format!("{member}/{MANIFEST}")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let exclusions = workspace
+            .exclude
+            .iter()
+            .map(|exclude| ValidatedGlob::from_str(&format!("{exclude}/**")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut manifest_paths = globwalk::globwalk(
+            repo_root,
+            &inclusions,
+            &exclusions,
+            globwalk::WalkType::Files,
+        )?
+        .into_iter()
+        .filter(|path| path.parent() != Some(repo_root))
+        .collect::<Vec<_>>();
+        manifest_paths.sort();
+
+        let mut members = Vec::new();
+        for path in manifest_paths {
+            let Some(manifest) = Manifest::load(&path)? else {
+                continue;
+            };
+            if manifest.package.is_some() {
+                members.push((path, manifest));
+            }
+        }
+
+        let member_names = members
+            .iter()
+            .filter_map(|(_, manifest)| manifest.package.as_ref())
+            .map(|package| package.name.as_str())
+            .collect::<HashSet<_>>();
+        let lockfile = Lockfile::load(&repo_root.join_component(LOCKFILE))?;
+
+        let mut workspaces = Vec::with_capacity(members.len());
+        for (manifest_path, manifest) in &members {
+            let package = manifest.package.as_ref().expect("members have a package");
+            let version = match &package.version {
+                Some(toml::Value::String(version)) => Some(version.clone()),
+                Some(_) => workspace
+                    .package
+                    .as_ref()
+                    .and_then(|package| package.version.clone()),
+                None => None,
+            };
+            let internal_dependencies = manifest
+                .dependency_names(&workspace)
+                .filter(|name| member_names.contains(name) && *name != package.name)
+                .map(|name| name.to_string())
+                .collect::<BTreeSet<_>>();
+            let external_dependencies = lockfile.as_ref().map(|lockfile| {
+                lockfile.transitive_dependencies(&package.name, version.as_deref())
+            });
+
+            workspaces.push(ProvidedWorkspace {
+                name: package.name.clone(),
+                version,
+                manifest_path: manifest_path.clone(),
+                internal_dependencies,
+                external_dependencies,
+            });
+        }
+
+        Ok(workspaces)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Lockfile {
+    #[serde(default, rename = "package")]
+    packages: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    // Crates in the workspace don't have a source
+    source: Option<String>,
+    // Entries are `name`, `name version`, or `name version (source)`
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+impl Lockfile {
+    fn load(path: &AbsoluteSystemPath) -> Result<Option<Self>, Error> {
+        let Some(contents) = read_to_string(path)? else {
+            return Ok(None);
+        };
+        toml::from_str(&contents)
+            .map(Some)
+            .map_err(|e| parse_error(path, e))
+    }
+
+    /// Collects the external crates a workspace crate depends on. Crates
+    /// without a source belong to the workspace and aren't followed, their
+    /// dependencies are covered by their own hashes.
+    fn transitive_dependencies(&self, name: &str, version: Option<&str>) -> HashSet<Package> {
+        let mut by_name: HashMap<&str, Vec<&LockedPackage>> = HashMap::new();
+        for package in &self.packages {
+            by_name.entry(&package.name).or_default().push(package);
+        }
+
+        let mut dependencies = HashSet::new();
+        let Some(root) = by_name.get(name).and_then(|packages| {
+            packages.iter().find(|package| {
+                package.source.is_none()
+                    && version.map_or(true, |version| package.version == version)
+            })
+        }) else {
+            return dependencies;
+        };
+
+        let mut stack = vec![*root];
+        while let Some(package) = stack.pop() {
+            for dependency in &package.dependencies {
+                let mut parts = dependency.split_whitespace();
+                let Some(dependency_name) = parts.next() else {
+                    continue;
+                };
+                let version = parts.next();
+                let Some(resolved) = by_name.get(dependency_name).and_then(|packages| {
+                    packages
+                        .iter()
+                        .find(|package| version.map_or(true, |version| package.version == version))
+                }) else {
+                    continue;
+                };
+                if resolved.source.is_none() {
+                    continue;
+                }
+                if dependencies.insert(Package::new(&resolved.name, &resolved.version)) {
+                    stack.push(*resolved);
+                }
+            }
+        }
+
+        dependencies
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+    use crate::workspace_provider::write_file;
+
+    #[test]
+    fn test_discover_cargo_workspace() {
+        let tmp = TempDir::new().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        write_file(
+            &root,
+            "Cargo.toml",
+            r#"
+            [workspace]
+            members = ["crates/*"]
+            exclude = ["crates/ignored"]
+
+            [workspace.package]
+            version = "0.2.0"
+
+            [workspace.dependencies]
+            core = { path = "crates/core" }
+            "#,
+        );
+        write_file(
+            &root,
+            "crates/core/Cargo.toml",
+            r#"
+            [package]
+            name = "core"
+            version.workspace = true
+
+            [dependencies]
+            serde = "1"
+            "#,
+        );
+        write_file(
+            &root,
+            "crates/cli/Cargo.toml",
+            r#"
+            [package]
+            name = "cli"
+            version = "1.0.0"
+
+            [dependencies]
+            core = { workspace = true }
+            "#,
+        );
+        write_file(
+            &root,
+            "crates/ignored/Cargo.toml",
+            r#"
+            [package]
+            name = "ignored"
+            "#,
+        );
+        write_file(
+            &root,
+            "Cargo.lock",
+            r#"
+            version = 3
+
+            [[package]]
+            name = "cli"
+            version = "1.0.0"
+            dependencies = ["core"]
+
+            [[package]]
+            name = "core"
+            version = "0.2.0"
+            dependencies = ["serde"]
+
+            [[package]]
+            name = "serde"
+            version = "1.0.197"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            dependencies = ["serde_derive"]
+
+            [[package]]
+            name = "serde_derive"
+            version = "1.0.197"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            "#,
+        );
+
+        let workspaces = CargoProvider.discover(&root).unwrap();
+        let names = workspaces
+            .iter()
+            .map(|workspace| workspace.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["cli", "core"]);
+
+        let cli = &workspaces[0];
+        assert_eq!(cli.version.as_deref(), Some("1.0.0"));
+        assert_eq!(
+            cli.internal_dependencies,
+            ["core".to_string()].into_iter().collect()
+        );
+        assert_eq!(cli.external_dependencies, Some(HashSet::new()));
+
+        let core = &workspaces[1];
+        assert_eq!(core.version.as_deref(), Some("0.2.0"));
+        assert!(core.internal_dependencies.is_empty());
+        assert_eq!(
+            core.external_dependencies,
+            Some(
+                [
+                    Package::new("serde", "1.0.197"),
+                    Package::new("serde_derive", "1.0.197"),
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn test_no_cargo_workspace() {
+        let tmp = TempDir::new().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        write_file(
+            &root,
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "single"
+            "#,
+        );
+
+        assert!(CargoProvider.discover(&root).unwrap().is_empty());
+    }
+}
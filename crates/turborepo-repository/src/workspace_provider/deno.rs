@@ -0,0 +1,299 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use serde::Deserialize;
+use serde_json::Value;
+use turbopath::AbsoluteSystemPath;
+use turborepo_lockfiles::Package;
+
+use super::{
+    member_manifest, parse_error, read_to_string, Error, ProvidedWorkspace, WorkspaceProvider,
+    WorkspaceProviderKind,
+};
+
+pub const MANIFEST: &str = "deno.json";
+pub const LOCKFILE: &str = "deno.lock";
+
+/// Discovers the members listed in the `workspace` field of the root
+/// `deno.json`. Members without a name are skipped, the same as
+/// `package.json` files without a name.
+pub struct DenoProvider;
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    name: Option<String>,
+    version: Option<String>,
+    workspace: Option<Workspace>,
+    #[serde(default)]
+    imports: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Workspace {
+    TopLevel(Vec<String>),
+    Nested { members: Vec<String> },
+}
+
+impl Workspace {
+    fn members(&self) -> &[String] {
+        match self {
+            Workspace::TopLevel(members) => members,
+            Workspace::Nested { members } => members,
+        }
+    }
+}
+
+impl Manifest {
+    fn load(path: &AbsoluteSystemPath) -> Result<Option<Self>, Error> {
+        let Some(contents) = read_to_string(path)? else {
+            return Ok(None);
+        };
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| parse_error(path, e))
+    }
+}
+
+impl WorkspaceProvider for DenoProvider {
+    fn kind(&self) -> WorkspaceProviderKind {
+        WorkspaceProviderKind::Deno
+    }
+
+    fn discover(&self, repo_root: &AbsoluteSystemPath) -> Result<Vec<ProvidedWorkspace>, Error> {
+        let Some(workspace) =
+            Manifest::load(&repo_root.join_component(MANIFEST))?.and_then(|root| root.workspace)
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut members = Vec::new();
+        for directory in workspace.members() {
+            let manifest_path = member_manifest(repo_root, directory, MANIFEST)?;
+            if let Some(manifest @ Manifest { name: Some(_), .. }) = Manifest::load(&manifest_path)?
+            {
+                members.push((manifest_path, manifest));
+            }
+        }
+
+        let member_names = members
+            .iter()
+            .filter_map(|(_, manifest)| manifest.name.as_deref())
+            .collect::<HashSet<_>>();
+        let lockfile = Lockfile::load(&repo_root.join_component(LOCKFILE))?;
+
+        Ok(members
+            .iter()
+            .map(|(manifest_path, manifest)| {
+                let name = manifest.name.clone().expect("members have a name");
+                let internal_dependencies = manifest
+                    .imports
+                    .iter()
+                    .flat_map(|(alias, specifier)| [alias.as_str(), package_name(specifier)])
+                    .filter(|dependency| member_names.contains(dependency) && *dependency != name)
+                    .map(|dependency| dependency.to_string())
+                    .collect::<BTreeSet<_>>();
+                let external_dependencies = lockfile.as_ref().map(|lockfile| {
+                    lockfile.transitive_dependencies(
+                        manifest
+                            .imports
+                            .values()
+                            .filter(|specifier| !member_names.contains(package_name(specifier))),
+                    )
+                });
+                ProvidedWorkspace {
+                    name,
+                    version: manifest.version.clone(),
+                    manifest_path: manifest_path.clone(),
+                    internal_dependencies,
+                    external_dependencies,
+                }
+            })
+            .collect())
+    }
+}
+
+/// The lockfile maps each specifier, like `jsr:@std/path@^1`, to the version
+/// it resolved to, and each resolved package to the specifiers it depends on.
+#[derive(Debug, Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    specifiers: BTreeMap<String, String>,
+    #[serde(default)]
+    jsr: BTreeMap<String, Value>,
+    #[serde(default)]
+    npm: BTreeMap<String, Value>,
+}
+
+impl Lockfile {
+    fn load(path: &AbsoluteSystemPath) -> Result<Option<Self>, Error> {
+        let Some(contents) = read_to_string(path)? else {
+            return Ok(None);
+        };
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| parse_error(path, e))
+    }
+
+    fn transitive_dependencies<'a>(
+        &self,
+        specifiers: impl Iterator<Item = &'a String>,
+    ) -> HashSet<Package> {
+        let mut dependencies = HashSet::new();
+        let mut stack = specifiers.cloned().collect::<Vec<_>>();
+        while let Some(specifier) = stack.pop() {
+            let Some((registry, package)) = self.resolve(&specifier) else {
+                continue;
+            };
+            let entry = match registry {
+                "jsr" => &self.jsr,
+                "npm" => &self.npm,
+                _ => continue,
+            }
+            .get(&format!(
+                "{}@{}",
+                package_name(&package.key),
+                package.version
+            ));
+            if !dependencies.insert(package) {
+                continue;
+            }
+
+            let Some(Value::Array(entry_dependencies)) =
+                entry.and_then(|entry| entry.get("dependencies"))
+            else {
+                continue;
+            };
+            // npm packages list their dependencies without a registry
+            stack.extend(
+                entry_dependencies
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|dependency| match dependency.contains(':') {
+                        true => dependency.to_string(),
+                        false => format!("{registry}:{dependency}"),
+                    }),
+            );
+        }
+        dependencies
+    }
+
+    /// Resolves a specifier to the package it's locked to, along with the
+    /// registry it's from. Lockfiles before version 4 map a specifier to
+    /// another specifier rather than a version, and dependencies that aren't
+    /// listed as specifiers are already resolved.
+    fn resolve<'a>(&self, specifier: &'a str) -> Option<(&'a str, Package)> {
+        let (registry, package) = specifier.split_once(':')?;
+        let version = match self.specifiers.get(specifier) {
+            Some(resolved) => match resolved.split_once(':') {
+                Some((_, resolved)) => version(resolved)?,
+                None => resolved,
+            },
+            None => version(package)?,
+        };
+        let package = Package::new(format!("{registry}:{}", package_name(specifier)), version);
+        Some((registry, package))
+    }
+}
+
+/// Strips the registry and version from a specifier, e.g.
+/// `jsr:@std/path@^1` is `@std/path`
+fn package_name(specifier: &str) -> &str {
+    let package = specifier
+        .split_once(':')
+        .map_or(specifier, |(_, package)| package);
+    match package.rfind('@') {
+        Some(index) if index > 0 => &package[..index],
+        _ => package,
+    }
+}
+
+/// The version of a specifier without its registry, e.g. `@std/path@1.0.8`
+/// is `1.0.8`
+fn version(package: &str) -> Option<&str> {
+    match package.rfind('@') {
+        Some(index) if index > 0 => Some(&package[index + 1..]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tempfile::TempDir;
+    use test_case::test_case;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+    use crate::workspace_provider::write_file;
+
+    #[test_case("jsr:@std/path@^1", "@std/path" ; "scoped jsr")]
+    #[test_case("npm:chalk@5", "chalk" ; "npm")]
+    #[test_case("jsr:@std/path", "@std/path" ; "no version")]
+    #[test_case("@acme/utils", "@acme/utils" ; "bare")]
+    fn test_package_name(specifier: &str, expected: &str) {
+        assert_eq!(package_name(specifier), expected);
+    }
+
+    #[test]
+    fn test_discover_deno_workspace() {
+        let tmp = TempDir::new().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        write_file(
+            &root,
+            "deno.json",
+            r#"{ "workspace": ["./api", "./utils"] }"#,
+        );
+        write_file(
+            &root,
+            "api/deno.json",
+            r#"{
+                "name": "@acme/api",
+                "imports": { "@acme/utils": "jsr:@acme/utils@^1", "@std/path": "jsr:@std/path@^1" }
+            }"#,
+        );
+        write_file(
+            &root,
+            "utils/deno.json",
+            r#"{ "name": "@acme/utils", "version": "1.0.0" }"#,
+        );
+        write_file(
+            &root,
+            "deno.lock",
+            r#"{
+                "version": "4",
+                "specifiers": {
+                    "jsr:@std/path@^1": "1.0.8",
+                    "jsr:@std/internal@^1.0.5": "1.0.5"
+                },
+                "jsr": {
+                    "@std/path@1.0.8": { "dependencies": ["jsr:@std/internal@^1.0.5"] },
+                    "@std/internal@1.0.5": {}
+                }
+            }"#,
+        );
+
+        let workspaces = DenoProvider.discover(&root).unwrap();
+        assert_eq!(workspaces.len(), 2);
+
+        let api = &workspaces[0];
+        assert_eq!(api.name, "@acme/api");
+        assert_eq!(
+            api.internal_dependencies,
+            ["@acme/utils".to_string()].into_iter().collect()
+        );
+        assert_eq!(
+            api.external_dependencies,
+            Some(
+                [
+                    Package::new("jsr:@std/path", "1.0.8"),
+                    Package::new("jsr:@std/internal", "1.0.5"),
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+
+        let utils = &workspaces[1];
+        assert_eq!(utils.version.as_deref(), Some("1.0.0"));
+        assert!(utils.internal_dependencies.is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::{BTreeSet, HashSet};
+
+use turbopath::AbsoluteSystemPath;
+use turborepo_lockfiles::Package;
+
+use super::{
+    member_manifest, parse_error, read_to_string, Error, ProvidedWorkspace, WorkspaceProvider,
+    WorkspaceProviderKind,
+};
+
+pub const WORKSPACE_FILE: &str = "go.work";
+pub const MANIFEST: &str = "go.mod";
+
+/// Discovers the modules listed by the `use` directives in the root `go.work`.
+///
+/// Since Go 1.17 a module's `go.mod` requires every module in its build
+/// list, so its `require` directives act as the module's lockfile.
+pub struct GoProvider;
+
+struct Module {
+    path: String,
+    requires: Vec<(String, String)>,
+}
+
+impl Module {
+    fn load(manifest_path: &AbsoluteSystemPath) -> Result<Option<Self>, Error> {
+        let Some(contents) = read_to_string(manifest_path)? else {
+            return Ok(None);
+        };
+        let path = directives(&contents, "module")
+            .into_iter()
+            .find_map(|args| args.first().map(|path| path.to_string()))
+            .ok_or_else(|| parse_error(manifest_path, "missing module directive"))?;
+        let requires = directives(&contents, "require")
+            .into_iter()
+            .filter_map(|args| match args.as_slice() {
+                [module, version, ..] => Some((module.to_string(), version.to_string())),
+                _ => None,
+            })
+            .collect();
+        Ok(Some(Self { path, requires }))
+    }
+}
+
+impl WorkspaceProvider for GoProvider {
+    fn kind(&self) -> WorkspaceProviderKind {
+        WorkspaceProviderKind::Go
+    }
+
+    fn discover(&self, repo_root: &AbsoluteSystemPath) -> Result<Vec<ProvidedWorkspace>, Error> {
+        let Some(go_work) = read_to_string(&repo_root.join_component(WORKSPACE_FILE))? else {
+            return Ok(Vec::new());
+        };
+
+        let mut modules = Vec::new();
+        for args in directives(&go_work, "use") {
+            let Some(directory) = args.first() else {
+                continue;
+            };
+            let manifest_path = member_manifest(repo_root, directory, MANIFEST)?;
+            // The module at the root of the repository is covered by the root workspace
+            if manifest_path.parent() == Some(repo_root) {
+                continue;
+            }
+            if let Some(module) = Module::load(&manifest_path)? {
+                modules.push((manifest_path, module));
+            }
+        }
+
+        let module_paths = modules
+            .iter()
+            .map(|(_, module)| module.path.as_str())
+            .collect::<HashSet<_>>();
+
+        Ok(modules
+            .iter()
+            .map(|(manifest_path, module)| {
+                let (internal, external): (Vec<_>, Vec<_>) = module
+                    .requires
+                    .iter()
+                    .partition(|(path, _)| module_paths.contains(path.as_str()));
+                ProvidedWorkspace {
+                    name: module.path.clone(),
+                    version: None,
+                    manifest_path: manifest_path.clone(),
+                    internal_dependencies: internal
+                        .into_iter()
+                        .map(|(path, _)| path.clone())
+                        .collect::<BTreeSet<_>>(),
+                    external_dependencies: Some(
+                        external
+                            .into_iter()
+                            .map(|(path, version)| Package::new(path, version))
+                            .collect(),
+                    ),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Returns the arguments of every `verb` directive in a `go.mod` or `go.work`
+/// file, including those inside a `verb ( ... )` block.
+fn directives<'a>(contents: &'a str, verb: &str) -> Vec<Vec<&'a str>> {
+    let fields = |line: &'a str| {
+        line.split_whitespace()
+            .map(|field| field.trim_matches('"'))
+            .collect::<Vec<_>>()
+    };
+
+    let mut directives = Vec::new();
+    let mut in_block = false;
+    for line in contents.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if in_block {
+            match line {
+                ")" => in_block = false,
+                "" => (),
+                line => directives.push(fields(line)),
+            }
+            continue;
+        }
+
+        let Some(rest) = line.strip_prefix(verb) else {
+            continue;
+        };
+        if !(rest.is_empty() || rest.starts_with(char::is_whitespace) || rest.starts_with('(')) {
+            continue;
+        }
+        match rest.trim() {
+            "(" => in_block = true,
+            "" => (),
+            rest => directives.push(fields(rest)),
+        }
+    }
+
+    directives
+}
+
+#[cfg(test)]
+mod test {
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+    use crate::workspace_provider::write_file;
+
+    #[test]
+    fn test_directives() {
+        let contents = r#"
+module example.com/api // the api
+
+require example.com/single v1.0.0
+
+require (
+    // comment
+    example.com/lib v0.0.0
+    golang.org/x/text v0.14.0 // indirect
+)
+
+replace example.com/lib => ../lib
+"#;
+        assert_eq!(
+            directives(contents, "module"),
+            vec![vec!["example.com/api"]]
+        );
+        assert_eq!(
+            directives(contents, "require"),
+            vec![
+                vec!["example.com/single", "v1.0.0"],
+                vec!["example.com/lib", "v0.0.0"],
+                vec!["golang.org/x/text", "v0.14.0"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discover_go_workspace() {
+        let tmp = TempDir::new().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        write_file(
+            &root,
+            "go.work",
+            "go 1.21\n\nuse (\n    ./api\n    ./lib\n)\n",
+        );
+        write_file(
+            &root,
+            "api/go.mod",
+            "module example.com/api\n\nrequire (\n    example.com/lib v0.0.0\n    \
+             golang.org/x/text v0.14.0\n)\n",
+        );
+        write_file(&root, "lib/go.mod", "module example.com/lib\n");
+
+        let workspaces = GoProvider.discover(&root).unwrap();
+        assert_eq!(workspaces.len(), 2);
+
+        let api = &workspaces[0];
+        assert_eq!(api.name, "example.com/api");
+        assert_eq!(
+            api.internal_dependencies,
+            ["example.com/lib".to_string()].into_iter().collect()
+        );
+        assert_eq!(
+            api.external_dependencies,
+            Some(
+                [Package::new("golang.org/x/text", "v0.14.0")]
+                    .into_iter()
+                    .collect()
+            )
+        );
+
+        let lib = &workspaces[1];
+        assert_eq!(lib.name, "example.com/lib");
+        assert!(lib.internal_dependencies.is_empty());
+        assert_eq!(lib.external_dependencies, Some(HashSet::new()));
+    }
+}
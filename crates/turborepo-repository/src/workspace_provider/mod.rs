@@ -0,0 +1,152 @@
+//! Workspace providers discover workspaces that aren't managed by a
+//! JavaScript package manager, like the crates in a Cargo workspace or the
+//! modules in a `go.work` file.
+//!
+//! Each provider reads its own manifests to find workspaces and the
+//! dependencies between them, and its own lockfile to find the external
+//! dependencies that should be included in a workspace's hash.
+
+mod cargo;
+mod deno;
+mod go;
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+pub use cargo::CargoProvider;
+pub use deno::DenoProvider;
+pub use go::GoProvider;
+use serde::Serialize;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, RelativeUnixPathBuf};
+use turborepo_lockfiles::Package;
+
+#[derive(Debug, Serialize, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceProviderKind {
+    Cargo,
+    Deno,
+    Go,
+}
+
+impl WorkspaceProviderKind {
+    pub fn provider(&self) -> Box<dyn WorkspaceProvider> {
+        match self {
+            WorkspaceProviderKind::Cargo => Box::new(CargoProvider),
+            WorkspaceProviderKind::Deno => Box::new(DenoProvider),
+            WorkspaceProviderKind::Go => Box::new(GoProvider),
+        }
+    }
+}
+
+impl Display for WorkspaceProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkspaceProviderKind::Cargo => write!(f, "cargo"),
+            WorkspaceProviderKind::Deno => write!(f, "deno"),
+            WorkspaceProviderKind::Go => write!(f, "go"),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unknown workspace provider \"{0}\", expected one of: cargo, deno, go")]
+pub struct UnknownWorkspaceProvider(String);
+
+impl FromStr for WorkspaceProviderKind {
+    type Err = UnknownWorkspaceProvider;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cargo" => Ok(WorkspaceProviderKind::Cargo),
+            "deno" => Ok(WorkspaceProviderKind::Deno),
+            "go" => Ok(WorkspaceProviderKind::Go),
+            _ => Err(UnknownWorkspaceProvider(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("unable to read {path}: {source}")]
+    Io {
+        path: AbsoluteSystemPathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("unable to parse {path}: {reason}")]
+    Parse {
+        path: AbsoluteSystemPathBuf,
+        reason: String,
+    },
+    #[error(transparent)]
+    Path(#[from] turbopath::PathError),
+    #[error("invalid workspace glob: {0}")]
+    Glob(#[from] globwalk::GlobError),
+    #[error(transparent)]
+    Walk(#[from] globwalk::WalkError),
+}
+
+/// A workspace found by a `WorkspaceProvider`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvidedWorkspace {
+    pub name: String,
+    pub version: Option<String>,
+    /// The manifest that defines the workspace, e.g. its `Cargo.toml`
+    pub manifest_path: AbsoluteSystemPathBuf,
+    /// The names of other workspaces found by the same provider that this
+    /// workspace depends on
+    pub internal_dependencies: BTreeSet<String>,
+    /// The external dependencies of this workspace resolved from the
+    /// provider's lockfile, or `None` if there is no lockfile
+    pub external_dependencies: Option<HashSet<Package>>,
+}
+
+/// Defines a strategy for discovering workspaces from a language's own
+/// manifests rather than from `package.json` files.
+pub trait WorkspaceProvider: Send + Sync {
+    fn kind(&self) -> WorkspaceProviderKind;
+
+    /// Returns the workspaces in the repository. A repository that doesn't
+    /// use this provider has no workspaces, which isn't an error.
+    fn discover(&self, repo_root: &AbsoluteSystemPath) -> Result<Vec<ProvidedWorkspace>, Error>;
+}
+
+fn read_to_string(path: &AbsoluteSystemPath) -> Result<Option<String>, Error> {
+    match path.read_to_string() {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(Error::Io {
+            path: path.to_owned(),
+            source,
+        }),
+    }
+}
+
+fn parse_error(path: &AbsoluteSystemPath, reason: impl ToString) -> Error {
+    Error::Parse {
+        path: path.to_owned(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Resolves the manifest of a workspace member listed by its directory
+fn member_manifest(
+    repo_root: &AbsoluteSystemPath,
+    directory: &str,
+    manifest: &str,
+) -> Result<AbsoluteSystemPathBuf, Error> {
+    let directory = repo_root.join_unix_path(RelativeUnixPathBuf::new(directory)?)?;
+    Ok(directory.join_component(manifest))
+}
+
+#[cfg(test)]
+fn write_file(repo_root: &AbsoluteSystemPath, path: &str, contents: &str) {
+    let path = repo_root
+        .join_unix_path(RelativeUnixPathBuf::new(path).unwrap())
+        .unwrap();
+    path.ensure_dir().unwrap();
+    path.create_with_contents(contents).unwrap();
+}
@@ -1,11 +1,11 @@
 use std::io::{ErrorKind, Read};
 
-use globwalk::fix_glob_pattern;
+use globwalk::{fix_glob_pattern, MultiGlob};
 use hex::ToHex;
 use ignore::WalkBuilder;
 use sha1::{Digest, Sha1};
 use turbopath::{AbsoluteSystemPath, AnchoredSystemPath, IntoUnix};
-use wax::{any, Glob, Program};
+use wax::Glob;
 
 use crate::{package_deps::GitHashes, Error};
 
@@ -28,7 +28,7 @@ fn git_like_hash_file(path: &AbsoluteSystemPath) -> Result<String, Error> {
     Ok(result.encode_hex::<String>())
 }
 
-fn to_glob(input: &str) -> Result<Glob, Error> {
+fn to_glob(input: &str) -> Result<Glob<'static>, Error> {
     let glob = fix_glob_pattern(input).into_unix();
     let g = Glob::new(glob.as_str()).map(|g| g.into_owned())?;
 
@@ -80,9 +80,9 @@ pub(crate) fn get_package_file_hashes_without_git<S: AsRef<str>>(
             includes.push(g);
         }
     }
-    let include_pattern = if includes.is_empty() {
-        None
-    } else {
+    // with no includes, every file in the package is an input
+    let include_all = includes.is_empty();
+    if !include_all {
         // Add in package.json and turbo.json to input patterns. Both file paths are
         // relative to pkgPath
         //
@@ -97,14 +97,8 @@ pub(crate) fn get_package_file_hashes_without_git<S: AsRef<str>>(
         let package_g = to_glob("turbo.json")?;
         includes.push(turbo_g);
         includes.push(package_g);
-
-        Some(any(includes)?)
-    };
-    let exclude_pattern = if excludes.is_empty() {
-        None
-    } else {
-        Some(any(excludes)?)
-    };
+    }
+    let globs = MultiGlob::new(includes, excludes)?;
 
     let walker = walker_builder
         .follow_links(false)
@@ -129,17 +123,13 @@ pub(crate) fn get_package_file_hashes_without_git<S: AsRef<str>>(
         let relative_path = relative_path.to_unix();
 
         // if we have includes, and this path doesn't match any of them, skip it
-        if let Some(include_pattern) = include_pattern.as_ref() {
-            if !include_pattern.is_match(relative_path.as_str()) {
-                continue;
-            }
+        if !include_all && !globs.is_included(relative_path.as_str()) {
+            continue;
         }
 
-        // if we have excludes, and this path matches one of them, skip it
-        if let Some(exclude_pattern) = exclude_pattern.as_ref() {
-            if exclude_pattern.is_match(relative_path.as_str()) {
-                continue;
-            }
+        // if this path matches any of the excludes, skip it
+        if globs.is_excluded(relative_path.as_str()) {
+            continue;
         }
 
         // FIXME: we don't hash symlinks...
@@ -173,13 +163,11 @@ pub(crate) fn get_package_file_hashes_without_git<S: AsRef<str>>(
             let relative_path = full_package_path.anchor(path)?;
             let relative_path = relative_path.to_unix();
 
-            if let Some(exclude_pattern) = exclude_pattern.as_ref() {
-                if exclude_pattern.is_match(relative_path.as_str()) {
-                    // track excludes so we can exclude them to the hash map later
-                    if !metadata.is_symlink() {
-                        let hash = git_like_hash_file(path)?;
-                        excluded_file_hashes.insert(relative_path.clone(), hash);
-                    }
+            if globs.is_excluded(relative_path.as_str()) {
+                // track excludes so we can exclude them to the hash map later
+                if !metadata.is_symlink() {
+                    let hash = git_like_hash_file(path)?;
+                    excluded_file_hashes.insert(relative_path.clone(), hash);
                 }
             }
 
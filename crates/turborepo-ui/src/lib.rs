@@ -16,7 +16,10 @@ use thiserror::Error;
 
 pub use crate::{
     color_selector::ColorSelector,
-    logs::{replay_logs, LogWriter},
+    logs::{
+        read_logs, read_stderr_logs, replay_logs, replay_stderr_logs, LogStream, LogWriter,
+        StreamWrite,
+    },
     output::{OutputClient, OutputClientBehavior, OutputSink, OutputWriter},
     prefixed::{PrefixedUI, PrefixedWriter},
 };
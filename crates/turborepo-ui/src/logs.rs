@@ -1,17 +1,60 @@
 use std::{
+    borrow::Cow,
     fs::File,
-    io::{BufRead, BufReader, BufWriter, Write},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
+use serde::{Deserialize, Serialize};
 use tracing::{debug, warn};
 use turbopath::AbsoluteSystemPath;
 
 use crate::{prefixed::PrefixedUI, Error, PrefixedWriter};
 
+// The first line of a structured log file, used to tell it apart from a raw
+// log when replaying
+const STRUCTURED_LOG_HEADER: &str = r#"{"format":"turbo-task-log","version":1}"#;
+
+/// The output stream of a task that a line was written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// A writer that can be told which stream the bytes it receives came from.
+/// By default the stream is ignored.
+pub trait StreamWrite: Write {
+    fn write_stream(&mut self, stream: LogStream, buf: &[u8]) -> io::Result<()> {
+        let _ = stream;
+        self.write_all(buf)
+    }
+}
+
+impl<T: StreamWrite + ?Sized> StreamWrite for &mut T {
+    fn write_stream(&mut self, stream: LogStream, buf: &[u8]) -> io::Result<()> {
+        (**self).write_stream(stream, buf)
+    }
+}
+
+impl StreamWrite for Vec<u8> {}
+
+/// A single line of a structured log file
+#[derive(Debug, Serialize, Deserialize)]
+struct LogRecord<'a> {
+    stream: LogStream,
+    // milliseconds since the unix epoch
+    timestamp: u64,
+    line: Cow<'a, str>,
+}
+
 /// Receives logs and multiplexes them to a log file and/or a prefixed
 /// writer
 pub struct LogWriter<W> {
     log_file: Option<BufWriter<File>>,
+    // whether the log file holds `LogRecord`s instead of the raw output
+    structured: bool,
     prefixed_writer: Option<PrefixedWriter<W>>,
 }
 
@@ -21,6 +64,7 @@ impl<W> Default for LogWriter<W> {
     fn default() -> Self {
         Self {
             log_file: None,
+            structured: false,
             prefixed_writer: None,
         }
     }
@@ -43,6 +87,21 @@ impl<W: Write> LogWriter<W> {
         Ok(())
     }
 
+    /// Like `with_log_file`, but the log file records the stream and time of
+    /// every line that is written.
+    pub fn with_structured_log_file(
+        &mut self,
+        log_file_path: &AbsoluteSystemPath,
+    ) -> Result<(), Error> {
+        self.with_log_file(log_file_path)?;
+        if let Some(log_file) = &mut self.log_file {
+            writeln!(log_file, "{STRUCTURED_LOG_HEADER}").map_err(Error::CannotWriteLogs)?;
+        }
+        self.structured = true;
+
+        Ok(())
+    }
+
     pub fn with_prefixed_writer(&mut self, prefixed_writer: PrefixedWriter<W>) {
         self.prefixed_writer = Some(prefixed_writer);
     }
@@ -50,6 +109,10 @@ impl<W: Write> LogWriter<W> {
 
 impl<W: Write> Write for LogWriter<W> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.structured {
+            self.write_stream(LogStream::Stdout, buf)?;
+            return Ok(buf.len());
+        }
         match (&mut self.log_file, &mut self.prefixed_writer) {
             (Some(log_file), Some(prefixed_writer)) => {
                 let _ = prefixed_writer.write(buf)?;
@@ -77,10 +140,86 @@ impl<W: Write> Write for LogWriter<W> {
     }
 }
 
+impl<W: Write> StreamWrite for LogWriter<W> {
+    fn write_stream(&mut self, stream: LogStream, buf: &[u8]) -> io::Result<()> {
+        if !self.structured {
+            return self.write_all(buf);
+        }
+        if let Some(prefixed_writer) = &mut self.prefixed_writer {
+            prefixed_writer.write_all(buf)?;
+        }
+        if let Some(log_file) = &mut self.log_file {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since_epoch| since_epoch.as_millis() as u64);
+            for line in buf.split_inclusive(|byte| *byte == b'\n') {
+                let line = line.strip_suffix(b"\n").unwrap_or(line);
+                let record = LogRecord {
+                    stream,
+                    timestamp,
+                    line: String::from_utf8_lossy(line),
+                };
+                serde_json::to_writer(&mut *log_file, &record)?;
+                log_file.write_all(b"\n")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 pub fn replay_logs<W: Write>(
     output: &mut PrefixedUI<W>,
     log_file_name: &AbsoluteSystemPath,
 ) -> Result<(), Error> {
+    // Construct a PrefixedWriter which allows for non UTF-8 bytes to be written to
+    // it.
+    let mut prefixed_writer = output.output_prefixed_writer();
+    replay(&mut prefixed_writer, log_file_name, None)?;
+
+    Ok(())
+}
+
+/// Replays only the lines a task wrote to stderr. Raw logs don't record
+/// which stream a line came from, so they are replayed whole, as are logs
+/// with nothing on stderr.
+pub fn replay_stderr_logs<W: Write>(
+    output: &mut PrefixedUI<W>,
+    log_file_name: &AbsoluteSystemPath,
+) -> Result<(), Error> {
+    let mut prefixed_writer = output.output_prefixed_writer();
+    if replay(&mut prefixed_writer, log_file_name, Some(LogStream::Stderr))? == 0 {
+        replay(&mut prefixed_writer, log_file_name, None)?;
+    }
+
+    Ok(())
+}
+
+/// Reads a log file back into the output that the task produced, regardless
+/// of the format it was written in.
+pub fn read_logs(log_file_name: &AbsoluteSystemPath) -> Result<Vec<u8>, Error> {
+    let mut output = Vec::new();
+    replay(&mut output, log_file_name, None)?;
+    Ok(output)
+}
+
+/// Reads back only the lines a task wrote to stderr, falling back to the
+/// whole log like `replay_stderr_logs`.
+pub fn read_stderr_logs(log_file_name: &AbsoluteSystemPath) -> Result<Vec<u8>, Error> {
+    let mut output = Vec::new();
+    if replay(&mut output, log_file_name, Some(LogStream::Stderr))? == 0 {
+        replay(&mut output, log_file_name, None)?;
+    }
+    Ok(output)
+}
+
+// Writes the lines of the log file that came from `stream`, or all of them if
+// no stream is given, and returns how many lines were written.
+fn replay(
+    output: &mut impl Write,
+    log_file_name: &AbsoluteSystemPath,
+    stream: Option<LogStream>,
+) -> Result<usize, Error> {
     debug!("start replaying logs");
 
     let log_file = File::open(log_file_name).map_err(|err| {
@@ -88,12 +227,11 @@ pub fn replay_logs<W: Write>(
         Error::CannotReadLogs(err)
     })?;
 
-    // Construct a PrefixedWriter which allows for non UTF-8 bytes to be written to
-    // it.
-    let mut prefixed_writer = output.output_prefixed_writer();
     let mut log_reader = BufReader::new(log_file);
 
     let mut buffer = Vec::new();
+    let mut structured = None;
+    let mut replayed = 0;
     loop {
         let num_bytes = log_reader
             .read_until(b'\n', &mut buffer)
@@ -102,21 +240,38 @@ pub fn replay_logs<W: Write>(
             break;
         }
 
-        // If the log file doesn't end with a newline, then we add one to ensure the
-        // underlying writer receives a full line.
-        if !buffer.ends_with(b"\n") {
-            buffer.push(b'\n');
+        let is_structured = *structured.get_or_insert_with(|| {
+            buffer.strip_suffix(b"\n").unwrap_or(&buffer) == STRUCTURED_LOG_HEADER.as_bytes()
+        });
+        if is_structured {
+            match serde_json::from_slice::<LogRecord>(&buffer) {
+                Ok(record) if stream.map_or(true, |stream| stream == record.stream) => {
+                    output
+                        .write_all(record.line.as_bytes())
+                        .and_then(|_| output.write_all(b"\n"))
+                        .map_err(Error::CannotReadLogs)?;
+                    replayed += 1;
+                }
+                Ok(_) => (),
+                // the header, or a line cut short when the task was interrupted
+                Err(err) => debug!("skipping log record: {err}"),
+            }
+        } else {
+            // If the log file doesn't end with a newline, then we add one to ensure the
+            // underlying writer receives a full line.
+            if !buffer.ends_with(b"\n") {
+                buffer.push(b'\n');
+            }
+            output.write_all(&buffer).map_err(Error::CannotReadLogs)?;
+            replayed += 1;
         }
-        prefixed_writer
-            .write_all(&buffer)
-            .map_err(Error::CannotReadLogs)?;
 
         buffer.clear();
     }
 
     debug!("finish replaying logs");
 
-    Ok(())
+    Ok(replayed)
 }
 
 #[cfg(test)]
@@ -128,8 +283,8 @@ mod tests {
     use turbopath::AbsoluteSystemPathBuf;
 
     use crate::{
-        logs::{replay_logs, PrefixedUI},
-        LogWriter, PrefixedWriter, BOLD, CYAN, UI,
+        logs::{read_logs, replay_logs, replay_stderr_logs, PrefixedUI},
+        LogStream, LogWriter, PrefixedWriter, StreamWrite, BOLD, CYAN, UI,
     };
 
     #[test]
@@ -208,4 +363,66 @@ mod tests {
         assert_eq!(output, [b'>', 0, 159, 146, 150, b'\n']);
         Ok(())
     }
+    #[test]
+    fn test_structured_logs() -> Result<()> {
+        let dir = tempdir()?;
+        let log_file_path = AbsoluteSystemPathBuf::try_from(dir.path().join("test.log"))?;
+        let mut log_writer = LogWriter::<Vec<u8>>::default();
+
+        log_writer.with_structured_log_file(&log_file_path)?;
+        log_writer.write_stream(LogStream::Stdout, b"compiling\n")?;
+        log_writer.write_stream(LogStream::Stderr, b"error: oops\n")?;
+        log_writer.write_stream(LogStream::Stdout, b"done\n")?;
+        log_writer.flush()?;
+
+        let records = log_file_path
+            .read_to_string()?
+            .lines()
+            .skip(1)
+            .map(serde_json::from_str::<serde_json::Value>)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (record["stream"].as_str(), record["line"].as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some("stdout"), Some("compiling")),
+                (Some("stderr"), Some("error: oops")),
+                (Some("stdout"), Some("done")),
+            ]
+        );
+        assert!(records.iter().all(|record| record["timestamp"].is_u64()));
+
+        assert_eq!(
+            read_logs(&log_file_path)?,
+            b"compiling\nerror: oops\ndone\n"
+        );
+
+        let ui = UI::new(true);
+        let mut output = Vec::new();
+        let mut err = Vec::new();
+        let mut prefixed_ui = PrefixedUI::new(ui, &mut output, &mut err)
+            .with_output_prefix(CYAN.apply_to(">".to_string()));
+        replay_stderr_logs(&mut prefixed_ui, &log_file_path)?;
+        assert_eq!(String::from_utf8(output)?, ">error: oops\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay_stderr_logs_raw() -> Result<()> {
+        let ui = UI::new(true);
+        let mut output = Vec::new();
+        let mut err = Vec::new();
+        let mut prefixed_ui = PrefixedUI::new(ui, &mut output, &mut err)
+            .with_output_prefix(CYAN.apply_to(">".to_string()));
+        let dir = tempdir()?;
+        let log_file_path = AbsoluteSystemPathBuf::try_from(dir.path().join("test.txt"))?;
+        fs::write(&log_file_path, "one fish\ntwo fish\n")?;
+        replay_stderr_logs(&mut prefixed_ui, &log_file_path)?;
+
+        assert_eq!(String::from_utf8(output)?, ">one fish\n>two fish\n");
+        Ok(())
+    }
 }
@@ -12,8 +12,9 @@ use axum::{
 use futures_util::StreamExt;
 use tokio::sync::Mutex;
 use turborepo_vercel_api::{
-    AnalyticsEvent, CachingStatus, CachingStatusResponse, Membership, Role, Space, SpaceRun,
-    SpacesResponse, Team, TeamsResponse, User, UserResponse, VerificationResponse,
+    AnalyticsEvent, ArtifactInfo, ArtifactQueryResult, ArtifactsQueryRequest,
+    ArtifactsQueryResponse, CachingStatus, CachingStatusResponse, Membership, Role, Space,
+    SpaceRun, SpacesResponse, Team, TeamsResponse, User, UserResponse, VerificationResponse,
 };
 
 pub const EXPECTED_TOKEN: &str = "expected_token";
@@ -39,8 +40,10 @@ pub async fn start_test_server(port: u16) -> Result<()> {
     let get_durations_ref = Arc::new(Mutex::new(HashMap::new()));
     let head_durations_ref = get_durations_ref.clone();
     let put_durations_ref = get_durations_ref.clone();
+    let query_durations_ref = get_durations_ref.clone();
     let put_tempdir_ref = Arc::new(tempfile::tempdir()?);
     let get_tempdir_ref = put_tempdir_ref.clone();
+    let query_tempdir_ref = put_tempdir_ref.clone();
 
     let get_analytics_events_ref = Arc::new(Mutex::new(Vec::new()));
     let post_analytics_events_ref = get_analytics_events_ref.clone();
@@ -143,6 +146,30 @@ pub async fn start_test_server(port: u16) -> Result<()> {
                 })
             }),
         )
+        .route(
+            "/v8/artifacts",
+            post(|Json(request): Json<ArtifactsQueryRequest>| async move {
+                let durations = query_durations_ref.lock().await;
+                let response: ArtifactsQueryResponse = request
+                    .hashes
+                    .into_iter()
+                    .map(|hash| {
+                        let info = durations.get(&hash).map(|duration| {
+                            let size = std::fs::metadata(query_tempdir_ref.path().join(&hash))
+                                .map_or(0, |metadata| metadata.len());
+                            ArtifactQueryResult::Found(ArtifactInfo {
+                                size,
+                                task_duration_ms: *duration as u64,
+                                tag: None,
+                            })
+                        });
+                        (hash, info)
+                    })
+                    .collect();
+
+                Json(response)
+            }),
+        )
         .route(
             "/v8/artifacts/:hash",
             put(
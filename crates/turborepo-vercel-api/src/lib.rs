@@ -1,6 +1,8 @@
 //! Types for interacting with the Vercel API. Used for both
 //! the client (`turborepo-api-client`) and for the
 //! mock server (`turborepo-vercel-api-mock`)
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use url::Url;
 
@@ -38,6 +40,38 @@ pub struct ArtifactResponse {
     pub body: Vec<u8>,
 }
 
+/// The body of a request querying the status of several artifacts at once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactsQueryRequest {
+    pub hashes: Vec<String>,
+}
+
+/// Information about an artifact that exists in the remote cache
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactInfo {
+    pub size: u64,
+    pub task_duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactQueryError {
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ArtifactQueryResult {
+    Found(ArtifactInfo),
+    Error { error: ArtifactQueryError },
+}
+
+/// Maps each queried hash to its status. Missing artifacts are either `null`
+/// or an error.
+pub type ArtifactsQueryResponse = HashMap<String, Option<ArtifactQueryResult>>;
+
 /// Membership is the relationship between the logged-in user and a particular
 /// team
 #[derive(Debug, Clone, Serialize, Deserialize)]
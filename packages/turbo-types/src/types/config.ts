@@ -1,4 +1,7 @@
-/* This file generates the `schema.json` file. */
+/*
+ * The published `schema.json` is printed by `turbo schema`, from the types
+ * turbo.json is parsed into. Keep these types in line with it.
+ */
 export type Schema = RootSchema | WorkspaceSchema;
 
 export interface BaseSchema {
@@ -22,6 +25,17 @@ export interface BaseSchema {
      */
     [script: string]: Pipeline;
   };
+
+  /**
+   * How the tasks of this workspace can use the local and remote caches.
+   * Settings in the root turbo.json apply to every workspace, and a Workspace
+   * Config overrides them key by key.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#cache
+   *
+   * @defaultValue `{}`
+   */
+  cache?: CacheConfig;
 }
 
 export interface WorkspaceSchema extends BaseSchema {
@@ -33,7 +47,9 @@ export interface WorkspaceSchema extends BaseSchema {
    * and overrides with the keys provided
    * in your Workspace Configs.
    *
-   * Currently, only the "//" value is allowed.
+   * "//" refers to the root `turbo.json`. Any other value is the name of a
+   * workspace, or of an npm package installed in `node_modules`, whose
+   * `turbo.json` is applied, in order, before this one.
    *
    * @defaultValue ["//"]
    */
@@ -103,6 +119,16 @@ export interface RootSchema extends BaseSchema {
    */
   globalDotEnv?: null | Array<AnchoredUnixPath>;
 
+  /**
+   * Other tools whose workspaces are included alongside the workspaces found
+   * from package.json files.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#workspaceproviders
+   *
+   * @defaultValue []
+   */
+  workspaceProviders?: Array<"cargo" | "deno" | "go">;
+
   /**
    * Configuration options that control how turbo interfaces with the remote cache.
    *
@@ -169,9 +195,19 @@ export interface Pipeline {
    */
   passThroughEnv?: null | Array<EnvWildcard>;
 
+  /**
+   * The environment mode for this task, overriding the `--env-mode` of the run.
+   *
+   * @defaultValue null
+   */
+  envMode?: null | "infer" | "loose" | "strict";
+
   /**
    * A priority-ordered (most-significant to least-significant) array of workspace-anchored
-   * Unix-style paths to `.env` files to include in the task hash.
+   * Unix-style paths to `.env` files to include in the task hash. The variables they define
+   * are also set in the task's environment, layered over the root `globalDotEnv` files.
+   *
+   * A `{mode}` placeholder is replaced with the value of `NODE_ENV`, e.g. `.env.{mode}.local`.
    *
    * Documentation: https://turbo.build/repo/docs/reference/configuration#dotEnv
    *
@@ -179,6 +215,17 @@ export interface Pipeline {
    */
   dotEnv?: null | Array<AnchoredUnixPath>;
 
+  /**
+   * Whether the workspace's `.env`, `.env.local`, `.env.{mode}` and `.env.{mode}.local`
+   * files are included in the task hash and loaded into its environment. Each file
+   * overrides the ones before it, and all of them sit under the `dotEnv` files.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#dotEnvCascade
+   *
+   * @defaultValue false
+   */
+  dotEnvCascade?: boolean;
+
   /**
    * The set of glob patterns indicating a task's cacheable filesystem outputs.
    *
@@ -249,6 +296,72 @@ export interface Pipeline {
    * @defaultValue false
    */
   persistent?: boolean;
+
+  /**
+   * The signal sent to the task's process when turbo shuts down the run,
+   * for example after another task fails or when turbo receives Ctrl-C.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#stopsignal
+   *
+   * @defaultValue "SIGINT"
+   */
+  stopSignal?: StopSignal;
+
+  /**
+   * How long, in milliseconds, the task has to exit after receiving its
+   * `stopSignal` before turbo kills it.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#stopgraceperiod
+   *
+   * @defaultValue 500
+   */
+  stopGracePeriod?: number;
+
+  /**
+   * Whether the stop signal, and the kill that may follow it, are sent to
+   * every process the task started instead of just the task's own process.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#stopprocessgroup
+   *
+   * @defaultValue true
+   */
+  stopProcessGroup?: boolean;
+
+  /**
+   * Arguments passed to the task's script, before any arguments passed after
+   * `--` on the command line. They are included in the task's hash.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#args
+   *
+   * @defaultValue []
+   */
+  args?: Array<string>;
+
+  /**
+   * A command to run with the system shell instead of a package.json script.
+   * It is included in the task's hash.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#command
+   */
+  command?: string;
+
+  /**
+   * The directory the task runs in, relative to its workspace's directory.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#cwd
+   */
+  cwd?: string;
+
+  /**
+   * Named variants of this task, run as `<task>:<variant>`, e.g. `test:ci`.
+   * A variant runs the same script as the task, with its configuration
+   * applied on top of the task's, and its `args` appended to the task's.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#variants
+   *
+   * @defaultValue {}
+   */
+  variants?: Record<string, Pipeline>;
 }
 
 export interface RemoteCache {
@@ -262,6 +375,16 @@ export interface RemoteCache {
    */
   signature?: boolean;
 
+  /**
+   * Indicates if artifacts are encrypted before they are uploaded to the remote cache. When
+   * `true`, Turborepo will encrypt every uploaded artifact with AES-256-GCM using the first
+   * key in the environment variable `TURBO_REMOTE_CACHE_ENCRYPTION_KEY`, and will report an
+   * error for any downloaded artifact it cannot decrypt.
+   *
+   * @defaultValue false
+   */
+  encryption?: boolean;
+
   /**
    * Indicates if the remote cache is enabled. When `false`, Turborepo will disable
    * all remote cache operations, even if the repo has a valid token. If true, remote caching
@@ -273,6 +396,24 @@ export interface RemoteCache {
   enabled?: boolean;
 }
 
+export interface CacheConfig {
+  /**
+   * How the local filesystem cache can be used.
+   *
+   * @defaultValue "readwrite"
+   */
+  local?: CacheActions;
+
+  /**
+   * How the remote cache can be used.
+   *
+   * @defaultValue "readwrite"
+   */
+  remote?: CacheActions;
+}
+
+export type CacheActions = boolean | "read" | "write" | "readwrite";
+
 export type OutputMode =
   | "full"
   | "hash-only"
@@ -280,5 +421,7 @@ export type OutputMode =
   | "errors-only"
   | "none";
 
+export type StopSignal = "SIGINT" | "SIGTERM" | "SIGHUP";
+
 export type AnchoredUnixPath = string;
 export type EnvWildcard = string;
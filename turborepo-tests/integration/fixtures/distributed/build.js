@@ -0,0 +1,4 @@
+// Workers only pass on the env vars a task declares, so RAN_BY is only set
+// when the task runs on the coordinator
+const ranBy = process.env.RAN_BY || "worker";
+require("fs").writeFileSync("built.txt", `${ranBy}\n`);
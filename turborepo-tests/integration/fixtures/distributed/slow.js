@@ -0,0 +1,10 @@
+const fs = require("fs");
+
+const ranBy = process.env.RAN_BY || "worker";
+fs.writeFileSync("started.txt", "");
+// Keeps running until the test has killed the workers it wants to
+const wait = () =>
+  fs.existsSync("release.txt")
+    ? fs.writeFileSync(`slow-${ranBy}.txt`, `${ranBy}\n`)
+    : setTimeout(wait, 50);
+wait();
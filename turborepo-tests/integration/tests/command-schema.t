@@ -0,0 +1,15 @@
+Setup
+  $ . ${TESTDIR}/../../helpers/setup.sh
+
+The docs publish this output as schema.json
+  $ ${TURBO} schema > schema.json
+  $ jq -r '.title' schema.json
+  turbo.json
+  $ jq '.properties | keys | contains(["$schema", "extends", "globalEnv", "pipeline"])' schema.json
+  true
+  $ jq '.definitions.RawTaskDefinition.properties | keys | contains(["dependsOn", "dotEnvCascade", "outputs"])' schema.json
+  true
+
+Options can be omitted, but can't be null
+  $ jq '[.. | objects | .type? | select(. == "null" or (type == "array" and any(.[]; . == "null")))] | length' schema.json
+  0
@@ -11,14 +11,17 @@ Make sure exit code is 2 when no args are passed
     bin         Get the path to the Turbo binary
     completion  Generate the autocompletion script for the specified shell
     daemon      Runs the Turborepo background daemon
+    doctor      Check your repository for common problems
     generate    Generate a new app / package
     telemetry   Enable or disable anonymous telemetry
     link        Link your local directory to a Vercel organization and enable remote caching
     login       Login to your Vercel account
     logout      Logout to your Vercel account
+    migrate     Apply migrations for deprecated turbo.json options
     prune       Prepare a subset of your monorepo
     run         Run tasks across projects in your monorepo
     unlink      Unlink the current directory from your Vercel organization and disable Remote Caching
+    worker      Execute tasks dispatched by `turbo run --workers`
   
   Options:
         --version                         
@@ -94,10 +97,20 @@ Make sure exit code is 2 when no args are passed
             Ignore the local filesystem cache for all tasks. Only allow reading and caching artifacts using the remote cache [env: TURBO_REMOTE_ONLY=] [default: false] [possible values: true, false]
         --remote-cache-read-only [<BOOL>]
             Treat remote cache as read only [env: TURBO_REMOTE_CACHE_READ_ONLY=] [default: false] [possible values: true, false]
+        --lazy-restore [<BOOL>]
+            Write the outputs of cache hits to disk only once a task that depends on them executes, or when the run finishes. Tasks whose logs are replayed are still restored right away [env: TURBO_LAZY_RESTORE=] [default: false] [possible values: true, false]
         --summarize [<SUMMARIZE>]
-            Generate a summary of the turbo run [env: TURBO_RUN_SUMMARY=] [possible values: true, false]
+            Generate a summary of the turbo run, use "html" for an interactive report [env: TURBO_RUN_SUMMARY=] [possible values: true, false, json, html]
         --log-prefix <LOG_PREFIX>
             Use "none" to remove prefixes from task logs. Use "task" to get task id prefixing. Use "auto" to let turbo decide how to prefix the logs based on the execution environment. In most cases this will be the same as "task". Note that tasks running in parallel interleave their logs, so removing prefixes can make it difficult to associate logs with tasks. Use --log-order=grouped to prevent interleaving. (default auto) [default: auto] [possible values: auto, none, task]
+        --log-format <LOG_FORMAT>
+            Set the format of the task log files written to .turbo. Use "structured" to record the stream and time of every line as JSON, which lets --output-logs=errors-only show just the stderr of failed tasks. (default raw) [env: TURBO_LOG_FORMAT=] [default: raw] [possible values: raw, structured]
+        --output <OUTPUT>
+            Set how turbo reports the run on stdout. Use "ndjson" to print one JSON event per line as tasks are queued, started, hit or miss the cache, log and finish, instead of the usual human readable output. Task logs are still written to their log files. (default text) [default: text] [possible values: text, ndjson]
+        --workers <WORKERS>
+            Addresses of `turbo worker` processes to run tasks that miss the cache on, separated by commas
+        --worker-token <WORKER_TOKEN>
+            The token to present to workers, which must match their --worker-token [env: TURBO_WORKER_TOKEN]
   [1]
 
   $ ${TURBO} run
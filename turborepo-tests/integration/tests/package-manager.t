@@ -5,6 +5,10 @@ Run test run
   $ TURBO_LOG_VERBOSITY=off ${TURBO} info --json | jq .packageManager
   "npm"
 
+Report the pinned package manager version
+  $ TURBO_LOG_VERBOSITY=off ${TURBO} info --json | jq .packageManagerVersion.pinned
+  "npm@8.19.4"
+
 Set package manager to yarn in package.json
   $ jq '.packageManager = "yarn@1.22.7"' package.json > package.json.tmp && mv package.json.tmp package.json
 
@@ -20,6 +20,7 @@ Check
     "attempted",
     "cached",
     "command",
+    "criticalPath",
     "endTime",
     "exitCode",
     "failed",
@@ -131,6 +132,7 @@ Check Rust implementation
     "attempted",
     "cached",
     "command",
+    "criticalPath",
     "endTime",
     "exitCode",
     "failed",
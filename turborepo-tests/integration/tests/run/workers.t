@@ -0,0 +1,42 @@
+Setup
+  $ . ${TESTDIR}/../../../helpers/setup_integration_test.sh distributed
+
+Start two workers in the same checkout, each running one task at a time
+  $ export TURBO_WORKER_TOKEN=secret RAN_BY=coordinator
+  $ LOGS=$(mktemp -d)
+  $ start_worker() {
+  >   ${TURBO} worker --listen 127.0.0.1:0 --concurrency 1 > "$LOGS/$1.log" 2>&1 &
+  >   echo $! > "$LOGS/$1.pid"
+  >   for _ in $(seq 100); do grep -q "listening on" "$LOGS/$1.log" && break; sleep 0.1; done
+  >   sed -n 's/.*listening on \([^ ]*\) with.*/\1/p' "$LOGS/$1.log"
+  > }
+  $ wait_for() {
+  >   for _ in $(seq 100); do [ -f "$1" ] && return; sleep 0.1; done
+  >   echo "timed out waiting for $1"
+  > }
+  $ FIRST=$(start_worker first)
+  $ SECOND=$(start_worker second)
+
+Tasks that miss the cache run on the workers
+  $ ${TURBO} run build --workers "$FIRST,$SECOND" > run.log 2>&1
+  $ grep "Distributing" run.log
+  \xe2\x80\xa2 Distributing tasks to 2 workers (esc)
+  $ cat packages/util/built.txt packages/another/built.txt
+  worker
+  worker
+
+A task whose worker dies is retried on the other worker, and runs locally once
+no workers are left
+  $ ${TURBO} run slow --workers "$FIRST,$SECOND" --filter=util > run.log 2>&1 &
+  $ RUN=$!
+  $ wait_for packages/util/started.txt
+  $ rm packages/util/started.txt && kill -9 "$(cat "$LOGS/first.pid")"
+  $ wait_for packages/util/started.txt
+  $ rm packages/util/started.txt && kill -9 "$(cat "$LOGS/second.pid")"
+  $ wait_for packages/util/started.txt
+  $ touch packages/util/release.txt
+  $ wait $RUN
+  $ grep -c "running task locally" run.log
+  1
+  $ cat packages/util/slow-coordinator.txt
+  coordinator
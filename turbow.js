@@ -2,12 +2,13 @@
 
 const path = require("path");
 
-let binPath;
-if (path.sep === "\\") {
-  binPath = ".\\target\\debug\\turbo.exe";
-} else {
-  binPath = "./target/debug/turbo";
-}
+// Resolved from this file so it can be run from any workspace
+const binPath = path.join(
+  __dirname,
+  "target",
+  "debug",
+  path.sep === "\\" ? "turbo.exe" : "turbo"
+);
 
 try {
   require("child_process").execFileSync(binPath, process.argv.slice(2), {